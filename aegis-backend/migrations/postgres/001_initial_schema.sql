@@ -354,7 +354,7 @@ CREATE INDEX idx_admins_email ON admins(email);
 CREATE INDEX idx_admins_is_active ON admins(is_active);
 
 -- Enterprise Auth Indexes (Critical for performance)
-CREATE INDEX idx_user_sessions_active ON user_sessions(user_id, user_type) WHERE NOT revoked AND expires_at > NOW();
+CREATE INDEX idx_user_sessions_active ON user_sessions(user_id, user_type, expires_at) WHERE NOT revoked;
 CREATE INDEX idx_user_sessions_token ON user_sessions(session_token) WHERE NOT revoked;
 CREATE INDEX idx_user_sessions_refresh ON user_sessions(refresh_token) WHERE NOT revoked;
 CREATE INDEX idx_user_sessions_cleanup ON user_sessions(expires_at) WHERE NOT revoked;
@@ -364,8 +364,8 @@ CREATE INDEX idx_audit_logs_user ON audit_logs(user_id, user_type, created_at DE
 CREATE INDEX idx_audit_logs_action ON audit_logs(action, created_at DESC);
 CREATE INDEX idx_audit_logs_session ON audit_logs(session_id);
 
-CREATE INDEX idx_rate_limits_active ON rate_limits(identifier, identifier_type, action) WHERE blocked_until > NOW();
-CREATE INDEX idx_rate_limits_cleanup ON rate_limits(window_start) WHERE blocked_until IS NULL OR blocked_until < NOW();
+CREATE INDEX idx_rate_limits_active ON rate_limits(identifier, identifier_type, action) WHERE blocked_until IS NOT NULL;
+CREATE INDEX idx_rate_limits_cleanup ON rate_limits(window_start);
 
 CREATE INDEX idx_api_keys_lookup ON api_keys(key_id) WHERE is_active;
 CREATE INDEX idx_api_keys_owner ON api_keys(owner_id, owner_type) WHERE is_active;
@@ -0,0 +1,22 @@
+-- 🪪 Player identity verification (KYC), separate from email verification
+ALTER TABLE players ADD COLUMN identity_verified BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE players ADD COLUMN identity_verified_at TIMESTAMPTZ;
+
+CREATE TABLE identity_verifications (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    player_id UUID NOT NULL REFERENCES players(id) ON DELETE CASCADE,
+    document_type VARCHAR(50) NOT NULL,
+    document_keys TEXT[] NOT NULL DEFAULT '{}',
+    status approval_status DEFAULT 'pending',
+    submitted_at TIMESTAMPTZ DEFAULT NOW(),
+    reviewed_by UUID REFERENCES admins(id),
+    reviewed_at TIMESTAMPTZ,
+    rejection_reason TEXT,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+CREATE INDEX idx_identity_verifications_player ON identity_verifications(player_id, submitted_at DESC);
+CREATE UNIQUE INDEX idx_identity_verifications_one_pending ON identity_verifications(player_id) WHERE status = 'pending';
+CREATE INDEX idx_identity_verifications_queue ON identity_verifications(submitted_at) WHERE status = 'pending';
+CREATE INDEX idx_players_identity_verified ON players(identity_verified);
@@ -0,0 +1,23 @@
+-- 🚦 Rate limiting: one row per bucket, shared by every instance when
+-- AEGIS_RATE_LIMIT__BACKEND=postgres. Replaces the per-action counters in
+-- rate_limits, which stop being written.
+CREATE TABLE rate_limit_buckets (
+    key TEXT PRIMARY KEY,
+    level DOUBLE PRECISION NOT NULL,
+    previous DOUBLE PRECISION NOT NULL DEFAULT 0,
+    stamp DOUBLE PRECISION NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_rate_limit_buckets_expires ON rate_limit_buckets(expires_at);
+
+-- The session cleanup trigger no longer needs to sweep rate_limits
+CREATE OR REPLACE FUNCTION cleanup_expired_sessions()
+RETURNS TRIGGER AS $$
+BEGIN
+    DELETE FROM user_sessions WHERE expires_at < NOW() - INTERVAL '30 days';
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TABLE rate_limits;
@@ -0,0 +1,22 @@
+-- 🔐 TOTP two-factor authentication for every account type, and the roles
+-- that must use it to sign in
+CREATE TABLE two_factor_credentials (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    user_id UUID NOT NULL,
+    user_type VARCHAR(20) NOT NULL,
+    secret VARCHAR(64) NOT NULL,
+    recovery_code_hashes TEXT[] NOT NULL DEFAULT '{}',
+    last_used_step BIGINT,
+    enabled_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    UNIQUE (user_id, user_type)
+);
+
+-- role is an admin role, 'organization' or 'player'
+CREATE TABLE two_factor_policies (
+    role VARCHAR(20) PRIMARY KEY,
+    required BOOLEAN NOT NULL DEFAULT FALSE,
+    updated_by UUID REFERENCES admins(id),
+    updated_at TIMESTAMPTZ DEFAULT NOW()
+);
@@ -0,0 +1,21 @@
+use aegis_backend::config::AwsClients;
+use aegis_backend::repositories::DynamoRepository;
+use aegis_backend::scripts::backfill_comment_refs::backfill_comment_refs;
+use aegis_backend::scripts::migrate_memberships::migrate_memberships;
+use aegis_backend::scripts::reindex_follows::reindex_follows;
+use aegis_backend::scripts::rekey_messages::rekey_messages;
+use dotenvy::dotenv;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    dotenv().ok();
+
+    let aws = AwsClients::new().await;
+    let dynamo = DynamoRepository::new(aws.dynamodb);
+    migrate_memberships(&dynamo).await?;
+    rekey_messages(&dynamo).await?;
+    backfill_comment_refs(&dynamo).await?;
+    reindex_follows(&dynamo).await?;
+
+    Ok(())
+}
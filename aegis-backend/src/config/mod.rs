@@ -2,4 +2,4 @@ pub mod aws;
 pub mod settings;
 
 pub use aws::AwsClients;
-pub use settings::{EmailConfig, Settings};
+pub use settings::{EmailConfig, EmailTransportKind, RateLimitBackend, Settings, StorageBackend};
@@ -6,7 +6,10 @@ pub struct Settings {
     pub server: ServerConfig,
     pub database: DatabaseConfig,
     pub jwt: JwtConfig,
+    pub api_keys: ApiKeyConfig,
     pub email: EmailConfig,
+    pub storage: StorageConfig,
+    pub rate_limit: RateLimitConfig,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -17,6 +20,43 @@ pub struct EmailConfig {
     pub smtp_pass: String,
     pub from_email: String,
     pub from_name: String,
+    pub frontend_url: String, // Base for links in emails, without a trailing slash
+    pub templates_dir: String, // See `EmailTemplates` for the layout
+    pub transport: EmailTransportKind,
+}
+
+/// How `EmailService` hands off rendered messages.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailTransportKind {
+    Smtp,         // Only logs when SMTP credentials are empty (development mode)
+    File(String), // Writes each message as an .eml file into this directory
+    Memory,       // Keeps messages in process memory; for tests
+}
+
+/// Where the social layer (chats, posts, communities, feeds) keeps its data.
+#[derive(Debug, Deserialize, Clone)]
+pub struct StorageConfig {
+    pub backend: StorageBackend,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageBackend {
+    DynamoDb,
+    Memory, // Process memory; for tests and local runs without LocalStack
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RateLimitConfig {
+    pub backend: RateLimitBackend,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitBackend {
+    Memory,   // Per instance; fine for a single instance
+    Postgres, // Shared by all instances
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -37,6 +77,11 @@ pub struct JwtConfig {
     pub expiration: i64,
 }
 
+#[derive(Debug, Deserialize, Clone)]
+pub struct ApiKeyConfig {
+    pub digest_secret: String, // HMAC key for stored API key digests; changing it invalidates keys
+}
+
 impl Settings {
     pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
         Ok(Settings {
@@ -52,6 +97,11 @@ impl Settings {
                 secret: env::var("AEGIS_JWT__SECRET")?,
                 expiration: env::var("AEGIS_JWT__EXPIRATION")?.parse()?,
             },
+            api_keys: ApiKeyConfig {
+                // Deployments predating the setting keep working on the JWT secret
+                digest_secret: env::var("AEGIS_API_KEYS__DIGEST_SECRET")
+                    .or_else(|_| env::var("AEGIS_JWT__SECRET"))?,
+            },
             email: EmailConfig {
                 smtp_host: env::var("AEGIS_EMAIL__SMTP_HOST")
                     .unwrap_or_else(|_| "smtp.gmail.com".to_string()),
@@ -64,6 +114,35 @@ impl Settings {
                     .unwrap_or_else(|_| "noreply@aegis.com".to_string()),
                 from_name: env::var("AEGIS_EMAIL__FROM_NAME")
                     .unwrap_or_else(|_| "Aegis Gaming".to_string()),
+                frontend_url: env::var("AEGIS_EMAIL__FRONTEND_URL")
+                    .unwrap_or_else(|_| "http://localhost:5173".to_string()),
+                templates_dir: env::var("AEGIS_EMAIL__TEMPLATES_DIR")
+                    .unwrap_or_else(|_| "templates/email".to_string()),
+                transport: match env::var("AEGIS_EMAIL__TRANSPORT").as_deref() {
+                    Err(_) | Ok("smtp") => EmailTransportKind::Smtp,
+                    Ok("file") => EmailTransportKind::File(
+                        env::var("AEGIS_EMAIL__FILE_DIR")
+                            .unwrap_or_else(|_| "tmp/emails".to_string()),
+                    ),
+                    Ok("memory") => EmailTransportKind::Memory,
+                    Ok(other) => return Err(format!("Unknown email transport: {}", other).into()),
+                },
+            },
+            storage: StorageConfig {
+                backend: match env::var("AEGIS_STORAGE__BACKEND").as_deref() {
+                    Err(_) | Ok("dynamodb") => StorageBackend::DynamoDb,
+                    Ok("memory") => StorageBackend::Memory,
+                    Ok(other) => return Err(format!("Unknown storage backend: {}", other).into()),
+                },
+            },
+            rate_limit: RateLimitConfig {
+                backend: match env::var("AEGIS_RATE_LIMIT__BACKEND").as_deref() {
+                    Err(_) | Ok("memory") => RateLimitBackend::Memory,
+                    Ok("postgres") => RateLimitBackend::Postgres,
+                    Ok(other) => {
+                        return Err(format!("Unknown rate limit backend: {}", other).into())
+                    }
+                },
             },
         })
     }
@@ -0,0 +1,55 @@
+use super::chat::{ApiResponse, CursorQuery};
+use crate::models::dynamodb::{ActivityLog, ActivityType};
+use crate::repositories::{Page, ScanDirection};
+use crate::{utils::errors::AppError, AppState};
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use uuid::Uuid;
+
+// GET /players/:id/activity - A player's activity timeline, newest first
+pub async fn get_player_activity(
+    State(state): State<AppState>,
+    Path(player_id): Path<Uuid>,
+    Query(params): Query<CursorQuery>,
+) -> Result<Json<ApiResponse<Page<ActivityLog>>>, AppError> {
+    let request = params
+        .page_request(ScanDirection::Backward)
+        .map_err(|_| AppError::Validation("Invalid cursor".to_string()))?;
+
+    let activity = state
+        .activity_service
+        .get_player_timeline(&player_id.to_string(), &request)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get activity for player {}: {}", player_id, e);
+            AppError::InternalServerError
+        })?;
+
+    Ok(Json(ApiResponse::success(activity)))
+}
+
+// GET /activity/recent/:activity_type - Recent activity across all players (landing page)
+pub async fn get_recent_activity(
+    State(state): State<AppState>,
+    Path(activity_type): Path<ActivityType>,
+    Query(params): Query<CursorQuery>,
+) -> Result<Json<ApiResponse<Page<ActivityLog>>>, AppError> {
+    let request = params
+        .page_request(ScanDirection::Backward)
+        .map_err(|_| AppError::Validation("Invalid cursor".to_string()))?;
+
+    let activity = state
+        .activity_service
+        .get_recent(activity_type, &request)
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Failed to get recent {} activity: {}",
+                activity_type.as_str(),
+                e
+            );
+            AppError::InternalServerError
+        })?;
+
+    Ok(Json(ApiResponse::success(activity)))
+}
@@ -0,0 +1,168 @@
+use super::chat::ApiResponse;
+use crate::models::enums::AdminRole;
+use crate::models::postgres::admin::{self, role_permissions, AdminPermission};
+use crate::services::auth_service::Claims;
+use crate::{utils::errors::AppError, AppState};
+use axum::extract::{Extension, Path, State};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use uuid::Uuid;
+
+#[derive(Serialize)]
+pub struct AdminResponse {
+    #[serde(flatten)]
+    pub admin: admin::Model,
+    pub effective_permissions: BTreeSet<AdminPermission>,
+}
+
+impl From<admin::Model> for AdminResponse {
+    fn from(admin: admin::Model) -> Self {
+        Self {
+            effective_permissions: admin.effective_permissions(),
+            admin,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct PermissionCatalogue {
+    pub permissions: &'static [AdminPermission],
+    pub roles: BTreeMap<&'static str, &'static [AdminPermission]>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateAdminRequest {
+    pub username: String,
+    pub email: String,
+    pub password: String,
+    pub role: AdminRole,
+    #[serde(default)]
+    pub overrides: BTreeMap<AdminPermission, bool>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateAdminPermissionsRequest {
+    pub role: Option<AdminRole>,
+    #[serde(default)]
+    pub overrides: BTreeMap<AdminPermission, bool>,
+}
+
+// GET /admin/permissions - Every permission and what each role holds by default
+pub async fn get_permission_catalogue() -> Json<ApiResponse<PermissionCatalogue>> {
+    let roles = [
+        AdminRole::SuperAdmin,
+        AdminRole::Admin,
+        AdminRole::Moderator,
+    ]
+    .iter()
+    .map(|role| (role.as_str(), role_permissions(role)))
+    .collect();
+
+    Json(ApiResponse::success(PermissionCatalogue {
+        permissions: AdminPermission::ALL,
+        roles,
+    }))
+}
+
+// GET /admin/admins
+pub async fn list_admins(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<AdminResponse>>>, AppError> {
+    let admins = state.admin_service.list_admins().await?;
+
+    Ok(Json(ApiResponse::success(
+        admins.into_iter().map(AdminResponse::from).collect(),
+    )))
+}
+
+// POST /admin/admins - Create an admin with a role and optional overrides
+pub async fn create_admin(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<CreateAdminRequest>,
+) -> Result<Json<ApiResponse<AdminResponse>>, AppError> {
+    let acting_admin_id = Uuid::parse_str(&claims.sub)?;
+    let admin = state
+        .admin_service
+        .create_admin(
+            payload.username,
+            payload.email,
+            payload.password,
+            payload.role,
+            payload.overrides,
+        )
+        .await?;
+
+    log_admin_change(&state, &claims, acting_admin_id, "admin_create", &admin).await;
+
+    Ok(Json(ApiResponse::success(admin.into())))
+}
+
+// GET /admin/admins/:id
+pub async fn get_admin(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<AdminResponse>>, AppError> {
+    let admin = state
+        .admin_service
+        .get_by_id(id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    Ok(Json(ApiResponse::success(admin.into())))
+}
+
+// PUT /admin/admins/:id/permissions - Replace the role and overrides
+pub async fn update_admin_permissions(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateAdminPermissionsRequest>,
+) -> Result<Json<ApiResponse<AdminResponse>>, AppError> {
+    let acting_admin_id = Uuid::parse_str(&claims.sub)?;
+    let admin = state
+        .admin_service
+        .update_permissions(acting_admin_id, id, payload.role, payload.overrides)
+        .await?;
+
+    log_admin_change(
+        &state,
+        &claims,
+        acting_admin_id,
+        "admin_permissions_update",
+        &admin,
+    )
+    .await;
+
+    Ok(Json(ApiResponse::success(admin.into())))
+}
+
+async fn log_admin_change(
+    state: &AppState,
+    claims: &Claims,
+    acting_admin_id: Uuid,
+    action: &str,
+    admin: &admin::Model,
+) {
+    let _ = state
+        .audit_service
+        .log_action(
+            Some(acting_admin_id),
+            Some("admin".to_string()),
+            Uuid::parse_str(&claims.session_id).ok(),
+            action.to_string(),
+            Some("admin".to_string()),
+            Some(admin.id),
+            None,
+            None,
+            true,
+            None,
+            None,
+            Some(serde_json::json!({
+                "role": admin.role.as_str(),
+                "overrides": admin.permissions,
+            })),
+        )
+        .await;
+}
@@ -0,0 +1,115 @@
+use super::chat::ApiResponse;
+use crate::models::postgres::api_key;
+use crate::services::auth_service::Claims;
+use crate::{utils::errors::AppError, AppState};
+use axum::extract::{Extension, Path, State};
+use axum::Json;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize)]
+pub struct CreateApiKeyResponse {
+    pub api_key: api_key::Model,
+    pub key: String, // Sent as X-API-Key; shown only in this response
+}
+
+// POST /organizations/me/api-keys - Issue a key with the default hourly quota
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> Result<Json<ApiResponse<CreateApiKeyResponse>>, AppError> {
+    let organization_id = Uuid::parse_str(&claims.sub)?;
+    let (api_key, key) = state
+        .api_key_service
+        .create_api_key(
+            payload.name,
+            organization_id,
+            claims.user_type.clone(),
+            payload.scopes,
+            None,
+            payload.expires_at,
+        )
+        .await?;
+
+    let _ = state
+        .audit_service
+        .log_action(
+            Some(organization_id),
+            Some(claims.user_type.clone()),
+            Uuid::parse_str(&claims.session_id).ok(),
+            "api_key_create".to_string(),
+            Some("api_key".to_string()),
+            Some(api_key.id),
+            None,
+            None,
+            true,
+            None,
+            None,
+            None,
+        )
+        .await;
+
+    Ok(Json(ApiResponse::success(CreateApiKeyResponse {
+        api_key,
+        key,
+    })))
+}
+
+// GET /organizations/me/api-keys - Active keys, without secrets
+pub async fn list_api_keys(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<Vec<api_key::Model>>>, AppError> {
+    let organization_id = Uuid::parse_str(&claims.sub)?;
+    let keys = state
+        .api_key_service
+        .list_owner_keys(organization_id, claims.user_type.clone())
+        .await?;
+
+    Ok(Json(ApiResponse::success(keys)))
+}
+
+// DELETE /organizations/me/api-keys/:key_id
+pub async fn revoke_api_key(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(key_id): Path<String>,
+) -> Result<Json<ApiResponse<String>>, AppError> {
+    let organization_id = Uuid::parse_str(&claims.sub)?;
+    let revoked = state
+        .api_key_service
+        .revoke_api_key(organization_id, claims.user_type.clone(), key_id.clone())
+        .await?;
+    if !revoked {
+        return Err(AppError::NotFound);
+    }
+
+    let _ = state
+        .audit_service
+        .log_action(
+            Some(organization_id),
+            Some(claims.user_type.clone()),
+            Uuid::parse_str(&claims.session_id).ok(),
+            "api_key_revoke".to_string(),
+            Some("api_key".to_string()),
+            None,
+            None,
+            None,
+            true,
+            None,
+            None,
+            Some(serde_json::json!({ "key_id": key_id })),
+        )
+        .await;
+
+    Ok(Json(ApiResponse::success("API key revoked".to_string())))
+}
@@ -1,11 +1,15 @@
+use crate::models::postgres::{admin, organization, player};
 use crate::services::auth_service::{Claims, UserType};
+use crate::services::email_outbox_service::OutboxEmail;
 use crate::{utils::errors::AppError, AppState};
 use axum::extract::Path;
 use axum::{
     extract::{ConnectInfo, Extension, State},
     http::HeaderMap,
+    response::{IntoResponse, Response},
     Json,
 };
+use chrono::Duration;
 use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
 use uuid::Uuid;
@@ -42,6 +46,21 @@ pub struct AuthResponse {
     pub refresh_token: String,
     pub session_id: String,
     pub user: UserInfo,
+    /// Shown once, when sign-in completed a two-factor enrolment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovery_codes: Option<Vec<String>>,
+}
+
+/// The reply to a password sign-in that still needs a second factor. The
+/// challenge token is exchanged for a session at `/auth/2fa/challenge`.
+#[derive(Serialize)]
+pub struct TwoFactorChallengeResponse {
+    pub message: String,
+    pub challenge_token: String,
+    /// The account's role requires two-factor sign-in and it hasn't
+    /// enrolled yet: enrol with the challenge token first.
+    pub enrolment_required: bool,
+    pub expires_in: i64,
 }
 
 #[derive(Serialize)]
@@ -72,6 +91,11 @@ pub struct ResetPasswordRequest {
     pub new_password: String,
 }
 
+/// Temp token types of the two sign-in challenges
+pub(crate) const TWO_FACTOR_CHALLENGE: &str = "two_factor";
+pub(crate) const TWO_FACTOR_ENROLMENT: &str = "two_factor_enrolment";
+const TWO_FACTOR_CHALLENGE_MINUTES: i64 = 5;
+
 fn create_auth_cookie(token: &str) -> String {
     format!(
         "token={}; HttpOnly; SameSite=Lax; Max-Age={}; Path=/; Secure",
@@ -88,7 +112,7 @@ fn create_refresh_cookie(refresh_token: &str) -> String {
     )
 }
 
-fn extract_client_info(
+pub(crate) fn extract_client_info(
     headers: &HeaderMap,
     addr: Option<SocketAddr>,
 ) -> (Option<String>, Option<String>) {
@@ -132,76 +156,27 @@ fn create_auth_response_with_session(
             refresh_token: session.refresh_token,
             session_id: session.id.to_string(),
             user,
+            recovery_codes: None,
         }),
     ))
 }
 
-pub async fn login(
-    State(state): State<AppState>,
-    ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    headers: HeaderMap,
-    Json(payload): Json<LoginRequest>,
-) -> Result<(HeaderMap, Json<AuthResponse>), AppError> {
-    let (ip_address, user_agent) = extract_client_info(&headers, Some(addr));
-
-    // Check if already blocked (but don't increment counter yet)
-    if let Some(ip) = &ip_address {
-        let is_blocked = state
-            .rate_limit_service
-            .is_blocked(ip.clone(), "ip".to_string(), "login".to_string())
-            .await?;
-
-        if is_blocked {
-            return Err(AppError::RateLimited);
-        }
-    }
-
-    // Try player authentication first
-    if let Some((player, _)) = state
-        .player_service
-        .authenticate(payload.email.clone(), payload.password.clone())
-        .await?
-    {
-        let session = state
-            .session_service
-            .create_session(
-                player.id,
-                "player".to_string(),
-                ip_address.clone(),
-                user_agent.clone(),
-            )
-            .await?;
-
-        let token = state.auth_service.generate_jwt(
-            player.id,
-            UserType::Player,
-            None,
-            session.id.to_string(),
-        )?;
-
-        // Audit log
-        let _ = state
-            .audit_service
-            .log_action(
-                Some(player.id),
-                Some("player".to_string()),
-                Some(session.id),
-                "login".to_string(),
-                Some("player".to_string()),
-                Some(player.id),
-                ip_address.clone(),
-                user_agent.clone(),
-                true,
-                None,
-                None,
-                None,
-            )
-            .await;
+/// An account a sign-in resolved to.
+pub(crate) struct Account {
+    pub id: Uuid,
+    pub user_type: UserType,
+    /// The admin role, carried in the JWT
+    pub role: Option<String>,
+    pub info: UserInfo,
+}
 
-        return create_auth_response_with_session(
-            token,
-            session,
-            UserInfo {
+impl Account {
+    fn player(player: player::Model) -> Self {
+        Self {
+            id: player.id,
+            user_type: UserType::Player,
+            role: None,
+            info: UserInfo {
                 id: player.id,
                 email: player.email,
                 username: Some(player.username),
@@ -210,56 +185,15 @@ pub async fn login(
                 verified: player.verified,
                 approval_status: None,
             },
-        );
+        }
     }
 
-    // Try admin authentication
-    if let Some((admin, _)) = state
-        .admin_service
-        .authenticate(payload.email.clone(), payload.password.clone())
-        .await?
-    {
-        let session = state
-            .session_service
-            .create_session(
-                admin.id,
-                "admin".to_string(),
-                ip_address.clone(),
-                user_agent.clone(),
-            )
-            .await?;
-
-        // ✅ FIXED: Generate JWT with actual session ID
-        let token = state.auth_service.generate_jwt(
-            admin.id,
-            UserType::Admin,
-            Some(admin.role.as_str().to_string()),
-            session.id.to_string(),
-        )?;
-
-        // Audit log
-        let _ = state
-            .audit_service
-            .log_action(
-                Some(admin.id),
-                Some("admin".to_string()),
-                Some(session.id),
-                "login".to_string(),
-                Some("admin".to_string()),
-                Some(admin.id),
-                ip_address.clone(),
-                user_agent.clone(),
-                true,
-                None,
-                None,
-                None,
-            )
-            .await;
-
-        return create_auth_response_with_session(
-            token,
-            session,
-            UserInfo {
+    fn admin(admin: admin::Model) -> Self {
+        Self {
+            id: admin.id,
+            user_type: UserType::Admin,
+            role: Some(admin.role.as_str().to_string()),
+            info: UserInfo {
                 id: admin.id,
                 email: admin.email,
                 username: Some(admin.username),
@@ -272,56 +206,15 @@ pub async fn login(
                     "inactive".to_string()
                 }),
             },
-        );
+        }
     }
 
-    // Try organization authentication
-    if let Some((org, _)) = state
-        .organization_service
-        .authenticate(payload.email.clone(), payload.password)
-        .await?
-    {
-        let session = state
-            .session_service
-            .create_session(
-                org.id,
-                "organization".to_string(),
-                ip_address.clone(),
-                user_agent.clone(),
-            )
-            .await?;
-
-        // ✅ FIXED: Generate JWT with actual session ID
-        let token = state.auth_service.generate_jwt(
-            org.id,
-            UserType::Organization,
-            None,
-            session.id.to_string(),
-        )?;
-
-        // Audit log
-        let _ = state
-            .audit_service
-            .log_action(
-                Some(org.id),
-                Some("organization".to_string()),
-                Some(session.id),
-                "login".to_string(),
-                Some("organization".to_string()),
-                Some(org.id),
-                ip_address.clone(),
-                user_agent.clone(),
-                true,
-                None,
-                None,
-                None,
-            )
-            .await;
-
-        return create_auth_response_with_session(
-            token,
-            session,
-            UserInfo {
+    fn organization(org: organization::Model) -> Self {
+        Self {
+            id: org.id,
+            user_type: UserType::Organization,
+            role: None,
+            info: UserInfo {
                 id: org.id,
                 email: org.email,
                 username: None,
@@ -330,66 +223,216 @@ pub async fn login(
                 verified: org.email_verified,
                 approval_status: Some(org.approval_status.as_str().to_string()),
             },
-        );
+        }
     }
 
-    if let Some(ip) = &ip_address {
-        let _ = state
-            .rate_limit_service
-            .check_rate_limit(
-                ip.clone(),
-                "ip".to_string(),
-                "login".to_string(),
-                5,  // 5 attempts per hour
-                60, // 60 minutes window
-            )
-            .await;
+    /// The role whose two-factor policy applies: admins are held to their
+    /// admin role's, everyone else to their user type's.
+    pub fn two_factor_role(&self) -> &str {
+        self.role.as_deref().unwrap_or(self.user_type.as_str())
     }
+}
 
-    // Failed login audit
+pub(crate) async fn find_account(
+    state: &AppState,
+    user_id: Uuid,
+    user_type: &str,
+) -> Result<Option<Account>, AppError> {
+    Ok(match user_type {
+        "player" => state
+            .player_service
+            .get_by_id(user_id)
+            .await?
+            .map(Account::player),
+        "admin" => state
+            .admin_service
+            .get_by_id(user_id)
+            .await?
+            .map(Account::admin),
+        "organization" => state
+            .organization_service
+            .get_by_id(user_id)
+            .await?
+            .map(Account::organization),
+        _ => None,
+    })
+}
+
+/// The challenge a password sign-in has to answer before it gets a session:
+/// a code for accounts with two-factor enabled, or enrolment for accounts
+/// whose role requires it.
+async fn two_factor_challenge(
+    state: &AppState,
+    account: &Account,
+) -> Result<Option<TwoFactorChallengeResponse>, AppError> {
+    let (token_type, message) = if state
+        .two_factor_service
+        .is_enabled(account.id, account.user_type.as_str())
+        .await?
+    {
+        (
+            TWO_FACTOR_CHALLENGE,
+            "Enter the code from your authenticator app or a recovery code.",
+        )
+    } else if state
+        .two_factor_service
+        .is_required(account.two_factor_role())
+        .await?
+    {
+        (
+            TWO_FACTOR_ENROLMENT,
+            "Two-factor authentication is required for your account. Enrol to continue.",
+        )
+    } else {
+        return Ok(None);
+    };
+
+    let challenge_token = state
+        .auth_service
+        .generate_short_lived_token(
+            account.id,
+            account.user_type.clone(),
+            token_type,
+            Duration::minutes(TWO_FACTOR_CHALLENGE_MINUTES),
+        )
+        .map_err(|_| AppError::InternalServerError)?;
+
+    Ok(Some(TwoFactorChallengeResponse {
+        message: message.to_string(),
+        challenge_token,
+        enrolment_required: token_type == TWO_FACTOR_ENROLMENT,
+        expires_in: TWO_FACTOR_CHALLENGE_MINUTES * 60,
+    }))
+}
+
+/// Signs `account` in: a new session, its JWT and cookies, and the login
+/// audit entry. `recovery_codes` are passed through to the response when
+/// sign-in completed a two-factor enrolment.
+pub(crate) async fn start_session(
+    state: &AppState,
+    account: Account,
+    ip_address: Option<String>,
+    user_agent: Option<String>,
+    recovery_codes: Option<Vec<String>>,
+    details: Option<serde_json::Value>,
+) -> Result<(HeaderMap, Json<AuthResponse>), AppError> {
+    let user_type = account.user_type.as_str().to_string();
+    let session = state
+        .session_service
+        .create_session(
+            account.id,
+            user_type.clone(),
+            ip_address.clone(),
+            user_agent.clone(),
+        )
+        .await?;
+
+    let token = state.auth_service.generate_jwt(
+        account.id,
+        account.user_type,
+        account.role,
+        session.id.to_string(),
+    )?;
+
+    // Audit log
     let _ = state
         .audit_service
         .log_action(
-            None,
-            None,
-            None,
+            Some(account.id),
+            Some(user_type.clone()),
+            Some(session.id),
             "login".to_string(),
-            None,
-            None,
+            Some(user_type),
+            Some(account.id),
             ip_address,
             user_agent,
-            false,
-            Some("Invalid credentials".to_string()),
+            true,
             None,
             None,
+            details,
         )
         .await;
 
-    Err(AppError::Unauthorized)
+    let (headers, Json(mut response)) =
+        create_auth_response_with_session(token, session, account.info)?;
+    response.recovery_codes = recovery_codes;
+    Ok((headers, Json(response)))
 }
 
-pub async fn register(
+pub async fn login(
     State(state): State<AppState>,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
-    Json(payload): Json<RegisterRequest>,
-) -> Result<(HeaderMap, Json<AuthResponse>), AppError> {
+    Json(payload): Json<LoginRequest>,
+) -> Result<Response, AppError> {
     let (ip_address, user_agent) = extract_client_info(&headers, Some(addr));
 
-    // Rate limiting check
-    if let Some(ip) = &ip_address {
-        state
-            .rate_limit_service
-            .check_rate_limit(
-                ip.clone(),
-                "ip".to_string(),
-                "register".to_string(),
-                3,  // 3 registrations per hour
-                60, // 60 minutes window
+    // Players first, then admins, then organizations
+    let account = if let Some((player, _)) = state
+        .player_service
+        .authenticate(payload.email.clone(), payload.password.clone())
+        .await?
+    {
+        Some(Account::player(player))
+    } else if let Some((admin, _)) = state
+        .admin_service
+        .authenticate(payload.email.clone(), payload.password.clone())
+        .await?
+    {
+        Some(Account::admin(admin))
+    } else if let Some((org, _)) = state
+        .organization_service
+        .authenticate(payload.email.clone(), payload.password)
+        .await?
+    {
+        Some(Account::organization(org))
+    } else {
+        None
+    };
+
+    let Some(account) = account else {
+        // Failed login audit
+        let _ = state
+            .audit_service
+            .log_action(
+                None,
+                None,
+                None,
+                "login".to_string(),
+                None,
+                None,
+                ip_address,
+                user_agent,
+                false,
+                Some("Invalid credentials".to_string()),
+                None,
+                None,
             )
-            .await?;
+            .await;
+
+        return Err(AppError::Unauthorized);
+    };
+
+    // The password alone only earns a challenge when a second factor is due
+    if let Some(challenge) = two_factor_challenge(&state, &account).await? {
+        return Ok(Json(challenge).into_response());
     }
 
+    Ok(
+        start_session(&state, account, ip_address, user_agent, None, None)
+            .await?
+            .into_response(),
+    )
+}
+
+pub async fn register(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<RegisterRequest>,
+) -> Result<(HeaderMap, Json<AuthResponse>), AppError> {
+    let (ip_address, user_agent) = extract_client_info(&headers, Some(addr));
+
     match payload.user_type.as_str() {
         "player" => register_player(state, payload, ip_address, user_agent).await,
         "organization" => register_organization(state, payload, ip_address, user_agent).await,
@@ -454,21 +497,6 @@ async fn register_player(
         }
     };
 
-    println!("DEBUG: About to generate verification token");
-    let verification_token = state.auth_service.generate_temp_token(
-        player.id,
-        UserType::Player,
-        "verify_email",
-        24, // 24 hours expiry
-    )?;
-
-    println!("DEBUG: About to send verification email");
-    let _ = state
-        .email_service
-        .send_verification_email(&player.email, &verification_token)
-        .await;
-    println!("DEBUG: Email sending completed (may have failed silently)");
-
     println!("DEBUG: About to log audit action");
     let _ = state
         .audit_service
@@ -603,22 +631,6 @@ async fn register_organization(
         }
     };
 
-    // 🚨 ADD THIS MISSING VERIFICATION EMAIL SECTION:
-    println!("DEBUG: About to generate verification token");
-    let verification_token = state.auth_service.generate_temp_token(
-        org.id,
-        UserType::Organization,
-        "verify_email",
-        24, // 24 hours expiry
-    )?;
-
-    println!("DEBUG: About to send verification email");
-    let _ = state
-        .email_service
-        .send_verification_email(&org.email, &verification_token)
-        .await;
-    println!("DEBUG: Email sending completed (may have failed silently)");
-
     println!("DEBUG: About to log audit action");
     let _ = state
         .audit_service
@@ -872,10 +884,17 @@ pub async fn forgot_password(
         )?;
 
         println!("🔑 DEV MODE - Password Reset Token: {}", token);
-        let _ = state
-            .email_service
-            .send_password_reset(&player.email, &token)
-            .await;
+        state
+            .email_outbox_service
+            .enqueue(
+                OutboxEmail::new(
+                    &player.email,
+                    "password_reset",
+                    serde_json::json!({ "token": token }),
+                )
+                .with_languages(player.languages.clone()),
+            )
+            .await?;
         reset_token_sent = true;
     }
 
@@ -895,10 +914,14 @@ pub async fn forgot_password(
 
             println!("🔑 DEV MODE - Password Reset Token: {}", token);
 
-            let _ = state
-                .email_service
-                .send_password_reset(&admin.email, &token)
-                .await;
+            state
+                .email_outbox_service
+                .enqueue(OutboxEmail::new(
+                    &admin.email,
+                    "password_reset",
+                    serde_json::json!({ "token": token }),
+                ))
+                .await?;
             reset_token_sent = true;
         }
     }
@@ -917,10 +940,14 @@ pub async fn forgot_password(
                 1,
             )?;
             println!("🔑 DEV MODE - Password Reset Token: {}", token);
-            let _ = state
-                .email_service
-                .send_password_reset(&org.email, &token)
-                .await;
+            state
+                .email_outbox_service
+                .enqueue(OutboxEmail::new(
+                    &org.email,
+                    "password_reset",
+                    serde_json::json!({ "token": token }),
+                ))
+                .await?;
         }
     }
 
@@ -1029,15 +1056,15 @@ pub async fn send_verification_email(
     )?;
     println!("🔑 DEV MODE - Verification Token: {}", verification_token);
 
-    // Get user email based on type
-    let email = match claims.user_type.as_str() {
+    // Get user email (and players' languages) based on type
+    let (email, languages) = match claims.user_type.as_str() {
         "player" => {
             let player = state
                 .player_service
                 .get_by_id(user_id)
                 .await?
                 .ok_or(AppError::NotFound)?;
-            player.email
+            (player.email, player.languages)
         }
         "admin" => {
             let admin = state
@@ -1045,7 +1072,7 @@ pub async fn send_verification_email(
                 .get_by_id(user_id)
                 .await?
                 .ok_or(AppError::NotFound)?;
-            admin.email
+            (admin.email, Vec::new())
         }
         "organization" => {
             let org = state
@@ -1053,14 +1080,21 @@ pub async fn send_verification_email(
                 .get_by_id(user_id)
                 .await?
                 .ok_or(AppError::NotFound)?;
-            org.email
+            (org.email, Vec::new())
         }
         _ => return Err(AppError::Validation("Invalid user type".to_string())),
     };
 
     state
-        .email_service
-        .send_verification_email(&email, &verification_token)
+        .email_outbox_service
+        .enqueue(
+            OutboxEmail::new(
+                &email,
+                "verify_email",
+                serde_json::json!({ "token": verification_token }),
+            )
+            .with_languages(languages),
+        )
         .await?;
 
     Ok(Json(serde_json::json!({
@@ -1,4 +1,8 @@
+use crate::models::dynamodb::{Chat, ChatMessage, ChatParticipant, NotificationKind};
+use crate::repositories::{Page, PageRequest, ScanDirection};
 use crate::services::auth_service::Claims;
+use crate::services::chat_service::{ChatSummary, ReadReceipt};
+use crate::services::notification_service::NewNotification;
 use crate::{utils::errors::AppError, AppState};
 use axum::extract::Extension;
 use axum::{
@@ -9,6 +13,8 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+const CREATABLE_CHAT_TYPES: &[&str] = &["public", "general", "tournament"];
+
 #[derive(Deserialize)]
 pub struct CreateChatRequest {
     pub name: String,
@@ -22,8 +28,44 @@ pub struct SendMessageRequest {
 }
 
 #[derive(Deserialize)]
-pub struct GetMessagesQuery {
+pub struct EditMessageRequest {
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+pub struct ReactionRequest {
+    pub emoji: String,
+}
+
+#[derive(Deserialize)]
+pub struct MarkReadRequest {
+    pub message_id: String,
+}
+
+const MAX_MESSAGE_CHARS: usize = 4000;
+const MAX_EMOJI_CHARS: usize = 16;
+const MAX_MENTIONS_PER_MESSAGE: usize = 10;
+
+#[derive(Deserialize)]
+pub struct CursorQuery {
     pub limit: Option<i32>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+impl CursorQuery {
+    pub fn page_request(
+        &self,
+        default_direction: ScanDirection,
+    ) -> Result<PageRequest, StatusCode> {
+        PageRequest::from_cursors(
+            self.limit,
+            self.before.as_deref(),
+            self.after.as_deref(),
+            default_direction,
+        )
+        .map_err(|_| StatusCode::BAD_REQUEST)
+    }
 }
 
 #[derive(Serialize)]
@@ -57,6 +99,12 @@ pub async fn create_chat(
     Json(payload): Json<CreateChatRequest>,
 ) -> Result<Json<ApiResponse<String>>, StatusCode> {
     let creator_id = Uuid::parse_str(&claims.sub).map_err(|_| StatusCode::BAD_REQUEST)?;
+
+    // Direct and team chats are opened through their own endpoints
+    if !CREATABLE_CHAT_TYPES.contains(&payload.chat_type.as_str()) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
     match state
         .chat_service
         .create_chat(payload.name, payload.chat_type, creator_id.to_string()) // Use JWT user ID
@@ -73,15 +121,10 @@ pub async fn create_chat(
 pub async fn get_chat(
     State(state): State<AppState>,
     Path(chat_id): Path<String>,
-) -> Result<Json<ApiResponse<crate::models::dynamodb::Chat>>, StatusCode> {
-    match state.chat_service.get_chat(&chat_id).await {
-        Ok(Some(chat)) => Ok(Json(ApiResponse::success(chat))),
-        Ok(None) => Err(StatusCode::NOT_FOUND),
-        Err(e) => {
-            tracing::error!("Failed to get chat: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
-        }
-    }
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<Chat>>, AppError> {
+    let chat = load_chat_for_participant(&state, &chat_id, &claims.sub).await?;
+    Ok(Json(ApiResponse::success(chat)))
 }
 
 pub async fn send_message(
@@ -89,8 +132,14 @@ pub async fn send_message(
     Path(chat_id): Path<String>,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<SendMessageRequest>,
-) -> Result<Json<ApiResponse<String>>, StatusCode> {
-    let sender_id = Uuid::parse_str(&claims.sub).map_err(|_| StatusCode::BAD_REQUEST)?;
+) -> Result<Json<ApiResponse<String>>, AppError> {
+    if payload.message.chars().count() > MAX_MESSAGE_CHARS {
+        return Err(AppError::Validation("Message is too long".to_string()));
+    }
+
+    let sender_id = Uuid::parse_str(&claims.sub)?;
+    let chat = load_chat_for_participant(&state, &chat_id, &claims.sub).await?;
+    let text = payload.message.clone();
 
     match state
         .chat_service
@@ -102,10 +151,13 @@ pub async fn send_message(
         )
         .await
     {
-        Ok(message_id) => Ok(Json(ApiResponse::success(message_id))),
+        Ok(message_id) => {
+            notify_mentions(&state, &chat, &claims.sub, &message_id, &text).await;
+            Ok(Json(ApiResponse::success(message_id)))
+        }
         Err(e) => {
             tracing::error!("Failed to send message: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(AppError::InternalServerError)
         }
     }
 }
@@ -113,17 +165,19 @@ pub async fn send_message(
 pub async fn get_messages(
     State(state): State<AppState>,
     Path(chat_id): Path<String>,
-    Query(params): Query<GetMessagesQuery>,
-) -> Result<Json<ApiResponse<Vec<crate::models::dynamodb::ChatMessage>>>, StatusCode> {
-    match state
-        .chat_service
-        .get_messages(&chat_id, params.limit)
-        .await
-    {
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<CursorQuery>,
+) -> Result<Json<ApiResponse<Page<ChatMessage>>>, AppError> {
+    let request = params
+        .page_request(ScanDirection::Backward)
+        .map_err(|_| AppError::Validation("Invalid cursor".to_string()))?;
+    load_chat_for_participant(&state, &chat_id, &claims.sub).await?;
+
+    match state.chat_service.get_messages(&chat_id, &request).await {
         Ok(messages) => Ok(Json(ApiResponse::success(messages))),
         Err(e) => {
             tracing::error!("Failed to get messages: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(AppError::InternalServerError)
         }
     }
 }
@@ -133,13 +187,30 @@ pub async fn join_chat(
     Path((chat_id, user_id)): Path<(String, String)>,
     Extension(claims): Extension<Claims>,
 ) -> Result<Json<ApiResponse<String>>, AppError> {
-    // Extract user_id from path and claims
-    let requesting_user_id = Uuid::parse_str(&claims.sub)?;
+    let requesting_user_id = Uuid::parse_str(&claims.sub)?.to_string();
+
+    let chat = state
+        .chat_service
+        .get_chat(&chat_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get chat: {}", e);
+            AppError::InternalServerError
+        })?
+        .ok_or(AppError::NotFound)?;
+
+    // Direct chats are fixed pairs and team chats follow the roster
+    if chat.has_managed_membership() {
+        return Err(AppError::Forbidden);
+    }
 
-    // Authorization check
-    if requesting_user_id.to_string() != user_id {
+    // Public chats can be joined uninvited; everything else needs a chat
+    // admin to add the member
+    let joining_public_chat = chat.is_public() && requesting_user_id == user_id;
+    if !joining_public_chat && !chat.is_admin(&requesting_user_id) {
         return Err(AppError::Forbidden);
     }
+
     match state.chat_service.join_chat(&chat_id, &user_id).await {
         Ok(_) => Ok(Json(ApiResponse::success(
             "Joined chat successfully".to_string(),
@@ -150,3 +221,380 @@ pub async fn join_chat(
         }
     }
 }
+
+// POST /chats/direct/:player_id - Open (or reuse) the DM chat with another player
+pub async fn open_direct_chat(
+    State(state): State<AppState>,
+    Path(player_id): Path<Uuid>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<Chat>>, AppError> {
+    if claims.user_type != "player" {
+        return Err(AppError::Forbidden);
+    }
+    let initiator_id = Uuid::parse_str(&claims.sub)?;
+    if initiator_id == player_id {
+        return Err(AppError::Validation(
+            "Cannot start a chat with yourself".to_string(),
+        ));
+    }
+
+    state
+        .player_service
+        .get_by_id(player_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let chat = state
+        .chat_service
+        .get_or_create_direct_chat(&initiator_id.to_string(), &player_id.to_string())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to open direct chat: {}", e);
+            AppError::InternalServerError
+        })?;
+
+    Ok(Json(ApiResponse::success(chat)))
+}
+
+// POST /chats/team/:team_id - Open the team chat, syncing members from the roster
+pub async fn open_team_chat(
+    State(state): State<AppState>,
+    Path(team_id): Path<Uuid>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<Chat>>, AppError> {
+    let chat = state.team_service.sync_chat(team_id).await?;
+    ensure_participant(&state, &chat.id, &claims.sub).await?;
+    Ok(Json(ApiResponse::success(chat)))
+}
+
+// GET /chats/:chat_id/participants
+pub async fn get_chat_participants(
+    State(state): State<AppState>,
+    Path(chat_id): Path<String>,
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<CursorQuery>,
+) -> Result<Json<ApiResponse<Page<ChatParticipant>>>, AppError> {
+    let request = params
+        .page_request(ScanDirection::Forward)
+        .map_err(|_| AppError::Validation("Invalid cursor".to_string()))?;
+    load_chat_for_participant(&state, &chat_id, &claims.sub).await?;
+
+    let participants = state
+        .chat_service
+        .get_participants(&chat_id, &request)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to list chat participants: {}", e);
+            AppError::InternalServerError
+        })?;
+
+    Ok(Json(ApiResponse::success(participants)))
+}
+
+// GET /chats/me - Chats the caller is in, with unread counts
+pub async fn get_my_chats(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<CursorQuery>,
+) -> Result<Json<ApiResponse<Page<ChatSummary>>>, AppError> {
+    let request = params
+        .page_request(ScanDirection::Forward)
+        .map_err(|_| AppError::Validation("Invalid cursor".to_string()))?;
+
+    let chats = state
+        .chat_service
+        .get_my_chats(&claims.sub, &request)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to list chats: {}", e);
+            AppError::InternalServerError
+        })?;
+
+    Ok(Json(ApiResponse::success(chats)))
+}
+
+// PUT /chats/:chat_id/messages/:message_id - Author edits their message
+pub async fn edit_message(
+    State(state): State<AppState>,
+    Path((chat_id, message_id)): Path<(String, String)>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<EditMessageRequest>,
+) -> Result<Json<ApiResponse<ChatMessage>>, AppError> {
+    if payload.message.trim().is_empty() {
+        return Err(AppError::Validation("Message cannot be empty".to_string()));
+    }
+    if payload.message.chars().count() > MAX_MESSAGE_CHARS {
+        return Err(AppError::Validation("Message is too long".to_string()));
+    }
+
+    load_chat_for_participant(&state, &chat_id, &claims.sub).await?;
+    let message = load_message(&state, &chat_id, &message_id).await?;
+    if message.sender != claims.sub {
+        return Err(AppError::Forbidden);
+    }
+    if message.deleted {
+        return Err(AppError::Validation(
+            "Deleted messages cannot be edited".to_string(),
+        ));
+    }
+
+    let message = state
+        .chat_service
+        .edit_message(message, payload.message)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to edit message: {}", e);
+            AppError::InternalServerError
+        })?;
+
+    Ok(Json(ApiResponse::success(message)))
+}
+
+// DELETE /chats/:chat_id/messages/:message_id - Author or chat admin leaves a tombstone
+pub async fn delete_message(
+    State(state): State<AppState>,
+    Path((chat_id, message_id)): Path<(String, String)>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<ChatMessage>>, AppError> {
+    let chat = load_chat_for_participant(&state, &chat_id, &claims.sub).await?;
+    let message = load_message(&state, &chat_id, &message_id).await?;
+    if message.sender != claims.sub && !chat.is_admin(&claims.sub) {
+        return Err(AppError::Forbidden);
+    }
+    if message.deleted {
+        return Ok(Json(ApiResponse::success(message)));
+    }
+
+    let message = state
+        .chat_service
+        .delete_message(message)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to delete message: {}", e);
+            AppError::InternalServerError
+        })?;
+
+    Ok(Json(ApiResponse::success(message)))
+}
+
+// POST /chats/:chat_id/messages/:message_id/reactions
+pub async fn add_reaction(
+    State(state): State<AppState>,
+    Path((chat_id, message_id)): Path<(String, String)>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<ReactionRequest>,
+) -> Result<Json<ApiResponse<ChatMessage>>, AppError> {
+    let emoji = payload.emoji.trim();
+    if emoji.is_empty() || emoji.chars().count() > MAX_EMOJI_CHARS {
+        return Err(AppError::Validation("Invalid reaction".to_string()));
+    }
+
+    load_chat_for_participant(&state, &chat_id, &claims.sub).await?;
+    let message = load_message(&state, &chat_id, &message_id).await?;
+    if message.deleted {
+        return Err(AppError::Validation(
+            "Cannot react to a deleted message".to_string(),
+        ));
+    }
+
+    let message = state
+        .chat_service
+        .add_reaction(message, emoji, &claims.sub)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to add reaction: {}", e);
+            AppError::InternalServerError
+        })?;
+    if !message.has_reacted(emoji, &claims.sub) {
+        return Err(AppError::Validation(
+            "Too many reactions on this message".to_string(),
+        ));
+    }
+
+    Ok(Json(ApiResponse::success(message)))
+}
+
+// DELETE /chats/:chat_id/messages/:message_id/reactions/:emoji
+pub async fn remove_reaction(
+    State(state): State<AppState>,
+    Path((chat_id, message_id, emoji)): Path<(String, String, String)>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<ChatMessage>>, AppError> {
+    load_chat_for_participant(&state, &chat_id, &claims.sub).await?;
+    let message = load_message(&state, &chat_id, &message_id).await?;
+
+    let message = state
+        .chat_service
+        .remove_reaction(message, &emoji, &claims.sub)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to remove reaction: {}", e);
+            AppError::InternalServerError
+        })?;
+
+    Ok(Json(ApiResponse::success(message)))
+}
+
+// POST /chats/:chat_id/read - Move the caller's read marker
+pub async fn mark_chat_read(
+    State(state): State<AppState>,
+    Path(chat_id): Path<String>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<MarkReadRequest>,
+) -> Result<Json<ApiResponse<ReadReceipt>>, AppError> {
+    load_chat_for_participant(&state, &chat_id, &claims.sub).await?;
+    let receipt = mark_read(&state, &chat_id, &payload.message_id, &claims.sub).await?;
+    Ok(Json(ApiResponse::success(receipt)))
+}
+
+/// Shared by the REST endpoint and the socket `read` event.
+pub(crate) async fn mark_read(
+    state: &AppState,
+    chat_id: &str,
+    message_id: &str,
+    user_id: &str,
+) -> Result<ReadReceipt, AppError> {
+    let message = load_message(state, chat_id, message_id).await?;
+    state
+        .chat_service
+        .mark_read(&message, user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to mark chat read: {}", e);
+            AppError::InternalServerError
+        })
+}
+
+async fn load_message(
+    state: &AppState,
+    chat_id: &str,
+    message_id: &str,
+) -> Result<ChatMessage, AppError> {
+    state
+        .chat_service
+        .get_message(chat_id, message_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get message: {}", e);
+            AppError::InternalServerError
+        })?
+        .ok_or(AppError::NotFound)
+}
+
+/// Loads a chat and checks the caller is one of its participants. Team chat
+/// membership is kept in step with the roster by `TeamService::sync_chat`.
+pub(crate) async fn load_chat_for_participant(
+    state: &AppState,
+    chat_id: &str,
+    user_id: &str,
+) -> Result<Chat, AppError> {
+    let chat = state
+        .chat_service
+        .get_chat(chat_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get chat: {}", e);
+            AppError::InternalServerError
+        })?
+        .ok_or(AppError::NotFound)?;
+
+    ensure_participant(state, &chat.id, user_id).await?;
+    Ok(chat)
+}
+
+// `@username` tokens, deduplicated, ignoring trailing punctuation
+fn mentioned_usernames(text: &str) -> Vec<&str> {
+    let mut usernames: Vec<&str> = Vec::new();
+    for word in text.split_whitespace() {
+        let Some(username) = word.strip_prefix('@') else {
+            continue;
+        };
+        let username =
+            username.trim_end_matches(|c: char| !c.is_alphanumeric() && c != '_' && c != '-');
+        if !username.is_empty() && !usernames.contains(&username) {
+            usernames.push(username);
+        }
+    }
+    usernames.truncate(MAX_MENTIONS_PER_MESSAGE);
+    usernames
+}
+
+// Mentions only reach players who can read the chat
+async fn notify_mentions(
+    state: &AppState,
+    chat: &Chat,
+    sender_id: &str,
+    message_id: &str,
+    text: &str,
+) {
+    for username in mentioned_usernames(text) {
+        let player = match state
+            .player_service
+            .get_by_username(username.to_string())
+            .await
+        {
+            Ok(Some(player)) => player,
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::warn!("Failed to look up mentioned player {}: {}", username, e);
+                continue;
+            }
+        };
+        let player_id = player.id.to_string();
+        if player_id == sender_id {
+            continue;
+        }
+        match state
+            .chat_service
+            .is_participant(&chat.id, &player_id)
+            .await
+        {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(e) => {
+                tracing::warn!("Failed to check mention recipient: {}", e);
+                continue;
+            }
+        }
+
+        let notification = NewNotification::new(
+            NotificationKind::ChatMention,
+            format!("You were mentioned in {}", chat.name),
+            text.to_string(),
+        )
+        .with_link(format!("/chats/{}", chat.id))
+        .with_metadata(serde_json::json!({
+            "chat_id": chat.id,
+            "message_id": message_id,
+            "sender_id": sender_id,
+        }));
+        if let Err(e) = state
+            .notification_service
+            .notify(&player_id, notification)
+            .await
+        {
+            tracing::warn!("Failed to notify mention of {}: {}", player_id, e);
+        }
+    }
+}
+
+async fn ensure_participant(
+    state: &AppState,
+    chat_id: &str,
+    user_id: &str,
+) -> Result<(), AppError> {
+    let is_participant = state
+        .chat_service
+        .is_participant(chat_id, user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to check chat membership: {}", e);
+            AppError::InternalServerError
+        })?;
+
+    if is_participant {
+        Ok(())
+    } else {
+        Err(AppError::Forbidden)
+    }
+}
@@ -0,0 +1,211 @@
+use super::chat::{load_chat_for_participant, mark_read, ApiResponse};
+use crate::middleware::auth::{authenticate_token, extract_token_from_headers};
+use crate::services::chat_service::{chat_room, user_room, CHAT_NAMESPACE};
+use crate::utils::errors::AppError;
+use crate::AppState;
+use serde::{Deserialize, Serialize};
+use socketioxide::extract::{AckSender, Data, SocketRef, TryData};
+use socketioxide::socket::Sid;
+use socketioxide::SocketIo;
+use std::time::Duration;
+
+/// How often a connected socket's session is checked again, so a logout,
+/// revocation or expiry closes the socket instead of leaving it in its rooms.
+const SESSION_RECHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Deserialize)]
+pub struct ChatSocketAuth {
+    pub token: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ChatRoomPayload {
+    pub chat_id: String,
+}
+
+#[derive(Deserialize)]
+pub struct TypingPayload {
+    pub chat_id: String,
+    pub is_typing: bool,
+}
+
+#[derive(Deserialize)]
+pub struct ReadPayload {
+    pub chat_id: String,
+    pub message_id: String,
+}
+
+#[derive(Serialize)]
+pub struct TypingEvent {
+    pub chat_id: String,
+    pub user_id: String,
+    pub is_typing: bool,
+}
+
+// Socket.IO namespace for real-time chat.
+//
+// Clients connect to `/chat` with `{ auth: { token } }` (or the same
+// Authorization header / `token` cookie the REST API accepts), then emit
+// `join` / `leave` with a chat ID. Server-sent events: `message`,
+// `message_updated`, `typing`, `read`, plus `notification` for the
+// connected user's own notifications. The session is checked again on every
+// `join` and once a minute; a socket whose session has ended is disconnected.
+pub fn register_chat_namespace(io: &SocketIo, state: AppState) {
+    let handle = io.clone();
+    io.ns(
+        CHAT_NAMESPACE,
+        move |socket: SocketRef, TryData(auth): TryData<ChatSocketAuth>| {
+            let state = state.clone();
+            let io = handle.clone();
+            async move { on_connect(socket, auth.ok(), state, io).await }
+        },
+    );
+}
+
+async fn on_connect(
+    socket: SocketRef,
+    auth: Option<ChatSocketAuth>,
+    state: AppState,
+    io: SocketIo,
+) {
+    let token = auth
+        .and_then(|a| a.token)
+        .or_else(|| extract_token_from_headers(&socket.req_parts().headers).ok());
+
+    let Some(token) = token else {
+        tracing::warn!("Rejected chat socket {}: no token", socket.id);
+        let _ = socket.disconnect();
+        return;
+    };
+    let claims = authenticate_token(&state, &token).await;
+    let user_id = match claims {
+        Ok(claims) => claims.sub,
+        Err(e) => {
+            tracing::warn!("Rejected chat socket {}: {}", socket.id, e);
+            let _ = socket.disconnect();
+            return;
+        }
+    };
+
+    tracing::info!("Chat socket {} connected for user {}", socket.id, user_id);
+
+    if let Err(e) = socket.join(user_room(&user_id)) {
+        tracing::error!("Failed to join user room: {:?}", e);
+    }
+
+    spawn_session_recheck(io, socket.id, state.clone(), token.clone());
+
+    socket.on("join", {
+        let state = state.clone();
+        let user_id = user_id.clone();
+        move |socket: SocketRef, Data(payload): Data<ChatRoomPayload>, ack: AckSender| {
+            let state = state.clone();
+            let user_id = user_id.clone();
+            let token = token.clone();
+            async move { on_join(socket, payload, ack, state, user_id, token).await }
+        }
+    });
+
+    socket.on(
+        "leave",
+        |socket: SocketRef, Data(payload): Data<ChatRoomPayload>| {
+            let _ = socket.leave(chat_room(&payload.chat_id));
+        },
+    );
+
+    socket.on("typing", {
+        let user_id = user_id.clone();
+        move |socket: SocketRef, Data(payload): Data<TypingPayload>| {
+            if !in_room(&socket, &payload.chat_id) {
+                return;
+            }
+            let room = chat_room(&payload.chat_id);
+            let event = TypingEvent {
+                chat_id: payload.chat_id,
+                user_id: user_id.clone(),
+                is_typing: payload.is_typing,
+            };
+            let _ = socket.to(room).emit("typing", event);
+        }
+    });
+
+    // Read markers are persisted; the service broadcasts the receipt to the room
+    socket.on(
+        "read",
+        move |socket: SocketRef, Data(payload): Data<ReadPayload>| {
+            let state = state.clone();
+            let user_id = user_id.clone();
+            async move {
+                if !in_room(&socket, &payload.chat_id) {
+                    return;
+                }
+                if let Err(e) =
+                    mark_read(&state, &payload.chat_id, &payload.message_id, &user_id).await
+                {
+                    tracing::warn!("Failed to record read marker: {}", e);
+                }
+            }
+        },
+    );
+}
+
+async fn on_join(
+    socket: SocketRef,
+    payload: ChatRoomPayload,
+    ack: AckSender,
+    state: AppState,
+    user_id: String,
+    token: String,
+) {
+    // A session revoked since the handshake can't pick up new rooms
+    if authenticate_token(&state, &token).await.is_err() {
+        let _ = ack.send(ApiResponse::<String>::error("Session expired".to_string()));
+        let _ = socket.disconnect();
+        return;
+    }
+
+    let response = match load_chat_for_participant(&state, &payload.chat_id, &user_id).await {
+        Ok(_) => match socket.join(chat_room(&payload.chat_id)) {
+            Ok(_) => ApiResponse::success(payload.chat_id),
+            Err(e) => {
+                tracing::error!("Failed to join chat room: {:?}", e);
+                ApiResponse::error("Failed to join chat".to_string())
+            }
+        },
+        Err(AppError::Forbidden) => {
+            ApiResponse::error("Not a participant of this chat".to_string())
+        }
+        Err(AppError::NotFound) => ApiResponse::error("Chat not found".to_string()),
+        Err(_) => ApiResponse::error("Failed to join chat".to_string()),
+    };
+    let _ = ack.send(response);
+}
+
+/// Re-authenticates the socket's token every `SESSION_RECHECK_INTERVAL`
+/// until the socket goes away, and disconnects it once the session is no
+/// longer valid.
+fn spawn_session_recheck(io: SocketIo, sid: Sid, state: AppState, token: String) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(SESSION_RECHECK_INTERVAL);
+        ticker.tick().await; // The first tick is immediate; the handshake just checked
+        loop {
+            ticker.tick().await;
+            let Some(socket) = io.of(CHAT_NAMESPACE).and_then(|ns| ns.get_socket(sid)) else {
+                return;
+            };
+            if let Err(e) = authenticate_token(&state, &token).await {
+                tracing::info!("Closing chat socket {}: {}", sid, e);
+                let _ = socket.disconnect();
+                return;
+            }
+        }
+    });
+}
+
+fn in_room(socket: &SocketRef, chat_id: &str) -> bool {
+    let room = chat_room(chat_id);
+    socket
+        .rooms()
+        .map(|rooms| rooms.iter().any(|r| *r == room))
+        .unwrap_or(false)
+}
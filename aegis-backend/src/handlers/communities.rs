@@ -1,9 +1,16 @@
-use super::chat::ApiResponse;
+use super::chat::{ApiResponse, CursorQuery};
+use crate::models::dynamodb::{
+    Community, CommunityJoinRequest, CommunityMember, CommunityPost, NotificationKind,
+    COMMUNITY_TYPES,
+};
+use crate::repositories::{Page, ScanDirection};
 use crate::services::auth_service::Claims;
+use crate::services::community_service::CommunityMembership;
+use crate::services::notification_service::NewNotification;
 use crate::{utils::errors::AppError, AppState};
 use axum::extract::Extension;
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::StatusCode,
     response::Json,
 };
@@ -29,6 +36,9 @@ pub async fn create_community(
     Json(payload): Json<CreateCommunityRequest>,
 ) -> Result<Json<ApiResponse<String>>, StatusCode> {
     let owner_id = Uuid::parse_str(&claims.sub).map_err(|_| StatusCode::BAD_REQUEST)?;
+    if !COMMUNITY_TYPES.contains(&payload.community_type.as_str()) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
     match state
         .community_service
         .create_community(
@@ -66,19 +76,52 @@ pub async fn add_post_to_community(
     Path(community_id): Path<String>,
     Extension(claims): Extension<Claims>,
     Json(payload): Json<AddPostToCommunityRequest>,
-) -> Result<Json<ApiResponse<String>>, StatusCode> {
-    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| StatusCode::BAD_REQUEST)?;
+) -> Result<Json<ApiResponse<String>>, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub)?;
     let pinned = payload.pinned.unwrap_or(false);
 
+    load_community(&state, &community_id).await?;
+    let member = load_member(&state, &community_id, &claims.sub)
+        .await?
+        .ok_or(AppError::Forbidden)?;
+    if member.muted || (pinned && !member.is_moderator()) {
+        return Err(AppError::Forbidden);
+    }
+
+    // Members share their own posts; moderators can share anyone's
+    let post = state
+        .post_service
+        .get_post(&payload.post_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get post: {}", e);
+            AppError::InternalServerError
+        })?
+        .ok_or(AppError::NotFound)?;
+    if post.author != claims.sub && !member.is_moderator() {
+        return Err(AppError::Forbidden);
+    }
+
     match state
         .community_service
-        .add_post_to_community(community_id, payload.post_id, pinned, user_id.to_string())
+        .add_post_to_community(
+            community_id.clone(),
+            post.id.clone(),
+            pinned,
+            user_id.to_string(),
+        )
         .await
     {
-        Ok(id) => Ok(Json(ApiResponse::success(id))),
+        Ok(Some(id)) => {
+            tokio::spawn(fan_out_community_post(state, community_id, post.id));
+            Ok(Json(ApiResponse::success(id)))
+        }
+        Ok(None) => Err(AppError::Validation(
+            "Post is already in this community".to_string(),
+        )),
         Err(e) => {
             tracing::error!("Failed to add post to community: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(AppError::InternalServerError)
         }
     }
 }
@@ -86,20 +129,89 @@ pub async fn add_post_to_community(
 pub async fn get_community_posts(
     State(state): State<AppState>,
     Path(community_id): Path<String>,
-) -> Result<Json<ApiResponse<Vec<crate::models::dynamodb::CommunityPost>>>, StatusCode> {
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<CursorQuery>,
+) -> Result<Json<ApiResponse<Page<CommunityPost>>>, AppError> {
+    let request = params
+        .page_request(ScanDirection::Forward)
+        .map_err(|_| AppError::Validation("Invalid cursor".to_string()))?;
+
+    let community = load_community(&state, &community_id).await?;
+    if community.is_private()
+        && load_member(&state, &community_id, &claims.sub)
+            .await?
+            .is_none()
+    {
+        return Err(AppError::Forbidden);
+    }
+
     match state
         .community_service
-        .get_community_posts(&community_id)
+        .get_community_posts(&community_id, &request)
         .await
     {
         Ok(posts) => Ok(Json(ApiResponse::success(posts))),
         Err(e) => {
             tracing::error!("Failed to get community posts: {}", e);
-            Err(StatusCode::INTERNAL_SERVER_ERROR)
+            Err(AppError::InternalServerError)
         }
     }
 }
 
+// GET /communities/me - Communities the caller belongs to
+pub async fn get_my_communities(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<CursorQuery>,
+) -> Result<Json<ApiResponse<Page<CommunityMembership>>>, AppError> {
+    let request = params
+        .page_request(ScanDirection::Forward)
+        .map_err(|_| AppError::Validation("Invalid cursor".to_string()))?;
+
+    let communities = state
+        .community_service
+        .get_my_communities(&claims.sub, &request)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get user communities: {}", e);
+            AppError::InternalServerError
+        })?;
+
+    Ok(Json(ApiResponse::success(communities)))
+}
+
+// GET /communities/:community_id/members
+pub async fn get_community_members(
+    State(state): State<AppState>,
+    Path(community_id): Path<String>,
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<CursorQuery>,
+) -> Result<Json<ApiResponse<Page<CommunityMember>>>, AppError> {
+    let request = params
+        .page_request(ScanDirection::Forward)
+        .map_err(|_| AppError::Validation("Invalid cursor".to_string()))?;
+
+    let community = load_community(&state, &community_id).await?;
+    if community.is_private()
+        && load_member(&state, &community_id, &claims.sub)
+            .await?
+            .is_none()
+    {
+        return Err(AppError::Forbidden);
+    }
+
+    let members = state
+        .community_service
+        .get_members(&community_id, &request)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get community members: {}", e);
+            AppError::InternalServerError
+        })?;
+
+    Ok(Json(ApiResponse::success(members)))
+}
+
 pub async fn join_community(
     State(state): State<AppState>,
     Path((community_id, user_id)): Path<(String, String)>,
@@ -112,14 +224,43 @@ pub async fn join_community(
     if requesting_user_id.to_string() != user_id {
         return Err(AppError::Forbidden);
     }
+
+    let community = load_community(&state, &community_id).await?;
+    if is_banned(&state, &community_id, &user_id).await? {
+        return Err(AppError::Forbidden);
+    }
+
+    // Private communities take a join request for moderators to approve
+    if community.is_private()
+        && load_member(&state, &community_id, &user_id)
+            .await?
+            .is_none()
+    {
+        let created = state
+            .community_service
+            .request_to_join(&community_id, &user_id)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to request to join community: {}", e);
+                AppError::InternalServerError
+            })?;
+        let message = if created {
+            "Join request submitted"
+        } else {
+            "Join request already pending"
+        };
+        return Ok(Json(ApiResponse::success(message.to_string())));
+    }
+
     match state
         .community_service
         .join_community(&community_id, &user_id)
         .await
     {
-        Ok(_) => Ok(Json(ApiResponse::success(
+        Ok(true) => Ok(Json(ApiResponse::success(
             "Joined community successfully".to_string(),
         ))),
+        Ok(false) => Ok(Json(ApiResponse::success("Already a member".to_string()))),
         Err(e) => {
             tracing::error!("Failed to join community: {}", e);
             Err(AppError::InternalServerError)
@@ -139,17 +280,455 @@ pub async fn leave_community(
     if requesting_user_id.to_string() != user_id {
         return Err(AppError::Forbidden);
     }
+
+    let community = load_community(&state, &community_id).await?;
+    if community.is_owner(&user_id) {
+        return Err(AppError::Validation(
+            "The owner cannot leave the community".to_string(),
+        ));
+    }
+
     match state
         .community_service
         .leave_community(&community_id, &user_id)
         .await
     {
-        Ok(_) => Ok(Json(ApiResponse::success(
+        Ok(true) => Ok(Json(ApiResponse::success(
             "Left community successfully".to_string(),
         ))),
+        Ok(false) => Err(AppError::NotFound),
         Err(e) => {
             tracing::error!("Failed to leave community: {}", e);
             Err(AppError::InternalServerError)
         }
     }
 }
+
+// GET /communities/:community_id/join-requests - Moderators only
+pub async fn get_community_join_requests(
+    State(state): State<AppState>,
+    Path(community_id): Path<String>,
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<CursorQuery>,
+) -> Result<Json<ApiResponse<Page<CommunityJoinRequest>>>, AppError> {
+    let request = params
+        .page_request(ScanDirection::Forward)
+        .map_err(|_| AppError::Validation("Invalid cursor".to_string()))?;
+    load_as_moderator(&state, &community_id, &claims).await?;
+
+    let requests = state
+        .community_service
+        .get_join_requests(&community_id, &request)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get join requests: {}", e);
+            AppError::InternalServerError
+        })?;
+
+    Ok(Json(ApiResponse::success(requests)))
+}
+
+// POST /communities/:community_id/join-requests/:user_id/approve
+pub async fn approve_community_join_request(
+    State(state): State<AppState>,
+    Path((community_id, user_id)): Path<(String, String)>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<String>>, AppError> {
+    load_as_moderator(&state, &community_id, &claims).await?;
+    if is_banned(&state, &community_id, &user_id).await? {
+        return Err(AppError::Validation("User is banned".to_string()));
+    }
+
+    let approved = state
+        .community_service
+        .approve_join_request(&community_id, &user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to approve join request: {}", e);
+            AppError::InternalServerError
+        })?;
+    if !approved {
+        return Err(AppError::NotFound);
+    }
+
+    notify_join_approved(&state, &community_id, &user_id).await;
+
+    Ok(Json(ApiResponse::success(
+        "Join request approved".to_string(),
+    )))
+}
+
+// POST /communities/:community_id/join-requests/:user_id/reject
+pub async fn reject_community_join_request(
+    State(state): State<AppState>,
+    Path((community_id, user_id)): Path<(String, String)>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<String>>, AppError> {
+    load_as_moderator(&state, &community_id, &claims).await?;
+
+    let rejected = state
+        .community_service
+        .reject_join_request(&community_id, &user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to reject join request: {}", e);
+            AppError::InternalServerError
+        })?;
+    if !rejected {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(Json(ApiResponse::success(
+        "Join request rejected".to_string(),
+    )))
+}
+
+// PUT /communities/:community_id/moderators/:user_id - Owner only
+pub async fn add_community_moderator(
+    State(state): State<AppState>,
+    Path((community_id, user_id)): Path<(String, String)>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<String>>, AppError> {
+    let community = load_community(&state, &community_id).await?;
+    if !community.is_owner(&claims.sub) {
+        return Err(AppError::Forbidden);
+    }
+    if community.is_owner(&user_id) {
+        return Err(AppError::Validation(
+            "The owner is always a moderator".to_string(),
+        ));
+    }
+
+    if !set_moderator(&state, &community_id, &user_id, true).await? {
+        return Err(AppError::Validation(
+            "Only members can be made moderators".to_string(),
+        ));
+    }
+    Ok(Json(ApiResponse::success("Moderator added".to_string())))
+}
+
+// DELETE /communities/:community_id/moderators/:user_id - Owner only
+pub async fn remove_community_moderator(
+    State(state): State<AppState>,
+    Path((community_id, user_id)): Path<(String, String)>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<String>>, AppError> {
+    let community = load_community(&state, &community_id).await?;
+    if !community.is_owner(&claims.sub) {
+        return Err(AppError::Forbidden);
+    }
+    if community.is_owner(&user_id) {
+        return Err(AppError::Validation(
+            "The owner is always a moderator".to_string(),
+        ));
+    }
+
+    if !set_moderator(&state, &community_id, &user_id, false).await? {
+        return Err(AppError::NotFound);
+    }
+    Ok(Json(ApiResponse::success("Moderator removed".to_string())))
+}
+
+// PUT /communities/:community_id/posts/:post_id/pin - Moderators only
+pub async fn pin_community_post(
+    State(state): State<AppState>,
+    Path((community_id, post_id)): Path<(String, String)>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<String>>, AppError> {
+    set_pinned(&state, &community_id, &post_id, &claims, true).await?;
+    Ok(Json(ApiResponse::success("Post pinned".to_string())))
+}
+
+// DELETE /communities/:community_id/posts/:post_id/pin - Moderators only
+pub async fn unpin_community_post(
+    State(state): State<AppState>,
+    Path((community_id, post_id)): Path<(String, String)>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<String>>, AppError> {
+    set_pinned(&state, &community_id, &post_id, &claims, false).await?;
+    Ok(Json(ApiResponse::success("Post unpinned".to_string())))
+}
+
+// DELETE /communities/:community_id/posts/:post_id - Moderators, or whoever added it
+pub async fn remove_community_post(
+    State(state): State<AppState>,
+    Path((community_id, post_id)): Path<(String, String)>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<String>>, AppError> {
+    load_community(&state, &community_id).await?;
+    let community_post = state
+        .community_service
+        .get_community_post(&community_id, &post_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get community post: {}", e);
+            AppError::InternalServerError
+        })?
+        .ok_or(AppError::NotFound)?;
+
+    if community_post.added_by != claims.sub
+        && !load_member(&state, &community_id, &claims.sub)
+            .await?
+            .is_some_and(|member| member.is_moderator())
+    {
+        return Err(AppError::Forbidden);
+    }
+
+    let removed = state
+        .community_service
+        .remove_post(&community_id, &post_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to remove community post: {}", e);
+            AppError::InternalServerError
+        })?;
+    if !removed {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(Json(ApiResponse::success("Post removed".to_string())))
+}
+
+// PUT /communities/:community_id/bans/:user_id - Moderators only
+pub async fn ban_community_member(
+    State(state): State<AppState>,
+    Path((community_id, user_id)): Path<(String, String)>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<String>>, AppError> {
+    let actor = load_as_moderator(&state, &community_id, &claims).await?;
+    let target = load_member(&state, &community_id, &user_id).await?;
+    if actor.user_id == user_id || !can_moderate(&actor, target.as_ref()) {
+        return Err(AppError::Forbidden);
+    }
+
+    state
+        .community_service
+        .ban(&community_id, &user_id, &claims.sub)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to ban community member: {}", e);
+            AppError::InternalServerError
+        })?;
+
+    Ok(Json(ApiResponse::success("Member banned".to_string())))
+}
+
+// DELETE /communities/:community_id/bans/:user_id - Moderators only
+pub async fn unban_community_member(
+    State(state): State<AppState>,
+    Path((community_id, user_id)): Path<(String, String)>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<String>>, AppError> {
+    load_as_moderator(&state, &community_id, &claims).await?;
+
+    let unbanned = state
+        .community_service
+        .unban(&community_id, &user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to unban community member: {}", e);
+            AppError::InternalServerError
+        })?;
+    if !unbanned {
+        return Err(AppError::NotFound);
+    }
+
+    Ok(Json(ApiResponse::success("Member unbanned".to_string())))
+}
+
+// PUT /communities/:community_id/mutes/:user_id - Moderators only
+pub async fn mute_community_member(
+    State(state): State<AppState>,
+    Path((community_id, user_id)): Path<(String, String)>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<String>>, AppError> {
+    let actor = load_as_moderator(&state, &community_id, &claims).await?;
+    let target = load_member(&state, &community_id, &user_id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+    if actor.user_id == user_id || !can_moderate(&actor, Some(&target)) {
+        return Err(AppError::Forbidden);
+    }
+
+    if !set_muted(&state, &community_id, &user_id, true).await? {
+        return Err(AppError::NotFound);
+    }
+    Ok(Json(ApiResponse::success("Member muted".to_string())))
+}
+
+// DELETE /communities/:community_id/mutes/:user_id - Moderators only
+pub async fn unmute_community_member(
+    State(state): State<AppState>,
+    Path((community_id, user_id)): Path<(String, String)>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<String>>, AppError> {
+    load_as_moderator(&state, &community_id, &claims).await?;
+
+    if !set_muted(&state, &community_id, &user_id, false).await? {
+        return Err(AppError::NotFound);
+    }
+    Ok(Json(ApiResponse::success("Member unmuted".to_string())))
+}
+
+async fn load_community(state: &AppState, community_id: &str) -> Result<Community, AppError> {
+    state
+        .community_service
+        .get_community(community_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get community: {}", e);
+            AppError::InternalServerError
+        })?
+        .ok_or(AppError::NotFound)
+}
+
+async fn load_member(
+    state: &AppState,
+    community_id: &str,
+    user_id: &str,
+) -> Result<Option<CommunityMember>, AppError> {
+    state
+        .community_service
+        .get_member(community_id, user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get community member: {}", e);
+            AppError::InternalServerError
+        })
+}
+
+async fn is_banned(state: &AppState, community_id: &str, user_id: &str) -> Result<bool, AppError> {
+    state
+        .community_service
+        .is_banned(community_id, user_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to check community ban: {}", e);
+            AppError::InternalServerError
+        })
+}
+
+async fn notify_join_approved(state: &AppState, community_id: &str, user_id: &str) {
+    let Ok(community) = load_community(state, community_id).await else {
+        return;
+    };
+    let notification = NewNotification::new(
+        NotificationKind::JoinRequestApproved,
+        format!("Welcome to {}", community.name),
+        format!("Your request to join {} was approved.", community.name),
+    )
+    .with_link(format!("/communities/{}", community.id))
+    .with_metadata(serde_json::json!({ "community_id": community.id }));
+
+    if let Err(e) = state
+        .notification_service
+        .notify(user_id, notification)
+        .await
+    {
+        tracing::warn!("Failed to notify {} of join approval: {}", user_id, e);
+    }
+}
+
+/// Returns the caller's membership once the community exists and they moderate it.
+async fn load_as_moderator(
+    state: &AppState,
+    community_id: &str,
+    claims: &Claims,
+) -> Result<CommunityMember, AppError> {
+    load_community(state, community_id).await?;
+    match load_member(state, community_id, &claims.sub).await? {
+        Some(member) if member.is_moderator() => Ok(member),
+        _ => Err(AppError::Forbidden),
+    }
+}
+
+// Nobody can act on the owner, and only the owner can act on moderators
+fn can_moderate(actor: &CommunityMember, target: Option<&CommunityMember>) -> bool {
+    match target {
+        Some(target) if target.is_owner() => false,
+        Some(target) if target.is_moderator() => actor.is_owner(),
+        _ => true,
+    }
+}
+
+async fn set_moderator(
+    state: &AppState,
+    community_id: &str,
+    user_id: &str,
+    moderator: bool,
+) -> Result<bool, AppError> {
+    state
+        .community_service
+        .set_moderator(community_id, user_id, moderator)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update community moderators: {}", e);
+            AppError::InternalServerError
+        })
+}
+
+async fn set_muted(
+    state: &AppState,
+    community_id: &str,
+    user_id: &str,
+    muted: bool,
+) -> Result<bool, AppError> {
+    state
+        .community_service
+        .set_muted(community_id, user_id, muted)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update community mutes: {}", e);
+            AppError::InternalServerError
+        })
+}
+
+async fn set_pinned(
+    state: &AppState,
+    community_id: &str,
+    post_id: &str,
+    claims: &Claims,
+    pinned: bool,
+) -> Result<(), AppError> {
+    load_as_moderator(state, community_id, claims).await?;
+
+    let updated = state
+        .community_service
+        .set_post_pinned(community_id, post_id, pinned)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update pinned post: {}", e);
+            AppError::InternalServerError
+        })?;
+    if !updated {
+        return Err(AppError::NotFound);
+    }
+    Ok(())
+}
+
+async fn fan_out_community_post(state: AppState, community_id: String, post_id: String) {
+    let loaded = tokio::try_join!(
+        state.community_service.get_member_ids(&community_id),
+        state.post_service.get_post(&post_id),
+    );
+
+    let result = match loaded {
+        Ok((members, Some(post))) => {
+            state
+                .feed_service
+                .publish_to_community(&post, &community_id, members)
+                .await
+        }
+        Ok(_) => return,
+        Err(e) => Err(e),
+    };
+
+    if let Err(e) = result {
+        tracing::error!(
+            "Failed to fan out post {} to community {}: {}",
+            post_id,
+            community_id,
+            e
+        );
+    }
+}
@@ -0,0 +1,39 @@
+use super::chat::ApiResponse;
+use crate::models::postgres::email_outbox;
+use crate::{utils::errors::AppError, AppState};
+use axum::extract::{Path, Query, State};
+use axum::Json;
+use serde::Deserialize;
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+pub struct StuckEmailsQuery {
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+// GET /admin/email-outbox/stuck - Failed emails and ones still being retried,
+// with tokens redacted
+pub async fn list_stuck_emails(
+    State(state): State<AppState>,
+    Query(query): Query<StuckEmailsQuery>,
+) -> Result<Json<ApiResponse<Vec<email_outbox::Model>>>, AppError> {
+    let limit = query.limit.unwrap_or(20).min(100);
+    let offset = query.offset.unwrap_or(0);
+
+    let messages = state.email_outbox_service.list_stuck(limit, offset).await?;
+
+    Ok(Json(ApiResponse::success(
+        messages.into_iter().map(email_outbox::Model::redacted).collect(),
+    )))
+}
+
+// POST /admin/email-outbox/:id/retry - Requeue a failed email
+pub async fn retry_email(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<email_outbox::Model>>, AppError> {
+    let message = state.email_outbox_service.retry(id).await?;
+
+    Ok(Json(ApiResponse::success(message.redacted())))
+}
@@ -0,0 +1,166 @@
+use super::chat::{ApiResponse, CursorQuery};
+use crate::models::dynamodb::{Follow, FollowTargetType};
+use crate::repositories::{Page, ScanDirection};
+use crate::services::auth_service::Claims;
+use crate::services::follow_service::FollowStats;
+use crate::{utils::errors::AppError, AppState};
+use axum::extract::{Extension, Path, Query, State};
+use axum::Json;
+use uuid::Uuid;
+
+// POST /follows/:target_type/:target_id - Follow a player, team or organization
+pub async fn follow(
+    State(state): State<AppState>,
+    Path((target_type, target_id)): Path<(FollowTargetType, Uuid)>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<String>>, AppError> {
+    ensure_target_exists(&state, target_type, target_id).await?;
+    if target_type.as_str() == claims.user_type && target_id.to_string() == claims.sub {
+        return Err(AppError::Validation(
+            "You cannot follow yourself".to_string(),
+        ));
+    }
+
+    let created = state
+        .follow_service
+        .follow(
+            &claims.user_type,
+            &claims.sub,
+            target_type.as_str(),
+            &target_id.to_string(),
+        )
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Failed to follow {} {}: {}",
+                target_type.as_str(),
+                target_id,
+                e
+            );
+            AppError::InternalServerError
+        })?;
+
+    let message = if created {
+        "Followed successfully"
+    } else {
+        "Already following"
+    };
+    Ok(Json(ApiResponse::success(message.to_string())))
+}
+
+// DELETE /follows/:target_type/:target_id
+pub async fn unfollow(
+    State(state): State<AppState>,
+    Path((target_type, target_id)): Path<(FollowTargetType, Uuid)>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<String>>, AppError> {
+    let removed = state
+        .follow_service
+        .unfollow(
+            &claims.user_type,
+            &claims.sub,
+            target_type.as_str(),
+            &target_id.to_string(),
+        )
+        .await
+        .map_err(|e| {
+            tracing::error!(
+                "Failed to unfollow {} {}: {}",
+                target_type.as_str(),
+                target_id,
+                e
+            );
+            AppError::InternalServerError
+        })?;
+
+    let message = if removed {
+        "Unfollowed successfully"
+    } else {
+        "Not following"
+    };
+    Ok(Json(ApiResponse::success(message.to_string())))
+}
+
+// GET /follows/:target_type/:target_id - Follower/following counts
+pub async fn get_follow_stats(
+    State(state): State<AppState>,
+    Path((target_type, target_id)): Path<(FollowTargetType, Uuid)>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<FollowStats>>, AppError> {
+    let stats = state
+        .follow_service
+        .get_stats(target_type.as_str(), &target_id.to_string(), &claims.sub)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get follow stats: {}", e);
+            AppError::InternalServerError
+        })?;
+
+    Ok(Json(ApiResponse::success(stats)))
+}
+
+// GET /follows/:target_type/:target_id/followers - Newest first
+pub async fn get_followers(
+    State(state): State<AppState>,
+    Path((target_type, target_id)): Path<(FollowTargetType, Uuid)>,
+    Query(params): Query<CursorQuery>,
+) -> Result<Json<ApiResponse<Page<Follow>>>, AppError> {
+    let request = params
+        .page_request(ScanDirection::Backward)
+        .map_err(|_| AppError::Validation("Invalid cursor".to_string()))?;
+
+    let followers = state
+        .follow_service
+        .get_followers(target_type.as_str(), &target_id.to_string(), &request)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get followers: {}", e);
+            AppError::InternalServerError
+        })?;
+
+    Ok(Json(ApiResponse::success(followers)))
+}
+
+// GET /follows/:target_type/:target_id/following - What this player or organization follows
+pub async fn get_following(
+    State(state): State<AppState>,
+    Path((_, target_id)): Path<(FollowTargetType, Uuid)>,
+    Query(params): Query<CursorQuery>,
+) -> Result<Json<ApiResponse<Page<Follow>>>, AppError> {
+    let request = params
+        .page_request(ScanDirection::Forward)
+        .map_err(|_| AppError::Validation("Invalid cursor".to_string()))?;
+
+    let following = state
+        .follow_service
+        .get_following(&target_id.to_string(), &request)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get following: {}", e);
+            AppError::InternalServerError
+        })?;
+
+    Ok(Json(ApiResponse::success(following)))
+}
+
+async fn ensure_target_exists(
+    state: &AppState,
+    target_type: FollowTargetType,
+    target_id: Uuid,
+) -> Result<(), AppError> {
+    let exists = match target_type {
+        FollowTargetType::Player => state.player_service.get_by_id(target_id).await?.is_some(),
+        FollowTargetType::Team => state.team_service.get_by_id(target_id).await?.is_some(),
+        FollowTargetType::Organization => state
+            .organization_service
+            .get_by_id(target_id)
+            .await?
+            .is_some(),
+    };
+
+    if exists {
+        Ok(())
+    } else {
+        Err(AppError::NotFound)
+    }
+}
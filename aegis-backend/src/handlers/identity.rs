@@ -0,0 +1,262 @@
+use super::chat::ApiResponse;
+use crate::models::postgres::identity_verification;
+use crate::services::auth_service::Claims;
+use crate::{utils::errors::AppError, AppState};
+use axum::extract::{Extension, Multipart, Path, Query, State};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+const MAX_DOCUMENT_BYTES: usize = 10 * 1024 * 1024;
+const MAX_DOCUMENTS: usize = 4;
+const PRESIGNED_URL_TTL_SECS: u64 = 300;
+
+#[derive(Deserialize)]
+pub struct VerificationQueueQuery {
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub struct RejectVerificationRequest {
+    pub reason: String,
+}
+
+#[derive(Serialize)]
+pub struct VerificationReviewResponse {
+    pub verification: identity_verification::Model,
+    pub document_urls: Vec<String>,
+}
+
+// POST /players/me/identity - Submit ID documents for review
+pub async fn submit_identity_verification(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    mut multipart: Multipart,
+) -> Result<Json<ApiResponse<identity_verification::Model>>, AppError> {
+    let player_id = Uuid::parse_str(&claims.sub)?;
+
+    let mut document_type = None;
+    let mut documents = Vec::new();
+
+    while let Some(field) = multipart.next_field().await.map_err(|e| {
+        tracing::error!("Multipart field error: {}", e);
+        AppError::Validation("Invalid multipart data".to_string())
+    })? {
+        match field.name().unwrap_or("") {
+            "document_type" => {
+                document_type = Some(
+                    field
+                        .text()
+                        .await
+                        .map_err(|_| AppError::Validation("Invalid document type".to_string()))?,
+                );
+            }
+            "file" => {
+                let filename = field.file_name().unwrap_or("document").to_string();
+                let data = field.bytes().await.map_err(|e| {
+                    tracing::error!("Failed to read file bytes: {}", e);
+                    AppError::Validation("Failed to read file".to_string())
+                })?;
+
+                if data.is_empty() {
+                    return Err(AppError::Validation("File is empty".to_string()));
+                }
+                if data.len() > MAX_DOCUMENT_BYTES {
+                    return Err(AppError::Validation("File exceeds 10MB".to_string()));
+                }
+                if documents.len() == MAX_DOCUMENTS {
+                    return Err(AppError::Validation(format!(
+                        "At most {} documents per submission",
+                        MAX_DOCUMENTS
+                    )));
+                }
+                documents.push((filename, data));
+            }
+            _ => {}
+        }
+    }
+
+    let document_type = document_type
+        .ok_or_else(|| AppError::Validation("document_type is required".to_string()))?;
+
+    // Nothing is uploaded for a submission that would be refused anyway
+    state
+        .identity_verification_service
+        .check_submission(player_id, &document_type, documents.len())
+        .await?;
+
+    let mut document_keys = Vec::with_capacity(documents.len());
+    for (filename, data) in documents {
+        let key = state
+            .s3_service
+            .upload_identity_document(&player_id.to_string(), &filename, data.to_vec())
+            .await
+            .map_err(|e| {
+                tracing::error!("❌ Identity document upload failed: {}", e);
+                AppError::InternalServerError
+            })?;
+        document_keys.push(key);
+    }
+
+    let verification = state
+        .identity_verification_service
+        .submit(player_id, document_type, document_keys)
+        .await?;
+
+    let _ = state
+        .audit_service
+        .log_action(
+            Some(player_id),
+            Some("player".to_string()),
+            Some(Uuid::parse_str(&claims.session_id)?),
+            "identity_submit".to_string(),
+            Some("identity_verification".to_string()),
+            Some(verification.id),
+            None,
+            None,
+            true,
+            None,
+            None,
+            None,
+        )
+        .await;
+
+    Ok(Json(ApiResponse::success(verification)))
+}
+
+// GET /players/me/identity - Latest verification request for the current player
+pub async fn get_my_identity_verification(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<Option<identity_verification::Model>>>, AppError> {
+    let player_id = Uuid::parse_str(&claims.sub)?;
+    let verification = state
+        .identity_verification_service
+        .get_latest_for_player(player_id)
+        .await?;
+
+    Ok(Json(ApiResponse::success(verification)))
+}
+
+// GET /admin/identity-verifications - Pending review queue
+pub async fn list_identity_verification_queue(
+    State(state): State<AppState>,
+    Query(query): Query<VerificationQueueQuery>,
+) -> Result<Json<ApiResponse<Vec<identity_verification::Model>>>, AppError> {
+    let limit = query.limit.unwrap_or(20).min(100);
+    let offset = query.offset.unwrap_or(0);
+
+    let queue = state
+        .identity_verification_service
+        .get_pending_queue(limit, offset)
+        .await?;
+
+    Ok(Json(ApiResponse::success(queue)))
+}
+
+// GET /admin/identity-verifications/:id - Review a submission with short-lived document links
+pub async fn get_identity_verification(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<VerificationReviewResponse>>, AppError> {
+    let verification = state
+        .identity_verification_service
+        .get_by_id(id)
+        .await?
+        .ok_or(AppError::NotFound)?;
+
+    let mut document_urls = Vec::with_capacity(verification.document_keys.len());
+    for key in &verification.document_keys {
+        let url = state
+            .s3_service
+            .get_presigned_url(key, PRESIGNED_URL_TTL_SECS)
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to generate presigned URL: {}", e);
+                AppError::InternalServerError
+            })?;
+        document_urls.push(url);
+    }
+
+    Ok(Json(ApiResponse::success(VerificationReviewResponse {
+        verification,
+        document_urls,
+    })))
+}
+
+// POST /admin/identity-verifications/:id/approve
+pub async fn approve_identity_verification(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<identity_verification::Model>>, AppError> {
+    let admin_id = Uuid::parse_str(&claims.sub)?;
+    let verification = state
+        .identity_verification_service
+        .approve(id, admin_id)
+        .await?;
+
+    log_review(&state, &claims, admin_id, "identity_approve", id, None).await;
+
+    Ok(Json(ApiResponse::success(verification)))
+}
+
+// POST /admin/identity-verifications/:id/reject
+pub async fn reject_identity_verification(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<RejectVerificationRequest>,
+) -> Result<Json<ApiResponse<identity_verification::Model>>, AppError> {
+    if payload.reason.trim().is_empty() {
+        return Err(AppError::Validation(
+            "Rejection reason is required".to_string(),
+        ));
+    }
+
+    let admin_id = Uuid::parse_str(&claims.sub)?;
+    let verification = state
+        .identity_verification_service
+        .reject(id, admin_id, payload.reason.clone())
+        .await?;
+
+    log_review(
+        &state,
+        &claims,
+        admin_id,
+        "identity_reject",
+        id,
+        Some(serde_json::json!({ "reason": payload.reason })),
+    )
+    .await;
+
+    Ok(Json(ApiResponse::success(verification)))
+}
+
+async fn log_review(
+    state: &AppState,
+    claims: &Claims,
+    admin_id: Uuid,
+    action: &str,
+    verification_id: Uuid,
+    details: Option<serde_json::Value>,
+) {
+    let _ = state
+        .audit_service
+        .log_action(
+            Some(admin_id),
+            Some("admin".to_string()),
+            Uuid::parse_str(&claims.session_id).ok(),
+            action.to_string(),
+            Some("identity_verification".to_string()),
+            Some(verification_id),
+            None,
+            None,
+            true,
+            None,
+            None,
+            details,
+        )
+        .await;
+}
@@ -1,11 +1,26 @@
+pub mod activity;
+pub mod admins;
+pub mod api_keys;
 pub mod auth;
 pub mod chat;
+pub mod chat_socket;
 pub mod communities;
+pub mod email_outbox;
+pub mod follows;
+pub mod identity;
+pub mod notifications;
 pub mod players;
 pub mod post;
 pub mod tournaments;
+pub mod two_factor;
 pub mod uploads;
+pub mod webhooks;
 
+pub use activity::{get_player_activity, get_recent_activity};
+pub use admins::{
+    create_admin, get_admin, get_permission_catalogue, list_admins, update_admin_permissions,
+};
+pub use api_keys::{create_api_key, list_api_keys, revoke_api_key};
 pub use auth::{
     forgot_password, login as auth_login, logout as auth_logout, refresh_token,
     register as auth_register, reset_password, revoke_all_sessions, send_verification_email,
@@ -13,14 +28,32 @@ pub use auth::{
 };
 
 pub use chat::*;
+pub use chat_socket::register_chat_namespace;
 pub use communities::*;
+pub use email_outbox::{list_stuck_emails, retry_email};
+pub use follows::{follow, get_follow_stats, get_followers, get_following, unfollow};
+pub use identity::*;
+pub use notifications::{
+    get_notification_preferences, get_notifications, get_unread_notification_count,
+    mark_all_notifications_read, mark_notification_read, unsubscribe_from_notifications,
+    update_notification_preferences,
+};
 pub use players::{
     get_current_player, get_current_player_profile, get_player_by_id, get_player_by_username,
-    list_players, update_player_profile,
+    list_players, request_withdrawal, update_player_profile,
 };
 
 pub use post::*;
+pub use tournaments::{complete_battle, schedule_battle, update_standings};
+pub use two_factor::{
+    answer_two_factor_challenge, begin_challenge_enrolment, begin_two_factor_enrolment,
+    confirm_two_factor_enrolment, disable_two_factor, get_two_factor_status,
+    list_two_factor_policies, regenerate_recovery_codes, update_two_factor_policy,
+};
 pub use uploads::*;
+pub use webhooks::{
+    create_webhook, delete_webhook, list_webhook_deliveries, list_webhooks, send_test_webhook,
+};
 
 use axum::{http::StatusCode, Json};
 use serde_json::{json, Value};
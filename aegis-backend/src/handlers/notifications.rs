@@ -0,0 +1,198 @@
+use super::chat::{ApiResponse, CursorQuery};
+use crate::models::dynamodb::{
+    Notification, NotificationCategory, NotificationChannel, NotificationPreferences, QuietHours,
+};
+use crate::repositories::{Page, ScanDirection};
+use crate::services::auth_service::Claims;
+use crate::services::notification_service::UNSUBSCRIBE_TOKEN_PREFIX;
+use crate::{utils::errors::AppError, AppState};
+use axum::extract::{Extension, Path, Query, State};
+use axum::Json;
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Serialize)]
+pub struct UnreadCount {
+    pub unread: i64,
+}
+
+#[derive(Serialize)]
+pub struct MarkedRead {
+    pub marked: usize,
+}
+
+/// Replaces the caller's preferences; categories left out use their defaults.
+#[derive(Deserialize)]
+pub struct UpdateNotificationPreferencesRequest {
+    #[serde(default)]
+    pub channels: BTreeMap<NotificationCategory, Vec<NotificationChannel>>,
+    #[serde(default = "utc")]
+    pub timezone: Tz,
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>,
+}
+
+fn utc() -> Tz {
+    Tz::UTC
+}
+
+// GET /notifications - The caller's inbox, newest first
+pub async fn get_notifications(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<CursorQuery>,
+) -> Result<Json<ApiResponse<Page<Notification>>>, AppError> {
+    let request = params
+        .page_request(ScanDirection::Backward)
+        .map_err(|_| AppError::Validation("Invalid cursor".to_string()))?;
+
+    let inbox = state
+        .notification_service
+        .get_inbox(&claims.sub, &request)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get notifications: {}", e);
+            AppError::InternalServerError
+        })?;
+
+    Ok(Json(ApiResponse::success(inbox)))
+}
+
+// GET /notifications/unread-count
+pub async fn get_unread_notification_count(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<UnreadCount>>, AppError> {
+    let unread = state
+        .notification_service
+        .unread_count(&claims.sub)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to count unread notifications: {}", e);
+            AppError::InternalServerError
+        })?;
+
+    Ok(Json(ApiResponse::success(UnreadCount { unread })))
+}
+
+// POST /notifications/:notification_id/read
+pub async fn mark_notification_read(
+    State(state): State<AppState>,
+    Path(notification_id): Path<String>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<Notification>>, AppError> {
+    let notification = state
+        .notification_service
+        .mark_read(&claims.sub, &notification_id)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to mark notification read: {}", e);
+            AppError::InternalServerError
+        })?
+        .ok_or(AppError::NotFound)?;
+
+    Ok(Json(ApiResponse::success(notification)))
+}
+
+// POST /notifications/read-all
+pub async fn mark_all_notifications_read(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<MarkedRead>>, AppError> {
+    let marked = state
+        .notification_service
+        .mark_all_read(&claims.sub)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to mark notifications read: {}", e);
+            AppError::InternalServerError
+        })?;
+
+    Ok(Json(ApiResponse::success(MarkedRead { marked })))
+}
+
+// GET /notifications/preferences
+pub async fn get_notification_preferences(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<NotificationPreferences>>, AppError> {
+    let preferences = state
+        .notification_service
+        .get_preferences(&claims.sub)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to get notification preferences: {}", e);
+            AppError::InternalServerError
+        })?;
+
+    Ok(Json(ApiResponse::success(preferences)))
+}
+
+// PUT /notifications/preferences
+pub async fn update_notification_preferences(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<UpdateNotificationPreferencesRequest>,
+) -> Result<Json<ApiResponse<NotificationPreferences>>, AppError> {
+    if payload
+        .quiet_hours
+        .as_ref()
+        .is_some_and(|quiet| quiet.start == quiet.end)
+    {
+        return Err(AppError::Validation(
+            "Quiet hours must start and end at different times".to_string(),
+        ));
+    }
+
+    let mut channels = payload.channels;
+    for enabled in channels.values_mut() {
+        enabled.sort();
+        enabled.dedup();
+    }
+
+    let preferences = state
+        .notification_service
+        .update_preferences(&claims.sub, |preferences| {
+            preferences.channels = channels.clone();
+            preferences.timezone = payload.timezone;
+            preferences.quiet_hours = payload.quiet_hours.clone();
+            true
+        })
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to update notification preferences: {}", e);
+            AppError::InternalServerError
+        })?;
+
+    Ok(Json(ApiResponse::success(preferences)))
+}
+
+// POST /notifications/unsubscribe/:token - From an email footer; no login needed
+pub async fn unsubscribe_from_notifications(
+    State(state): State<AppState>,
+    Path(token): Path<String>,
+) -> Result<Json<ApiResponse<String>>, AppError> {
+    let claims = state.auth_service.verify_temp_token(&token)?;
+    let category = claims
+        .token_type
+        .strip_prefix(UNSUBSCRIBE_TOKEN_PREFIX)
+        .and_then(NotificationCategory::parse)
+        .ok_or_else(|| AppError::Validation("Invalid token type".to_string()))?;
+
+    let unsubscribed = state
+        .notification_service
+        .unsubscribe(&claims.sub, category)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to unsubscribe {}: {}", claims.sub, e);
+            AppError::InternalServerError
+        })?;
+
+    let message = if unsubscribed {
+        format!("Unsubscribed from {} emails", category.as_str())
+    } else {
+        format!("Already unsubscribed from {} emails", category.as_str())
+    };
+    Ok(Json(ApiResponse::success(message)))
+}
@@ -54,6 +54,7 @@ pub struct PlayerProfileResponse {
     pub username: String,
     pub email: String,
     pub verified: bool,
+    pub identity_verified: bool,
 
     // Profile Information
     pub in_game_name: Option<String>,
@@ -184,6 +185,7 @@ pub async fn get_current_player_profile(
         username: player.username,
         email: player.email,
         verified: player.verified,
+        identity_verified: player.identity_verified,
         in_game_name: player.in_game_name,
         real_name: player.real_name,
         bio: player.bio,
@@ -236,20 +238,6 @@ pub async fn update_player_profile(
         }
     }
 
-    // Rate limiting for profile updates
-    if let Some(ip) = &ip_address {
-        state
-            .rate_limit_service
-            .check_rate_limit(
-                ip.clone(),
-                "ip".to_string(),
-                "profile_update".to_string(),
-                10, // 10 updates per hour
-                60,
-            )
-            .await?;
-    }
-
     let updated_player = state
         .player_service
         .update_profile(player_id, payload)
@@ -279,6 +267,7 @@ pub async fn update_player_profile(
         username: updated_player.username,
         email: updated_player.email,
         verified: updated_player.verified,
+        identity_verified: updated_player.identity_verified,
         in_game_name: updated_player.in_game_name,
         real_name: updated_player.real_name,
         bio: updated_player.bio,
@@ -347,3 +336,47 @@ pub async fn list_players(
         }
     })))
 }
+
+#[derive(Deserialize)]
+pub struct WithdrawalRequest {
+    pub amount: Decimal,
+    pub currency: Option<String>,
+}
+
+// POST /players/me/withdrawals - Request a prize payout (identity verification required)
+pub async fn request_withdrawal(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<WithdrawalRequest>,
+) -> Result<Json<crate::models::postgres::transaction::Model>, AppError> {
+    let player_id = Uuid::parse_str(&claims.sub)?;
+
+    let withdrawal = state
+        .transaction_service
+        .request_withdrawal(
+            player_id,
+            payload.amount,
+            payload.currency.unwrap_or_else(|| "USD".to_string()),
+        )
+        .await?;
+
+    let _ = state
+        .audit_service
+        .log_action(
+            Some(player_id),
+            Some("player".to_string()),
+            Some(Uuid::parse_str(&claims.session_id)?),
+            "withdrawal_request".to_string(),
+            Some("transaction".to_string()),
+            Some(withdrawal.id),
+            None,
+            None,
+            true,
+            None,
+            None,
+            None,
+        )
+        .await;
+
+    Ok(Json(withdrawal))
+}
@@ -1,15 +1,21 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Extension, Path, Query, State},
     http::StatusCode,
     response::Json,
 };
 use serde::Deserialize;
+use uuid::Uuid;
 use crate::AppState;
-use super::chat::ApiResponse;
+use crate::middleware::ActiveAdmin;
+use crate::models::dynamodb::{Post, PostComment};
+use crate::repositories::{Page, PageRequest, ScanDirection};
+use crate::services::auth_service::Claims;
+use crate::services::feed_service::FeedEntry;
+use crate::services::post_service::{CommentSort, PostView};
+use super::chat::{ApiResponse, CursorQuery};
 
 #[derive(Deserialize)]
 pub struct CreatePostRequest {
-    pub author: String,
     pub title: String,
     pub content: String,
     pub post_type: String,
@@ -18,22 +24,42 @@ pub struct CreatePostRequest {
 
 #[derive(Deserialize)]
 pub struct AddCommentRequest {
-    pub author: String,
     pub content: String,
+    pub parent_id: Option<String>,     // Reply to a top-level comment
+}
+
+#[derive(Deserialize)]
+pub struct EditCommentRequest {
+    pub content: String,
+}
+
+#[derive(Deserialize)]
+pub struct CommentListQuery {
+    pub limit: Option<i32>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub sort: Option<CommentSort>,
 }
 
 pub async fn create_post(
     State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
     Json(payload): Json<CreatePostRequest>,
 ) -> Result<Json<ApiResponse<String>>, StatusCode> {
+    let author_id = Uuid::parse_str(&claims.sub).map_err(|_| StatusCode::BAD_REQUEST)?;
     match state.post_service.create_post(
-        payload.author,
+        author_id.to_string(), // Use JWT user ID
         payload.title,
         payload.content,
         payload.post_type,
         payload.tags,
     ).await {
-        Ok(post_id) => Ok(Json(ApiResponse::success(post_id))),
+        Ok(post) => {
+            let post_id = post.id.clone();
+            // Timeline writes happen off the request path
+            tokio::spawn(fan_out_post(state, post));
+            Ok(Json(ApiResponse::success(post_id)))
+        }
         Err(e) => {
             tracing::error!("Failed to create post: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
@@ -44,8 +70,9 @@ pub async fn create_post(
 pub async fn get_post(
     State(state): State<AppState>,
     Path(post_id): Path<String>,
-) -> Result<Json<ApiResponse<crate::models::dynamodb::Post>>, StatusCode> {
-    match state.post_service.get_post(&post_id).await {
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<PostView>>, StatusCode> {
+    match state.post_service.get_post_view(&post_id, &claims.sub).await {
         Ok(Some(post)) => Ok(Json(ApiResponse::success(post))),
         Ok(None) => Err(StatusCode::NOT_FOUND),
         Err(e) => {
@@ -58,12 +85,27 @@ pub async fn get_post(
 pub async fn add_comment(
     State(state): State<AppState>,
     Path(post_id): Path<String>,
+    Extension(claims): Extension<Claims>,
     Json(payload): Json<AddCommentRequest>,
 ) -> Result<Json<ApiResponse<String>>, StatusCode> {
+    let author_id = Uuid::parse_str(&claims.sub).map_err(|_| StatusCode::BAD_REQUEST)?;
+    if payload.content.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    ensure_post_exists(&state, &post_id).await?;
+
+    if let Some(parent_id) = &payload.parent_id {
+        let parent = load_comment(&state, &post_id, parent_id).await?;
+        if parent.parent_id.is_some() || parent.deleted {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
     match state.post_service.add_comment(
         post_id,
-        payload.author,
+        author_id.to_string(),
         payload.content,
+        payload.parent_id,
     ).await {
         Ok(comment_id) => Ok(Json(ApiResponse::success(comment_id))),
         Err(e) => {
@@ -73,11 +115,22 @@ pub async fn add_comment(
     }
 }
 
+// GET /posts/:post_id/comments?sort=newest|top - Top-level comments
 pub async fn get_comments(
     State(state): State<AppState>,
     Path(post_id): Path<String>,
-) -> Result<Json<ApiResponse<Vec<crate::models::dynamodb::PostComment>>>, StatusCode> {
-    match state.post_service.get_comments(&post_id).await {
+    Query(params): Query<CommentListQuery>,
+) -> Result<Json<ApiResponse<Page<PostComment>>>, StatusCode> {
+    let request = PageRequest::from_cursors(
+        params.limit,
+        params.before.as_deref(),
+        params.after.as_deref(),
+        ScanDirection::Backward,
+    )
+    .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let sort = params.sort.unwrap_or_default();
+
+    match state.post_service.get_comments(&post_id, sort, &request).await {
         Ok(comments) => Ok(Json(ApiResponse::success(comments))),
         Err(e) => {
             tracing::error!("Failed to get comments: {}", e);
@@ -86,12 +139,106 @@ pub async fn get_comments(
     }
 }
 
+// GET /posts/:post_id/comments/:comment_id/replies - Oldest first
+pub async fn get_comment_replies(
+    State(state): State<AppState>,
+    Path((post_id, comment_id)): Path<(String, String)>,
+    Query(params): Query<CursorQuery>,
+) -> Result<Json<ApiResponse<Page<PostComment>>>, StatusCode> {
+    let request = params.page_request(ScanDirection::Forward)?;
+    match state.post_service.get_replies(&post_id, &comment_id, &request).await {
+        Ok(replies) => Ok(Json(ApiResponse::success(replies))),
+        Err(e) => {
+            tracing::error!("Failed to get replies: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// PUT /posts/:post_id/comments/:comment_id - Author only
+pub async fn edit_comment(
+    State(state): State<AppState>,
+    Path((post_id, comment_id)): Path<(String, String)>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<EditCommentRequest>,
+) -> Result<Json<ApiResponse<String>>, StatusCode> {
+    if payload.content.trim().is_empty() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let comment = load_comment(&state, &post_id, &comment_id).await?;
+    if comment.author != claims.sub {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    match state.post_service.edit_comment(&post_id, &comment_id, &payload.content).await {
+        Ok(true) => Ok(Json(ApiResponse::success("Comment updated successfully".to_string()))),
+        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to edit comment: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// DELETE /posts/:post_id/comments/:comment_id - Comment author or post author
+pub async fn delete_comment(
+    State(state): State<AppState>,
+    Path((post_id, comment_id)): Path<(String, String)>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<String>>, StatusCode> {
+    let comment = load_comment(&state, &post_id, &comment_id).await?;
+
+    if comment.author != claims.sub {
+        let post = match state.post_service.get_post(&post_id).await {
+            Ok(Some(post)) => post,
+            Ok(None) => return Err(StatusCode::NOT_FOUND),
+            Err(e) => {
+                tracing::error!("Failed to get post: {}", e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        };
+        if post.author != claims.sub {
+            return Err(StatusCode::FORBIDDEN);
+        }
+    }
+
+    match state.post_service.delete_comment(&comment, &claims.sub).await {
+        Ok(true) => Ok(Json(ApiResponse::success("Comment deleted successfully".to_string()))),
+        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to delete comment: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+// DELETE /admin/posts/:post_id/comments/:comment_id - Admins holding content.moderate
+pub async fn moderate_delete_comment(
+    State(state): State<AppState>,
+    Path((post_id, comment_id)): Path<(String, String)>,
+    ActiveAdmin(admin): ActiveAdmin,
+) -> Result<Json<ApiResponse<String>>, StatusCode> {
+    let comment = load_comment(&state, &post_id, &comment_id).await?;
+    match state.post_service.delete_comment(&comment, &admin.id.to_string()).await {
+        Ok(true) => Ok(Json(ApiResponse::success("Comment deleted successfully".to_string()))),
+        Ok(false) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to delete comment: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
 pub async fn like_post(
     State(state): State<AppState>,
     Path(post_id): Path<String>,
+    Extension(claims): Extension<Claims>,
 ) -> Result<Json<ApiResponse<String>>, StatusCode> {
-    match state.post_service.like_post(&post_id).await {
-        Ok(_) => Ok(Json(ApiResponse::success("Post liked successfully".to_string()))),
+    ensure_post_exists(&state, &post_id).await?;
+    match state.post_service.like_post(&post_id, &claims.sub).await {
+        Ok(true) => Ok(Json(ApiResponse::success("Post liked successfully".to_string()))),
+        Ok(false) => Ok(Json(ApiResponse::success("Post already liked".to_string()))),
         Err(e) => {
             tracing::error!("Failed to like post: {}", e);
             Err(StatusCode::INTERNAL_SERVER_ERROR)
@@ -99,11 +246,28 @@ pub async fn like_post(
     }
 }
 
+pub async fn unlike_post(
+    State(state): State<AppState>,
+    Path(post_id): Path<String>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<String>>, StatusCode> {
+    ensure_post_exists(&state, &post_id).await?;
+    match state.post_service.unlike_post(&post_id, &claims.sub).await {
+        Ok(true) => Ok(Json(ApiResponse::success("Post unliked successfully".to_string()))),
+        Ok(false) => Ok(Json(ApiResponse::success("Post was not liked".to_string()))),
+        Err(e) => {
+            tracing::error!("Failed to unlike post: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
 pub async fn get_posts_by_author(
     State(state): State<AppState>,
     Path(author_id): Path<String>,
-) -> Result<Json<ApiResponse<Vec<crate::models::dynamodb::Post>>>, StatusCode> {
-    match state.post_service.get_posts_by_author(&author_id).await {
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<Vec<PostView>>>, StatusCode> {
+    match state.post_service.get_posts_by_author(&author_id, &claims.sub).await {
         Ok(posts) => Ok(Json(ApiResponse::success(posts))),
         Err(e) => {
             tracing::error!("Failed to get posts by author: {}", e);
@@ -111,3 +275,79 @@ pub async fn get_posts_by_author(
         }
     }
 }
+
+// GET /feed - Home timeline: followed players, joined communities and team
+pub async fn get_home_feed(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Query(params): Query<CursorQuery>,
+) -> Result<Json<ApiResponse<Page<FeedEntry>>>, StatusCode> {
+    let request = params.page_request(ScanDirection::Backward)?;
+    match state.feed_service.get_home_feed(&claims.sub, &request).await {
+        Ok(feed) => Ok(Json(ApiResponse::success(feed))),
+        Err(e) => {
+            tracing::error!("Failed to get home feed: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn fan_out_post(state: AppState, post: Post) {
+    let team = match team_roster(&state, &post.author).await {
+        Ok(team) => team,
+        Err(e) => {
+            tracing::warn!("Failed to load team for post fan-out: {}", e);
+            None
+        }
+    };
+
+    if let Err(e) = state.feed_service.publish_post(&post, team).await {
+        tracing::error!("Failed to fan out post {}: {}", post.id, e);
+    }
+}
+
+async fn team_roster(
+    state: &AppState,
+    player_id: &str,
+) -> Result<Option<(String, Vec<String>)>, crate::utils::errors::AppError> {
+    let player_id = Uuid::parse_str(player_id)?;
+    let Some(team_id) = state
+        .player_service
+        .get_by_id(player_id)
+        .await?
+        .and_then(|p| p.team_id)
+    else {
+        return Ok(None);
+    };
+
+    let roster = state
+        .team_service
+        .get_roster(team_id)
+        .await?
+        .into_iter()
+        .map(|p| p.id.to_string())
+        .collect();
+    Ok(Some((team_id.to_string(), roster)))
+}
+
+async fn ensure_post_exists(state: &AppState, post_id: &str) -> Result<(), StatusCode> {
+    match state.post_service.get_post(post_id).await {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to get post: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn load_comment(state: &AppState, post_id: &str, comment_id: &str) -> Result<PostComment, StatusCode> {
+    match state.post_service.get_comment(post_id, comment_id).await {
+        Ok(Some(comment)) => Ok(comment),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            tracing::error!("Failed to get comment: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
@@ -1,6 +1,13 @@
+use super::chat::ApiResponse;
+use crate::models::postgres::{battle, tournament};
+use crate::services::auth_service::Claims;
+use crate::{utils::errors::AppError, AppState};
+use axum::extract::{Extension, Path};
 use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
 use serde_json::{json, Value};
-use crate::AppState;
+use uuid::Uuid;
 
 pub async fn get_tournaments(
     State(_state): State<AppState>,
@@ -10,4 +17,100 @@ pub async fn get_tournaments(
         "total": 0
     })))
 }
-    
\ No newline at end of file
+
+#[derive(Deserialize)]
+pub struct ScheduleBattleRequest {
+    pub battle_number: i32,
+    pub scheduled_start_time: DateTime<Utc>,
+    pub map: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CompleteBattleRequest {
+    pub battle_stats: Value,
+    pub winning_team_id: Option<Uuid>,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateStandingsRequest {
+    pub standings: Value,
+}
+
+// POST /organizations/me/tournaments/:tournament_id/battles
+pub async fn schedule_battle(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(tournament_id): Path<Uuid>,
+    Json(payload): Json<ScheduleBattleRequest>,
+) -> Result<Json<ApiResponse<battle::Model>>, AppError> {
+    let organization_id = Uuid::parse_str(&claims.sub)?;
+    let tournament = state
+        .tournament_service
+        .get_organized_by(organization_id, tournament_id)
+        .await?;
+    if payload.battle_number < 1 {
+        return Err(AppError::Validation(
+            "Battle number must be positive".to_string(),
+        ));
+    }
+
+    let battle = state
+        .battle_service
+        .schedule_battle(
+            tournament.id,
+            payload.battle_number,
+            payload.scheduled_start_time,
+            payload.map,
+        )
+        .await?;
+
+    Ok(Json(ApiResponse::success(battle)))
+}
+
+// POST /organizations/me/tournaments/:tournament_id/battles/:battle_id/complete
+pub async fn complete_battle(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path((tournament_id, battle_id)): Path<(Uuid, Uuid)>,
+    Json(payload): Json<CompleteBattleRequest>,
+) -> Result<Json<ApiResponse<battle::Model>>, AppError> {
+    let organization_id = Uuid::parse_str(&claims.sub)?;
+    let tournament = state
+        .tournament_service
+        .get_organized_by(organization_id, tournament_id)
+        .await?;
+    state
+        .battle_service
+        .get_by_id(battle_id)
+        .await?
+        .filter(|battle| battle.tournament == tournament.id)
+        .ok_or(AppError::NotFound)?;
+
+    let battle = state
+        .battle_service
+        .complete_battle(battle_id, payload.battle_stats, payload.winning_team_id)
+        .await?;
+
+    Ok(Json(ApiResponse::success(battle)))
+}
+
+// PUT /organizations/me/tournaments/:tournament_id/standings
+pub async fn update_standings(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(tournament_id): Path<Uuid>,
+    Json(payload): Json<UpdateStandingsRequest>,
+) -> Result<Json<ApiResponse<tournament::Model>>, AppError> {
+    let organization_id = Uuid::parse_str(&claims.sub)?;
+    let tournament = state
+        .tournament_service
+        .get_organized_by(organization_id, tournament_id)
+        .await?;
+
+    let tournament = state
+        .tournament_service
+        .update_standings(tournament.id, payload.standings)
+        .await?;
+
+    Ok(Json(ApiResponse::success(tournament)))
+}
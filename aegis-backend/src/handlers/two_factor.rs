@@ -0,0 +1,350 @@
+use super::auth::{
+    extract_client_info, find_account, start_session, Account, AuthResponse, TWO_FACTOR_CHALLENGE,
+    TWO_FACTOR_ENROLMENT,
+};
+use super::chat::ApiResponse;
+use crate::middleware::rate_limit::{user_key, TWO_FACTOR};
+use crate::models::postgres::two_factor_policy;
+use crate::services::auth_service::{Claims, TempTokenClaims};
+use crate::services::two_factor_service::Enrolment;
+use crate::{utils::errors::AppError, AppState};
+use axum::extract::{ConnectInfo, Extension, Path, State};
+use axum::http::HeaderMap;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use uuid::Uuid;
+
+#[derive(Serialize)]
+pub struct TwoFactorStatus {
+    pub enabled: bool,
+    /// Enrolment was started but no code has been confirmed yet
+    pub pending: bool,
+    /// The caller's role requires two-factor sign-in
+    pub required: bool,
+    pub recovery_codes_remaining: usize,
+}
+
+#[derive(Deserialize)]
+pub struct TwoFactorCodeRequest {
+    /// A TOTP code, or a recovery code where one is accepted
+    pub code: String,
+}
+
+#[derive(Serialize)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ChallengeEnrolmentRequest {
+    pub challenge_token: String,
+}
+
+#[derive(Deserialize)]
+pub struct ChallengeRequest {
+    pub challenge_token: String,
+    pub code: String,
+}
+
+#[derive(Deserialize)]
+pub struct UpdateTwoFactorPolicyRequest {
+    pub required: bool,
+}
+
+async fn current_account(state: &AppState, claims: &Claims) -> Result<Account, AppError> {
+    let user_id = Uuid::parse_str(&claims.sub)?;
+    find_account(state, user_id, &claims.user_type)
+        .await?
+        .ok_or(AppError::Unauthorized)
+}
+
+// GET /auth/2fa - Two-factor state of the signed-in account
+pub async fn get_two_factor_status(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<TwoFactorStatus>>, AppError> {
+    let account = current_account(&state, &claims).await?;
+    let credential = state
+        .two_factor_service
+        .get(account.id, account.user_type.as_str())
+        .await?;
+    let required = state
+        .two_factor_service
+        .is_required(account.two_factor_role())
+        .await?;
+
+    Ok(Json(ApiResponse::success(TwoFactorStatus {
+        enabled: credential.as_ref().is_some_and(|c| c.is_enabled()),
+        pending: credential.as_ref().is_some_and(|c| !c.is_enabled()),
+        required,
+        recovery_codes_remaining: credential
+            .filter(|c| c.is_enabled())
+            .map_or(0, |c| c.recovery_code_hashes.len()),
+    })))
+}
+
+// POST /auth/2fa/enroll - New secret and provisioning URI for the QR code
+pub async fn begin_two_factor_enrolment(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<Enrolment>>, AppError> {
+    let account = current_account(&state, &claims).await?;
+    let enrolment = state
+        .two_factor_service
+        .begin_enrolment(account.id, account.user_type.as_str(), &account.info.email)
+        .await?;
+
+    Ok(Json(ApiResponse::success(enrolment)))
+}
+
+// POST /auth/2fa/confirm - Enable 2FA with the first code from the app
+pub async fn confirm_two_factor_enrolment(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<TwoFactorCodeRequest>,
+) -> Result<Json<ApiResponse<RecoveryCodesResponse>>, AppError> {
+    let account = current_account(&state, &claims).await?;
+    let recovery_codes = state
+        .two_factor_service
+        .confirm_enrolment(account.id, account.user_type.as_str(), &payload.code)
+        .await?;
+
+    log_two_factor_change(&state, &claims, "two_factor_enable").await;
+
+    Ok(Json(ApiResponse::success(RecoveryCodesResponse {
+        recovery_codes,
+    })))
+}
+
+// POST /auth/2fa/recovery-codes - Replace the recovery codes
+pub async fn regenerate_recovery_codes(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<TwoFactorCodeRequest>,
+) -> Result<Json<ApiResponse<RecoveryCodesResponse>>, AppError> {
+    let account = current_account(&state, &claims).await?;
+    let recovery_codes = state
+        .two_factor_service
+        .regenerate_recovery_codes(account.id, account.user_type.as_str(), &payload.code)
+        .await?;
+
+    log_two_factor_change(&state, &claims, "two_factor_recovery_codes").await;
+
+    Ok(Json(ApiResponse::success(RecoveryCodesResponse {
+        recovery_codes,
+    })))
+}
+
+// POST /auth/2fa/disable - Turn 2FA off, unless the caller's role requires it
+pub async fn disable_two_factor(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<TwoFactorCodeRequest>,
+) -> Result<Json<ApiResponse<()>>, AppError> {
+    let account = current_account(&state, &claims).await?;
+    if state
+        .two_factor_service
+        .is_required(account.two_factor_role())
+        .await?
+    {
+        return Err(AppError::Validation(
+            "Two-factor authentication is required for your role".to_string(),
+        ));
+    }
+
+    state
+        .two_factor_service
+        .disable(account.id, account.user_type.as_str(), &payload.code)
+        .await?;
+
+    log_two_factor_change(&state, &claims, "two_factor_disable").await;
+
+    Ok(Json(ApiResponse::success(())))
+}
+
+/// The account a login challenge was issued to.
+async fn challenged_account(
+    state: &AppState,
+    challenge_token: &str,
+) -> Result<(Account, TempTokenClaims), AppError> {
+    let challenge = state.auth_service.verify_temp_token(challenge_token)?;
+    if challenge.token_type != TWO_FACTOR_CHALLENGE && challenge.token_type != TWO_FACTOR_ENROLMENT
+    {
+        return Err(AppError::Unauthorized);
+    }
+
+    let user_id = Uuid::parse_str(&challenge.sub)?;
+    let account = find_account(state, user_id, &challenge.user_type)
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+    Ok((account, challenge))
+}
+
+// POST /auth/2fa/challenge/enroll - Enrol during login when the role requires 2FA
+pub async fn begin_challenge_enrolment(
+    State(state): State<AppState>,
+    Json(payload): Json<ChallengeEnrolmentRequest>,
+) -> Result<Json<ApiResponse<Enrolment>>, AppError> {
+    let (account, challenge) = challenged_account(&state, &payload.challenge_token).await?;
+    if challenge.token_type != TWO_FACTOR_ENROLMENT {
+        return Err(AppError::Unauthorized);
+    }
+
+    let enrolment = state
+        .two_factor_service
+        .begin_enrolment(account.id, account.user_type.as_str(), &account.info.email)
+        .await?;
+
+    Ok(Json(ApiResponse::success(enrolment)))
+}
+
+// POST /auth/2fa/challenge - Exchange a login challenge and a code for a session
+pub async fn answer_two_factor_challenge(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<ChallengeRequest>,
+) -> Result<(HeaderMap, Json<AuthResponse>), AppError> {
+    let (ip_address, user_agent) = extract_client_info(&headers, Some(addr));
+    let (account, challenge) = challenged_account(&state, &payload.challenge_token).await?;
+    let user_type = account.user_type.as_str();
+
+    // The route is limited per IP, which guesses spread over many addresses
+    // get around; the account's own bucket is the one its signed-in 2FA
+    // routes share
+    match state
+        .rate_limit_service
+        .check(&TWO_FACTOR, &user_key(user_type, &account.id.to_string()))
+        .await
+    {
+        Ok(decision) if !decision.allowed => return Err(AppError::RateLimited),
+        Ok(_) => {}
+        // Fail open, as the rate limit layer does
+        Err(e) => tracing::error!("Rate limit check for {} failed: {:?}", TWO_FACTOR.name, e),
+    }
+
+    // An enrolment challenge is answered with the first code from the new
+    // secret, which enables 2FA and hands out the recovery codes
+    if challenge.token_type == TWO_FACTOR_ENROLMENT
+        && !state
+            .two_factor_service
+            .is_enabled(account.id, user_type)
+            .await?
+    {
+        let recovery_codes = state
+            .two_factor_service
+            .confirm_enrolment(account.id, user_type, &payload.code)
+            .await?;
+        return start_session(
+            &state,
+            account,
+            ip_address,
+            user_agent,
+            Some(recovery_codes),
+            Some(serde_json::json!({ "two_factor": "enrolled" })),
+        )
+        .await;
+    }
+
+    let Some(method) = state
+        .two_factor_service
+        .verify(account.id, user_type, &payload.code)
+        .await?
+    else {
+        let _ = state
+            .audit_service
+            .log_action(
+                Some(account.id),
+                Some(user_type.to_string()),
+                None,
+                "login".to_string(),
+                Some(user_type.to_string()),
+                Some(account.id),
+                ip_address,
+                user_agent,
+                false,
+                Some("Invalid two-factor code".to_string()),
+                None,
+                None,
+            )
+            .await;
+        return Err(AppError::Unauthorized);
+    };
+
+    start_session(
+        &state,
+        account,
+        ip_address,
+        user_agent,
+        None,
+        Some(serde_json::json!({ "two_factor": method.as_str() })),
+    )
+    .await
+}
+
+// GET /admin/two-factor/policies - Which roles must sign in with 2FA
+pub async fn list_two_factor_policies(
+    State(state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<two_factor_policy::Model>>>, AppError> {
+    let policies = state.two_factor_service.list_policies().await?;
+    Ok(Json(ApiResponse::success(policies)))
+}
+
+// PUT /admin/two-factor/policies/:role - Require (or stop requiring) 2FA for a role
+pub async fn update_two_factor_policy(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(role): Path<String>,
+    Json(payload): Json<UpdateTwoFactorPolicyRequest>,
+) -> Result<Json<ApiResponse<two_factor_policy::Model>>, AppError> {
+    let admin_id = Uuid::parse_str(&claims.sub)?;
+    let policy = state
+        .two_factor_service
+        .set_required(&role, payload.required, admin_id)
+        .await?;
+
+    let _ = state
+        .audit_service
+        .log_action(
+            Some(admin_id),
+            Some("admin".to_string()),
+            Uuid::parse_str(&claims.session_id).ok(),
+            "two_factor_policy_update".to_string(),
+            Some("two_factor_policy".to_string()),
+            None,
+            None,
+            None,
+            true,
+            None,
+            None,
+            Some(serde_json::json!({
+                "role": policy.role,
+                "required": policy.required,
+            })),
+        )
+        .await;
+
+    Ok(Json(ApiResponse::success(policy)))
+}
+
+async fn log_two_factor_change(state: &AppState, claims: &Claims, action: &str) {
+    let user_id = Uuid::parse_str(&claims.sub).ok();
+    let _ = state
+        .audit_service
+        .log_action(
+            user_id,
+            Some(claims.user_type.clone()),
+            Uuid::parse_str(&claims.session_id).ok(),
+            action.to_string(),
+            Some(claims.user_type.clone()),
+            user_id,
+            None,
+            None,
+            true,
+            None,
+            None,
+            None,
+        )
+        .await;
+}
@@ -1,4 +1,4 @@
-use super::chat::ApiResponse;
+use super::chat::{load_chat_for_participant, ApiResponse};
 use crate::services::auth_service::Claims;
 use crate::{utils::errors::AppError, AppState};
 use axum::extract::Extension;
@@ -74,13 +74,16 @@ pub async fn upload_profile_picture(
 pub async fn upload_chat_attachment(
     State(state): State<AppState>,
     Path(chat_id): Path<String>,
+    Extension(claims): Extension<Claims>,
     mut multipart: Multipart,
-) -> Result<Json<ApiResponse<String>>, StatusCode> {
+) -> Result<Json<ApiResponse<String>>, AppError> {
     tracing::info!("📎 Processing chat attachment upload for chat: {}", chat_id);
 
+    load_chat_for_participant(&state, &chat_id, &claims.sub).await?;
+
     while let Some(field) = multipart.next_field().await.map_err(|e| {
         tracing::error!("Multipart field error: {}", e);
-        StatusCode::BAD_REQUEST
+        AppError::Validation("Invalid multipart data".to_string())
     })? {
         let name = field.name().unwrap_or("").to_string();
 
@@ -88,7 +91,7 @@ pub async fn upload_chat_attachment(
             let filename = field.file_name().unwrap_or("attachment").to_string();
             let data = field.bytes().await.map_err(|e| {
                 tracing::error!("Failed to read file bytes: {}", e);
-                StatusCode::BAD_REQUEST
+                AppError::Validation("Failed to read file".to_string())
             })?;
 
             if data.is_empty() {
@@ -0,0 +1,108 @@
+use super::chat::ApiResponse;
+use crate::models::postgres::webhook_endpoint::WebhookEvent;
+use crate::models::postgres::{webhook_delivery, webhook_endpoint};
+use crate::services::auth_service::Claims;
+use crate::{utils::errors::AppError, AppState};
+use axum::extract::{Extension, Path, Query, State};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Deserialize)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+}
+
+#[derive(Serialize)]
+pub struct CreateWebhookResponse {
+    pub endpoint: webhook_endpoint::Model,
+    pub secret: String, // Signs deliveries; shown only in this response
+}
+
+#[derive(Deserialize)]
+pub struct DeliveryLogQuery {
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+// POST /organizations/me/webhooks - Register an endpoint
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<CreateWebhookRequest>,
+) -> Result<Json<ApiResponse<CreateWebhookResponse>>, AppError> {
+    let organization_id = Uuid::parse_str(&claims.sub)?;
+    let (endpoint, secret) = state
+        .webhook_service
+        .create_endpoint(organization_id, payload.url, payload.events)
+        .await?;
+
+    Ok(Json(ApiResponse::success(CreateWebhookResponse {
+        endpoint,
+        secret,
+    })))
+}
+
+// GET /organizations/me/webhooks
+pub async fn list_webhooks(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> Result<Json<ApiResponse<Vec<webhook_endpoint::Model>>>, AppError> {
+    let organization_id = Uuid::parse_str(&claims.sub)?;
+    let endpoints = state
+        .webhook_service
+        .list_endpoints(organization_id)
+        .await?;
+
+    Ok(Json(ApiResponse::success(endpoints)))
+}
+
+// DELETE /organizations/me/webhooks/:id
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<String>>, AppError> {
+    let organization_id = Uuid::parse_str(&claims.sub)?;
+    state
+        .webhook_service
+        .delete_endpoint(organization_id, id)
+        .await?;
+
+    Ok(Json(ApiResponse::success("Webhook deleted".to_string())))
+}
+
+// GET /organizations/me/webhooks/:id/deliveries - Delivery log, newest first
+pub async fn list_webhook_deliveries(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<DeliveryLogQuery>,
+) -> Result<Json<ApiResponse<Vec<webhook_delivery::Model>>>, AppError> {
+    let organization_id = Uuid::parse_str(&claims.sub)?;
+    let limit = query.limit.unwrap_or(20).min(100);
+    let offset = query.offset.unwrap_or(0);
+
+    let deliveries = state
+        .webhook_service
+        .list_deliveries(organization_id, id, limit, offset)
+        .await?;
+
+    Ok(Json(ApiResponse::success(deliveries)))
+}
+
+// POST /organizations/me/webhooks/:id/test - Send a test event now
+pub async fn send_test_webhook(
+    State(state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<webhook_delivery::Model>>, AppError> {
+    let organization_id = Uuid::parse_str(&claims.sub)?;
+    let delivery = state
+        .webhook_service
+        .send_test_event(organization_id, id)
+        .await?;
+
+    Ok(Json(ApiResponse::success(delivery)))
+}
@@ -9,12 +9,22 @@ pub mod scripts;
 pub mod services;
 pub mod utils;
 
-use repositories::{ChatRepository, CommunityRepository, DynamoRepository, PostRepository};
+// The Postgres fixture the integration tests use, for unit tests that need it
+#[cfg(test)]
+#[path = "../tests/common/mod.rs"]
+mod test_db;
+
+use repositories::{
+    ActivityRepository, ChatRepository, CommunityRepository, DynamoRepository, FeedRepository,
+    FollowRepository, NotificationRepository, PostRepository,
+};
 use services::{
-    AdminService, ApiKeyService, AuditService, AuthService, BattleService, ChatService,
-    CommunityService, EmailService, OrganizationService, PlayerGameStatsService, PlayerService,
-    PostService, RateLimitService, RewardService, S3Service, SessionService, TeamService,
-    TournamentService, TournamentTeamInviteService, TournamentTeamService, TransactionService,
+    ActivityService, AdminService, ApiKeyService, AuditService, AuthService, BattleService,
+    ChatService, CommunityService, EmailOutboxService, EmailService, FeedService, FollowService,
+    IdentityVerificationService, NotificationService, OrganizationService, PlayerGameStatsService,
+    PlayerService, PostService, RateLimitService, RewardService, S3Service, SessionService,
+    TeamService, TournamentService, TournamentTeamInviteService, TournamentTeamService,
+    TransactionService, TwoFactorService, WebhookService,
 };
 
 #[derive(Clone)]
@@ -35,14 +45,23 @@ pub struct AppState {
     pub reward_service: RewardService,
     pub transaction_service: TransactionService,
     pub email_service: EmailService,
+    pub email_outbox_service: EmailOutboxService,
     pub chat_service: ChatService,
     pub post_service: PostService,
     pub community_service: CommunityService,
+    pub feed_service: FeedService,
+    pub follow_service: FollowService,
+    pub activity_service: ActivityService,
+    pub notification_service: NotificationService,
     pub s3_service: S3Service,
     pub session_service: SessionService,
     pub audit_service: AuditService,
     pub rate_limit_service: RateLimitService,
     pub api_key_service: ApiKeyService,
+    pub identity_verification_service: IdentityVerificationService,
+    pub webhook_service: WebhookService,
+    pub two_factor_service: TwoFactorService,
+    pub io: socketioxide::SocketIo,
 }
 
 impl AppState {
@@ -50,22 +69,52 @@ impl AppState {
         db: sea_orm::DatabaseConnection,
         aws: config::AwsClients,
         settings: config::Settings,
+        io: socketioxide::SocketIo,
     ) -> Self {
         let auth_service = AuthService::new(settings.jwt.secret.clone(), settings.jwt.expiration);
         let email_service =
             EmailService::new(settings.email.clone()).expect("Failed to initialize email service");
+        let email_outbox_service = EmailOutboxService::new(db.clone(), email_service.clone());
+
+        // DynamoDB storage; built first because the activity log, chats and
+        // notifications are shared with the Postgres-backed gaming services below
+        let dynamo_repo = match settings.storage.backend {
+            config::StorageBackend::DynamoDb => DynamoRepository::new(aws.dynamodb.clone()),
+            config::StorageBackend::Memory => DynamoRepository::in_memory(),
+        };
+        let activity_service = ActivityService::new(ActivityRepository::new(dynamo_repo.clone()));
+        let chat_service = ChatService::new(ChatRepository::new(dynamo_repo.clone()), io.clone());
 
         // Core user services
         let player_service = PlayerService::new(db.clone(), auth_service.clone());
         let admin_service = AdminService::new(db.clone(), auth_service.clone());
         let organization_service = OrganizationService::new(db.clone(), auth_service.clone());
+        let notification_service = NotificationService::new(
+            NotificationRepository::new(dynamo_repo.clone()),
+            email_outbox_service.clone(),
+            player_service.clone(),
+            auth_service.clone(),
+            io.clone(),
+        );
 
         // Gaming services - ADD auth_service where needed
-        let team_service = TeamService::new(db.clone());
-        let tournament_service = TournamentService::new(db.clone());
-        let tournament_team_service = TournamentTeamService::new(db.clone());
-        let tournament_team_invite_service = TournamentTeamInviteService::new(db.clone());
-        let battle_service = BattleService::new(db.clone());
+        let webhook_service = WebhookService::new(db.clone());
+        let team_service =
+            TeamService::new(db.clone(), activity_service.clone(), chat_service.clone());
+        let tournament_service = TournamentService::new(db.clone(), webhook_service.clone());
+        let tournament_team_service = TournamentTeamService::new(
+            db.clone(),
+            activity_service.clone(),
+            webhook_service.clone(),
+        );
+        let tournament_team_invite_service =
+            TournamentTeamInviteService::new(db.clone(), notification_service.clone());
+        let battle_service = BattleService::new(
+            db.clone(),
+            activity_service.clone(),
+            notification_service.clone(),
+            webhook_service.clone(),
+        );
         let player_game_stats_service = PlayerGameStatsService::new(db.clone());
         let reward_service = RewardService::new(db.clone());
         let transaction_service = TransactionService::new(db.clone());
@@ -73,16 +122,26 @@ impl AppState {
         // Enterprise security services - ADD auth_service
         let session_service = SessionService::new(db.clone());
         let audit_service = AuditService::new(db.clone());
-        let rate_limit_service = RateLimitService::new(db.clone());
-        let api_key_service = ApiKeyService::new(db.clone(), auth_service.clone());
+        let rate_limit_service = match settings.rate_limit.backend {
+            config::RateLimitBackend::Memory => RateLimitService::in_memory(),
+            config::RateLimitBackend::Postgres => RateLimitService::postgres(db.clone()),
+        };
+        let api_key_service = ApiKeyService::new(
+            db.clone(),
+            auth_service.clone(),
+            settings.api_keys.digest_secret.clone(),
+        );
+        let identity_verification_service = IdentityVerificationService::new(db.clone());
+        let two_factor_service = TwoFactorService::new(db.clone());
 
         // DynamoDB services
-        let dynamo_repo = DynamoRepository::new(aws.dynamodb.clone());
-        let chat_repo = ChatRepository::new(dynamo_repo.clone());
         let post_repo = PostRepository::new(dynamo_repo.clone());
-        let community_repo = CommunityRepository::new(dynamo_repo);
+        let community_repo = CommunityRepository::new(dynamo_repo.clone());
+        let follow_repo = FollowRepository::new(dynamo_repo.clone());
+        let feed_repo = FeedRepository::new(dynamo_repo);
 
-        let chat_service = ChatService::new(chat_repo);
+        let feed_service = FeedService::new(feed_repo, follow_repo.clone(), post_repo.clone());
+        let follow_service = FollowService::new(follow_repo);
         let post_service = PostService::new(post_repo);
         let community_service = CommunityService::new(community_repo);
         let s3_service = S3Service::new(aws.s3.clone());
@@ -104,14 +163,23 @@ impl AppState {
             reward_service,
             transaction_service,
             email_service,
+            email_outbox_service,
             chat_service,
             post_service,
             community_service,
+            feed_service,
+            follow_service,
+            activity_service,
+            notification_service,
             s3_service,
             session_service,
             audit_service,
             rate_limit_service,
             api_key_service,
+            identity_verification_service,
+            webhook_service,
+            two_factor_service,
+            io,
         }
     }
 }
@@ -1,9 +1,13 @@
+use crate::middleware::api_key::{api_key_auth_middleware, extract_api_key};
 use crate::models::enums::ApprovalStatus;
+use crate::models::postgres::admin::{self, AdminPermission};
 use crate::services::auth_service::Claims;
 use crate::utils::errors::AppError;
 use crate::AppState;
 use axum::{
-    extract::{Request, State},
+    async_trait,
+    extract::{FromRef, FromRequestParts, Request, State},
+    http::{request::Parts, HeaderMap},
     middleware::Next,
     response::Response,
 };
@@ -15,7 +19,7 @@ pub async fn jwt_auth_middleware(
 ) -> Result<Response, AppError> {
     println!("DEBUG: JWT middleware - extracting token");
 
-    let token = match extract_token_from_request(&request) {
+    let token = match extract_token_from_headers(request.headers()) {
         Ok(t) => {
             println!("DEBUG: Token extracted successfully (length: {})", t.len());
             t
@@ -26,8 +30,19 @@ pub async fn jwt_auth_middleware(
         }
     };
 
+    let claims = authenticate_token(&state, &token).await?;
+
+    println!("DEBUG: JWT middleware - all checks passed");
+    request.extensions_mut().insert(claims);
+    Ok(next.run(request).await)
+}
+
+/// Verifies a bearer token and its backing session, returning the claims
+/// when both are valid. Shared by the HTTP middleware and the Socket.IO
+/// handshake so both transports enforce the same rules.
+pub async fn authenticate_token(state: &AppState, token: &str) -> Result<Claims, AppError> {
     println!("DEBUG: JWT middleware - verifying JWT");
-    let claims = match state.auth_service.verify_jwt(&token) {
+    let claims = match state.auth_service.verify_jwt(token) {
         Ok(c) => {
             println!(
                 "DEBUG: JWT verification successful, session_id: {}",
@@ -76,14 +91,12 @@ pub async fn jwt_auth_middleware(
         return Err(AppError::Unauthorized);
     }
 
-    println!("DEBUG: JWT middleware - all checks passed");
-    request.extensions_mut().insert(claims);
-    Ok(next.run(request).await)
+    Ok(claims)
 }
 
-fn extract_token_from_request(request: &Request) -> Result<String, AppError> {
+pub fn extract_token_from_headers(headers: &HeaderMap) -> Result<String, AppError> {
     // Try Authorization header first
-    if let Some(auth_header) = request.headers().get("authorization") {
+    if let Some(auth_header) = headers.get("authorization") {
         if let Ok(auth_str) = auth_header.to_str() {
             if let Some(token) = auth_str.strip_prefix("Bearer ") {
                 return Ok(token.to_string());
@@ -92,7 +105,7 @@ fn extract_token_from_request(request: &Request) -> Result<String, AppError> {
     }
 
     // Try cookie as fallback
-    if let Some(cookie_header) = request.headers().get("cookie") {
+    if let Some(cookie_header) = headers.get("cookie") {
         if let Ok(cookies) = cookie_header.to_str() {
             for cookie in cookies.split(';') {
                 let cookie = cookie.trim();
@@ -106,34 +119,94 @@ fn extract_token_from_request(request: &Request) -> Result<String, AppError> {
     Err(AppError::Unauthorized)
 }
 
-// Admin-only middleware
-pub async fn admin_only_middleware(
+/// Authenticates with the `X-API-Key` header when one is sent, otherwise
+/// with the JWT from the `Authorization` header or `token` cookie. API keys
+/// answer with their own status: 401, or 403 for a missing scope.
+pub async fn authenticated_middleware(
     State(state): State<AppState>,
     request: Request,
     next: Next,
 ) -> Result<Response, AppError> {
+    if extract_api_key(request.headers()).is_some() {
+        return api_key_auth_middleware(State(state), request, next).await;
+    }
+    jwt_auth_middleware(State(state), request, next).await
+}
+
+// Player-only middleware
+pub async fn player_only_middleware(request: Request, next: Next) -> Result<Response, AppError> {
     let claims = request
         .extensions()
         .get::<Claims>()
         .ok_or(AppError::Unauthorized)?;
 
-    if claims.user_type != "admin" {
-        return Err(AppError::Unauthorized);
+    if claims.user_type != "player" {
+        return Err(AppError::Forbidden);
     }
 
-    // Verify admin is still active
-    let admin_id = claims
-        .sub
-        .parse()
-        .map_err(|_| AppError::Validation("Invalid user ID".to_string()))?;
-    let admin = state
-        .admin_service
-        .get_by_id(admin_id)
-        .await?
-        .ok_or(AppError::Unauthorized)?;
+    Ok(next.run(request).await)
+}
 
-    if !admin.is_active {
-        return Err(AppError::Unauthorized);
+/// The signed-in admin, who must still be active.
+pub struct ActiveAdmin(pub admin::Model);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ActiveAdmin
+where
+    AppState: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let claims = parts
+            .extensions
+            .get::<Claims>()
+            .ok_or(AppError::Unauthorized)?;
+        if claims.user_type != "admin" {
+            return Err(AppError::Unauthorized);
+        }
+
+        let admin_id = claims
+            .sub
+            .parse()
+            .map_err(|_| AppError::Validation("Invalid user ID".to_string()))?;
+        let admin = AppState::from_ref(state)
+            .admin_service
+            .get_by_id(admin_id)
+            .await?
+            .ok_or(AppError::Unauthorized)?;
+
+        if !admin.is_active {
+            return Err(AppError::Unauthorized);
+        }
+        Ok(ActiveAdmin(admin))
+    }
+}
+
+/// State of `admin_permission_middleware`: the permission a group of admin
+/// routes requires.
+#[derive(Clone)]
+pub struct RequiredPermission {
+    pub state: AppState,
+    pub permission: AdminPermission,
+}
+
+impl FromRef<RequiredPermission> for AppState {
+    fn from_ref(required: &RequiredPermission) -> Self {
+        required.state.clone()
+    }
+}
+
+// Admin middleware: active admins holding the route's permission
+pub async fn admin_permission_middleware(
+    State(required): State<RequiredPermission>,
+    ActiveAdmin(admin): ActiveAdmin,
+    request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    if !admin.can(required.permission) {
+        return Err(AppError::Forbidden);
     }
 
     Ok(next.run(request).await)
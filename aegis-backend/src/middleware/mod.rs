@@ -1,5 +1,11 @@
+pub mod api_key;
 pub mod auth;
 pub mod cors;
 pub mod rate_limit;
 
-pub use auth::{admin_only_middleware, jwt_auth_middleware, organization_only_middleware};
+pub use api_key::api_key_auth_middleware;
+pub use auth::{
+    admin_permission_middleware, authenticated_middleware, jwt_auth_middleware,
+    organization_only_middleware, player_only_middleware, ActiveAdmin, RequiredPermission,
+};
+pub use rate_limit::RateLimitLayer;
@@ -1,69 +1,289 @@
+use crate::middleware::api_key::ApiKeyPrincipal;
+use crate::services::auth_service::Claims;
+use crate::services::rate_limit_service::{Decision, RateLimitPolicy, RateLimitService};
+use crate::utils::errors::AppError;
 use axum::{
-    extract::{ConnectInfo, Request},
-    http::StatusCode,
-    middleware::Next,
-    response::Response,
+    extract::{ConnectInfo, MatchedPath, Request},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
 };
-use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use std::convert::Infallible;
+use std::future::Future;
 use std::net::SocketAddr;
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
 
+pub const RATE_LIMIT_LIMIT: HeaderName = HeaderName::from_static("ratelimit-limit");
+pub const RATE_LIMIT_REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+pub const RATE_LIMIT_RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+
+/// Who a policy's buckets belong to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyBy {
+    Ip,
+    /// The authenticated user, or the IP for anonymous requests.
+    User,
+    /// The API key a request authenticated with, or the IP without one.
+    ApiKey,
+}
+
+/// Which requests use up a policy's allowance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Counts {
+    Every,
+    /// Only requests answered `401`. Each request takes its hit up front, so
+    /// concurrent ones can't all get in on the last of the allowance, and
+    /// gets it back if the answer is anything else. Once the allowance is
+    /// spent every request is refused until it recovers.
+    Failures,
+}
+
+/// Failed sign-ins per IP. Successful ones don't count, so people sharing an
+/// address aren't locked out by each other's logins.
+pub const LOGIN: RateLimitPolicy = RateLimitPolicy::sliding_window("login", 10, 15 * 60);
+pub const REGISTER: RateLimitPolicy = RateLimitPolicy::sliding_window("register", 3, 60 * 60);
+pub const FORGOT_PASSWORD: RateLimitPolicy =
+    RateLimitPolicy::sliding_window("forgot_password", 5, 60 * 60);
+pub const PROFILE_UPDATE: RateLimitPolicy =
+    RateLimitPolicy::sliding_window("profile_update", 10, 60 * 60);
+pub const SEND_MESSAGE: RateLimitPolicy = RateLimitPolicy::token_bucket("messages", 30, 60);
+/// Guesses at a six-digit code, shared by every route that checks one.
+pub const TWO_FACTOR: RateLimitPolicy = RateLimitPolicy::sliding_window("two_factor", 5, 5 * 60);
+
+/// Policy for each limited route, by method and route pattern. Requests made
+/// with an API key are additionally held to that key's hourly quota.
+pub const ROUTE_POLICIES: &[(&str, &str, RateLimitPolicy, KeyBy, Counts)] = &[
+    ("POST", "/auth/login", LOGIN, KeyBy::Ip, Counts::Failures),
+    ("POST", "/auth/register", REGISTER, KeyBy::Ip, Counts::Every),
+    (
+        "POST",
+        "/auth/forgot-password",
+        FORGOT_PASSWORD,
+        KeyBy::Ip,
+        Counts::Every,
+    ),
+    (
+        "POST",
+        "/auth/2fa/challenge",
+        TWO_FACTOR,
+        KeyBy::Ip,
+        Counts::Every,
+    ),
+    (
+        "POST",
+        "/auth/2fa/confirm",
+        TWO_FACTOR,
+        KeyBy::User,
+        Counts::Every,
+    ),
+    (
+        "POST",
+        "/auth/2fa/recovery-codes",
+        TWO_FACTOR,
+        KeyBy::User,
+        Counts::Every,
+    ),
+    (
+        "POST",
+        "/auth/2fa/disable",
+        TWO_FACTOR,
+        KeyBy::User,
+        Counts::Every,
+    ),
+    (
+        "PUT",
+        "/players/profile",
+        PROFILE_UPDATE,
+        KeyBy::User,
+        Counts::Every,
+    ),
+    (
+        "POST",
+        "/chats/:chat_id/messages",
+        SEND_MESSAGE,
+        KeyBy::User,
+        Counts::Every,
+    ),
+];
+
+pub fn api_key_quota(rate_limit_per_hour: i32) -> RateLimitPolicy {
+    RateLimitPolicy::sliding_window("api_key", rate_limit_per_hour.max(0) as u32, 60 * 60)
+}
+
+/// Limits requests by the policies that apply to them, answering `429 Too
+/// Many Requests` with `Retry-After` once a bucket is empty. Responses to
+/// limited requests carry `RateLimit-Limit`, `RateLimit-Remaining` and
+/// `RateLimit-Reset` for the tightest policy. Must run inside the router and
+/// the auth middleware, so the matched route and principal are known.
 #[derive(Clone)]
-pub struct RateLimiter {
-    attempts: Arc<Mutex<HashMap<String, Vec<Instant>>>>,
-    max_attempts: usize,
-    window_duration: Duration,
+pub struct RateLimitLayer {
+    limiter: RateLimitService,
 }
 
-impl RateLimiter {
-    pub fn new(max_attempts: usize, window_minutes: u64) -> Self {
-        Self {
-            attempts: Arc::new(Mutex::new(HashMap::new())),
-            max_attempts,
-            window_duration: Duration::from_secs(window_minutes * 60),
+impl RateLimitLayer {
+    pub fn new(limiter: RateLimitService) -> Self {
+        Self { limiter }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit {
+            inner,
+            limiter: self.limiter.clone(),
         }
     }
+}
 
-    pub fn check_rate_limit(&self, key: &str) -> bool {
-        let mut attempts = self.attempts.lock().unwrap();
-        let now = Instant::now();
+#[derive(Clone)]
+pub struct RateLimit<S> {
+    inner: S,
+    limiter: RateLimitService,
+}
 
-        let user_attempts = attempts.entry(key.to_string()).or_insert_with(Vec::new);
+impl<S> Service<Request> for RateLimit<S>
+where
+    S: Service<Request, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, Infallible>> + Send>>;
 
-        // Remove old attempts outside the window
-        user_attempts
-            .retain(|&attempt_time| now.duration_since(attempt_time) < self.window_duration);
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
 
-        if user_attempts.len() >= self.max_attempts {
-            false
-        } else {
-            user_attempts.push(now);
-            true
+    fn call(&mut self, request: Request) -> Self::Future {
+        // The clone may not be ready; keep the one `poll_ready` was called on
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let limiter = self.limiter.clone();
+
+        Box::pin(async move {
+            let now = Utc::now();
+            let mut tightest: Option<Decision> = None;
+            let mut on_failure = Vec::new();
+            for (policy, key, counts) in policies_for(&request) {
+                let decision = match limiter.check_at(&policy, &key, now).await {
+                    Ok(decision) => decision,
+                    Err(e) => {
+                        // Fail open: a store outage shouldn't take the API down
+                        tracing::error!("Rate limit check for {} failed: {:?}", policy.name, e);
+                        continue;
+                    }
+                };
+                if !decision.allowed {
+                    refund(&limiter, on_failure, now).await;
+                    let mut response = AppError::RateLimited.into_response();
+                    set_headers(response.headers_mut(), &decision);
+                    return Ok(response);
+                }
+                match counts {
+                    // Reported once the outcome is known
+                    Counts::Failures => on_failure.push((policy, key, decision)),
+                    Counts::Every => tighten(&mut tightest, decision),
+                }
+            }
+
+            let mut response = inner.call(request).await?;
+            if response.status() == StatusCode::UNAUTHORIZED {
+                for (.., decision) in on_failure {
+                    tighten(&mut tightest, decision);
+                }
+            } else {
+                refund(&limiter, on_failure, now).await;
+            }
+            if let Some(decision) = tightest {
+                set_headers(response.headers_mut(), &decision);
+            }
+            Ok(response)
+        })
+    }
+}
+
+/// Gives back the hits of requests that turned out not to be failures.
+async fn refund(
+    limiter: &RateLimitService,
+    hits: Vec<(RateLimitPolicy, String, Decision)>,
+    at: DateTime<Utc>,
+) {
+    for (policy, key, _) in hits {
+        if let Err(e) = limiter.refund(&policy, &key, at).await {
+            tracing::error!("Rate limit refund for {} failed: {:?}", policy.name, e);
         }
     }
 }
 
-pub fn create_login_rate_limiter() -> RateLimiter {
-    RateLimiter::new(5, 15) // 5 attempts per 15 minutes
+fn tighten(tightest: &mut Option<Decision>, decision: Decision) {
+    if tightest.is_none_or(|t| decision.remaining < t.remaining) {
+        *tightest = Some(decision);
+    }
 }
 
-pub async fn login_rate_limit_middleware(
-    ConnectInfo(addr): ConnectInfo<SocketAddr>,
-    request: Request,
-    next: Next,
-) -> Result<Response, StatusCode> {
-    let limiter = create_login_rate_limiter();
-    let ip = addr.ip().to_string();
-
-    // Only apply rate limiting to auth endpoints
-    if request.uri().path().contains("/auth/login")
-        || request.uri().path().contains("/auth/register")
-    {
-        if !limiter.check_rate_limit(&ip) {
-            return Err(StatusCode::TOO_MANY_REQUESTS);
-        }
+/// The policies a request counts against, each with the key of its bucket
+/// and which requests it counts.
+pub fn policies_for(request: &Request) -> Vec<(RateLimitPolicy, String, Counts)> {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|p| p.as_str())
+        .unwrap_or_default();
+
+    let method = request.method().as_str();
+
+    let mut policies: Vec<_> = ROUTE_POLICIES
+        .iter()
+        .filter(|(m, pattern, ..)| *m == method && *pattern == route)
+        .map(|(_, _, policy, key_by, counts)| (*policy, client_key(request, *key_by), *counts))
+        .collect();
+    if let Some(ApiKeyPrincipal(key)) = request.extensions().get::<ApiKeyPrincipal>() {
+        policies.push((
+            api_key_quota(key.rate_limit_per_hour),
+            client_key(request, KeyBy::ApiKey),
+            Counts::Every,
+        ));
+    }
+    policies
+}
+
+/// The bucket key `KeyBy::User` gives an account, for handlers that limit
+/// an account the middleware can't identify yet.
+pub fn user_key(user_type: &str, user_id: &str) -> String {
+    format!("{}:{}", user_type, user_id)
+}
+
+fn client_key(request: &Request, key_by: KeyBy) -> String {
+    let ip = || match request.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(addr)) => format!("ip:{}", addr.ip()),
+        None => "ip:unknown".to_string(),
+    };
+    let extensions = request.extensions();
+    match key_by {
+        KeyBy::Ip => ip(),
+        KeyBy::User => extensions
+            .get::<Claims>()
+            .map(|claims| user_key(&claims.user_type, &claims.sub))
+            .unwrap_or_else(ip),
+        KeyBy::ApiKey => extensions
+            .get::<ApiKeyPrincipal>()
+            .map(|ApiKeyPrincipal(key)| format!("key:{}", key.key_id))
+            .unwrap_or_else(ip),
     }
+}
 
-    Ok(next.run(request).await)
+fn set_headers(headers: &mut HeaderMap, decision: &Decision) {
+    headers.insert(RATE_LIMIT_LIMIT, HeaderValue::from(decision.limit));
+    headers.insert(RATE_LIMIT_REMAINING, HeaderValue::from(decision.remaining));
+    headers.insert(RATE_LIMIT_RESET, HeaderValue::from(decision.reset_secs));
+    if let Some(retry_after) = decision.retry_after_secs {
+        headers.insert(
+            axum::http::header::RETRY_AFTER,
+            HeaderValue::from(retry_after),
+        );
+    }
 }
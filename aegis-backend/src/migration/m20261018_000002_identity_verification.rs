@@ -0,0 +1,200 @@
+use sea_orm_migration::prelude::*;
+
+/// `migrations/postgres/002_identity_verification.sql`
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Players::Table)
+                    .add_column(
+                        ColumnDef::new(Players::IdentityVerified)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .add_column(
+                        ColumnDef::new(Players::IdentityVerifiedAt).timestamp_with_time_zone(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_table(
+                Table::create()
+                    .table(IdentityVerifications::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(IdentityVerifications::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key()
+                            .extra("DEFAULT gen_random_uuid()"),
+                    )
+                    .col(
+                        ColumnDef::new(IdentityVerifications::PlayerId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(IdentityVerifications::DocumentType)
+                            .string_len(50)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(IdentityVerifications::DocumentKeys)
+                            .array(ColumnType::Text)
+                            .not_null()
+                            .default(Expr::cust("'{}'")),
+                    )
+                    .col(
+                        ColumnDef::new(IdentityVerifications::Status)
+                            .custom(ApprovalStatus::Table)
+                            .default("pending"),
+                    )
+                    .col(
+                        ColumnDef::new(IdentityVerifications::SubmittedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(IdentityVerifications::ReviewedBy).uuid())
+                    .col(
+                        ColumnDef::new(IdentityVerifications::ReviewedAt)
+                            .timestamp_with_time_zone(),
+                    )
+                    .col(ColumnDef::new(IdentityVerifications::RejectionReason).text())
+                    .col(
+                        ColumnDef::new(IdentityVerifications::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(IdentityVerifications::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_identity_verifications_player")
+                            .from(
+                                IdentityVerifications::Table,
+                                IdentityVerifications::PlayerId,
+                            )
+                            .to(Players::Table, Players::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_identity_verifications_reviewer")
+                            .from(
+                                IdentityVerifications::Table,
+                                IdentityVerifications::ReviewedBy,
+                            )
+                            .to(Admins::Table, Admins::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_identity_verifications_player")
+                    .table(IdentityVerifications::Table)
+                    .col(IdentityVerifications::PlayerId)
+                    .col((IdentityVerifications::SubmittedAt, IndexOrder::Desc))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_players_identity_verified")
+                    .table(Players::Table)
+                    .col(Players::IdentityVerified)
+                    .to_owned(),
+            )
+            .await?;
+
+        // sea-query can't express partial indexes
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "CREATE INDEX idx_identity_verifications_queue ON identity_verifications(submitted_at) WHERE status = 'pending'",
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "CREATE UNIQUE INDEX idx_identity_verifications_one_pending ON identity_verifications(player_id) WHERE status = 'pending'",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(IdentityVerifications::Table).to_owned())
+            .await?;
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_players_identity_verified")
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Players::Table)
+                    .drop_column(Players::IdentityVerified)
+                    .drop_column(Players::IdentityVerifiedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum ApprovalStatus {
+    Table,
+}
+
+#[derive(Iden)]
+enum Players {
+    Table,
+    Id,
+    IdentityVerified,
+    IdentityVerifiedAt,
+}
+
+#[derive(Iden)]
+enum Admins {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum IdentityVerifications {
+    Table,
+    Id,
+    PlayerId,
+    DocumentType,
+    DocumentKeys,
+    Status,
+    SubmittedAt,
+    ReviewedBy,
+    ReviewedAt,
+    RejectionReason,
+    CreatedAt,
+    UpdatedAt,
+}
@@ -0,0 +1,122 @@
+use sea_orm_migration::prelude::*;
+
+/// `migrations/postgres/003_email_outbox.sql`
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(EmailOutbox::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(EmailOutbox::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key()
+                            .extra("DEFAULT gen_random_uuid()"),
+                    )
+                    .col(
+                        ColumnDef::new(EmailOutbox::Recipient)
+                            .string_len(255)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(EmailOutbox::Template)
+                            .string_len(100)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(EmailOutbox::Languages)
+                            .array(ColumnType::Text)
+                            .not_null()
+                            .default(Expr::cust("'{}'")),
+                    )
+                    .col(
+                        ColumnDef::new(EmailOutbox::Vars)
+                            .json_binary()
+                            .not_null()
+                            .default(Expr::cust("'{}'")),
+                    )
+                    .col(
+                        ColumnDef::new(EmailOutbox::Status)
+                            .string_len(20)
+                            .not_null()
+                            .default("pending"),
+                    )
+                    .col(
+                        ColumnDef::new(EmailOutbox::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(EmailOutbox::NextAttemptAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(ColumnDef::new(EmailOutbox::LastError).text())
+                    .col(ColumnDef::new(EmailOutbox::SentAt).timestamp_with_time_zone())
+                    .col(
+                        ColumnDef::new(EmailOutbox::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(EmailOutbox::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_email_outbox_status")
+                    .table(EmailOutbox::Table)
+                    .col(EmailOutbox::Status)
+                    .col((EmailOutbox::CreatedAt, IndexOrder::Desc))
+                    .to_owned(),
+            )
+            .await?;
+
+        // sea-query can't express partial indexes
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "CREATE INDEX idx_email_outbox_due ON email_outbox(next_attempt_at) WHERE status = 'pending'",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(EmailOutbox::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum EmailOutbox {
+    Table,
+    Id,
+    Recipient,
+    Template,
+    Languages,
+    Vars,
+    Status,
+    Attempts,
+    NextAttemptAt,
+    LastError,
+    SentAt,
+    CreatedAt,
+    UpdatedAt,
+}
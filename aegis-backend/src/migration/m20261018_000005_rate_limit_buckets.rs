@@ -0,0 +1,82 @@
+use sea_orm_migration::prelude::*;
+
+/// `migrations/postgres/005_rate_limit_buckets.sql`. The SQL file also
+/// rewrites the session cleanup trigger, which this migration set never
+/// created.
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(RateLimitBuckets::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(RateLimitBuckets::Key)
+                            .text()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(RateLimitBuckets::Level).double().not_null())
+                    .col(
+                        ColumnDef::new(RateLimitBuckets::Previous)
+                            .double()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(RateLimitBuckets::Stamp).double().not_null())
+                    .col(
+                        ColumnDef::new(RateLimitBuckets::ExpiresAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_rate_limit_buckets_expires")
+                    .table(RateLimitBuckets::Table)
+                    .col(RateLimitBuckets::ExpiresAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(
+                Table::drop()
+                    .table(RateLimits::Table)
+                    .if_exists()
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RateLimitBuckets::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(Iden)]
+enum RateLimits {
+    Table,
+}
+
+#[derive(Iden)]
+enum RateLimitBuckets {
+    Table,
+    Key,
+    Level,
+    Previous,
+    Stamp,
+    ExpiresAt,
+}
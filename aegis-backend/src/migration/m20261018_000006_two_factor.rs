@@ -0,0 +1,147 @@
+use sea_orm_migration::prelude::*;
+
+/// `migrations/postgres/006_two_factor.sql`
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(TwoFactorCredentials::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TwoFactorCredentials::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key()
+                            .extra("DEFAULT gen_random_uuid()"),
+                    )
+                    .col(
+                        ColumnDef::new(TwoFactorCredentials::UserId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TwoFactorCredentials::UserType)
+                            .string_len(20)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TwoFactorCredentials::Secret)
+                            .string_len(64)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(TwoFactorCredentials::RecoveryCodeHashes)
+                            .array(ColumnType::Text)
+                            .not_null()
+                            .default(Expr::cust("'{}'")),
+                    )
+                    .col(ColumnDef::new(TwoFactorCredentials::LastUsedStep).big_integer())
+                    .col(ColumnDef::new(TwoFactorCredentials::EnabledAt).timestamp_with_time_zone())
+                    .col(
+                        ColumnDef::new(TwoFactorCredentials::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(
+                        ColumnDef::new(TwoFactorCredentials::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_two_factor_credentials_user")
+                    .table(TwoFactorCredentials::Table)
+                    .col(TwoFactorCredentials::UserId)
+                    .col(TwoFactorCredentials::UserType)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        // role is an admin role, 'organization' or 'player'
+        manager
+            .create_table(
+                Table::create()
+                    .table(TwoFactorPolicies::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(TwoFactorPolicies::Role)
+                            .string_len(20)
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(TwoFactorPolicies::Required)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(TwoFactorPolicies::UpdatedBy).uuid())
+                    .col(
+                        ColumnDef::new(TwoFactorPolicies::UpdatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_two_factor_policies_updated_by")
+                            .from(TwoFactorPolicies::Table, TwoFactorPolicies::UpdatedBy)
+                            .to(Admins::Table, Admins::Id),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(TwoFactorPolicies::Table).to_owned())
+            .await?;
+        manager
+            .drop_table(Table::drop().table(TwoFactorCredentials::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Iden)]
+enum Admins {
+    Table,
+    Id,
+}
+
+#[derive(Iden)]
+enum TwoFactorCredentials {
+    Table,
+    Id,
+    UserId,
+    UserType,
+    Secret,
+    RecoveryCodeHashes,
+    LastUsedStep,
+    EnabledAt,
+    CreatedAt,
+    UpdatedAt,
+}
+
+#[derive(Iden)]
+enum TwoFactorPolicies {
+    Table,
+    Role,
+    Required,
+    UpdatedBy,
+    UpdatedAt,
+}
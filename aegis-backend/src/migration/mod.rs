@@ -5,11 +5,23 @@ pub struct Migrator;
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20240101_000001_initial::Migration)]
+        vec![
+            Box::new(m20240101_000001_initial::Migration),
+            Box::new(m20261018_000002_identity_verification::Migration),
+            Box::new(m20261018_000003_email_outbox::Migration),
+            Box::new(m20261018_000004_webhooks::Migration),
+            Box::new(m20261018_000005_rate_limit_buckets::Migration),
+            Box::new(m20261018_000006_two_factor::Migration),
+        ]
     }
 }
 
 pub mod cli;
 mod m20240101_000001_initial;
+mod m20261018_000002_identity_verification;
+mod m20261018_000003_email_outbox;
+mod m20261018_000004_webhooks;
+mod m20261018_000005_rate_limit_buckets;
+mod m20261018_000006_two_factor;
 
 pub use cli::{run_migrations, Cli};
@@ -1,11 +1,30 @@
 use serde::{Deserialize, Serialize};
 use super::entity::GameEntity;
 
+/// User-facing activity shown on player timelines and the landing page.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityType {
+    TeamJoined,
+    TournamentRegistered,
+    BattleWon,
+}
+
+impl ActivityType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TeamJoined => "team_joined",
+            Self::TournamentRegistered => "tournament_registered",
+            Self::BattleWon => "battle_won",
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ActivityLog {
     pub id: String,
     pub user_id: String,               // Player UUID
-    pub activity_type: String,         // "login", "match", "tournament_join"
+    pub activity_type: String,         // See `ActivityType`
     pub description: String,
     pub metadata: serde_json::Value,
     pub timestamp: String,
@@ -27,7 +46,7 @@ impl ActivityLog {
         GameEntity::new(
             "activity",
             &format!("USER#{}", self.user_id),
-            &format!("ACTIVITY#{}", self.timestamp)
+            &format!("ACTIVITY#{}#{}", self.timestamp, self.id)
         )
         .with_gsi(&format!("TYPE#{}", self.activity_type), &self.timestamp)
         .with_data(self)
@@ -1,13 +1,21 @@
 use super::entity::GameEntity;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Chat {
     pub id: String,
-    pub chat_type: String, // "general", "team", "tournament"
+    pub chat_type: String, // "public", "general", "team", "tournament", "direct"
     pub name: String,
-    pub participants: Vec<String>, // Player UUIDs
-    pub created_by: String,        // Player UUID
+    #[serde(default)]
+    pub participant_count: i32, // Maintained atomically alongside participant items
+    #[serde(default)]
+    pub admins: Vec<String>, // Player UUIDs allowed to add others
+    #[serde(default)]
+    pub team_id: Option<String>, // Set for team chats, whose members follow the roster
+    #[serde(default)]
+    pub version: u64,
+    pub created_by: String, // Player UUID
     pub created_at: String,
     pub updated_at: String,
 }
@@ -20,9 +28,79 @@ pub struct ChatMessage {
     pub message: String,
     pub message_type: String, // "text", "image", "file"
     pub timestamp: String,
+    #[serde(default)]
+    pub edited_at: Option<String>,
+    #[serde(default)]
+    pub edit_history: Vec<MessageEdit>, // Previous versions, oldest first
+    #[serde(default)]
+    pub deleted: bool, // Tombstone: content cleared, entry kept in the timeline
+    #[serde(default)]
+    pub deleted_at: Option<String>,
+    #[serde(default)]
+    pub reactions: BTreeMap<String, Vec<String>>, // Emoji -> Player UUIDs
+    #[serde(default)]
+    pub version: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MessageEdit {
+    pub message: String,
+    pub edited_at: String,
+}
+
+/// Chat membership: one item per participant. Indexed on GSI2 by user so
+/// "my chats" is a single query, and carries the last-read marker used for
+/// unread counts.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChatParticipant {
+    pub chat_id: String,
+    pub user_id: String,
+    pub joined_at: String,
+    pub last_read_at: Option<String>, // Timestamp of the last message read
+    pub last_read_message_id: Option<String>,
+}
+
+/// Pointer from a message ID to the message's sort key, since messages are
+/// keyed by timestamp for ordering.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChatMessageRef {
+    pub chat_id: String,
+    pub message_id: String,
+    pub sort_key: String,
 }
 
 impl Chat {
+    /// Direct chats are keyed by the sorted player pair so both sides
+    /// always resolve to the same chat.
+    pub fn direct_chat_id(player_a: &str, player_b: &str) -> String {
+        let (low, high) = if player_a <= player_b {
+            (player_a, player_b)
+        } else {
+            (player_b, player_a)
+        };
+        format!("direct_{}_{}", low, high)
+    }
+
+    pub fn team_chat_id(team_id: &str) -> String {
+        format!("team_{}", team_id)
+    }
+
+    /// Chats created before `admins` existed fall back to their creator.
+    pub fn is_admin(&self, user_id: &str) -> bool {
+        self.admins.iter().any(|a| a == user_id)
+            || (self.admins.is_empty() && self.created_by == user_id)
+    }
+
+    /// Public chats are the only ones anyone can join without an invite.
+    pub fn is_public(&self) -> bool {
+        self.chat_type == "public"
+    }
+
+    /// Direct and team chats have fixed membership rules and can't be joined freely.
+    pub fn has_managed_membership(&self) -> bool {
+        self.chat_type == "direct" || self.chat_type == "team"
+    }
+
     pub fn to_entity(&self) -> Result<GameEntity, serde_json::Error> {
         GameEntity::new("chat", &format!("CHAT#{}", self.id), "METADATA")
             .with_gsi(&format!("CHAT_TYPE#{}", self.chat_type), &self.created_at)
@@ -35,13 +113,71 @@ impl Chat {
 }
 
 impl ChatMessage {
+    /// Ordered by time; the ID keeps messages sent in the same instant apart.
+    pub fn sort_key(timestamp: &str, id: &str) -> String {
+        format!("MSG#{}#{}", timestamp, id)
+    }
+
     pub fn to_entity(&self) -> Result<GameEntity, serde_json::Error> {
         GameEntity::new(
             "message",
             &format!("CHAT#{}", self.chat_id),
-            &format!("MSG#{}", self.timestamp),
+            &Self::sort_key(&self.timestamp, &self.id),
         )
         .with_gsi(&format!("USER#{}", self.sender), &self.timestamp)
         .with_data(self)
     }
+
+    pub fn has_reacted(&self, emoji: &str, user_id: &str) -> bool {
+        self.reactions
+            .get(emoji)
+            .map(|users| users.iter().any(|u| u == user_id))
+            .unwrap_or(false)
+    }
+
+    /// How many distinct emoji the user has reacted with.
+    pub fn reaction_count(&self, user_id: &str) -> usize {
+        self.reactions
+            .values()
+            .filter(|users| users.iter().any(|u| u == user_id))
+            .count()
+    }
+}
+
+impl ChatParticipant {
+    pub fn sort_key(user_id: &str) -> String {
+        format!("PARTICIPANT#{}", user_id)
+    }
+
+    /// The last message read, as `(timestamp, message_id)`.
+    pub fn read_marker(&self) -> Option<(&str, &str)> {
+        Some((
+            self.last_read_at.as_deref()?,
+            self.last_read_message_id.as_deref()?,
+        ))
+    }
+
+    pub fn to_entity(&self) -> Result<GameEntity, serde_json::Error> {
+        GameEntity::new(
+            "chat_participant",
+            &format!("CHAT#{}", self.chat_id),
+            &Self::sort_key(&self.user_id),
+        )
+        .with_gsi2(
+            &format!("USER#{}", self.user_id),
+            &format!("CHAT#{}", self.chat_id),
+        )
+        .with_data(self)
+    }
+}
+
+impl ChatMessageRef {
+    pub fn to_entity(&self) -> Result<GameEntity, serde_json::Error> {
+        GameEntity::new(
+            "message_ref",
+            &format!("CHAT#{}", self.chat_id),
+            &format!("MSGID#{}", self.message_id),
+        )
+        .with_data(self)
+    }
 }
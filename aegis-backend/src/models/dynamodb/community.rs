@@ -1,18 +1,44 @@
 use super::entity::GameEntity;
 use serde::{Deserialize, Serialize};
 
+pub const COMMUNITY_TYPES: &[&str] = &["public", "private", "tournament"];
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Community {
     pub id: String,
     pub name: String,
     pub description: String,
-    pub community_type: String,  // "public", "private", "tournament"
-    pub owner: String,           // Player UUID
-    pub moderators: Vec<String>, // Player UUIDs
-    pub members: Vec<String>,    // Player UUIDs
-    pub member_count: i32,
+    pub community_type: String, // "public", "private", "tournament"
+    pub owner: String,          // Player UUID
+    pub member_count: i32,      // Maintained atomically alongside member items
     pub created_at: String,
     pub updated_at: String,
+    #[serde(default)]
+    pub version: u64,
+}
+
+pub const ROLE_OWNER: &str = "owner";
+pub const ROLE_MODERATOR: &str = "moderator";
+pub const ROLE_MEMBER: &str = "member";
+
+/// One item per member, indexed on GSI2 by user for "communities I'm in".
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CommunityMember {
+    pub community_id: String,
+    pub user_id: String,
+    pub role: String, // "owner", "moderator", "member"
+    #[serde(default)]
+    pub muted: bool, // Muted members cannot post
+    pub joined_at: String,
+}
+
+// Banned users cannot join or request to join
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CommunityBan {
+    pub community_id: String,
+    pub user_id: String,
+    pub banned_by: String,
+    pub created_at: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -25,7 +51,23 @@ pub struct CommunityPost {
     pub created_at: String,
 }
 
+// Pending request to join a private community
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CommunityJoinRequest {
+    pub community_id: String,
+    pub user_id: String,
+    pub created_at: String,
+}
+
 impl Community {
+    pub fn is_private(&self) -> bool {
+        self.community_type == "private"
+    }
+
+    pub fn is_owner(&self, user_id: &str) -> bool {
+        self.owner == user_id
+    }
+
     pub fn to_entity(&self) -> Result<GameEntity, serde_json::Error> {
         GameEntity::new("community", &format!("COMMUNITY#{}", self.id), "METADATA")
             .with_gsi(&format!("OWNER#{}", self.owner), &self.created_at)
@@ -44,3 +86,60 @@ impl CommunityPost {
         .with_data(self)
     }
 }
+
+impl CommunityJoinRequest {
+    pub fn sort_key(user_id: &str) -> String {
+        format!("JOINREQ#{}", user_id)
+    }
+
+    pub fn to_entity(&self) -> Result<GameEntity, serde_json::Error> {
+        GameEntity::new(
+            "community_join_request",
+            &format!("COMMUNITY#{}", self.community_id),
+            &Self::sort_key(&self.user_id),
+        )
+        .with_data(self)
+    }
+}
+
+impl CommunityMember {
+    pub fn sort_key(user_id: &str) -> String {
+        format!("MEMBER#{}", user_id)
+    }
+
+    pub fn is_owner(&self) -> bool {
+        self.role == ROLE_OWNER
+    }
+
+    pub fn is_moderator(&self) -> bool {
+        self.role == ROLE_OWNER || self.role == ROLE_MODERATOR
+    }
+
+    pub fn to_entity(&self) -> Result<GameEntity, serde_json::Error> {
+        GameEntity::new(
+            "community_member",
+            &format!("COMMUNITY#{}", self.community_id),
+            &Self::sort_key(&self.user_id),
+        )
+        .with_gsi2(
+            &format!("USER#{}", self.user_id),
+            &format!("COMMUNITY#{}", self.community_id),
+        )
+        .with_data(self)
+    }
+}
+
+impl CommunityBan {
+    pub fn sort_key(user_id: &str) -> String {
+        format!("BAN#{}", user_id)
+    }
+
+    pub fn to_entity(&self) -> Result<GameEntity, serde_json::Error> {
+        GameEntity::new(
+            "community_ban",
+            &format!("COMMUNITY#{}", self.community_id),
+            &Self::sort_key(&self.user_id),
+        )
+        .with_data(self)
+    }
+}
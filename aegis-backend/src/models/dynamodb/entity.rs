@@ -2,14 +2,24 @@ use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GameEntity {
-    pub pk: String,              // Partition Key
-    pub sk: String,              // Sort Key
-    pub entity_type: String,     // "chat", "post", "community", etc.
+    pub pk: String,          // Partition Key
+    pub sk: String,          // Sort Key
+    pub entity_type: String, // "chat", "post", "community", etc.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub gsi1_pk: Option<String>, // GSI1 Partition Key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub gsi1_sk: Option<String>, // GSI1 Sort Key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gsi2_pk: Option<String>, // GSI2 Partition Key (sparse)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gsi2_sk: Option<String>, // GSI2 Sort Key (sparse)
     pub data: serde_json::Value, // Entity-specific data
-    pub created_at: String,      // ISO 8601 timestamp
-    pub updated_at: String,      // ISO 8601 timestamp
+    #[serde(default)]
+    pub version: u64, // Bumped on every update; mirrored in `data.version` for versioned models
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<i64>, // Epoch seconds after which DynamoDB may expire the item
+    pub created_at: String,  // ISO 8601 timestamp
+    pub updated_at: String,  // ISO 8601 timestamp
 }
 
 impl GameEntity {
@@ -21,7 +31,11 @@ impl GameEntity {
             entity_type: entity_type.to_string(),
             gsi1_pk: None,
             gsi1_sk: None,
+            gsi2_pk: None,
+            gsi2_sk: None,
             data: serde_json::Value::Null,
+            version: 0,
+            ttl: None,
             created_at: now.clone(),
             updated_at: now,
         }
@@ -33,8 +47,27 @@ impl GameEntity {
         self
     }
 
+    pub fn with_gsi2(mut self, gsi2_pk: &str, gsi2_sk: &str) -> Self {
+        self.gsi2_pk = Some(gsi2_pk.to_string());
+        self.gsi2_sk = Some(gsi2_sk.to_string());
+        self
+    }
+
+    pub fn with_ttl(mut self, expires_at: chrono::DateTime<chrono::Utc>) -> Self {
+        self.ttl = Some(expires_at.timestamp());
+        self
+    }
+
+    /// Models opt into optimistic concurrency by carrying a `version: u64`
+    /// field. It is copied into `GameEntity::version` here, which is what
+    /// `DynamoRepository::put_item_versioned` checks and bumps.
     pub fn with_data<T: Serialize>(mut self, data: &T) -> Result<Self, serde_json::Error> {
         self.data = serde_json::to_value(data)?;
+        self.version = self
+            .data
+            .get("version")
+            .and_then(serde_json::Value::as_u64)
+            .unwrap_or(0);
         Ok(self)
     }
 }
@@ -0,0 +1,27 @@
+use super::entity::GameEntity;
+use serde::{Deserialize, Serialize};
+
+/// One post in a user's home timeline, written at post time (fan-out on
+/// write) so reading the feed is a single partition query.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TimelineItem {
+    pub user_id: String, // Timeline owner
+    pub post_id: String,
+    pub author: String,
+    pub source: String,            // "self", "follow", "community", "team"
+    pub source_id: Option<String>, // Community or team the post arrived through
+    pub post_created_at: String,
+}
+
+impl TimelineItem {
+    /// Keyed by post time so the feed is ordered by recency, with the post ID
+    /// appended so the same post reaching a user twice lands on one item.
+    pub fn to_entity(&self) -> Result<GameEntity, serde_json::Error> {
+        GameEntity::new(
+            "timeline_item",
+            &format!("FEED#{}", self.user_id),
+            &format!("POST#{}#{}", self.post_created_at, self.post_id),
+        )
+        .with_data(self)
+    }
+}
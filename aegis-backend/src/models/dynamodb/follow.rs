@@ -0,0 +1,80 @@
+use super::entity::GameEntity;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FollowTargetType {
+    Player,
+    Team,
+    Organization,
+}
+
+impl FollowTargetType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Player => "player",
+            Self::Team => "team",
+            Self::Organization => "organization",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Follow {
+    pub follower_id: String, // Player UUID
+    pub target_type: String, // "player", "team", "organization"
+    pub target_id: String,
+    pub created_at: String,
+}
+
+/// Denormalized counters for one player, team or organization.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct FollowCounts {
+    pub followers: i64,
+    pub following: i64,
+}
+
+impl Follow {
+    pub fn target_key(target_type: &str, target_id: &str) -> String {
+        format!("{}#{}", target_type.to_uppercase(), target_id)
+    }
+
+    pub fn follower_sort_key(created_at: &str, follower_id: &str) -> String {
+        format!("{}#USER#{}", created_at, follower_id)
+    }
+
+    /// Adjacency item under the follower, with GSI1 as the inverse
+    /// (followers of a target) edge, sorted by when the follow happened.
+    pub fn to_entity(&self) -> Result<GameEntity, serde_json::Error> {
+        let target = Self::target_key(&self.target_type, &self.target_id);
+        GameEntity::new(
+            "follow",
+            &format!("USER#{}", self.follower_id),
+            &format!("FOLLOWS#{}", target),
+        )
+        .with_gsi(
+            &format!("FOLLOWERS#{}", target),
+            &Self::follower_sort_key(&self.created_at, &self.follower_id),
+        )
+        .with_data(self)
+    }
+}
+
+impl FollowCounts {
+    pub fn key(target_type: &str, target_id: &str) -> String {
+        format!("FOLLOWCOUNT#{}", Follow::target_key(target_type, target_id))
+    }
+
+    pub fn to_entity(
+        &self,
+        target_type: &str,
+        target_id: &str,
+    ) -> Result<GameEntity, serde_json::Error> {
+        GameEntity::new(
+            "follow_count",
+            &Self::key(target_type, target_id),
+            "METADATA",
+        )
+        .with_data(self)
+    }
+}
@@ -3,9 +3,21 @@ pub mod chat;
 pub mod post;
 pub mod community;
 pub mod activity;
+pub mod follow;
+pub mod feed;
+pub mod notification;
 
 pub use entity::GameEntity;
-pub use chat::{Chat, ChatMessage};
-pub use post::{Post, PostComment};
-pub use community::{Community, CommunityPost};
-pub use activity::{ActivityLog, TryoutChat};
+pub use chat::{Chat, ChatMessage, ChatMessageRef, ChatParticipant, MessageEdit};
+pub use post::{Post, PostComment, PostCommentRef, PostLike};
+pub use community::{
+    Community, CommunityBan, CommunityJoinRequest, CommunityMember, CommunityPost, COMMUNITY_TYPES,
+    ROLE_MEMBER, ROLE_MODERATOR, ROLE_OWNER,
+};
+pub use activity::{ActivityLog, ActivityType, TryoutChat};
+pub use follow::{Follow, FollowCounts, FollowTargetType};
+pub use feed::TimelineItem;
+pub use notification::{
+    Notification, NotificationCategory, NotificationChannel, NotificationKind,
+    NotificationPreferences, NotificationRef, QuietHours, NOTIFICATION_TTL_DAYS,
+};
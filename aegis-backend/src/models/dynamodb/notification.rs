@@ -0,0 +1,264 @@
+use super::entity::GameEntity;
+use chrono::{DateTime, Duration, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// How long notifications stay in the inbox before DynamoDB expires them.
+pub const NOTIFICATION_TTL_DAYS: i64 = 90;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    TeamInvite,
+    JoinRequestApproved,
+    BattleResult,
+    ChatMention,
+}
+
+impl NotificationKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TeamInvite => "team_invite",
+            Self::JoinRequestApproved => "join_request_approved",
+            Self::BattleResult => "battle_result",
+            Self::ChatMention => "chat_mention",
+        }
+    }
+
+    pub fn category(&self) -> NotificationCategory {
+        match self {
+            Self::TeamInvite => NotificationCategory::TeamInvites,
+            Self::JoinRequestApproved => NotificationCategory::CommunityUpdates,
+            Self::BattleResult => NotificationCategory::TournamentUpdates,
+            Self::ChatMention => NotificationCategory::ChatMentions,
+        }
+    }
+}
+
+/// What users opt in or out of; each `NotificationKind` belongs to one.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationCategory {
+    TournamentUpdates,
+    TeamInvites,
+    CommunityUpdates,
+    ChatMentions,
+    Marketing,
+}
+
+impl NotificationCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TournamentUpdates => "tournament_updates",
+            Self::TeamInvites => "team_invites",
+            Self::CommunityUpdates => "community_updates",
+            Self::ChatMentions => "chat_mentions",
+            Self::Marketing => "marketing",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(value.to_string())).ok()
+    }
+
+    /// Channels used until the user chooses their own. Marketing email is
+    /// opt-in.
+    pub fn default_channels(&self) -> Vec<NotificationChannel> {
+        match self {
+            Self::ChatMentions | Self::Marketing => vec![NotificationChannel::InApp],
+            Self::TournamentUpdates | Self::TeamInvites | Self::CommunityUpdates => {
+                vec![NotificationChannel::InApp, NotificationChannel::Email]
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationChannel {
+    InApp,
+    Email,
+    Webhook,
+}
+
+/// A daily window, in the user's timezone, during which nothing is sent
+/// outside the app. Windows may wrap past midnight (22:00-07:00).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct QuietHours {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+/// Per-user delivery choices. One item per user; categories without an entry
+/// use `NotificationCategory::default_channels`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NotificationPreferences {
+    pub user_id: String,
+    #[serde(default)]
+    pub channels: BTreeMap<NotificationCategory, Vec<NotificationChannel>>,
+    #[serde(default = "default_timezone")]
+    pub timezone: Tz,
+    #[serde(default)]
+    pub quiet_hours: Option<QuietHours>,
+    #[serde(default)]
+    pub version: u64,
+    pub updated_at: String,
+}
+
+fn default_timezone() -> Tz {
+    Tz::UTC
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Notification {
+    pub id: String,
+    pub user_id: String, // Recipient UUID
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+    #[serde(default)]
+    pub link: Option<String>, // Client route to open, e.g. "/communities/<id>"
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+    pub status: String, // "unread" or "read"; a string so unread counts can filter on it
+    #[serde(default)]
+    pub read_at: Option<String>,
+    pub created_at: String,
+}
+
+/// Pointer from a notification ID to its sort key, since notifications are
+/// keyed by time for the inbox ordering.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NotificationRef {
+    pub user_id: String,
+    pub notification_id: String,
+    pub created_at: String,
+}
+
+impl Notification {
+    pub const UNREAD: &'static str = "unread";
+    pub const READ: &'static str = "read";
+
+    pub fn sort_key(created_at: &str, id: &str) -> String {
+        format!("NOTIF#{}#{}", created_at, id)
+    }
+
+    pub fn is_unread(&self) -> bool {
+        self.status == Self::UNREAD
+    }
+
+    pub fn expires_at(&self) -> chrono::DateTime<chrono::Utc> {
+        chrono::DateTime::parse_from_rfc3339(&self.created_at)
+            .map(|t| t.with_timezone(&chrono::Utc))
+            .unwrap_or_else(|_| chrono::Utc::now())
+            + chrono::Duration::days(NOTIFICATION_TTL_DAYS)
+    }
+
+    pub fn to_entity(&self) -> Result<GameEntity, serde_json::Error> {
+        GameEntity::new(
+            "notification",
+            &format!("USER#{}", self.user_id),
+            &Self::sort_key(&self.created_at, &self.id),
+        )
+        .with_ttl(self.expires_at())
+        .with_data(self)
+    }
+}
+
+impl NotificationRef {
+    pub fn to_entity(
+        &self,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<GameEntity, serde_json::Error> {
+        GameEntity::new(
+            "notification_ref",
+            &format!("USER#{}", self.user_id),
+            &format!("NOTIFID#{}", self.notification_id),
+        )
+        .with_ttl(expires_at)
+        .with_data(self)
+    }
+}
+
+impl NotificationPreferences {
+    pub const SORT_KEY: &'static str = "NOTIFICATION_PREFERENCES";
+
+    pub fn new(user_id: &str) -> Self {
+        Self {
+            user_id: user_id.to_string(),
+            channels: BTreeMap::new(),
+            timezone: default_timezone(),
+            quiet_hours: None,
+            version: 0,
+            updated_at: Utc::now().to_rfc3339(),
+        }
+    }
+
+    pub fn channels_for(&self, category: NotificationCategory) -> Vec<NotificationChannel> {
+        self.channels
+            .get(&category)
+            .cloned()
+            .unwrap_or_else(|| category.default_channels())
+    }
+
+    /// Returns `false` if the channel was already off.
+    pub fn disable(
+        &mut self,
+        category: NotificationCategory,
+        channel: NotificationChannel,
+    ) -> bool {
+        let mut channels = self.channels_for(category);
+        if !channels.contains(&channel) {
+            return false;
+        }
+        channels.retain(|c| *c != channel);
+        self.channels.insert(category, channels);
+        true
+    }
+
+    pub fn in_quiet_hours(&self, now: DateTime<Utc>) -> bool {
+        self.quiet_hours_end(now).is_some()
+    }
+
+    /// When the quiet window that `now` falls in ends, or `None` outside
+    /// quiet hours.
+    pub fn quiet_hours_end(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let quiet = self.quiet_hours.as_ref()?;
+        let local = now.with_timezone(&self.timezone).naive_local();
+        let time = local.time();
+        let in_window = if quiet.start <= quiet.end {
+            quiet.start <= time && time < quiet.end
+        } else {
+            time >= quiet.start || time < quiet.end
+        };
+        if !in_window {
+            return None;
+        }
+
+        let mut end = local.date().and_time(quiet.end);
+        if end <= local {
+            end += Duration::days(1);
+        }
+        // An end that falls in a DST gap is pushed past the gap
+        let end = self
+            .timezone
+            .from_local_datetime(&end)
+            .earliest()
+            .or_else(|| {
+                self.timezone
+                    .from_local_datetime(&(end + Duration::hours(1)))
+                    .earliest()
+            })?;
+        Some(end.with_timezone(&Utc))
+    }
+
+    pub fn to_entity(&self) -> Result<GameEntity, serde_json::Error> {
+        GameEntity::new(
+            "notification_preferences",
+            &format!("USER#{}", self.user_id),
+            Self::SORT_KEY,
+        )
+        .with_data(self)
+    }
+}
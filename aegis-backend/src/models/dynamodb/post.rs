@@ -13,6 +13,8 @@ pub struct Post {
     pub comments_count: i32,
     pub created_at: String,
     pub updated_at: String,
+    #[serde(default)]
+    pub version: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -22,6 +24,34 @@ pub struct PostComment {
     pub author: String,                // Player UUID
     pub content: String,
     pub created_at: String,
+    #[serde(default)]
+    pub parent_id: Option<String>,     // Set on replies; replies are one level deep
+    #[serde(default)]
+    pub reply_count: i32,
+    #[serde(default)]
+    pub edited_at: Option<String>,
+    #[serde(default)]
+    pub deleted: bool,
+    #[serde(default)]
+    pub deleted_at: Option<String>,
+    #[serde(default)]
+    pub deleted_by: Option<String>,
+}
+
+// Points a comment ID at its sort key so comments can be loaded by ID
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PostCommentRef {
+    pub post_id: String,
+    pub comment_id: String,
+    pub sort_key: String,
+}
+
+// One item per (post, user); its presence is the like
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PostLike {
+    pub post_id: String,
+    pub user_id: String,               // Player UUID
+    pub created_at: String,
 }
 
 impl Post {
@@ -33,13 +63,67 @@ impl Post {
 }
 
 impl PostComment {
+    /// Top-level comments sort by time; replies are grouped under their parent.
+    pub fn sort_key(&self) -> String {
+        match &self.parent_id {
+            Some(parent_id) => format!("{}{}#{}", Self::reply_prefix(parent_id), self.created_at, self.id),
+            None => format!("COMMENT#{}#{}", self.created_at, self.id),
+        }
+    }
+
+    pub fn reply_prefix(parent_id: &str) -> String {
+        format!("REPLY#{}#", parent_id)
+    }
+
+    // Top-level comments are also indexed by reply count for "top" ordering
+    pub fn top_index_pk(post_id: &str) -> String {
+        format!("COMMENTS#{}", post_id)
+    }
+
+    pub fn top_index_sk(reply_count: i64, created_at: &str) -> String {
+        format!("{:010}#{}", reply_count, created_at)
+    }
+
     pub fn to_entity(&self) -> Result<GameEntity, serde_json::Error> {
-        GameEntity::new(
+        let mut entity = GameEntity::new(
             "comment",
             &format!("POST#{}", self.post_id),
-            &format!("COMMENT#{}", self.created_at)
+            &self.sort_key()
+        )
+        .with_gsi(&format!("USER#{}", self.author), &self.created_at);
+
+        if self.parent_id.is_none() {
+            entity = entity.with_gsi2(
+                &Self::top_index_pk(&self.post_id),
+                &Self::top_index_sk(self.reply_count.into(), &self.created_at)
+            );
+        }
+        entity.with_data(self)
+    }
+}
+
+impl PostCommentRef {
+    pub fn to_entity(&self) -> Result<GameEntity, serde_json::Error> {
+        GameEntity::new(
+            "comment_ref",
+            &format!("POST#{}", self.post_id),
+            &format!("COMMENTID#{}", self.comment_id)
+        )
+        .with_data(self)
+    }
+}
+
+impl PostLike {
+    pub fn sort_key(user_id: &str) -> String {
+        format!("LIKE#{}", user_id)
+    }
+
+    pub fn to_entity(&self) -> Result<GameEntity, serde_json::Error> {
+        GameEntity::new(
+            "post_like",
+            &format!("POST#{}", self.post_id),
+            &Self::sort_key(&self.user_id)
         )
-        .with_gsi(&format!("USER#{}", self.author), &self.created_at)
         .with_data(self)
     }
 }
@@ -1,8 +1,88 @@
 use crate::models::enums::AdminRole;
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use uuid::Uuid;
 
+/// What an admin may do. Each admin route requires one of these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum AdminPermission {
+    #[serde(rename = "orgs.approve")]
+    OrgsApprove,
+    #[serde(rename = "tournaments.approve")]
+    TournamentsApprove,
+    #[serde(rename = "users.ban")]
+    UsersBan,
+    #[serde(rename = "payouts.approve")]
+    PayoutsApprove,
+    #[serde(rename = "audit.read")]
+    AuditRead,
+    #[serde(rename = "identity.review")]
+    IdentityReview,
+    #[serde(rename = "email_outbox.manage")]
+    EmailOutboxManage,
+    #[serde(rename = "content.moderate")]
+    ContentModerate,
+    /// Creating admins and editing their permissions. Reserved for super
+    /// admins: overrides can't grant it.
+    #[serde(rename = "admins.manage")]
+    AdminsManage,
+}
+
+impl AdminPermission {
+    pub const ALL: &'static [AdminPermission] = &[
+        AdminPermission::OrgsApprove,
+        AdminPermission::TournamentsApprove,
+        AdminPermission::UsersBan,
+        AdminPermission::PayoutsApprove,
+        AdminPermission::AuditRead,
+        AdminPermission::IdentityReview,
+        AdminPermission::EmailOutboxManage,
+        AdminPermission::ContentModerate,
+        AdminPermission::AdminsManage,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdminPermission::OrgsApprove => "orgs.approve",
+            AdminPermission::TournamentsApprove => "tournaments.approve",
+            AdminPermission::UsersBan => "users.ban",
+            AdminPermission::PayoutsApprove => "payouts.approve",
+            AdminPermission::AuditRead => "audit.read",
+            AdminPermission::IdentityReview => "identity.review",
+            AdminPermission::EmailOutboxManage => "email_outbox.manage",
+            AdminPermission::ContentModerate => "content.moderate",
+            AdminPermission::AdminsManage => "admins.manage",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|p| p.as_str() == value)
+    }
+}
+
+/// Permissions an admin of `role` holds unless their overrides say otherwise.
+pub fn role_permissions(role: &AdminRole) -> &'static [AdminPermission] {
+    match role {
+        AdminRole::SuperAdmin => AdminPermission::ALL,
+        AdminRole::Admin => &[
+            AdminPermission::OrgsApprove,
+            AdminPermission::TournamentsApprove,
+            AdminPermission::UsersBan,
+            AdminPermission::PayoutsApprove,
+            AdminPermission::AuditRead,
+            AdminPermission::IdentityReview,
+            AdminPermission::EmailOutboxManage,
+            AdminPermission::ContentModerate,
+        ],
+        AdminRole::Moderator => &[
+            AdminPermission::UsersBan,
+            AdminPermission::IdentityReview,
+            AdminPermission::ContentModerate,
+        ],
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "admins")]
 pub struct Model {
@@ -10,8 +90,11 @@ pub struct Model {
     pub id: Uuid,
     pub username: String,
     pub email: String,
+    #[serde(skip_serializing)]
     pub password: String,
     pub role: AdminRole,
+    /// Per-admin overrides of the role's permissions, as
+    /// `{"<permission>": true | false}`. Unknown keys are ignored.
     pub permissions: Json,
     pub is_active: bool,
     pub last_login: Option<ChronoDateTimeUtc>,
@@ -21,6 +104,43 @@ pub struct Model {
     pub updated_at: ChronoDateTimeUtc,
 }
 
+impl Model {
+    pub fn permission_overrides(&self) -> BTreeMap<AdminPermission, bool> {
+        self.permissions
+            .as_object()
+            .map(|overrides| {
+                overrides
+                    .iter()
+                    .filter_map(|(key, value)| {
+                        Some((AdminPermission::parse(key)?, value.as_bool()?))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The role's permissions with this admin's overrides applied. Super
+    /// admins hold every permission whatever their overrides say.
+    pub fn effective_permissions(&self) -> BTreeSet<AdminPermission> {
+        let mut permissions: BTreeSet<_> = role_permissions(&self.role).iter().copied().collect();
+        if self.role == AdminRole::SuperAdmin {
+            return permissions;
+        }
+        for (permission, granted) in self.permission_overrides() {
+            if granted && permission != AdminPermission::AdminsManage {
+                permissions.insert(permission);
+            } else {
+                permissions.remove(&permission);
+            }
+        }
+        permissions
+    }
+
+    pub fn can(&self, permission: AdminPermission) -> bool {
+        self.effective_permissions().contains(&permission)
+    }
+}
+
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {}
 
@@ -8,6 +8,7 @@ pub struct Model {
     #[sea_orm(primary_key)]
     pub id: Uuid,
     pub key_id: String,
+    #[serde(skip_serializing)]
     pub key_hash: String,
     pub name: String,
     pub owner_id: Uuid,
@@ -0,0 +1,49 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// `status` values; messages move from pending to sent, or to failed once
+/// the worker runs out of attempts.
+pub const PENDING: &str = "pending";
+pub const SENT: &str = "sent";
+pub const FAILED: &str = "failed";
+
+const REDACTED: &str = "[redacted]";
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "email_outbox")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub recipient: String,
+    pub template: String,
+    pub languages: Vec<String>,
+    pub vars: Json,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: ChronoDateTimeUtc,
+    pub last_error: Option<String>,
+    pub sent_at: Option<ChronoDateTimeUtc>,
+    pub created_at: ChronoDateTimeUtc,
+    pub updated_at: ChronoDateTimeUtc,
+}
+
+impl Model {
+    /// The message with single-use tokens in `vars` (password reset,
+    /// verification and unsubscribe links) masked, for showing to admins.
+    pub fn redacted(mut self) -> Self {
+        if let Some(vars) = self.vars.as_object_mut() {
+            for (name, value) in vars.iter_mut() {
+                if name == "token" || name.ends_with("_token") {
+                    *value = Json::String(REDACTED.to_string());
+                }
+            }
+        }
+        self
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
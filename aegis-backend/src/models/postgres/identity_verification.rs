@@ -0,0 +1,39 @@
+use crate::models::enums::ApprovalStatus;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "identity_verifications")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub player_id: Uuid,
+    pub document_type: String,
+    pub document_keys: Vec<String>,
+    pub status: ApprovalStatus,
+    pub submitted_at: ChronoDateTimeUtc,
+    pub reviewed_by: Option<Uuid>,
+    pub reviewed_at: Option<ChronoDateTimeUtc>,
+    pub rejection_reason: Option<String>,
+    pub created_at: ChronoDateTimeUtc,
+    pub updated_at: ChronoDateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::player::Entity",
+        from = "Column::PlayerId",
+        to = "super::player::Column::Id"
+    )]
+    Player,
+}
+
+impl Related<super::player::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Player.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
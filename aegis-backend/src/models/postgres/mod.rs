@@ -2,30 +2,40 @@ pub mod admin;
 pub mod api_key;
 pub mod audit_log;
 pub mod battle;
+pub mod email_outbox;
+pub mod identity_verification;
 pub mod organization;
 pub mod player;
 pub mod player_game_stats;
-pub mod rate_limit;
 pub mod reward;
 pub mod team;
 pub mod tournament;
 pub mod tournament_team;
 pub mod tournament_team_invite;
 pub mod transaction;
+pub mod two_factor_credential;
+pub mod two_factor_policy;
 pub mod user_session;
+pub mod webhook_delivery;
+pub mod webhook_endpoint;
 
 pub use admin::Entity as Admin;
 pub use api_key::Entity as ApiKey;
 pub use audit_log::Entity as AuditLog;
 pub use battle::Entity as Battle;
+pub use email_outbox::Entity as EmailOutbox;
+pub use identity_verification::Entity as IdentityVerification;
 pub use organization::Entity as Organization;
 pub use player::Entity as Player;
 pub use player_game_stats::Entity as PlayerGameStats;
-pub use rate_limit::Entity as RateLimit;
 pub use reward::Entity as Reward;
 pub use team::Entity as Team;
 pub use tournament::Entity as Tournament;
 pub use tournament_team::Entity as TournamentTeam;
 pub use tournament_team_invite::Entity as TournamentTeamInvite;
 pub use transaction::Entity as Transaction;
+pub use two_factor_credential::Entity as TwoFactorCredential;
+pub use two_factor_policy::Entity as TwoFactorPolicy;
 pub use user_session::Entity as UserSession;
+pub use webhook_delivery::Entity as WebhookDelivery;
+pub use webhook_endpoint::Entity as WebhookEndpoint;
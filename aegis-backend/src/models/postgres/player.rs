@@ -15,6 +15,8 @@ pub struct Model {
     pub email: String,
     pub password: String,
     pub verified: bool,
+    pub identity_verified: bool,
+    pub identity_verified_at: Option<ChronoDateTimeUtc>,
     pub country: Option<String>,
     pub bio: String,
     pub profile_picture: String,
@@ -97,4 +97,11 @@ impl Related<super::transaction::Entity> for Entity {
     }
 }
 
+impl Model {
+    /// Pro-tier events pay out prize money, so every rostered player must be KYC verified.
+    pub fn requires_identity_verification(&self) -> bool {
+        self.tier.eq_ignore_ascii_case("pro")
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,36 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "two_factor_credentials")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub user_type: String,
+    /// Hex-encoded TOTP secret.
+    #[serde(skip_serializing)]
+    pub secret: String,
+    /// SHA-256 digests of the unused recovery codes.
+    #[serde(skip_serializing)]
+    pub recovery_code_hashes: Vec<String>,
+    /// The last TOTP time step accepted, so a code can't be replayed.
+    pub last_used_step: Option<i64>,
+    /// Set once the first code has been verified; until then the
+    /// enrolment is pending and sign-in doesn't ask for a code.
+    pub enabled_at: Option<ChronoDateTimeUtc>,
+    pub created_at: ChronoDateTimeUtc,
+    pub updated_at: ChronoDateTimeUtc,
+}
+
+impl Model {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled_at.is_some()
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -3,17 +3,13 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
-#[sea_orm(table_name = "rate_limits")]
+#[sea_orm(table_name = "two_factor_policies")]
 pub struct Model {
-    #[sea_orm(primary_key)]
-    pub id: Uuid,
-    pub identifier: String,
-    pub identifier_type: String,
-    pub action: String,
-    pub attempts: i32,
-    pub window_start: ChronoDateTimeUtc,
-    pub blocked_until: Option<ChronoDateTimeUtc>,
-    pub created_at: ChronoDateTimeUtc,
+    /// An admin role, `organization` or `player`.
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub role: String,
+    pub required: bool,
+    pub updated_by: Option<Uuid>,
     pub updated_at: ChronoDateTimeUtc,
 }
 
@@ -0,0 +1,78 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Events an organization can subscribe an endpoint to. `Test` is only sent
+/// on request and needs no subscription.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebhookEvent {
+    #[serde(rename = "tournament.registration")]
+    TournamentRegistration,
+    #[serde(rename = "battle.scheduled")]
+    BattleScheduled,
+    #[serde(rename = "battle.completed")]
+    BattleCompleted,
+    #[serde(rename = "standings.updated")]
+    StandingsUpdated,
+    #[serde(rename = "webhook.test")]
+    Test,
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::TournamentRegistration => "tournament.registration",
+            WebhookEvent::BattleScheduled => "battle.scheduled",
+            WebhookEvent::BattleCompleted => "battle.completed",
+            WebhookEvent::StandingsUpdated => "standings.updated",
+            WebhookEvent::Test => "webhook.test",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "webhook_endpoints")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub url: String,
+    #[serde(skip_serializing)] // Only revealed once, when the endpoint is created
+    pub secret: String,
+    pub events: Vec<String>,
+    pub is_active: bool,
+    pub created_at: ChronoDateTimeUtc,
+    pub updated_at: ChronoDateTimeUtc,
+}
+
+impl Model {
+    pub fn subscribes_to(&self, event: WebhookEvent) -> bool {
+        event == WebhookEvent::Test || self.events.iter().any(|e| e == event.as_str())
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::organization::Entity",
+        from = "Column::OrganizationId",
+        to = "super::organization::Column::Id"
+    )]
+    Organization,
+    #[sea_orm(has_many = "super::webhook_delivery::Entity")]
+    WebhookDelivery,
+}
+
+impl Related<super::organization::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Organization.def()
+    }
+}
+
+impl Related<super::webhook_delivery::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WebhookDelivery.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
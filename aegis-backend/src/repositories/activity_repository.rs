@@ -0,0 +1,49 @@
+use super::{DynamoRepository, Page, PageRequest};
+use crate::models::dynamodb::ActivityLog;
+use anyhow::Result;
+
+#[derive(Clone)]
+pub struct ActivityRepository {
+    dynamo: DynamoRepository,
+}
+
+impl ActivityRepository {
+    pub fn new(dynamo: DynamoRepository) -> Self {
+        Self { dynamo }
+    }
+
+    pub async fn record(&self, activity: &ActivityLog) -> Result<()> {
+        let entity = activity.to_entity()?;
+        self.dynamo.put_item(&entity).await
+    }
+
+    /// One player's timeline, ordered by time.
+    pub async fn get_user_activity(
+        &self,
+        user_id: &str,
+        request: &PageRequest,
+    ) -> Result<Page<ActivityLog>> {
+        let pk = format!("USER#{}", user_id);
+        let page = self
+            .dynamo
+            .query_page(&pk, Some("ACTIVITY#"), request)
+            .await?
+            .try_map(|entity| serde_json::from_value::<ActivityLog>(entity.data))?;
+        Ok(page)
+    }
+
+    /// Everyone's activity of one type, ordered by time (GSI1).
+    pub async fn get_activity_by_type(
+        &self,
+        activity_type: &str,
+        request: &PageRequest,
+    ) -> Result<Page<ActivityLog>> {
+        let gsi1_pk = format!("TYPE#{}", activity_type);
+        let page = self
+            .dynamo
+            .query_gsi1_page(&gsi1_pk, None, request)
+            .await?
+            .try_map(|entity| serde_json::from_value::<ActivityLog>(entity.data))?;
+        Ok(page)
+    }
+}
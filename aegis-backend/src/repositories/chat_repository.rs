@@ -1,7 +1,8 @@
 use anyhow::Result;
+use serde_json::json;
 use uuid::Uuid;
-use crate::models::dynamodb::{Chat, ChatMessage};
-use super::{Repository, DynamoRepository};
+use crate::models::dynamodb::{Chat, ChatMessage, ChatMessageRef, ChatParticipant};
+use super::{CountedWrite, CounterDelta, Repository, DynamoRepository, Page, PageRequest, ScanDirection};
 
 #[derive(Clone)]
 pub struct ChatRepository {
@@ -23,6 +24,13 @@ impl ChatRepository {
         Ok(chat.id)
     }
 
+    /// Creates a chat with a caller-chosen (deterministic) ID. Returns `false`
+    /// if a chat with that ID already exists.
+    pub async fn create_chat_if_absent(&self, chat: &Chat) -> Result<bool> {
+        let entity = chat.to_entity()?;
+        self.dynamo.put_item_if_absent(&entity).await
+    }
+
     pub async fn get_chat(&self, chat_id: &str) -> Result<Option<Chat>> {
         let pk = format!("CHAT#{}", chat_id);
         if let Some(entity) = self.dynamo.get_item(&pk, "METADATA").await? {
@@ -38,29 +46,143 @@ impl ChatRepository {
         }
         
         let entity = message.to_entity()?;
-        self.dynamo.put_item(&entity).await?;
+        if !self.dynamo.put_item_if_absent(&entity).await? {
+            anyhow::bail!("Message {} already exists in chat {}", message.id, message.chat_id);
+        }
+
+        let message_ref = ChatMessageRef {
+            chat_id: message.chat_id.clone(),
+            message_id: message.id.clone(),
+            sort_key: entity.sk.clone(),
+        };
+        self.dynamo.put_item(&message_ref.to_entity()?).await?;
+
         Ok(message.id)
     }
 
-    pub async fn get_messages(&self, chat_id: &str, limit: Option<i32>) -> Result<Vec<ChatMessage>> {
+    pub async fn get_message(&self, chat_id: &str, message_id: &str) -> Result<Option<ChatMessage>> {
         let pk = format!("CHAT#{}", chat_id);
-        let entities = self.dynamo.query_by_pk(&pk).await?;
-        
-        let mut messages: Vec<ChatMessage> = Vec::new();
-        for entity in entities {
-            if entity.entity_type == "message" {
-                messages.push(serde_json::from_value(entity.data)?);
-            }
+
+        if let Some(entity) = self.dynamo.get_item(&pk, &format!("MSGID#{}", message_id)).await? {
+            let message_ref: ChatMessageRef = serde_json::from_value(entity.data)?;
+            return match self.dynamo.get_item(&pk, &message_ref.sort_key).await? {
+                Some(entity) => Ok(Some(serde_json::from_value(entity.data)?)),
+                None => Ok(None),
+            };
         }
-        
-        // Sort by timestamp (newest first)
-        messages.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
-        
-        if let Some(limit) = limit {
-            messages.truncate(limit as usize);
+        Ok(None)
+    }
+
+    /// Fails with `VersionConflict` if the message changed since it was read.
+    pub async fn update_message(&self, message: &ChatMessage) -> Result<()> {
+        let entity = message.to_entity()?;
+        self.dynamo.put_item_versioned(&entity).await
+    }
+
+    /// Counts messages from others after the `(timestamp, message_id)` read
+    /// marker (or all, if unset).
+    pub async fn count_messages_after(&self, chat_id: &str, after: Option<(&str, &str)>, exclude_sender: &str) -> Result<i64> {
+        let pk = format!("CHAT#{}", chat_id);
+        let lower = match after {
+            Some((timestamp, message_id)) => ChatMessage::sort_key(timestamp, message_id),
+            None => "MSG#".to_string(),
+        };
+        self.dynamo
+            .count_sk_range(&pk, &lower, "MSG$", Some(("sender", exclude_sender)))
+            .await
+    }
+
+    /// Updates the name and admins without touching the participant count.
+    pub async fn update_chat_details(&self, chat_id: &str, name: &str, admins: &[String]) -> Result<bool> {
+        let pk = format!("CHAT#{}", chat_id);
+        self.dynamo
+            .update_fields(&pk, "METADATA", &[("name", json!(name)), ("admins", json!(admins))], None)
+            .await
+    }
+
+    /// Creates the participant record unless one exists, preserving read
+    /// markers, and bumps the chat's count in the same transaction. Returns
+    /// `false` if already present.
+    pub async fn add_participant(&self, participant: &ChatParticipant) -> Result<bool> {
+        let entity = participant.to_entity()?;
+        let pk = format!("CHAT#{}", participant.chat_id);
+        self.dynamo.write_counted(CountedWrite::Put(&entity), &[participant_count(&pk, 1)]).await
+    }
+
+    pub async fn get_participant(&self, chat_id: &str, user_id: &str) -> Result<Option<ChatParticipant>> {
+        let pk = format!("CHAT#{}", chat_id);
+        match self.dynamo.get_item(&pk, &ChatParticipant::sort_key(user_id)).await? {
+            Some(entity) => Ok(Some(serde_json::from_value(entity.data)?)),
+            None => Ok(None),
         }
-        
-        Ok(messages)
+    }
+
+    pub async fn get_participants(&self, chat_id: &str) -> Result<Vec<ChatParticipant>> {
+        let pk = format!("CHAT#{}", chat_id);
+        let mut participants = Vec::new();
+        for entity in self.dynamo.query_prefix(&pk, "PARTICIPANT#").await? {
+            participants.push(serde_json::from_value(entity.data)?);
+        }
+        Ok(participants)
+    }
+
+    pub async fn get_participants_page(&self, chat_id: &str, request: &PageRequest) -> Result<Page<ChatParticipant>> {
+        let pk = format!("CHAT#{}", chat_id);
+        let page = self
+            .dynamo
+            .query_page(&pk, Some("PARTICIPANT#"), request)
+            .await?
+            .try_map(|entity| serde_json::from_value::<ChatParticipant>(entity.data))?;
+        Ok(page)
+    }
+
+    /// Only updates an existing participant, so a read can't re-add someone
+    /// who has just left.
+    pub async fn update_read_marker(&self, chat_id: &str, user_id: &str, read_at: &str, message_id: &str) -> Result<bool> {
+        let pk = format!("CHAT#{}", chat_id);
+        self.dynamo
+            .update_fields(
+                &pk,
+                &ChatParticipant::sort_key(user_id),
+                &[("last_read_at", json!(read_at)), ("last_read_message_id", json!(message_id))],
+                None,
+            )
+            .await
+    }
+
+    /// Returns `false` if the user was not a participant.
+    pub async fn remove_participant(&self, chat_id: &str, user_id: &str) -> Result<bool> {
+        let pk = format!("CHAT#{}", chat_id);
+        let sk = ChatParticipant::sort_key(user_id);
+        self.dynamo
+            .write_counted(CountedWrite::Delete { pk: &pk, sk: &sk }, &[participant_count(&pk, -1)])
+            .await
+    }
+
+    pub async fn get_participations(&self, user_id: &str, request: &PageRequest) -> Result<Page<ChatParticipant>> {
+        let gsi2_pk = format!("USER#{}", user_id);
+        let page = self
+            .dynamo
+            .query_gsi2_page(&gsi2_pk, Some("CHAT#"), request)
+            .await?
+            .try_map(|entity| serde_json::from_value::<ChatParticipant>(entity.data))?;
+        Ok(page)
+    }
+
+    pub async fn get_messages(&self, chat_id: &str, request: &PageRequest) -> Result<Page<ChatMessage>> {
+        let pk = format!("CHAT#{}", chat_id);
+        let mut page = self
+            .dynamo
+            .query_page(&pk, Some("MSG#"), request)
+            .await?
+            .try_map(|entity| serde_json::from_value::<ChatMessage>(entity.data))?;
+
+        // Always newest first, whichever way the page was scanned
+        if request.direction == ScanDirection::Forward {
+            page.items.reverse();
+        }
+
+        Ok(page)
     }
 
     pub async fn get_chats_by_type(&self, chat_type: &str) -> Result<Vec<Chat>> {
@@ -90,7 +212,7 @@ impl Repository<Chat> for ChatRepository {
 
     async fn update(&self, chat: &Chat) -> Result<()> {
         let entity = chat.to_entity()?;
-        self.dynamo.put_item(&entity).await
+        self.dynamo.put_item_versioned(&entity).await
     }
 
     async fn delete(&self, id: &str) -> Result<()> {
@@ -98,3 +220,7 @@ impl Repository<Chat> for ChatRepository {
         self.dynamo.delete_item(&pk, "METADATA").await
     }
 }
+
+fn participant_count(pk: &str, delta: i64) -> CounterDelta<'_> {
+    CounterDelta { pk, sk: "METADATA", field: "participant_count", delta }
+}
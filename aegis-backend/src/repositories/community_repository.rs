@@ -1,6 +1,9 @@
-use super::{DynamoRepository, Repository};
-use crate::models::dynamodb::{Community, CommunityPost};
+use super::{CountedWrite, CounterDelta, DynamoRepository, Page, PageRequest, Repository};
+use crate::models::dynamodb::{
+    Community, CommunityBan, CommunityJoinRequest, CommunityMember, CommunityPost,
+};
 use anyhow::Result;
+use serde_json::json;
 use uuid::Uuid;
 
 #[derive(Clone)]
@@ -32,24 +35,224 @@ impl CommunityRepository {
         }
     }
 
-    pub async fn add_post_to_community(&self, community_post: CommunityPost) -> Result<String> {
+    /// Returns `false` if the post is already in the community.
+    pub async fn add_post_to_community(&self, community_post: &CommunityPost) -> Result<bool> {
         let entity = community_post.to_entity()?;
-        self.dynamo.put_item(&entity).await?;
-        Ok(community_post.id)
+        self.dynamo.put_item_if_absent(&entity).await
     }
 
-    pub async fn get_community_posts(&self, community_id: &str) -> Result<Vec<CommunityPost>> {
+    pub async fn get_community_posts(
+        &self,
+        community_id: &str,
+        request: &PageRequest,
+    ) -> Result<Page<CommunityPost>> {
         let pk = format!("COMMUNITY#{}", community_id);
-        let entities = self.dynamo.query_by_pk(&pk).await?;
+        let page = self
+            .dynamo
+            .query_page(&pk, Some("POST#"), request)
+            .await?
+            .try_map(|entity| serde_json::from_value::<CommunityPost>(entity.data))?;
 
-        let mut posts = Vec::new();
-        for entity in entities {
-            if entity.entity_type == "community_post" {
-                posts.push(serde_json::from_value(entity.data)?);
-            }
+        Ok(page)
+    }
+
+    pub async fn get_community_post(
+        &self,
+        community_id: &str,
+        post_id: &str,
+    ) -> Result<Option<CommunityPost>> {
+        let pk = format!("COMMUNITY#{}", community_id);
+        match self
+            .dynamo
+            .get_item(&pk, &format!("POST#{}", post_id))
+            .await?
+        {
+            Some(entity) => Ok(Some(serde_json::from_value(entity.data)?)),
+            None => Ok(None),
         }
+    }
 
-        Ok(posts)
+    /// Returns `false` if the post is not in the community.
+    pub async fn set_post_pinned(
+        &self,
+        community_id: &str,
+        post_id: &str,
+        pinned: bool,
+    ) -> Result<bool> {
+        let pk = format!("COMMUNITY#{}", community_id);
+        self.dynamo
+            .update_fields(
+                &pk,
+                &format!("POST#{}", post_id),
+                &[("pinned", json!(pinned))],
+                None,
+            )
+            .await
+    }
+
+    /// Returns `false` if the post is not in the community.
+    pub async fn remove_post(&self, community_id: &str, post_id: &str) -> Result<bool> {
+        let pk = format!("COMMUNITY#{}", community_id);
+        self.dynamo
+            .delete_item_if_present(&pk, &format!("POST#{}", post_id))
+            .await
+    }
+
+    /// Returns `false` if a request from this user is already pending.
+    pub async fn add_join_request(&self, request: &CommunityJoinRequest) -> Result<bool> {
+        let entity = request.to_entity()?;
+        self.dynamo.put_item_if_absent(&entity).await
+    }
+
+    pub async fn get_join_request(
+        &self,
+        community_id: &str,
+        user_id: &str,
+    ) -> Result<Option<CommunityJoinRequest>> {
+        let pk = format!("COMMUNITY#{}", community_id);
+        match self
+            .dynamo
+            .get_item(&pk, &CommunityJoinRequest::sort_key(user_id))
+            .await?
+        {
+            Some(entity) => Ok(Some(serde_json::from_value(entity.data)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn get_join_requests(
+        &self,
+        community_id: &str,
+        request: &PageRequest,
+    ) -> Result<Page<CommunityJoinRequest>> {
+        let pk = format!("COMMUNITY#{}", community_id);
+        let page = self
+            .dynamo
+            .query_page(&pk, Some("JOINREQ#"), request)
+            .await?
+            .try_map(|entity| serde_json::from_value::<CommunityJoinRequest>(entity.data))?;
+        Ok(page)
+    }
+
+    /// Returns `false` if there was no pending request.
+    pub async fn remove_join_request(&self, community_id: &str, user_id: &str) -> Result<bool> {
+        let pk = format!("COMMUNITY#{}", community_id);
+        self.dynamo
+            .delete_item_if_present(&pk, &CommunityJoinRequest::sort_key(user_id))
+            .await
+    }
+
+    /// Adds the member item and bumps `member_count` in one transaction.
+    /// Returns `false` if the user was already a member.
+    pub async fn add_member(&self, member: &CommunityMember) -> Result<bool> {
+        let entity = member.to_entity()?;
+        let pk = format!("COMMUNITY#{}", member.community_id);
+        self.dynamo
+            .write_counted(CountedWrite::Put(&entity), &[member_count(&pk, 1)])
+            .await
+    }
+
+    /// Returns `false` if the user was not a member.
+    pub async fn remove_member(&self, community_id: &str, user_id: &str) -> Result<bool> {
+        let pk = format!("COMMUNITY#{}", community_id);
+        let sk = CommunityMember::sort_key(user_id);
+        self.dynamo
+            .write_counted(
+                CountedWrite::Delete { pk: &pk, sk: &sk },
+                &[member_count(&pk, -1)],
+            )
+            .await
+    }
+
+    pub async fn get_member(
+        &self,
+        community_id: &str,
+        user_id: &str,
+    ) -> Result<Option<CommunityMember>> {
+        let pk = format!("COMMUNITY#{}", community_id);
+        match self
+            .dynamo
+            .get_item(&pk, &CommunityMember::sort_key(user_id))
+            .await?
+        {
+            Some(entity) => Ok(Some(serde_json::from_value(entity.data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Sets fields on an existing member item. Returns `false` if the user
+    /// is not a member.
+    pub async fn update_member(
+        &self,
+        community_id: &str,
+        user_id: &str,
+        fields: &[(&str, serde_json::Value)],
+    ) -> Result<bool> {
+        let pk = format!("COMMUNITY#{}", community_id);
+        self.dynamo
+            .update_fields(&pk, &CommunityMember::sort_key(user_id), fields, None)
+            .await
+    }
+
+    pub async fn get_members(
+        &self,
+        community_id: &str,
+        request: &PageRequest,
+    ) -> Result<Page<CommunityMember>> {
+        let pk = format!("COMMUNITY#{}", community_id);
+        let page = self
+            .dynamo
+            .query_page(&pk, Some("MEMBER#"), request)
+            .await?
+            .try_map(|entity| serde_json::from_value::<CommunityMember>(entity.data))?;
+        Ok(page)
+    }
+
+    /// Every member ID, for fan-out.
+    pub async fn get_member_ids(&self, community_id: &str) -> Result<Vec<String>> {
+        let pk = format!("COMMUNITY#{}", community_id);
+        let mut member_ids = Vec::new();
+        for entity in self.dynamo.query_prefix(&pk, "MEMBER#").await? {
+            let member: CommunityMember = serde_json::from_value(entity.data)?;
+            member_ids.push(member.user_id);
+        }
+        Ok(member_ids)
+    }
+
+    pub async fn get_memberships(
+        &self,
+        user_id: &str,
+        request: &PageRequest,
+    ) -> Result<Page<CommunityMember>> {
+        let gsi2_pk = format!("USER#{}", user_id);
+        let page = self
+            .dynamo
+            .query_gsi2_page(&gsi2_pk, Some("COMMUNITY#"), request)
+            .await?
+            .try_map(|entity| serde_json::from_value::<CommunityMember>(entity.data))?;
+        Ok(page)
+    }
+
+    pub async fn add_ban(&self, ban: &CommunityBan) -> Result<()> {
+        let entity = ban.to_entity()?;
+        self.dynamo.put_item(&entity).await
+    }
+
+    /// Returns `false` if the user was not banned.
+    pub async fn remove_ban(&self, community_id: &str, user_id: &str) -> Result<bool> {
+        let pk = format!("COMMUNITY#{}", community_id);
+        self.dynamo
+            .delete_item_if_present(&pk, &CommunityBan::sort_key(user_id))
+            .await
+    }
+
+    pub async fn is_banned(&self, community_id: &str, user_id: &str) -> Result<bool> {
+        let pk = format!("COMMUNITY#{}", community_id);
+        Ok(self
+            .dynamo
+            .get_item(&pk, &CommunityBan::sort_key(user_id))
+            .await?
+            .is_some())
     }
 
     pub async fn get_communities_by_owner(&self, owner_id: &str) -> Result<Vec<Community>> {
@@ -79,7 +282,7 @@ impl Repository<Community> for CommunityRepository {
 
     async fn update(&self, community: &Community) -> Result<()> {
         let entity = community.to_entity()?;
-        self.dynamo.put_item(&entity).await
+        self.dynamo.put_item_versioned(&entity).await
     }
 
     async fn delete(&self, id: &str) -> Result<()> {
@@ -87,3 +290,12 @@ impl Repository<Community> for CommunityRepository {
         self.dynamo.delete_item(&pk, "METADATA").await
     }
 }
+
+fn member_count(pk: &str, delta: i64) -> CounterDelta<'_> {
+    CounterDelta {
+        pk,
+        sk: "METADATA",
+        field: "member_count",
+        delta,
+    }
+}
@@ -1,69 +1,194 @@
+use super::storage::{
+    CountedWrite, CounterDelta, CursorKey, DynamoStorage, Index, KeyQuery, MemoryStorage,
+    PutCondition, ScanDirection, Storage,
+};
 use crate::models::dynamodb::GameEntity;
-use anyhow::Result;
-use aws_sdk_dynamodb::{types::AttributeValue, Client};
-use serde_dynamo::{from_item, to_item};
-use std::collections::HashMap;
+use anyhow::{anyhow, Result};
+use aws_sdk_dynamodb::Client;
+use serde::Serialize;
+use std::sync::Arc;
 
+/// How many times a read-modify-write is retried after a `VersionConflict`.
+pub const MAX_CONFLICT_RETRIES: u32 = 3;
+
+pub const DEFAULT_PAGE_SIZE: i32 = 50;
+pub const MAX_PAGE_SIZE: i32 = 100;
+
+#[derive(Debug, Clone)]
+pub struct PageRequest {
+    pub limit: i32,
+    pub direction: ScanDirection,
+    cursor: Option<CursorKey>,
+}
+
+impl PageRequest {
+    pub fn new(limit: Option<i32>, direction: ScanDirection) -> Self {
+        Self {
+            limit: limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE),
+            direction,
+            cursor: None,
+        }
+    }
+
+    /// Builds a request from the `before`/`after` query parameters exposed by
+    /// list endpoints. `before` walks backwards along the sort key from the
+    /// cursor, `after` walks forwards; with neither, `default_direction` is
+    /// used from the start of the collection.
+    pub fn from_cursors(
+        limit: Option<i32>,
+        before: Option<&str>,
+        after: Option<&str>,
+        default_direction: ScanDirection,
+    ) -> Result<Self> {
+        let request = Self::new(limit, default_direction);
+        match (before, after) {
+            (Some(_), Some(_)) => Err(anyhow!("Only one of before/after may be given")),
+            (Some(cursor), None) => request.with_cursor(cursor, ScanDirection::Backward),
+            (None, Some(cursor)) => request.with_cursor(cursor, ScanDirection::Forward),
+            (None, None) => Ok(request),
+        }
+    }
+
+    pub fn with_cursor(mut self, cursor: &str, direction: ScanDirection) -> Result<Self> {
+        self.cursor = Some(CursorKey::decode(cursor)?);
+        self.direction = direction;
+        Ok(self)
+    }
+}
+
+/// A page of results in scan order. `before` and `after` are cursors for the
+/// items preceding the first and following the last item in sort-key order,
+/// so either can be passed back regardless of which direction was scanned.
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub has_more: bool,
+}
+
+impl<T> Page<T> {
+    pub fn try_map<U, E>(self, f: impl FnMut(T) -> Result<U, E>) -> Result<Page<U>, E> {
+        Ok(Page {
+            items: self.items.into_iter().map(f).collect::<Result<_, _>>()?,
+            before: self.before,
+            after: self.after,
+            has_more: self.has_more,
+        })
+    }
+}
+
+/// A versioned write found the item changed since it was read. Returned
+/// inside the `anyhow::Error`; check with [`is_version_conflict`].
+#[derive(Debug, thiserror::Error)]
+#[error("Version conflict on {pk}/{sk}: expected version {expected}")]
+pub struct VersionConflict {
+    pub pk: String,
+    pub sk: String,
+    pub expected: u64,
+}
+
+pub fn is_version_conflict(error: &anyhow::Error) -> bool {
+    error.is::<VersionConflict>()
+}
+
+/// Single-table access for the social layer. Key semantics are those of
+/// DynamoDB; the actual reads and writes go through a `Storage` backend so
+/// the repositories above can run against memory in tests.
 #[derive(Clone)]
 pub struct DynamoRepository {
-    client: Client,
-    table_name: String,
+    storage: Arc<dyn Storage>,
 }
 
 impl DynamoRepository {
     pub fn new(client: Client) -> Self {
-        let table_name = std::env::var("DYNAMODB_TABLE_NAME")
-            .unwrap_or_else(|_| "aegis_gaming_table".to_string());
-
-        Self { client, table_name }
+        Self::with_storage(Arc::new(DynamoStorage::new(client)))
     }
 
-    pub async fn put_item(&self, entity: &GameEntity) -> Result<()> {
-        let item = to_item(entity)?;
+    pub fn in_memory() -> Self {
+        Self::with_storage(Arc::new(MemoryStorage::new()))
+    }
 
-        self.client
-            .put_item()
-            .table_name(&self.table_name)
-            .set_item(Some(item))
-            .send()
-            .await?;
+    pub fn with_storage(storage: Arc<dyn Storage>) -> Self {
+        Self { storage }
+    }
 
+    pub async fn put_item(&self, entity: &GameEntity) -> Result<()> {
+        self.storage.put(entity, PutCondition::Always).await?;
         Ok(())
     }
 
-    pub async fn get_item(&self, pk: &str, sk: &str) -> Result<Option<GameEntity>> {
-        let result = self
-            .client
-            .get_item()
-            .table_name(&self.table_name)
-            .key("pk", AttributeValue::S(pk.to_string()))
-            .key("sk", AttributeValue::S(sk.to_string()))
-            .send()
-            .await?;
+    /// Writes the item only if no item with the same key exists yet.
+    /// Returns `false` when another writer got there first.
+    pub async fn put_item_if_absent(&self, entity: &GameEntity) -> Result<bool> {
+        self.storage.put(entity, PutCondition::IfAbsent).await
+    }
 
-        match result.item {
-            Some(item) => Ok(Some(from_item(item)?)),
-            None => Ok(None),
+    /// Replaces an existing item only if it is still at the version it was
+    /// read at (`entity.version`), writing it back as the next version.
+    /// Fails with `VersionConflict` if another writer got there first.
+    pub async fn put_item_versioned(&self, entity: &GameEntity) -> Result<()> {
+        let expected = entity.version;
+        let mut entity = entity.clone();
+        entity.version = expected + 1;
+        entity.updated_at = chrono::Utc::now().to_rfc3339();
+        if let Some(data) = entity.data.as_object_mut() {
+            data.insert("version".to_string(), entity.version.into());
         }
+
+        if self
+            .storage
+            .put(&entity, PutCondition::IfVersion(expected))
+            .await?
+        {
+            Ok(())
+        } else {
+            Err(VersionConflict {
+                pk: entity.pk,
+                sk: entity.sk,
+                expected,
+            }
+            .into())
+        }
+    }
+
+    /// Writes many items in batches, retrying unprocessed items with
+    /// exponential backoff.
+    pub async fn batch_put_items(&self, entities: &[GameEntity]) -> Result<()> {
+        self.storage.batch_put(entities).await
+    }
+
+    /// Fetches many items by (pk, sk). Missing items are skipped and the
+    /// result order is not guaranteed.
+    pub async fn batch_get_items(&self, keys: &[(String, String)]) -> Result<Vec<GameEntity>> {
+        self.storage.batch_get(keys).await
+    }
+
+    pub async fn get_item(&self, pk: &str, sk: &str) -> Result<Option<GameEntity>> {
+        self.storage.get(pk, sk).await
     }
 
     pub async fn query_by_pk(&self, pk: &str) -> Result<Vec<GameEntity>> {
-        let result = self
-            .client
-            .query()
-            .table_name(&self.table_name)
-            .key_condition_expression("pk = :pk")
-            .expression_attribute_values(":pk", AttributeValue::S(pk.to_string()))
-            .send()
+        let output = self
+            .storage
+            .query(&KeyQuery::all(Index::Table, pk, None))
+            .await?;
+        Ok(output.items)
+    }
+
+    /// Every item in a partition whose sort key starts with `sk_prefix`.
+    pub async fn query_prefix(&self, pk: &str, sk_prefix: &str) -> Result<Vec<GameEntity>> {
+        let output = self
+            .storage
+            .query(&KeyQuery::all(Index::Table, pk, Some(sk_prefix)))
             .await?;
+        Ok(output.items)
+    }
 
-        let mut entities = Vec::new();
-        if let Some(items) = result.items {
-            for item in items {
-                entities.push(from_item(item)?);
-            }
-        }
-        Ok(entities)
+    /// Full-table scan for one entity type. Only meant for offline data
+    /// migrations.
+    pub async fn scan_entity_type(&self, entity_type: &str) -> Result<Vec<GameEntity>> {
+        self.storage.scan_entity_type(entity_type).await
     }
 
     pub async fn query_gsi1(
@@ -71,48 +196,150 @@ impl DynamoRepository {
         gsi1_pk: &str,
         gsi1_sk_prefix: Option<&str>,
     ) -> Result<Vec<GameEntity>> {
-        let mut query = self
-            .client
-            .query()
-            .table_name(&self.table_name)
-            .index_name("GSI1")
-            .key_condition_expression("gsi1_pk = :gsi1_pk");
-
-        let mut expression_values = HashMap::new();
-        expression_values.insert(
-            ":gsi1_pk".to_string(),
-            AttributeValue::S(gsi1_pk.to_string()),
-        );
-
-        if let Some(prefix) = gsi1_sk_prefix {
-            query = query
-                .key_condition_expression("gsi1_pk = :gsi1_pk AND begins_with(gsi1_sk, :prefix)");
-            expression_values.insert(":prefix".to_string(), AttributeValue::S(prefix.to_string()));
-        }
-
-        let result = query
-            .set_expression_attribute_values(Some(expression_values))
-            .send()
+        let output = self
+            .storage
+            .query(&KeyQuery::all(Index::Gsi1, gsi1_pk, gsi1_sk_prefix))
             .await?;
+        Ok(output.items)
+    }
 
-        let mut entities = Vec::new();
-        if let Some(items) = result.items {
-            for item in items {
-                entities.push(from_item(item)?);
-            }
-        }
-        Ok(entities)
+    /// Single page of a partition, ordered by `sk`. `sk_prefix` narrows the
+    /// page to one item type (e.g. `MSG#`) so the limit counts only those.
+    pub async fn query_page(
+        &self,
+        pk: &str,
+        sk_prefix: Option<&str>,
+        request: &PageRequest,
+    ) -> Result<Page<GameEntity>> {
+        self.query_page_on(Index::Table, pk, sk_prefix, request)
+            .await
     }
 
-    pub async fn delete_item(&self, pk: &str, sk: &str) -> Result<()> {
-        self.client
-            .delete_item()
-            .table_name(&self.table_name)
-            .key("pk", AttributeValue::S(pk.to_string()))
-            .key("sk", AttributeValue::S(sk.to_string()))
-            .send()
+    /// Single page of a GSI1 partition, ordered by `gsi1_sk`.
+    pub async fn query_gsi1_page(
+        &self,
+        gsi1_pk: &str,
+        gsi1_sk_prefix: Option<&str>,
+        request: &PageRequest,
+    ) -> Result<Page<GameEntity>> {
+        self.query_page_on(Index::Gsi1, gsi1_pk, gsi1_sk_prefix, request)
+            .await
+    }
+
+    /// Single page of a GSI2 partition, ordered by `gsi2_sk`.
+    pub async fn query_gsi2_page(
+        &self,
+        gsi2_pk: &str,
+        gsi2_sk_prefix: Option<&str>,
+        request: &PageRequest,
+    ) -> Result<Page<GameEntity>> {
+        self.query_page_on(Index::Gsi2, gsi2_pk, gsi2_sk_prefix, request)
+            .await
+    }
+
+    /// Counts items in a partition with `lower < sk < upper`, skipping items
+    /// whose `data.<field>` equals `exclude_value` when given.
+    pub async fn count_sk_range(
+        &self,
+        pk: &str,
+        lower: &str,
+        upper: &str,
+        exclude: Option<(&str, &str)>,
+    ) -> Result<i64> {
+        self.storage.count_sk_range(pk, lower, upper, exclude).await
+    }
+
+    async fn query_page_on(
+        &self,
+        index: Index,
+        pk: &str,
+        sk_prefix: Option<&str>,
+        request: &PageRequest,
+    ) -> Result<Page<GameEntity>> {
+        let output = self
+            .storage
+            .query(&KeyQuery {
+                index,
+                pk,
+                sk_prefix,
+                direction: request.direction,
+                limit: Some(request.limit),
+                start: request.cursor.as_ref(),
+            })
             .await?;
+        let items = output.items;
+
+        let first = items.first().map(|e| CursorKey::from_entity(e).encode());
+        let last = items.last().map(|e| CursorKey::from_entity(e).encode());
+        let (before, after) = match request.direction {
+            ScanDirection::Forward => (first, last),
+            ScanDirection::Backward => (last, first),
+        };
+
+        Ok(Page {
+            items,
+            before,
+            after,
+            has_more: output.has_more,
+        })
+    }
 
+    /// Atomically adds `delta` to the numeric `data.<field>` of an existing
+    /// item and returns the new value, or `None` if the item does not exist.
+    pub async fn increment_field(
+        &self,
+        pk: &str,
+        sk: &str,
+        field: &str,
+        delta: i64,
+    ) -> Result<Option<i64>> {
+        self.storage.increment_field(pk, sk, field, delta).await
+    }
+
+    /// Creates or removes an item and adjusts counters on existing items in a
+    /// single transaction. Returns `false`, writing nothing, if the item was
+    /// already present (for a put) or absent (for a delete).
+    pub async fn write_counted(
+        &self,
+        write: CountedWrite<'_>,
+        counters: &[CounterDelta<'_>],
+    ) -> Result<bool> {
+        self.storage.write_counted(write, counters).await
+    }
+
+    /// Sets `data.<field>` for each pair on an existing item. When `unless` is
+    /// given, nothing is written if `data.<field>` already holds that value.
+    /// Returns `false` if the item is missing or the guard matched.
+    pub async fn update_fields(
+        &self,
+        pk: &str,
+        sk: &str,
+        fields: &[(&str, serde_json::Value)],
+        unless: Option<(&str, serde_json::Value)>,
+    ) -> Result<bool> {
+        self.storage.update_fields(pk, sk, fields, unless).await
+    }
+
+    /// Moves an item within GSI2, but only while `data.<field>` still equals
+    /// `expected`, so concurrent counter updates converge on the latest value.
+    pub async fn set_gsi2_if(
+        &self,
+        pk: &str,
+        sk: &str,
+        gsi2: (&str, &str),
+        expected: (&str, i64),
+    ) -> Result<bool> {
+        self.storage.set_gsi2_if(pk, sk, gsi2, expected).await
+    }
+
+    /// Deletes the item only if it exists. Returns `false` when there was
+    /// nothing to delete.
+    pub async fn delete_item_if_present(&self, pk: &str, sk: &str) -> Result<bool> {
+        self.storage.delete(pk, sk).await
+    }
+
+    pub async fn delete_item(&self, pk: &str, sk: &str) -> Result<()> {
+        self.storage.delete(pk, sk).await?;
         Ok(())
     }
 }
@@ -0,0 +1,37 @@
+use super::{DynamoRepository, Page, PageRequest};
+use crate::models::dynamodb::TimelineItem;
+use anyhow::Result;
+
+#[derive(Clone)]
+pub struct FeedRepository {
+    dynamo: DynamoRepository,
+}
+
+impl FeedRepository {
+    pub fn new(dynamo: DynamoRepository) -> Self {
+        Self { dynamo }
+    }
+
+    pub async fn add_timeline_items(&self, items: &[TimelineItem]) -> Result<()> {
+        let entities = items
+            .iter()
+            .map(|item| item.to_entity())
+            .collect::<Result<Vec<_>, _>>()?;
+        self.dynamo.batch_put_items(&entities).await
+    }
+
+    pub async fn get_timeline(
+        &self,
+        user_id: &str,
+        request: &PageRequest,
+    ) -> Result<Page<TimelineItem>> {
+        let pk = format!("FEED#{}", user_id);
+        let page = self
+            .dynamo
+            .query_page(&pk, Some("POST#"), request)
+            .await?
+            .try_map(|entity| serde_json::from_value::<TimelineItem>(entity.data))?;
+
+        Ok(page)
+    }
+}
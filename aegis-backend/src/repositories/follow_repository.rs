@@ -0,0 +1,141 @@
+use super::{CountedWrite, CounterDelta, DynamoRepository, Page, PageRequest};
+use crate::models::dynamodb::{Follow, FollowCounts};
+use anyhow::Result;
+
+#[derive(Clone)]
+pub struct FollowRepository {
+    dynamo: DynamoRepository,
+}
+
+impl FollowRepository {
+    pub fn new(dynamo: DynamoRepository) -> Self {
+        Self { dynamo }
+    }
+
+    /// Writes the edge and bumps both counters in one transaction. Returns
+    /// `false` if the edge already existed.
+    pub async fn follow(&self, follow: &Follow, follower_type: &str) -> Result<bool> {
+        let entity = follow.to_entity()?;
+        let target_pk = self
+            .ensure_counts(&follow.target_type, &follow.target_id)
+            .await?;
+        let follower_pk = self
+            .ensure_counts(follower_type, &follow.follower_id)
+            .await?;
+
+        self.dynamo
+            .write_counted(
+                CountedWrite::Put(&entity),
+                &[
+                    count(&target_pk, "followers", 1),
+                    count(&follower_pk, "following", 1),
+                ],
+            )
+            .await
+    }
+
+    /// Returns `false` if there was no edge to remove.
+    pub async fn unfollow(
+        &self,
+        follower_type: &str,
+        follower_id: &str,
+        target_type: &str,
+        target_id: &str,
+    ) -> Result<bool> {
+        let pk = format!("USER#{}", follower_id);
+        let sk = format!("FOLLOWS#{}", Follow::target_key(target_type, target_id));
+        let target_pk = self.ensure_counts(target_type, target_id).await?;
+        let follower_pk = self.ensure_counts(follower_type, follower_id).await?;
+
+        self.dynamo
+            .write_counted(
+                CountedWrite::Delete { pk: &pk, sk: &sk },
+                &[
+                    count(&target_pk, "followers", -1),
+                    count(&follower_pk, "following", -1),
+                ],
+            )
+            .await
+    }
+
+    pub async fn is_following(
+        &self,
+        follower_id: &str,
+        target_type: &str,
+        target_id: &str,
+    ) -> Result<bool> {
+        let pk = format!("USER#{}", follower_id);
+        let sk = format!("FOLLOWS#{}", Follow::target_key(target_type, target_id));
+        Ok(self.dynamo.get_item(&pk, &sk).await?.is_some())
+    }
+
+    pub async fn get_counts(&self, target_type: &str, target_id: &str) -> Result<FollowCounts> {
+        let pk = FollowCounts::key(target_type, target_id);
+        match self.dynamo.get_item(&pk, "METADATA").await? {
+            Some(entity) => Ok(serde_json::from_value(entity.data)?),
+            None => Ok(FollowCounts::default()),
+        }
+    }
+
+    /// All follower IDs, for fan-out.
+    pub async fn get_followers(&self, target_type: &str, target_id: &str) -> Result<Vec<String>> {
+        let gsi1_pk = format!("FOLLOWERS#{}", Follow::target_key(target_type, target_id));
+        let entities = self.dynamo.query_gsi1(&gsi1_pk, None).await?;
+
+        let mut followers = Vec::new();
+        for entity in entities {
+            if entity.entity_type == "follow" {
+                let follow: Follow = serde_json::from_value(entity.data)?;
+                followers.push(follow.follower_id);
+            }
+        }
+
+        Ok(followers)
+    }
+
+    pub async fn get_followers_page(
+        &self,
+        target_type: &str,
+        target_id: &str,
+        request: &PageRequest,
+    ) -> Result<Page<Follow>> {
+        let gsi1_pk = format!("FOLLOWERS#{}", Follow::target_key(target_type, target_id));
+        let page = self
+            .dynamo
+            .query_gsi1_page(&gsi1_pk, None, request)
+            .await?
+            .try_map(|entity| serde_json::from_value::<Follow>(entity.data))?;
+        Ok(page)
+    }
+
+    pub async fn get_following_page(
+        &self,
+        follower_id: &str,
+        request: &PageRequest,
+    ) -> Result<Page<Follow>> {
+        let pk = format!("USER#{}", follower_id);
+        let page = self
+            .dynamo
+            .query_page(&pk, Some("FOLLOWS#"), request)
+            .await?
+            .try_map(|entity| serde_json::from_value::<Follow>(entity.data))?;
+        Ok(page)
+    }
+
+    /// Creates the counter item on first use, since the transaction can
+    /// only update items that exist. Returns its partition key.
+    async fn ensure_counts(&self, target_type: &str, target_id: &str) -> Result<String> {
+        let counts = FollowCounts::default().to_entity(target_type, target_id)?;
+        self.dynamo.put_item_if_absent(&counts).await?;
+        Ok(counts.pk)
+    }
+}
+
+fn count<'a>(pk: &'a str, field: &'a str, delta: i64) -> CounterDelta<'a> {
+    CounterDelta {
+        pk,
+        sk: "METADATA",
+        field,
+        delta,
+    }
+}
@@ -1,12 +1,25 @@
+pub mod storage;
 pub mod dynamodb_repository;
 pub mod chat_repository;
 pub mod post_repository;
 pub mod community_repository;
+pub mod follow_repository;
+pub mod feed_repository;
+pub mod activity_repository;
+pub mod notification_repository;
 
-pub use dynamodb_repository::DynamoRepository;
+pub use storage::{CountedWrite, CounterDelta, DynamoStorage, MemoryStorage, ScanDirection, Storage};
+pub use dynamodb_repository::{
+    is_version_conflict, DynamoRepository, Page, PageRequest, VersionConflict,
+    MAX_CONFLICT_RETRIES,
+};
 pub use chat_repository::ChatRepository;
 pub use post_repository::PostRepository;
 pub use community_repository::CommunityRepository;
+pub use follow_repository::FollowRepository;
+pub use feed_repository::FeedRepository;
+pub use activity_repository::ActivityRepository;
+pub use notification_repository::NotificationRepository;
 
 use anyhow::Result;
 
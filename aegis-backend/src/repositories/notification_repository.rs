@@ -0,0 +1,130 @@
+use super::{DynamoRepository, Page, PageRequest};
+use crate::models::dynamodb::{Notification, NotificationPreferences, NotificationRef};
+use anyhow::Result;
+use serde_json::json;
+
+#[derive(Clone)]
+pub struct NotificationRepository {
+    dynamo: DynamoRepository,
+}
+
+impl NotificationRepository {
+    pub fn new(dynamo: DynamoRepository) -> Self {
+        Self { dynamo }
+    }
+
+    /// Writes the notification and its ID pointer; both expire together.
+    pub async fn create(&self, notification: &Notification) -> Result<()> {
+        let notification_ref = NotificationRef {
+            user_id: notification.user_id.clone(),
+            notification_id: notification.id.clone(),
+            created_at: notification.created_at.clone(),
+        };
+        self.dynamo
+            .batch_put_items(&[
+                notification.to_entity()?,
+                notification_ref.to_entity(notification.expires_at())?,
+            ])
+            .await
+    }
+
+    pub async fn get(&self, user_id: &str, notification_id: &str) -> Result<Option<Notification>> {
+        let pk = format!("USER#{}", user_id);
+        let Some(entity) = self
+            .dynamo
+            .get_item(&pk, &format!("NOTIFID#{}", notification_id))
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let notification_ref: NotificationRef = serde_json::from_value(entity.data)?;
+        let sk = Notification::sort_key(&notification_ref.created_at, notification_id);
+        match self.dynamo.get_item(&pk, &sk).await? {
+            Some(entity) => Ok(Some(serde_json::from_value(entity.data)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn get_inbox(
+        &self,
+        user_id: &str,
+        request: &PageRequest,
+    ) -> Result<Page<Notification>> {
+        let pk = format!("USER#{}", user_id);
+        let page = self
+            .dynamo
+            .query_page(&pk, Some("NOTIF#"), request)
+            .await?
+            .try_map(|entity| serde_json::from_value::<Notification>(entity.data))?;
+        Ok(page)
+    }
+
+    pub async fn count_unread(&self, user_id: &str) -> Result<i64> {
+        let pk = format!("USER#{}", user_id);
+        self.dynamo
+            .count_sk_range(
+                &pk,
+                "NOTIF#",
+                "NOTIF$",
+                Some(("status", Notification::READ)),
+            )
+            .await
+    }
+
+    /// Returns `false` if the notification was already read.
+    pub async fn mark_read(&self, notification: &Notification, read_at: &str) -> Result<bool> {
+        let pk = format!("USER#{}", notification.user_id);
+        let sk = Notification::sort_key(&notification.created_at, &notification.id);
+        self.dynamo
+            .update_fields(
+                &pk,
+                &sk,
+                &[
+                    ("status", json!(Notification::READ)),
+                    ("read_at", json!(read_at)),
+                ],
+                Some(("status", json!(Notification::READ))),
+            )
+            .await
+    }
+
+    pub async fn get_unread(&self, user_id: &str) -> Result<Vec<Notification>> {
+        let pk = format!("USER#{}", user_id);
+        let mut unread = Vec::new();
+        for entity in self.dynamo.query_prefix(&pk, "NOTIF#").await? {
+            let notification: Notification = serde_json::from_value(entity.data)?;
+            if notification.is_unread() {
+                unread.push(notification);
+            }
+        }
+        Ok(unread)
+    }
+
+    pub async fn get_preferences(&self, user_id: &str) -> Result<Option<NotificationPreferences>> {
+        let pk = format!("USER#{}", user_id);
+        match self
+            .dynamo
+            .get_item(&pk, NotificationPreferences::SORT_KEY)
+            .await?
+        {
+            Some(entity) => Ok(Some(serde_json::from_value(entity.data)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Writes the user's first preferences. Returns `false` if they already
+    /// had some.
+    pub async fn create_preferences(&self, preferences: &NotificationPreferences) -> Result<bool> {
+        self.dynamo
+            .put_item_if_absent(&preferences.to_entity()?)
+            .await
+    }
+
+    /// Fails with `VersionConflict` if the preferences changed since they were read.
+    pub async fn update_preferences(&self, preferences: &NotificationPreferences) -> Result<()> {
+        self.dynamo
+            .put_item_versioned(&preferences.to_entity()?)
+            .await
+    }
+}
@@ -1,7 +1,9 @@
 use anyhow::Result;
+use std::collections::HashSet;
+use serde_json::json;
 use uuid::Uuid;
-use crate::models::dynamodb::{Post, PostComment};
-use super::{Repository, DynamoRepository};
+use crate::models::dynamodb::{Post, PostComment, PostCommentRef, PostLike};
+use super::{CountedWrite, CounterDelta, Repository, DynamoRepository, Page, PageRequest, ScanDirection};
 
 #[derive(Clone)]
 pub struct PostRepository {
@@ -32,30 +34,240 @@ impl PostRepository {
         }
     }
 
+    pub async fn get_posts(&self, post_ids: &[String]) -> Result<Vec<Post>> {
+        let keys: Vec<(String, String)> = post_ids
+            .iter()
+            .map(|id| (format!("POST#{}", id), "METADATA".to_string()))
+            .collect();
+
+        let mut posts = Vec::new();
+        for entity in self.dynamo.batch_get_items(&keys).await? {
+            posts.push(serde_json::from_value(entity.data)?);
+        }
+        Ok(posts)
+    }
+
+    /// Writes the comment and its ID pointer and bumps the post's
+    /// `comments_count` (and, for replies, the parent's `reply_count`) in one
+    /// transaction.
     pub async fn add_comment(&self, mut comment: PostComment) -> Result<String> {
         if comment.id.is_empty() {
             comment.id = Uuid::new_v4().to_string();
         }
-        
-        let entity = comment.to_entity()?;
-        self.dynamo.put_item(&entity).await?;
+
+        let pk = format!("POST#{}", comment.post_id);
+        let comment_ref = PostCommentRef {
+            post_id: comment.post_id.clone(),
+            comment_id: comment.id.clone(),
+            sort_key: comment.sort_key(),
+        };
+        let items = [comment.to_entity()?, comment_ref.to_entity()?];
+
+        let parent_sk = match &comment.parent_id {
+            Some(parent_id) => Some(
+                self.find_comment_key(&comment.post_id, parent_id)
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("Parent comment {} not found", parent_id))?,
+            ),
+            None => None,
+        };
+        let mut counters = vec![comment_count(&pk, 1)];
+        if let Some(parent_sk) = &parent_sk {
+            counters.push(CounterDelta { pk: &pk, sk: parent_sk, field: "reply_count", delta: 1 });
+        }
+
+        if !self.dynamo.write_counted(CountedWrite::PutAll(&items), &counters).await? {
+            anyhow::bail!("Comment {} already exists on post {}", comment.id, comment.post_id);
+        }
+
+        if let (Some(parent_id), Some(parent_sk)) = (&comment.parent_id, &parent_sk) {
+            let reply_count = self
+                .dynamo
+                .get_item(&pk, parent_sk)
+                .await?
+                .and_then(|entity| entity.data.get("reply_count").and_then(serde_json::Value::as_i64));
+            if let Some(reply_count) = reply_count {
+                self.rerank_parent(&comment.post_id, parent_id, parent_sk, reply_count).await?;
+            }
+        }
         Ok(comment.id)
     }
 
-    pub async fn get_comments(&self, post_id: &str) -> Result<Vec<PostComment>> {
+    pub async fn get_comment(&self, post_id: &str, comment_id: &str) -> Result<Option<PostComment>> {
         let pk = format!("POST#{}", post_id);
-        let entities = self.dynamo.query_by_pk(&pk).await?;
-        
-        let mut comments: Vec<PostComment> = Vec::new();
-        for entity in entities {
-            if entity.entity_type == "comment" {
-                comments.push(serde_json::from_value(entity.data)?);
-            }
+        match self.find_comment_key(post_id, comment_id).await? {
+            Some(sk) => match self.dynamo.get_item(&pk, &sk).await? {
+                Some(entity) => Ok(Some(serde_json::from_value(entity.data)?)),
+                None => Ok(None),
+            },
+            None => Ok(None),
         }
-        
-        // Sort by timestamp (oldest first)
-        comments.sort_by(|a, b| a.created_at.cmp(&b.created_at));
-        Ok(comments)
+    }
+
+    /// Returns `false` if the comment is missing or already deleted.
+    pub async fn update_comment_content(&self, post_id: &str, comment_id: &str, content: &str, edited_at: &str) -> Result<bool> {
+        let Some(sk) = self.find_comment_key(post_id, comment_id).await? else {
+            return Ok(false);
+        };
+
+        self.dynamo
+            .update_fields(
+                &format!("POST#{}", post_id),
+                &sk,
+                &[("content", json!(content)), ("edited_at", json!(edited_at))],
+                Some(("deleted", json!(true))),
+            )
+            .await
+    }
+
+    /// Replaces the comment with a tombstone so its thread stays intact, and
+    /// takes it out of the counts. Returns `false` if it was already deleted.
+    pub async fn delete_comment(&self, comment: &PostComment, deleted_by: &str, deleted_at: &str) -> Result<bool> {
+        let Some(sk) = self.find_comment_key(&comment.post_id, &comment.id).await? else {
+            return Ok(false);
+        };
+
+        let deleted = self
+            .dynamo
+            .update_fields(
+                &format!("POST#{}", comment.post_id),
+                &sk,
+                &[
+                    ("content", json!("")),
+                    ("deleted", json!(true)),
+                    ("deleted_at", json!(deleted_at)),
+                    ("deleted_by", json!(deleted_by)),
+                ],
+                Some(("deleted", json!(true))),
+            )
+            .await?;
+
+        if deleted {
+            self.adjust_counts(comment, -1).await?;
+        }
+        Ok(deleted)
+    }
+
+    /// Top-level comments, newest first.
+    pub async fn get_comments(&self, post_id: &str, request: &PageRequest) -> Result<Page<PostComment>> {
+        let pk = format!("POST#{}", post_id);
+        let mut page = self
+            .dynamo
+            .query_page(&pk, Some("COMMENT#"), request)
+            .await?
+            .try_map(|entity| serde_json::from_value::<PostComment>(entity.data))?;
+
+        if request.direction == ScanDirection::Forward {
+            page.items.reverse();
+        }
+
+        Ok(page)
+    }
+
+    /// Top-level comments, most replies first.
+    pub async fn get_top_comments(&self, post_id: &str, request: &PageRequest) -> Result<Page<PostComment>> {
+        let mut page = self
+            .dynamo
+            .query_gsi2_page(&PostComment::top_index_pk(post_id), None, request)
+            .await?
+            .try_map(|entity| serde_json::from_value::<PostComment>(entity.data))?;
+
+        if request.direction == ScanDirection::Forward {
+            page.items.reverse();
+        }
+
+        Ok(page)
+    }
+
+    /// Replies to a comment, oldest first.
+    pub async fn get_replies(&self, post_id: &str, parent_id: &str, request: &PageRequest) -> Result<Page<PostComment>> {
+        let pk = format!("POST#{}", post_id);
+        let mut page = self
+            .dynamo
+            .query_page(&pk, Some(&PostComment::reply_prefix(parent_id)), request)
+            .await?
+            .try_map(|entity| serde_json::from_value::<PostComment>(entity.data))?;
+
+        if request.direction == ScanDirection::Backward {
+            page.items.reverse();
+        }
+
+        Ok(page)
+    }
+
+    async fn find_comment_key(&self, post_id: &str, comment_id: &str) -> Result<Option<String>> {
+        let pk = format!("POST#{}", post_id);
+
+        if let Some(entity) = self.dynamo.get_item(&pk, &format!("COMMENTID#{}", comment_id)).await? {
+            let comment_ref: PostCommentRef = serde_json::from_value(entity.data)?;
+            return Ok(Some(comment_ref.sort_key));
+        }
+        Ok(None)
+    }
+
+    async fn adjust_counts(&self, comment: &PostComment, delta: i64) -> Result<()> {
+        let pk = format!("POST#{}", comment.post_id);
+        self.dynamo.increment_field(&pk, "METADATA", "comments_count", delta).await?;
+
+        let Some(parent_id) = &comment.parent_id else {
+            return Ok(());
+        };
+        let Some(parent_sk) = self.find_comment_key(&comment.post_id, parent_id).await? else {
+            return Ok(());
+        };
+        let Some(reply_count) = self.dynamo.increment_field(&pk, &parent_sk, "reply_count", delta).await? else {
+            return Ok(());
+        };
+        self.rerank_parent(&comment.post_id, parent_id, &parent_sk, reply_count).await
+    }
+
+    /// Re-ranks a parent by its reply count; a stale count loses the race to
+    /// the newer one. Parent keys are `COMMENT#<created_at>[#<id>]`.
+    async fn rerank_parent(&self, post_id: &str, parent_id: &str, parent_sk: &str, reply_count: i64) -> Result<()> {
+        let pk = format!("POST#{}", post_id);
+        let created_at = parent_sk.trim_start_matches("COMMENT#");
+        let created_at = created_at.strip_suffix(&format!("#{}", parent_id)).unwrap_or(created_at);
+        self.dynamo
+            .set_gsi2_if(
+                &pk,
+                parent_sk,
+                (&PostComment::top_index_pk(post_id), &PostComment::top_index_sk(reply_count, created_at)),
+                ("reply_count", reply_count),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Records the like and bumps the counter in one transaction. Returns
+    /// `false` if the user had already liked the post.
+    pub async fn add_like(&self, like: &PostLike) -> Result<bool> {
+        let entity = like.to_entity()?;
+        let pk = format!("POST#{}", like.post_id);
+        self.dynamo.write_counted(CountedWrite::Put(&entity), &[like_count(&pk, 1)]).await
+    }
+
+    /// Returns `false` if the user had not liked the post.
+    pub async fn remove_like(&self, post_id: &str, user_id: &str) -> Result<bool> {
+        let pk = format!("POST#{}", post_id);
+        let sk = PostLike::sort_key(user_id);
+        self.dynamo
+            .write_counted(CountedWrite::Delete { pk: &pk, sk: &sk }, &[like_count(&pk, -1)])
+            .await
+    }
+
+    /// Which of `post_ids` the user has liked.
+    pub async fn get_liked_post_ids(&self, user_id: &str, post_ids: &[String]) -> Result<HashSet<String>> {
+        let keys: Vec<(String, String)> = post_ids
+            .iter()
+            .map(|id| (format!("POST#{}", id), PostLike::sort_key(user_id)))
+            .collect();
+
+        let mut liked = HashSet::new();
+        for entity in self.dynamo.batch_get_items(&keys).await? {
+            let like: PostLike = serde_json::from_value(entity.data)?;
+            liked.insert(like.post_id);
+        }
+        Ok(liked)
     }
 
     pub async fn get_posts_by_author(&self, author_id: &str) -> Result<Vec<Post>> {
@@ -85,7 +297,7 @@ impl Repository<Post> for PostRepository {
 
     async fn update(&self, post: &Post) -> Result<()> {
         let entity = post.to_entity()?;
-        self.dynamo.put_item(&entity).await
+        self.dynamo.put_item_versioned(&entity).await
     }
 
     async fn delete(&self, id: &str) -> Result<()> {
@@ -93,3 +305,11 @@ impl Repository<Post> for PostRepository {
         self.dynamo.delete_item(&pk, "METADATA").await
     }
 }
+
+fn like_count(pk: &str, delta: i64) -> CounterDelta<'_> {
+    CounterDelta { pk, sk: "METADATA", field: "likes", delta }
+}
+
+fn comment_count(pk: &str, delta: i64) -> CounterDelta<'_> {
+    CounterDelta { pk, sk: "METADATA", field: "comments_count", delta }
+}
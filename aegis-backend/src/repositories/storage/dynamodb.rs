@@ -0,0 +1,774 @@
+use super::{
+    CountedWrite, CounterDelta, CursorKey, Index, KeyQuery, PutCondition, QueryOutput,
+    ScanDirection, Storage,
+};
+use crate::models::dynamodb::GameEntity;
+use anyhow::{anyhow, Result};
+use aws_sdk_dynamodb::{
+    operation::{
+        query::builders::QueryFluentBuilder, transact_write_items::TransactWriteItemsError,
+    },
+    types::{
+        AttributeValue, Delete, KeysAndAttributes, Put, PutRequest, ReturnValue, Select,
+        TransactWriteItem, Update, WriteRequest,
+    },
+    Client,
+};
+use serde_dynamo::{from_item, to_attribute_value, to_item};
+use std::collections::HashMap;
+
+const BATCH_WRITE_SIZE: usize = 25;
+const BATCH_GET_SIZE: usize = 100;
+const BATCH_MAX_RETRIES: u32 = 5;
+
+/// `Storage` backed by the DynamoDB table named by `DYNAMODB_TABLE_NAME`.
+#[derive(Clone)]
+pub struct DynamoStorage {
+    client: Client,
+    table_name: String,
+}
+
+impl DynamoStorage {
+    pub fn new(client: Client) -> Self {
+        let table_name = std::env::var("DYNAMODB_TABLE_NAME")
+            .unwrap_or_else(|_| "aegis_gaming_table".to_string());
+
+        Self { client, table_name }
+    }
+}
+
+impl Index {
+    fn name(&self) -> Option<&'static str> {
+        match self {
+            Index::Table => None,
+            Index::Gsi1 => Some("GSI1"),
+            Index::Gsi2 => Some("GSI2"),
+        }
+    }
+
+    fn attributes(&self) -> (&'static str, &'static str) {
+        match self {
+            Index::Table => ("pk", "sk"),
+            Index::Gsi1 => ("gsi1_pk", "gsi1_sk"),
+            Index::Gsi2 => ("gsi2_pk", "gsi2_sk"),
+        }
+    }
+}
+
+impl CursorKey {
+    /// Index queries need the index keys in addition to the table keys.
+    fn to_start_key(&self, index: Index) -> Result<HashMap<String, AttributeValue>> {
+        let mut key = HashMap::new();
+        key.insert("pk".to_string(), AttributeValue::S(self.pk.clone()));
+        key.insert("sk".to_string(), AttributeValue::S(self.sk.clone()));
+
+        let index_keys = match index {
+            Index::Gsi1 => Some((&self.gsi1_pk, &self.gsi1_sk)),
+            Index::Gsi2 => Some((&self.gsi2_pk, &self.gsi2_sk)),
+            Index::Table => None,
+        };
+        if let Some((pk, sk)) = index_keys {
+            let (Some(pk), Some(sk)) = (pk, sk) else {
+                return Err(anyhow!("Invalid cursor"));
+            };
+            let (pk_attr, sk_attr) = index.attributes();
+            key.insert(pk_attr.to_string(), AttributeValue::S(pk.clone()));
+            key.insert(sk_attr.to_string(), AttributeValue::S(sk.clone()));
+        }
+        Ok(key)
+    }
+}
+
+impl DynamoStorage {
+    /// The count query behind `count_sk_range`, or `None` when no key can
+    /// lie strictly between the bounds. DynamoDB refuses filters on key
+    /// attributes, so the bounds are made exclusive in the key condition
+    /// and only the excluded field is filtered.
+    fn count_query(
+        &self,
+        pk: &str,
+        lower: &str,
+        upper: &str,
+        exclude: Option<(&str, &str)>,
+    ) -> Option<QueryFluentBuilder> {
+        let (after_lower, before_upper) = exclusive_bounds(lower, upper)?;
+
+        let mut query = self
+            .client
+            .query()
+            .table_name(&self.table_name)
+            .key_condition_expression("pk = :pk AND sk BETWEEN :lower AND :upper")
+            .expression_attribute_values(":pk", AttributeValue::S(pk.to_string()))
+            .expression_attribute_values(":lower", AttributeValue::S(after_lower))
+            .expression_attribute_values(":upper", AttributeValue::S(before_upper))
+            .select(Select::Count);
+
+        if let Some((field, value)) = exclude {
+            query = query
+                .filter_expression("#data.#field <> :exclude")
+                .expression_attribute_names("#data", "data")
+                .expression_attribute_names("#field", field)
+                .expression_attribute_values(":exclude", AttributeValue::S(value.to_string()));
+        }
+        Some(query)
+    }
+}
+
+/// The transaction items for `Storage::write_counted`, with the guarded
+/// writes first so a cancellation can be traced back to them.
+fn counted_write_items(
+    table_name: &str,
+    write: CountedWrite<'_>,
+    counters: &[CounterDelta<'_>],
+) -> Result<Vec<TransactWriteItem>> {
+    let put = |entity: &GameEntity| -> Result<TransactWriteItem> {
+        Ok(TransactWriteItem::builder()
+            .put(
+                Put::builder()
+                    .table_name(table_name)
+                    .set_item(Some(to_item(entity)?))
+                    .condition_expression("attribute_not_exists(pk)")
+                    .build()?,
+            )
+            .build())
+    };
+
+    let mut items = match write {
+        CountedWrite::Put(entity) => vec![put(entity)?],
+        CountedWrite::PutAll(entities) => entities.iter().map(put).collect::<Result<_>>()?,
+        CountedWrite::Delete { pk, sk } => vec![TransactWriteItem::builder()
+            .delete(
+                Delete::builder()
+                    .table_name(table_name)
+                    .key("pk", AttributeValue::S(pk.to_string()))
+                    .key("sk", AttributeValue::S(sk.to_string()))
+                    .condition_expression("attribute_exists(pk)")
+                    .build()?,
+            )
+            .build()],
+    };
+    for counter in counters {
+        let update = Update::builder()
+            .table_name(table_name)
+            .key("pk", AttributeValue::S(counter.pk.to_string()))
+            .key("sk", AttributeValue::S(counter.sk.to_string()))
+            .update_expression("ADD #data.#field :delta, #version :one, #data.#version :one")
+            .condition_expression("attribute_exists(pk)")
+            .expression_attribute_names("#data", "data")
+            .expression_attribute_names("#field", counter.field)
+            .expression_attribute_names("#version", "version")
+            .expression_attribute_values(":delta", AttributeValue::N(counter.delta.to_string()))
+            .expression_attribute_values(":one", AttributeValue::N("1".to_string()))
+            .build()?;
+        items.push(TransactWriteItem::builder().update(update).build());
+    }
+    Ok(items)
+}
+
+/// Inclusive bounds equivalent to `lower < sk < upper`: the first string
+/// after `lower`, and the last string before `upper` that keys can take
+/// (any string below `upper` made of characters up to `char::MAX`).
+/// `None` when nothing lies between them.
+fn exclusive_bounds(lower: &str, upper: &str) -> Option<(String, String)> {
+    let after_lower = format!("{}\u{0}", lower);
+
+    let mut before_upper = upper.to_string();
+    match before_upper.pop()? {
+        // "ab\0" is the first string after "ab"
+        '\u{0}' => {}
+        last => {
+            let previous = match last as u32 - 1 {
+                // Skip the surrogate range, which holds no chars
+                0xDFFF => '\u{D7FF}',
+                code => char::from_u32(code)?,
+            };
+            before_upper.push(previous);
+            before_upper.push(char::MAX);
+        }
+    }
+
+    (after_lower <= before_upper).then_some((after_lower, before_upper))
+}
+
+#[async_trait::async_trait]
+impl Storage for DynamoStorage {
+    async fn put(&self, entity: &GameEntity, condition: PutCondition) -> Result<bool> {
+        let put = self
+            .client
+            .put_item()
+            .table_name(&self.table_name)
+            .set_item(Some(to_item(entity)?));
+
+        let put = match condition {
+            PutCondition::Always => put,
+            PutCondition::IfAbsent => put.condition_expression("attribute_not_exists(pk)"),
+            // Items written before versioning have no version attribute
+            PutCondition::IfVersion(0) => put
+                .condition_expression(
+                    "attribute_exists(pk) AND (attribute_not_exists(#version) OR #version = :expected)",
+                )
+                .expression_attribute_names("#version", "version")
+                .expression_attribute_values(":expected", AttributeValue::N("0".to_string())),
+            PutCondition::IfVersion(expected) => put
+                .condition_expression("attribute_exists(pk) AND #version = :expected")
+                .expression_attribute_names("#version", "version")
+                .expression_attribute_values(":expected", AttributeValue::N(expected.to_string())),
+        };
+
+        let result = put.send().await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) => {
+                let e = e.into_service_error();
+                if e.is_conditional_check_failed_exception() {
+                    Ok(false)
+                } else {
+                    Err(e.into())
+                }
+            }
+        }
+    }
+
+    /// Writes with BatchWriteItem, retrying unprocessed items with
+    /// exponential backoff.
+    async fn batch_put(&self, entities: &[GameEntity]) -> Result<()> {
+        for chunk in entities.chunks(BATCH_WRITE_SIZE) {
+            let mut requests = Vec::with_capacity(chunk.len());
+            for entity in chunk {
+                let put = PutRequest::builder()
+                    .set_item(Some(to_item(entity)?))
+                    .build()?;
+                requests.push(WriteRequest::builder().put_request(put).build());
+            }
+
+            let mut attempt = 0;
+            while !requests.is_empty() {
+                let result = self
+                    .client
+                    .batch_write_item()
+                    .request_items(&self.table_name, requests)
+                    .send()
+                    .await?;
+
+                requests = result
+                    .unprocessed_items
+                    .and_then(|mut items| items.remove(&self.table_name))
+                    .unwrap_or_default();
+
+                if !requests.is_empty() {
+                    attempt += 1;
+                    if attempt > BATCH_MAX_RETRIES {
+                        return Err(anyhow!(
+                            "Batch write left {} unprocessed items",
+                            requests.len()
+                        ));
+                    }
+                    tokio::time::sleep(std::time::Duration::from_millis(50 * 2_u64.pow(attempt)))
+                        .await;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn get(&self, pk: &str, sk: &str) -> Result<Option<GameEntity>> {
+        let result = self
+            .client
+            .get_item()
+            .table_name(&self.table_name)
+            .key("pk", AttributeValue::S(pk.to_string()))
+            .key("sk", AttributeValue::S(sk.to_string()))
+            .send()
+            .await?;
+
+        match result.item {
+            Some(item) => Ok(Some(from_item(item)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads with BatchGetItem, retrying unprocessed keys with exponential
+    /// backoff.
+    async fn batch_get(&self, keys: &[(String, String)]) -> Result<Vec<GameEntity>> {
+        let mut entities = Vec::with_capacity(keys.len());
+
+        for chunk in keys.chunks(BATCH_GET_SIZE) {
+            let key_maps = chunk
+                .iter()
+                .map(|(pk, sk)| {
+                    HashMap::from([
+                        ("pk".to_string(), AttributeValue::S(pk.clone())),
+                        ("sk".to_string(), AttributeValue::S(sk.clone())),
+                    ])
+                })
+                .collect();
+            let mut pending = Some(
+                KeysAndAttributes::builder()
+                    .set_keys(Some(key_maps))
+                    .build()?,
+            );
+
+            let mut attempt = 0;
+            while let Some(request) = pending.take() {
+                let result = self
+                    .client
+                    .batch_get_item()
+                    .request_items(&self.table_name, request)
+                    .send()
+                    .await?;
+
+                if let Some(mut responses) = result.responses {
+                    for item in responses.remove(&self.table_name).unwrap_or_default() {
+                        entities.push(from_item(item)?);
+                    }
+                }
+
+                pending = result
+                    .unprocessed_keys
+                    .and_then(|mut keys| keys.remove(&self.table_name))
+                    .filter(|keys| !keys.keys().is_empty());
+
+                if pending.is_some() {
+                    attempt += 1;
+                    if attempt > BATCH_MAX_RETRIES {
+                        return Err(anyhow!("Batch get left unprocessed keys"));
+                    }
+                    tokio::time::sleep(std::time::Duration::from_millis(50 * 2_u64.pow(attempt)))
+                        .await;
+                }
+            }
+        }
+        Ok(entities)
+    }
+
+    /// Unlimited queries follow LastEvaluatedKey so partitions over 1MB are
+    /// read in full.
+    async fn query(&self, query: &KeyQuery<'_>) -> Result<QueryOutput> {
+        let (pk_attr, sk_attr) = query.index.attributes();
+        let mut expression_values = HashMap::new();
+        expression_values.insert(":pk".to_string(), AttributeValue::S(query.pk.to_string()));
+
+        let key_condition = match query.sk_prefix {
+            Some(prefix) => {
+                expression_values
+                    .insert(":prefix".to_string(), AttributeValue::S(prefix.to_string()));
+                format!("{} = :pk AND begins_with({}, :prefix)", pk_attr, sk_attr)
+            }
+            None => format!("{} = :pk", pk_attr),
+        };
+
+        let request = self
+            .client
+            .query()
+            .table_name(&self.table_name)
+            .set_index_name(query.index.name().map(str::to_string))
+            .key_condition_expression(key_condition)
+            .set_expression_attribute_values(Some(expression_values))
+            .scan_index_forward(query.direction == ScanDirection::Forward)
+            .set_limit(query.limit);
+
+        let mut start_key = match query.start {
+            Some(cursor) => Some(cursor.to_start_key(query.index)?),
+            None => None,
+        };
+
+        let mut items = Vec::new();
+        loop {
+            let result = request
+                .clone()
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await?;
+
+            for item in result.items.unwrap_or_default() {
+                items.push(from_item(item)?);
+            }
+
+            start_key = result.last_evaluated_key;
+            if query.limit.is_some() || start_key.is_none() {
+                break;
+            }
+        }
+
+        Ok(QueryOutput {
+            items,
+            has_more: start_key.is_some(),
+        })
+    }
+
+    async fn count_sk_range(
+        &self,
+        pk: &str,
+        lower: &str,
+        upper: &str,
+        exclude: Option<(&str, &str)>,
+    ) -> Result<i64> {
+        let Some(query) = self.count_query(pk, lower, upper, exclude) else {
+            return Ok(0);
+        };
+
+        let mut count = 0i64;
+        let mut start_key = None;
+        loop {
+            let result = query
+                .clone()
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await?;
+            count += i64::from(result.count);
+
+            start_key = result.last_evaluated_key;
+            if start_key.is_none() {
+                break;
+            }
+        }
+        Ok(count)
+    }
+
+    async fn scan_entity_type(&self, entity_type: &str) -> Result<Vec<GameEntity>> {
+        let mut entities = Vec::new();
+        let mut start_key = None;
+
+        loop {
+            let result = self
+                .client
+                .scan()
+                .table_name(&self.table_name)
+                .filter_expression("entity_type = :entity_type")
+                .expression_attribute_values(
+                    ":entity_type",
+                    AttributeValue::S(entity_type.to_string()),
+                )
+                .set_exclusive_start_key(start_key)
+                .send()
+                .await?;
+
+            for item in result.items.unwrap_or_default() {
+                entities.push(from_item(item)?);
+            }
+
+            start_key = result.last_evaluated_key;
+            if start_key.is_none() {
+                break;
+            }
+        }
+        Ok(entities)
+    }
+
+    async fn increment_field(
+        &self,
+        pk: &str,
+        sk: &str,
+        field: &str,
+        delta: i64,
+    ) -> Result<Option<i64>> {
+        let result = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("pk", AttributeValue::S(pk.to_string()))
+            .key("sk", AttributeValue::S(sk.to_string()))
+            .update_expression("ADD #data.#field :delta, #version :one, #data.#version :one")
+            .condition_expression("attribute_exists(pk)")
+            .expression_attribute_names("#data", "data")
+            .expression_attribute_names("#field", field)
+            .expression_attribute_names("#version", "version")
+            .expression_attribute_values(":delta", AttributeValue::N(delta.to_string()))
+            .expression_attribute_values(":one", AttributeValue::N("1".to_string()))
+            .return_values(ReturnValue::UpdatedNew)
+            .send()
+            .await;
+
+        match result {
+            Ok(output) => {
+                let value = output
+                    .attributes
+                    .as_ref()
+                    .and_then(|attrs| attrs.get("data"))
+                    .and_then(|data| data.as_m().ok())
+                    .and_then(|data| data.get(field))
+                    .and_then(|value| value.as_n().ok())
+                    .and_then(|n| n.parse().ok())
+                    .ok_or_else(|| anyhow!("Missing updated value for {}", field))?;
+                Ok(Some(value))
+            }
+            Err(e) => {
+                let e = e.into_service_error();
+                if e.is_conditional_check_failed_exception() {
+                    Ok(None)
+                } else {
+                    Err(e.into())
+                }
+            }
+        }
+    }
+
+    async fn write_counted(
+        &self,
+        write: CountedWrite<'_>,
+        counters: &[CounterDelta<'_>],
+    ) -> Result<bool> {
+        let guarded = write.guarded_items();
+        let result = self
+            .client
+            .transact_write_items()
+            .set_transact_items(Some(counted_write_items(
+                &self.table_name,
+                write,
+                counters,
+            )?))
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) => match e.into_service_error() {
+                // Only the guarded write failing is an expected outcome; a
+                // missing counter item stays an error
+                TransactWriteItemsError::TransactionCanceledException(cancelled)
+                    if cancelled
+                        .cancellation_reasons()
+                        .iter()
+                        .take(guarded)
+                        .any(|reason| reason.code() == Some("ConditionalCheckFailed")) =>
+                {
+                    Ok(false)
+                }
+                e => Err(e.into()),
+            },
+        }
+    }
+
+    async fn update_fields(
+        &self,
+        pk: &str,
+        sk: &str,
+        fields: &[(&str, serde_json::Value)],
+        unless: Option<(&str, serde_json::Value)>,
+    ) -> Result<bool> {
+        let mut update = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("pk", AttributeValue::S(pk.to_string()))
+            .key("sk", AttributeValue::S(sk.to_string()))
+            .expression_attribute_names("#data", "data")
+            .expression_attribute_names("#updated_at", "updated_at")
+            .expression_attribute_names("#version", "version")
+            .expression_attribute_values(
+                ":updated_at",
+                AttributeValue::S(chrono::Utc::now().to_rfc3339()),
+            )
+            .expression_attribute_values(":one", AttributeValue::N("1".to_string()));
+
+        let mut assignments = vec!["#updated_at = :updated_at".to_string()];
+        for (i, (field, value)) in fields.iter().enumerate() {
+            assignments.push(format!("#data.#f{i} = :v{i}"));
+            update = update
+                .expression_attribute_names(format!("#f{i}"), *field)
+                .expression_attribute_values(format!(":v{i}"), to_attribute_value(value)?);
+        }
+        update = update.update_expression(format!(
+            "SET {} ADD #version :one, #data.#version :one",
+            assignments.join(", ")
+        ));
+
+        update = match unless {
+            Some((field, value)) => update
+                .condition_expression(
+                    "attribute_exists(pk) AND (attribute_not_exists(#data.#guard) OR #data.#guard <> :guard)",
+                )
+                .expression_attribute_names("#guard", field)
+                .expression_attribute_values(":guard", to_attribute_value(value)?),
+            None => update.condition_expression("attribute_exists(pk)"),
+        };
+
+        let result = update.send().await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) => {
+                let e = e.into_service_error();
+                if e.is_conditional_check_failed_exception() {
+                    Ok(false)
+                } else {
+                    Err(e.into())
+                }
+            }
+        }
+    }
+
+    async fn set_gsi2_if(
+        &self,
+        pk: &str,
+        sk: &str,
+        (gsi2_pk, gsi2_sk): (&str, &str),
+        (field, expected): (&str, i64),
+    ) -> Result<bool> {
+        let result = self
+            .client
+            .update_item()
+            .table_name(&self.table_name)
+            .key("pk", AttributeValue::S(pk.to_string()))
+            .key("sk", AttributeValue::S(sk.to_string()))
+            .update_expression("SET gsi2_pk = :gsi2_pk, gsi2_sk = :gsi2_sk")
+            .condition_expression("#data.#field = :expected")
+            .expression_attribute_names("#data", "data")
+            .expression_attribute_names("#field", field)
+            .expression_attribute_values(":gsi2_pk", AttributeValue::S(gsi2_pk.to_string()))
+            .expression_attribute_values(":gsi2_sk", AttributeValue::S(gsi2_sk.to_string()))
+            .expression_attribute_values(":expected", AttributeValue::N(expected.to_string()))
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) => {
+                let e = e.into_service_error();
+                if e.is_conditional_check_failed_exception() {
+                    Ok(false)
+                } else {
+                    Err(e.into())
+                }
+            }
+        }
+    }
+
+    async fn delete(&self, pk: &str, sk: &str) -> Result<bool> {
+        let result = self
+            .client
+            .delete_item()
+            .table_name(&self.table_name)
+            .key("pk", AttributeValue::S(pk.to_string()))
+            .key("sk", AttributeValue::S(sk.to_string()))
+            .condition_expression("attribute_exists(pk)")
+            .send()
+            .await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(e) => {
+                let e = e.into_service_error();
+                if e.is_conditional_check_failed_exception() {
+                    Ok(false)
+                } else {
+                    Err(e.into())
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_dynamodb::config::{BehaviorVersion, Config, Region};
+
+    fn storage() -> DynamoStorage {
+        let config = Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("us-east-1"))
+            .build();
+        DynamoStorage {
+            client: Client::from_conf(config),
+            table_name: "aegis_gaming_table".to_string(),
+        }
+    }
+
+    fn value<'a>(query: &'a QueryFluentBuilder, name: &str) -> &'a str {
+        query
+            .get_expression_attribute_values()
+            .as_ref()
+            .and_then(|values| values.get(name))
+            .and_then(|v| v.as_s().ok())
+            .unwrap()
+    }
+
+    #[test]
+    fn bounds_exclude_both_ends() {
+        let (lower, upper) = exclusive_bounds("MSG#2026-01-01T00:00:00Z", "MSG$").unwrap();
+
+        assert!(lower.as_str() > "MSG#2026-01-01T00:00:00Z");
+        assert!(lower.as_str() <= "MSG#2026-01-01T00:00:01Z");
+        assert!(upper.as_str() < "MSG$");
+        assert!(upper.as_str() >= "MSG#9999-12-31T23:59:59Z");
+
+        assert_eq!(exclusive_bounds("NOTIF#", "NOTIF#"), None);
+        assert_eq!(exclusive_bounds("a", ""), None);
+    }
+
+    #[test]
+    fn count_query_keeps_key_attributes_out_of_the_filter() {
+        let query = storage()
+            .count_query(
+                "CHAT#general",
+                "MSG#2026-01-01T00:00:00Z",
+                "MSG$",
+                Some(("sender", "alice")),
+            )
+            .unwrap();
+
+        assert_eq!(
+            query.get_key_condition_expression().as_deref(),
+            Some("pk = :pk AND sk BETWEEN :lower AND :upper")
+        );
+        let filter = query.get_filter_expression().as_deref().unwrap();
+        assert_eq!(filter, "#data.#field <> :exclude");
+        assert!(!filter.contains("sk"));
+
+        assert_eq!(value(&query, ":pk"), "CHAT#general");
+        assert_eq!(value(&query, ":exclude"), "alice");
+        assert!(value(&query, ":lower") > "MSG#2026-01-01T00:00:00Z");
+        assert!(value(&query, ":upper") < "MSG$");
+
+        let unfiltered = storage()
+            .count_query("USER#alice", "NOTIF#", "NOTIF$", None)
+            .unwrap();
+        assert!(unfiltered.get_filter_expression().is_none());
+    }
+
+    #[test]
+    fn counted_write_guards_the_item_and_counts_existing_counters() {
+        let items = counted_write_items(
+            "aegis_gaming_table",
+            CountedWrite::Delete {
+                pk: "POST#1",
+                sk: "LIKE#alice",
+            },
+            &[CounterDelta {
+                pk: "POST#1",
+                sk: "METADATA",
+                field: "likes",
+                delta: -1,
+            }],
+        )
+        .unwrap();
+        assert_eq!(items.len(), 2);
+
+        let delete = items[0].delete().unwrap();
+        assert_eq!(delete.condition_expression(), Some("attribute_exists(pk)"));
+        assert_eq!(
+            delete
+                .key()
+                .get("sk")
+                .and_then(|v| v.as_s().ok())
+                .map(String::as_str),
+            Some("LIKE#alice")
+        );
+
+        let update = items[1].update().unwrap();
+        assert!(update
+            .update_expression()
+            .starts_with("ADD #data.#field :delta"));
+        assert_eq!(update.condition_expression(), Some("attribute_exists(pk)"));
+        assert_eq!(
+            update
+                .expression_attribute_values()
+                .and_then(|v| v.get(":delta"))
+                .and_then(|v| v.as_n().ok())
+                .map(String::as_str),
+            Some("-1")
+        );
+    }
+}
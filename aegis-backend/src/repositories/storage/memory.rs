@@ -0,0 +1,308 @@
+use super::{
+    CountedWrite, CounterDelta, CursorKey, Index, KeyQuery, PutCondition, QueryOutput,
+    ScanDirection, Storage,
+};
+use crate::models::dynamodb::GameEntity;
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// `Storage` held in process memory, for tests and local runs without
+/// LocalStack. Every operation takes a single lock, so conditional writes
+/// are atomic just like their DynamoDB counterparts. Contents are lost when
+/// the last clone is dropped.
+#[derive(Clone, Default)]
+pub struct MemoryStorage {
+    items: Arc<Mutex<BTreeMap<(String, String), GameEntity>>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn items(&self) -> MutexGuard<'_, BTreeMap<(String, String), GameEntity>> {
+        // A panic mid-operation never leaves an item half-written
+        self.items
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn key(pk: &str, sk: &str) -> (String, String) {
+    (pk.to_string(), sk.to_string())
+}
+
+/// Sort position under an index. Ties on the index sort key fall back to
+/// the table key so the order (and cursors) are stable.
+fn position<'a>(
+    index: Index,
+    pk: &'a str,
+    sk: &'a str,
+    index_sk: &'a str,
+) -> (&'a str, &'a str, &'a str) {
+    match index {
+        Index::Table => (index_sk, "", ""),
+        Index::Gsi1 | Index::Gsi2 => (index_sk, pk, sk),
+    }
+}
+
+fn cursor_position(index: Index, cursor: &CursorKey) -> Option<(&str, &str, &str)> {
+    let index_sk = match index {
+        Index::Table => &cursor.sk,
+        Index::Gsi1 => cursor.gsi1_sk.as_ref()?,
+        Index::Gsi2 => cursor.gsi2_sk.as_ref()?,
+    };
+    Some(position(index, &cursor.pk, &cursor.sk, index_sk))
+}
+
+fn bump_version(entity: &mut GameEntity) {
+    entity.version += 1;
+    if let Some(data) = entity.data.as_object_mut() {
+        let version = data.get("version").and_then(Value::as_u64).unwrap_or(0);
+        data.insert("version".to_string(), (version + 1).into());
+    }
+}
+
+#[async_trait::async_trait]
+impl Storage for MemoryStorage {
+    async fn put(&self, entity: &GameEntity, condition: PutCondition) -> Result<bool> {
+        let mut items = self.items();
+        let key = key(&entity.pk, &entity.sk);
+
+        let allowed = match (condition, items.get(&key)) {
+            (PutCondition::Always, _) => true,
+            (PutCondition::IfAbsent, existing) => existing.is_none(),
+            (PutCondition::IfVersion(expected), Some(existing)) => existing.version == expected,
+            (PutCondition::IfVersion(_), None) => false,
+        };
+        if allowed {
+            items.insert(key, entity.clone());
+        }
+        Ok(allowed)
+    }
+
+    async fn batch_put(&self, entities: &[GameEntity]) -> Result<()> {
+        let mut items = self.items();
+        for entity in entities {
+            items.insert(key(&entity.pk, &entity.sk), entity.clone());
+        }
+        Ok(())
+    }
+
+    async fn get(&self, pk: &str, sk: &str) -> Result<Option<GameEntity>> {
+        Ok(self.items().get(&key(pk, sk)).cloned())
+    }
+
+    async fn batch_get(&self, keys: &[(String, String)]) -> Result<Vec<GameEntity>> {
+        let items = self.items();
+        Ok(keys
+            .iter()
+            .filter_map(|key| items.get(key).cloned())
+            .collect())
+    }
+
+    async fn query(&self, query: &KeyQuery<'_>) -> Result<QueryOutput> {
+        let items = self.items();
+        let prefix = query.sk_prefix.unwrap_or("");
+
+        let mut matches: Vec<&GameEntity> = items
+            .values()
+            .filter(|entity| {
+                query
+                    .index
+                    .keys(entity)
+                    .is_some_and(|(pk, sk)| pk == query.pk && sk.starts_with(prefix))
+            })
+            .collect();
+        matches.sort_by_cached_key(|entity| {
+            let (_, index_sk) = query.index.keys(entity).unwrap_or_default();
+            let (a, b, c) = position(query.index, &entity.pk, &entity.sk, index_sk);
+            (a.to_string(), b.to_string(), c.to_string())
+        });
+        if query.direction == ScanDirection::Backward {
+            matches.reverse();
+        }
+
+        if let Some(start) = query
+            .start
+            .and_then(|cursor| cursor_position(query.index, cursor))
+        {
+            matches.retain(|entity| {
+                let (_, index_sk) = query.index.keys(entity).unwrap_or_default();
+                let current = position(query.index, &entity.pk, &entity.sk, index_sk);
+                match query.direction {
+                    ScanDirection::Forward => current > start,
+                    ScanDirection::Backward => current < start,
+                }
+            });
+        }
+
+        let limit = query
+            .limit
+            .map_or(matches.len(), |limit| limit.max(0) as usize);
+        Ok(QueryOutput {
+            has_more: matches.len() > limit,
+            items: matches.into_iter().take(limit).cloned().collect(),
+        })
+    }
+
+    async fn count_sk_range(
+        &self,
+        pk: &str,
+        lower: &str,
+        upper: &str,
+        exclude: Option<(&str, &str)>,
+    ) -> Result<i64> {
+        let count = self
+            .items()
+            .range(key(pk, lower)..=key(pk, upper))
+            .filter(|((_, sk), entity)| {
+                sk.as_str() != lower
+                    && sk.as_str() != upper
+                    && !exclude.is_some_and(|(field, value)| {
+                        entity.data.get(field).and_then(Value::as_str) == Some(value)
+                    })
+            })
+            .count();
+        Ok(count as i64)
+    }
+
+    async fn scan_entity_type(&self, entity_type: &str) -> Result<Vec<GameEntity>> {
+        Ok(self
+            .items()
+            .values()
+            .filter(|entity| entity.entity_type == entity_type)
+            .cloned()
+            .collect())
+    }
+
+    async fn increment_field(
+        &self,
+        pk: &str,
+        sk: &str,
+        field: &str,
+        delta: i64,
+    ) -> Result<Option<i64>> {
+        let mut items = self.items();
+        let Some(entity) = items.get_mut(&key(pk, sk)) else {
+            return Ok(None);
+        };
+
+        let value = entity.data.get(field).and_then(Value::as_i64).unwrap_or(0) + delta;
+        if let Some(data) = entity.data.as_object_mut() {
+            data.insert(field.to_string(), value.into());
+        }
+        bump_version(entity);
+        Ok(Some(value))
+    }
+
+    async fn write_counted(
+        &self,
+        write: CountedWrite<'_>,
+        counters: &[CounterDelta<'_>],
+    ) -> Result<bool> {
+        let mut items = self.items();
+        let allowed = match write {
+            CountedWrite::Put(entity) => !items.contains_key(&key(&entity.pk, &entity.sk)),
+            CountedWrite::PutAll(entities) => entities
+                .iter()
+                .all(|entity| !items.contains_key(&key(&entity.pk, &entity.sk))),
+            CountedWrite::Delete { pk, sk } => items.contains_key(&key(pk, sk)),
+        };
+        if !allowed {
+            return Ok(false);
+        }
+        if let Some(missing) = counters
+            .iter()
+            .find(|counter| !items.contains_key(&key(counter.pk, counter.sk)))
+        {
+            return Err(anyhow!(
+                "Counter item {}/{} does not exist",
+                missing.pk,
+                missing.sk
+            ));
+        }
+
+        match write {
+            CountedWrite::Put(entity) => {
+                items.insert(key(&entity.pk, &entity.sk), entity.clone());
+            }
+            CountedWrite::PutAll(entities) => {
+                for entity in entities {
+                    items.insert(key(&entity.pk, &entity.sk), entity.clone());
+                }
+            }
+            CountedWrite::Delete { pk, sk } => {
+                items.remove(&key(pk, sk));
+            }
+        }
+        for counter in counters {
+            if let Some(entity) = items.get_mut(&key(counter.pk, counter.sk)) {
+                let value = entity
+                    .data
+                    .get(counter.field)
+                    .and_then(Value::as_i64)
+                    .unwrap_or(0)
+                    + counter.delta;
+                if let Some(data) = entity.data.as_object_mut() {
+                    data.insert(counter.field.to_string(), value.into());
+                }
+                bump_version(entity);
+            }
+        }
+        Ok(true)
+    }
+
+    async fn update_fields(
+        &self,
+        pk: &str,
+        sk: &str,
+        fields: &[(&str, serde_json::Value)],
+        unless: Option<(&str, serde_json::Value)>,
+    ) -> Result<bool> {
+        let mut items = self.items();
+        let Some(entity) = items.get_mut(&key(pk, sk)) else {
+            return Ok(false);
+        };
+        if let Some((field, value)) = unless {
+            if entity.data.get(field) == Some(&value) {
+                return Ok(false);
+            }
+        }
+
+        if let Some(data) = entity.data.as_object_mut() {
+            for (field, value) in fields {
+                data.insert(field.to_string(), value.clone());
+            }
+        }
+        entity.updated_at = chrono::Utc::now().to_rfc3339();
+        bump_version(entity);
+        Ok(true)
+    }
+
+    async fn set_gsi2_if(
+        &self,
+        pk: &str,
+        sk: &str,
+        (gsi2_pk, gsi2_sk): (&str, &str),
+        (field, expected): (&str, i64),
+    ) -> Result<bool> {
+        let mut items = self.items();
+        let Some(entity) = items.get_mut(&key(pk, sk)) else {
+            return Ok(false);
+        };
+        if entity.data.get(field).and_then(Value::as_i64) != Some(expected) {
+            return Ok(false);
+        }
+
+        entity.gsi2_pk = Some(gsi2_pk.to_string());
+        entity.gsi2_sk = Some(gsi2_sk.to_string());
+        Ok(true)
+    }
+
+    async fn delete(&self, pk: &str, sk: &str) -> Result<bool> {
+        Ok(self.items().remove(&key(pk, sk)).is_some())
+    }
+}
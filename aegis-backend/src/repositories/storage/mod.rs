@@ -0,0 +1,230 @@
+pub mod dynamodb;
+pub mod memory;
+
+pub use dynamodb::DynamoStorage;
+pub use memory::MemoryStorage;
+
+use crate::models::dynamodb::GameEntity;
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanDirection {
+    Forward,  // ascending sort key (oldest first for timestamped keys)
+    Backward, // descending sort key (newest first)
+}
+
+/// Which key a query runs against: the table's `pk`/`sk` or one of the
+/// `gsi1_*`/`gsi2_*` index key pairs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Index {
+    Table,
+    Gsi1,
+    Gsi2,
+}
+
+impl Index {
+    /// Partition and sort key of `entity` under this index, if it is indexed.
+    pub fn keys<'a>(&self, entity: &'a GameEntity) -> Option<(&'a str, &'a str)> {
+        match self {
+            Index::Table => Some((&entity.pk, &entity.sk)),
+            Index::Gsi1 => Some((entity.gsi1_pk.as_deref()?, entity.gsi1_sk.as_deref()?)),
+            Index::Gsi2 => Some((entity.gsi2_pk.as_deref()?, entity.gsi2_sk.as_deref()?)),
+        }
+    }
+}
+
+/// Position of an item in the table (and its indexes, when set). Serialized
+/// into an opaque base64 token so clients never depend on the key layout.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CursorKey {
+    pub pk: String,
+    pub sk: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gsi1_pk: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gsi1_sk: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gsi2_pk: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gsi2_sk: Option<String>,
+}
+
+impl CursorKey {
+    pub fn from_entity(entity: &GameEntity) -> Self {
+        Self {
+            pk: entity.pk.clone(),
+            sk: entity.sk.clone(),
+            gsi1_pk: entity.gsi1_pk.clone(),
+            gsi1_sk: entity.gsi1_sk.clone(),
+            gsi2_pk: entity.gsi2_pk.clone(),
+            gsi2_sk: entity.gsi2_sk.clone(),
+        }
+    }
+
+    pub(crate) fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub(crate) fn decode(cursor: &str) -> Result<Self> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(cursor)
+            .map_err(|_| anyhow!("Invalid cursor"))?;
+        serde_json::from_slice(&bytes).map_err(|_| anyhow!("Invalid cursor"))
+    }
+}
+
+/// When a put is allowed to go through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PutCondition {
+    Always,
+    /// No item with the same key exists yet.
+    IfAbsent,
+    /// The item exists and is at this version. Items written before
+    /// versioning count as version 0.
+    IfVersion(u64),
+}
+
+/// The item a counted write creates or removes. A put only goes through
+/// while the key is free, and a delete only while the item exists.
+#[derive(Debug, Clone, Copy)]
+pub enum CountedWrite<'a> {
+    Put(&'a GameEntity),
+    /// Items created together, such as a record and its ID pointer; every
+    /// key must be free.
+    PutAll(&'a [GameEntity]),
+    Delete {
+        pk: &'a str,
+        sk: &'a str,
+    },
+}
+
+impl CountedWrite<'_> {
+    /// How many guarded items the write covers, ahead of the counters.
+    pub fn guarded_items(&self) -> usize {
+        match self {
+            CountedWrite::PutAll(entities) => entities.len(),
+            CountedWrite::Put(_) | CountedWrite::Delete { .. } => 1,
+        }
+    }
+}
+
+/// A delta applied to `data.<field>` of an existing item alongside a
+/// counted write.
+#[derive(Debug, Clone, Copy)]
+pub struct CounterDelta<'a> {
+    pub pk: &'a str,
+    pub sk: &'a str,
+    pub field: &'a str,
+    pub delta: i64,
+}
+
+/// A key query: items whose partition key equals `pk` and whose sort key
+/// starts with `sk_prefix`, in sort-key order.
+#[derive(Debug, Clone)]
+pub struct KeyQuery<'a> {
+    pub index: Index,
+    pub pk: &'a str,
+    pub sk_prefix: Option<&'a str>,
+    pub direction: ScanDirection,
+    /// `None` reads every matching item; `Some` reads a single page.
+    pub limit: Option<i32>,
+    /// Exclusive start position, from a previous page.
+    pub start: Option<&'a CursorKey>,
+}
+
+impl<'a> KeyQuery<'a> {
+    /// Every item in an index partition, ascending.
+    pub fn all(index: Index, pk: &'a str, sk_prefix: Option<&'a str>) -> Self {
+        Self {
+            index,
+            pk,
+            sk_prefix,
+            direction: ScanDirection::Forward,
+            limit: None,
+            start: None,
+        }
+    }
+}
+
+pub struct QueryOutput {
+    pub items: Vec<GameEntity>,
+    /// More items may follow the last one returned.
+    pub has_more: bool,
+}
+
+/// Key-value operations the single-table repositories are built on. Key
+/// semantics follow DynamoDB: items are addressed by (`pk`, `sk`), sort keys
+/// compare bytewise, and prefix queries use `begins_with`. Conditional
+/// operations return `false` (or `None`) instead of failing when their
+/// condition does not hold.
+#[async_trait::async_trait]
+pub trait Storage: Send + Sync {
+    async fn put(&self, entity: &GameEntity, condition: PutCondition) -> Result<bool>;
+
+    async fn batch_put(&self, entities: &[GameEntity]) -> Result<()>;
+
+    async fn get(&self, pk: &str, sk: &str) -> Result<Option<GameEntity>>;
+
+    /// Missing items are skipped and the result order is not guaranteed.
+    async fn batch_get(&self, keys: &[(String, String)]) -> Result<Vec<GameEntity>>;
+
+    async fn query(&self, query: &KeyQuery<'_>) -> Result<QueryOutput>;
+
+    /// Counts items in a partition with `lower < sk < upper`, skipping items
+    /// whose `data.<field>` equals the excluded value when given.
+    async fn count_sk_range(
+        &self,
+        pk: &str,
+        lower: &str,
+        upper: &str,
+        exclude: Option<(&str, &str)>,
+    ) -> Result<i64>;
+
+    /// Full scan for one entity type. Only meant for offline data migrations.
+    async fn scan_entity_type(&self, entity_type: &str) -> Result<Vec<GameEntity>>;
+
+    /// Adds `delta` to `data.<field>` of an existing item and bumps its
+    /// version. Returns the new value, or `None` if the item does not exist.
+    async fn increment_field(
+        &self,
+        pk: &str,
+        sk: &str,
+        field: &str,
+        delta: i64,
+    ) -> Result<Option<i64>>;
+
+    /// Applies `write` and every counter delta as one all-or-nothing write,
+    /// bumping the counters' versions. Returns `false`, changing nothing, if
+    /// the write's condition does not hold. A missing counter item is an
+    /// error.
+    async fn write_counted(
+        &self,
+        write: CountedWrite<'_>,
+        counters: &[CounterDelta<'_>],
+    ) -> Result<bool>;
+
+    /// Sets `data.<field>` for each pair on an existing item, refreshing
+    /// `updated_at` and bumping the version. With `unless`, the update is
+    /// skipped when `data.<field>` already equals the given value.
+    async fn update_fields(
+        &self,
+        pk: &str,
+        sk: &str,
+        fields: &[(&str, serde_json::Value)],
+        unless: Option<(&str, serde_json::Value)>,
+    ) -> Result<bool>;
+
+    /// Sets the GSI2 keys only while `data.<field>` equals `expected`.
+    async fn set_gsi2_if(
+        &self,
+        pk: &str,
+        sk: &str,
+        gsi2: (&str, &str),
+        expected: (&str, i64),
+    ) -> Result<bool>;
+
+    /// Returns `false` when there was nothing to delete.
+    async fn delete(&self, pk: &str, sk: &str) -> Result<bool>;
+}
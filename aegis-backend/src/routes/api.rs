@@ -1,137 +1,534 @@
-use crate::{handlers, AppState};
+use crate::{
+    handlers,
+    middleware::{
+        admin_permission_middleware, authenticated_middleware, organization_only_middleware,
+        player_only_middleware, RateLimitLayer, RequiredPermission,
+    },
+    models::postgres::admin::AdminPermission,
+    AppState,
+};
 use axum::{
-    routing::{get, post, put},
+    middleware,
+    routing::{delete, get, post, put, MethodRouter},
     Router,
 };
 
-pub fn create_routes() -> Router<AppState> {
-    Router::new()
+/// Who may call a route.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    /// Anyone, signed in or not
+    Public,
+    /// Any signed-in user, by JWT or API key
+    Authenticated,
+    Player,
+    /// Approved organizations
+    Organization,
+    /// Active admins holding the permission
+    Admin(AdminPermission),
+}
+
+pub fn create_routes(state: AppState) -> Router<AppState> {
+    let mut tiers: Vec<(Access, Router<AppState>)> = Vec::new();
+    for (access, path, route) in route_table() {
+        match tiers.iter().position(|(a, _)| *a == access) {
+            Some(i) => tiers[i].1 = std::mem::take(&mut tiers[i].1).route(path, route),
+            None => tiers.push((access, Router::new().route(path, route))),
+        }
+    }
+
+    tiers
+        .into_iter()
+        .fold(Router::new(), |app, (access, routes)| {
+            app.merge(guard(routes, access, &state))
+        })
+}
+
+/// Every route with the access it requires. Routes are only reachable
+/// through this table, so none can be added without saying who may call it.
+pub fn route_table() -> Vec<(Access, &'static str, MethodRouter<AppState>)> {
+    use Access::*;
+
+    vec![
         // ========================================
-        // PUBLIC AUTH ENDPOINTS (No JWT Required)
+        // PUBLIC AUTH ENDPOINTS
         // ========================================
-        .route("/auth/login", post(handlers::auth_login))
-        .route("/auth/register", post(handlers::auth_register))
-        .route("/auth/forgot-password", post(handlers::forgot_password))
-        .route(
+        (Public, "/auth/login", post(handlers::auth_login)),
+        (Public, "/auth/register", post(handlers::auth_register)),
+        (
+            Public,
+            "/auth/forgot-password",
+            post(handlers::forgot_password),
+        ),
+        (
+            Public,
             "/auth/reset-password/:token",
             post(handlers::reset_password),
-        )
-        .route("/auth/verify-email/:token", post(handlers::verify_email))
+        ),
+        (
+            Public,
+            "/auth/verify-email/:token",
+            post(handlers::verify_email),
+        ),
+        (
+            Public,
+            "/auth/2fa/challenge",
+            post(handlers::answer_two_factor_challenge),
+        ),
+        (
+            Public,
+            "/auth/2fa/challenge/enroll",
+            post(handlers::begin_challenge_enrolment),
+        ),
+        (
+            Public,
+            "/notifications/unsubscribe/:token",
+            post(handlers::unsubscribe_from_notifications),
+        ),
+        // ========================================
+        // PUBLIC ACTIVITY AND PROFILE ENDPOINTS
+        // ========================================
+        (
+            Public,
+            "/activity/recent/:activity_type",
+            get(handlers::get_recent_activity),
+        ),
+        (
+            Public,
+            "/players/username/:username",
+            get(handlers::get_player_by_username),
+        ),
         // ========================================
-        // PROTECTED AUTH ENDPOINTS (JWT Required)
+        // AUTHENTICATED AUTH ENDPOINTS
         // ========================================
-        .route("/auth/logout", post(handlers::auth_logout))
-        .route("/auth/refresh", post(handlers::refresh_token))
-        .route("/auth/revoke-sessions", post(handlers::revoke_all_sessions))
-        .route(
+        (Authenticated, "/auth/logout", post(handlers::auth_logout)),
+        (
+            Authenticated,
+            "/auth/refresh",
+            post(handlers::refresh_token),
+        ),
+        (
+            Authenticated,
+            "/auth/revoke-sessions",
+            post(handlers::revoke_all_sessions),
+        ),
+        (
+            Authenticated,
             "/auth/send-verification",
             post(handlers::send_verification_email),
-        )
+        ),
+        (
+            Authenticated,
+            "/auth/2fa",
+            get(handlers::get_two_factor_status),
+        ),
+        (
+            Authenticated,
+            "/auth/2fa/enroll",
+            post(handlers::begin_two_factor_enrolment),
+        ),
+        (
+            Authenticated,
+            "/auth/2fa/confirm",
+            post(handlers::confirm_two_factor_enrolment),
+        ),
+        (
+            Authenticated,
+            "/auth/2fa/recovery-codes",
+            post(handlers::regenerate_recovery_codes),
+        ),
+        (
+            Authenticated,
+            "/auth/2fa/disable",
+            post(handlers::disable_two_factor),
+        ),
         // ========================================
-        // PROTECTED PLAYER ENDPOINTS (JWT Required)
+        // PLAYER ENDPOINTS
         // ========================================
-        .route("/players/me", get(handlers::get_current_player))
-        .route(
+        (Player, "/players/me", get(handlers::get_current_player)),
+        (
+            Player,
             "/players/profile",
-            get(handlers::get_current_player_profile),
-        )
-        .route("/players/profile", put(handlers::update_player_profile))
-        .route("/players", get(handlers::list_players))
-        .route("/players/:id", get(handlers::get_player_by_id))
-        .route(
-            "/players/username/:username",
-            get(handlers::get_player_by_username),
-        )
-        // ========================================
-        // PROTECTED SOCIAL ENDPOINTS (JWT Required)
-        // ========================================
-        .route("/chats", post(handlers::create_chat))
-        .route("/chats/:chat_id", get(handlers::get_chat))
-        .route("/chats/:chat_id/messages", post(handlers::send_message))
-        .route("/chats/:chat_id/messages", get(handlers::get_messages))
-        .route("/chats/:chat_id/join/:user_id", post(handlers::join_chat))
-        .route("/communities", post(handlers::create_community))
-        .route("/communities/:community_id", get(handlers::get_community))
-        .route(
-            "/communities/:community_id/posts",
-            post(handlers::add_post_to_community),
-        )
-        .route(
+            get(handlers::get_current_player_profile).put(handlers::update_player_profile),
+        ),
+        (Authenticated, "/players", get(handlers::list_players)),
+        (
+            Authenticated,
+            "/players/:id",
+            get(handlers::get_player_by_id),
+        ),
+        (
+            Authenticated,
+            "/players/:id/activity",
+            get(handlers::get_player_activity),
+        ),
+        (
+            Player,
+            "/players/me/identity",
+            post(handlers::submit_identity_verification)
+                .get(handlers::get_my_identity_verification),
+        ),
+        (
+            Player,
+            "/players/me/withdrawals",
+            post(handlers::request_withdrawal),
+        ),
+        // ========================================
+        // AUTHENTICATED SOCIAL ENDPOINTS
+        // ========================================
+        (Authenticated, "/chats", post(handlers::create_chat)),
+        (Authenticated, "/chats/me", get(handlers::get_my_chats)),
+        (Authenticated, "/chats/:chat_id", get(handlers::get_chat)),
+        (
+            Authenticated,
+            "/chats/:chat_id/messages",
+            post(handlers::send_message).get(handlers::get_messages),
+        ),
+        (
+            Authenticated,
+            "/chats/:chat_id/messages/:message_id",
+            put(handlers::edit_message).delete(handlers::delete_message),
+        ),
+        (
+            Authenticated,
+            "/chats/:chat_id/messages/:message_id/reactions",
+            post(handlers::add_reaction),
+        ),
+        (
+            Authenticated,
+            "/chats/:chat_id/messages/:message_id/reactions/:emoji",
+            delete(handlers::remove_reaction),
+        ),
+        (
+            Authenticated,
+            "/chats/:chat_id/read",
+            post(handlers::mark_chat_read),
+        ),
+        (
+            Authenticated,
+            "/chats/:chat_id/participants",
+            get(handlers::get_chat_participants),
+        ),
+        (
+            Authenticated,
+            "/chats/:chat_id/join/:user_id",
+            post(handlers::join_chat),
+        ),
+        (
+            Authenticated,
+            "/chats/direct/:player_id",
+            post(handlers::open_direct_chat),
+        ),
+        (
+            Authenticated,
+            "/chats/team/:team_id",
+            post(handlers::open_team_chat),
+        ),
+        (
+            Authenticated,
+            "/communities",
+            post(handlers::create_community),
+        ),
+        (
+            Authenticated,
+            "/communities/me",
+            get(handlers::get_my_communities),
+        ),
+        (
+            Authenticated,
+            "/communities/:community_id",
+            get(handlers::get_community),
+        ),
+        (
+            Authenticated,
+            "/communities/:community_id/members",
+            get(handlers::get_community_members),
+        ),
+        (
+            Authenticated,
             "/communities/:community_id/posts",
-            get(handlers::get_community_posts),
-        )
-        .route(
+            post(handlers::add_post_to_community).get(handlers::get_community_posts),
+        ),
+        (
+            Authenticated,
             "/communities/:community_id/join/:user_id",
             post(handlers::join_community),
-        )
-        .route(
+        ),
+        (
+            Authenticated,
             "/communities/:community_id/leave/:user_id",
             post(handlers::leave_community),
-        )
+        ),
+        (
+            Authenticated,
+            "/communities/:community_id/join-requests",
+            get(handlers::get_community_join_requests),
+        ),
+        (
+            Authenticated,
+            "/communities/:community_id/join-requests/:user_id/approve",
+            post(handlers::approve_community_join_request),
+        ),
+        (
+            Authenticated,
+            "/communities/:community_id/join-requests/:user_id/reject",
+            post(handlers::reject_community_join_request),
+        ),
+        (
+            Authenticated,
+            "/communities/:community_id/moderators/:user_id",
+            put(handlers::add_community_moderator).delete(handlers::remove_community_moderator),
+        ),
+        (
+            Authenticated,
+            "/communities/:community_id/posts/:post_id",
+            delete(handlers::remove_community_post),
+        ),
+        (
+            Authenticated,
+            "/communities/:community_id/posts/:post_id/pin",
+            put(handlers::pin_community_post).delete(handlers::unpin_community_post),
+        ),
+        (
+            Authenticated,
+            "/communities/:community_id/bans/:user_id",
+            put(handlers::ban_community_member).delete(handlers::unban_community_member),
+        ),
+        (
+            Authenticated,
+            "/communities/:community_id/mutes/:user_id",
+            put(handlers::mute_community_member).delete(handlers::unmute_community_member),
+        ),
+        (Authenticated, "/posts", post(handlers::create_post)),
+        (Authenticated, "/posts/:post_id", get(handlers::get_post)),
+        (
+            Authenticated,
+            "/posts/:post_id/comments",
+            post(handlers::add_comment).get(handlers::get_comments),
+        ),
+        (
+            Authenticated,
+            "/posts/:post_id/comments/:comment_id",
+            put(handlers::edit_comment).delete(handlers::delete_comment),
+        ),
+        (
+            Authenticated,
+            "/posts/:post_id/comments/:comment_id/replies",
+            get(handlers::get_comment_replies),
+        ),
+        (
+            Authenticated,
+            "/posts/:post_id/like",
+            post(handlers::like_post).delete(handlers::unlike_post),
+        ),
+        (
+            Authenticated,
+            "/posts/author/:author_id",
+            get(handlers::get_posts_by_author),
+        ),
+        (Authenticated, "/feed", get(handlers::get_home_feed)),
+        (
+            Authenticated,
+            "/follows/:target_type/:target_id",
+            post(handlers::follow)
+                .delete(handlers::unfollow)
+                .get(handlers::get_follow_stats),
+        ),
+        (
+            Authenticated,
+            "/follows/:target_type/:target_id/followers",
+            get(handlers::get_followers),
+        ),
+        (
+            Authenticated,
+            "/follows/:target_type/:target_id/following",
+            get(handlers::get_following),
+        ),
+        (
+            Authenticated,
+            "/notifications",
+            get(handlers::get_notifications),
+        ),
+        (
+            Authenticated,
+            "/notifications/unread-count",
+            get(handlers::get_unread_notification_count),
+        ),
+        (
+            Authenticated,
+            "/notifications/preferences",
+            get(handlers::get_notification_preferences)
+                .put(handlers::update_notification_preferences),
+        ),
+        (
+            Authenticated,
+            "/notifications/read-all",
+            post(handlers::mark_all_notifications_read),
+        ),
+        (
+            Authenticated,
+            "/notifications/:notification_id/read",
+            post(handlers::mark_notification_read),
+        ),
         // ========================================
-        // PROTECTED UPLOAD ENDPOINTS (JWT Required)
+        // AUTHENTICATED UPLOAD ENDPOINTS
         // ========================================
-        .route(
+        (
+            Authenticated,
             "/uploads/profile/:user_id",
             post(handlers::upload_profile_picture),
-        )
-        .route(
+        ),
+        (
+            Authenticated,
             "/uploads/chat/:chat_id",
             post(handlers::upload_chat_attachment),
-        )
-        .route("/uploads/presigned/:key", get(handlers::get_presigned_url))
-}
-
-/// These routes require valid JWT authentication and active session
-// pub fn protected_routes() -> Vec<&'static str> {
-//     vec![
-//         // ========================================
-//         // AUTHENTICATED USER MANAGEMENT
-//         // ========================================
-//         "/auth/logout",
-//         "/auth/refresh",
-//         "/auth/revoke-sessions",
-//         "/auth/send-verification",
-//         // ========================================
-//         // PLAYER DATA ACCESS (PII/SENSITIVE)
-//         // ========================================
-//         "/players/me",                 // Current user's data
-//         "/players/profile",            // Current user's profile
-//         "/players",                    // All players list (sensitive)
-//         "/players/:id",                // Individual player data
-//         "/players/username/:username", // Player lookup by username
-//         // ========================================
-//         // SOCIAL FEATURES (USER CONTEXT REQUIRED)
-//         // ========================================
-//         "/chats",                                    // Create chat
-//         "/chats/:chat_id",                           // View chat
-//         "/chats/:chat_id/messages",                  // Chat messages
-//         "/chats/:chat_id/join/:user_id",             // Join chat
-//         "/communities",                              // Create community
-//         "/communities/:community_id",                // View community
-//         "/communities/:community_id/posts",          // Community posts
-//         "/communities/:community_id/join/:user_id",  // Join community
-//         "/communities/:community_id/leave/:user_id", // Leave community
-//         // ========================================
-//         // FILE OPERATIONS (SECURITY CRITICAL)
-//         // ========================================
-//         "/uploads/profile/:user_id", // Profile picture upload
-//         "/uploads/chat/:chat_id",    // Chat file upload
-//         "/uploads/presigned/:key",   // S3 presigned URLs
-//     ]
-// }
-
-/// Enterprise Security Classification: Public Routes
-/// These routes are accessible without authentication
-pub fn public_routes() -> Vec<&'static str> {
-    vec![
+        ),
+        (
+            Authenticated,
+            "/uploads/presigned/:key",
+            get(handlers::get_presigned_url),
+        ),
+        // ========================================
+        // ORGANIZATION ENDPOINTS (Approved Organization)
         // ========================================
-        // AUTHENTICATION ENTRY POINTS
+        (
+            Organization,
+            "/organizations/me/api-keys",
+            get(handlers::list_api_keys).post(handlers::create_api_key),
+        ),
+        (
+            Organization,
+            "/organizations/me/api-keys/:key_id",
+            delete(handlers::revoke_api_key),
+        ),
+        (
+            Organization,
+            "/organizations/me/webhooks",
+            get(handlers::list_webhooks).post(handlers::create_webhook),
+        ),
+        (
+            Organization,
+            "/organizations/me/webhooks/:id",
+            delete(handlers::delete_webhook),
+        ),
+        (
+            Organization,
+            "/organizations/me/webhooks/:id/deliveries",
+            get(handlers::list_webhook_deliveries),
+        ),
+        (
+            Organization,
+            "/organizations/me/webhooks/:id/test",
+            post(handlers::send_test_webhook),
+        ),
+        (
+            Organization,
+            "/organizations/me/tournaments/:tournament_id/battles",
+            post(handlers::schedule_battle),
+        ),
+        (
+            Organization,
+            "/organizations/me/tournaments/:tournament_id/battles/:battle_id/complete",
+            post(handlers::complete_battle),
+        ),
+        (
+            Organization,
+            "/organizations/me/tournaments/:tournament_id/standings",
+            put(handlers::update_standings),
+        ),
         // ========================================
-        "/auth/login",                 // User login
-        "/auth/register",              // User registration
-        "/auth/forgot-password",       // Password reset request
-        "/auth/reset-password/:token", // Password reset completion
-        "/auth/verify-email/:token",   // Email verification completion
+        // ADMIN ENDPOINTS (Active Admin With Permission)
+        // ========================================
+        (
+            Admin(AdminPermission::AdminsManage),
+            "/admin/permissions",
+            get(handlers::get_permission_catalogue),
+        ),
+        (
+            Admin(AdminPermission::AdminsManage),
+            "/admin/admins",
+            get(handlers::list_admins).post(handlers::create_admin),
+        ),
+        (
+            Admin(AdminPermission::AdminsManage),
+            "/admin/admins/:id",
+            get(handlers::get_admin),
+        ),
+        (
+            Admin(AdminPermission::AdminsManage),
+            "/admin/admins/:id/permissions",
+            put(handlers::update_admin_permissions),
+        ),
+        (
+            Admin(AdminPermission::AdminsManage),
+            "/admin/two-factor/policies",
+            get(handlers::list_two_factor_policies),
+        ),
+        (
+            Admin(AdminPermission::AdminsManage),
+            "/admin/two-factor/policies/:role",
+            put(handlers::update_two_factor_policy),
+        ),
+        (
+            Admin(AdminPermission::IdentityReview),
+            "/admin/identity-verifications",
+            get(handlers::list_identity_verification_queue),
+        ),
+        (
+            Admin(AdminPermission::IdentityReview),
+            "/admin/identity-verifications/:id",
+            get(handlers::get_identity_verification),
+        ),
+        (
+            Admin(AdminPermission::IdentityReview),
+            "/admin/identity-verifications/:id/approve",
+            post(handlers::approve_identity_verification),
+        ),
+        (
+            Admin(AdminPermission::IdentityReview),
+            "/admin/identity-verifications/:id/reject",
+            post(handlers::reject_identity_verification),
+        ),
+        (
+            Admin(AdminPermission::EmailOutboxManage),
+            "/admin/email-outbox/stuck",
+            get(handlers::list_stuck_emails),
+        ),
+        (
+            Admin(AdminPermission::EmailOutboxManage),
+            "/admin/email-outbox/:id/retry",
+            post(handlers::retry_email),
+        ),
+        (
+            Admin(AdminPermission::ContentModerate),
+            "/admin/posts/:post_id/comments/:comment_id",
+            delete(handlers::moderate_delete_comment),
+        ),
     ]
 }
+
+/// Wraps one tier of routes in its access checks. Layers added later run
+/// first: authentication, then the tier's own check, then rate limits, which
+/// need the principal.
+fn guard(routes: Router<AppState>, access: Access, state: &AppState) -> Router<AppState> {
+    let routes = routes.route_layer(RateLimitLayer::new(state.rate_limit_service.clone()));
+    let routes = match access {
+        Access::Public => return routes,
+        Access::Authenticated => routes,
+        Access::Player => routes.route_layer(middleware::from_fn(player_only_middleware)),
+        Access::Organization => routes.route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            organization_only_middleware,
+        )),
+        Access::Admin(permission) => routes.route_layer(middleware::from_fn_with_state(
+            RequiredPermission {
+                state: state.clone(),
+                permission,
+            },
+            admin_permission_middleware,
+        )),
+    };
+    routes.route_layer(middleware::from_fn_with_state(
+        state.clone(),
+        authenticated_middleware,
+    ))
+}
@@ -3,6 +3,6 @@ pub mod api;
 use axum::Router;
 use crate::AppState;
 
-pub fn create_routes() -> Router<AppState> {
-    api::create_routes()
+pub fn create_routes(state: AppState) -> Router<AppState> {
+    api::create_routes(state)
 }
@@ -0,0 +1,25 @@
+use crate::models::dynamodb::{PostComment, PostCommentRef};
+use crate::repositories::DynamoRepository;
+use anyhow::Result;
+
+/// Writes the `COMMENTID#` pointer for comments created before pointers
+/// existed, so comments can be loaded by ID without scanning their post.
+/// Safe to run more than once: existing pointers are left alone.
+pub async fn backfill_comment_refs(dynamo: &DynamoRepository) -> Result<()> {
+    let mut written = 0;
+
+    for entity in dynamo.scan_entity_type("comment").await? {
+        let comment: PostComment = serde_json::from_value(entity.data.clone())?;
+        let comment_ref = PostCommentRef {
+            post_id: comment.post_id,
+            comment_id: comment.id,
+            sort_key: entity.sk,
+        };
+        if dynamo.put_item_if_absent(&comment_ref.to_entity()?).await? {
+            written += 1;
+        }
+    }
+
+    println!("✅ Backfilled {} comment pointers", written);
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use crate::models::dynamodb::{
+    Chat, ChatParticipant, Community, CommunityBan, CommunityMember, GameEntity, ROLE_MEMBER,
+    ROLE_MODERATOR, ROLE_OWNER,
+};
+use crate::repositories::DynamoRepository;
+use anyhow::Result;
+use serde_json::Value;
+
+/// Moves the membership arrays that used to be embedded in community and chat
+/// items out into one item per member, then rewrites the parent with its
+/// count. Safe to run more than once: items that were already migrated carry
+/// no arrays and are skipped.
+pub async fn migrate_memberships(dynamo: &DynamoRepository) -> Result<()> {
+    let communities = migrate_communities(dynamo).await?;
+    println!("✅ Migrated {} communities", communities);
+
+    let chats = migrate_chats(dynamo).await?;
+    println!("✅ Migrated {} chats", chats);
+
+    Ok(())
+}
+
+async fn migrate_communities(dynamo: &DynamoRepository) -> Result<usize> {
+    let mut migrated = 0;
+
+    for entity in dynamo.scan_entity_type("community").await? {
+        if !has_legacy_array(&entity, &["members", "moderators", "banned", "muted"]) {
+            continue;
+        }
+        let community: Community = serde_json::from_value(entity.data.clone())?;
+        let moderators = legacy_array(&entity, "moderators");
+        let muted = legacy_array(&entity, "muted");
+        let banned = legacy_array(&entity, "banned");
+
+        // The owner was not always listed in `members`
+        let mut user_ids = vec![community.owner.clone()];
+        user_ids.extend(moderators.iter().cloned());
+        user_ids.extend(legacy_array(&entity, "members"));
+
+        let mut seen = Vec::new();
+        for user_id in user_ids {
+            if seen.contains(&user_id) || banned.contains(&user_id) {
+                continue;
+            }
+            let role = if community.is_owner(&user_id) {
+                ROLE_OWNER
+            } else if moderators.contains(&user_id) {
+                ROLE_MODERATOR
+            } else {
+                ROLE_MEMBER
+            };
+            let member = CommunityMember {
+                community_id: community.id.clone(),
+                user_id: user_id.clone(),
+                role: role.to_string(),
+                muted: muted.contains(&user_id),
+                joined_at: community.created_at.clone(),
+            };
+            dynamo.put_item_if_absent(&member.to_entity()?).await?;
+            seen.push(user_id);
+        }
+
+        for user_id in banned {
+            let ban = CommunityBan {
+                community_id: community.id.clone(),
+                user_id,
+                banned_by: community.owner.clone(),
+                created_at: community.updated_at.clone(),
+            };
+            dynamo.put_item_if_absent(&ban.to_entity()?).await?;
+        }
+
+        let pk = format!("COMMUNITY#{}", community.id);
+        let member_count = dynamo.query_prefix(&pk, "MEMBER#").await?.len();
+        let community = Community {
+            member_count: member_count as i32,
+            ..community
+        };
+        dynamo.put_item(&community.to_entity()?).await?;
+        migrated += 1;
+    }
+
+    Ok(migrated)
+}
+
+async fn migrate_chats(dynamo: &DynamoRepository) -> Result<usize> {
+    let mut migrated = 0;
+
+    for entity in dynamo.scan_entity_type("chat").await? {
+        if !has_legacy_array(&entity, &["participants"]) {
+            continue;
+        }
+        let chat = Chat::from_entity(&entity)?;
+
+        for user_id in legacy_array(&entity, "participants") {
+            let participant = ChatParticipant {
+                chat_id: chat.id.clone(),
+                user_id,
+                joined_at: chat.created_at.clone(),
+                last_read_at: None,
+                last_read_message_id: None,
+            };
+            dynamo.put_item_if_absent(&participant.to_entity()?).await?;
+        }
+
+        let pk = format!("CHAT#{}", chat.id);
+        let participant_count = dynamo.query_prefix(&pk, "PARTICIPANT#").await?.len();
+        let chat = Chat {
+            participant_count: participant_count as i32,
+            ..chat
+        };
+        dynamo.put_item(&chat.to_entity()?).await?;
+        migrated += 1;
+    }
+
+    Ok(migrated)
+}
+
+fn has_legacy_array(entity: &GameEntity, fields: &[&str]) -> bool {
+    fields.iter().any(|field| entity.data.get(*field).is_some())
+}
+
+fn legacy_array(entity: &GameEntity, field: &str) -> Vec<String> {
+    entity
+        .data
+        .get(field)
+        .and_then(Value::as_array)
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(|value| value.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
@@ -1,2 +1,6 @@
+pub mod backfill_comment_refs;
+pub mod migrate_memberships;
+pub mod reindex_follows;
+pub mod rekey_messages;
 pub mod setup_dynamodb;
 pub mod setup_s3;
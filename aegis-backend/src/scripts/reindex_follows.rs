@@ -0,0 +1,26 @@
+use crate::models::dynamodb::Follow;
+use crate::repositories::DynamoRepository;
+use anyhow::Result;
+
+/// Rewrites follows created before the followers index was sorted by time,
+/// whose GSI1 sort key was just `USER#<follower_id>`. Safe to run more than
+/// once: follows already keyed by `created_at` are skipped.
+pub async fn reindex_follows(dynamo: &DynamoRepository) -> Result<()> {
+    let mut reindexed = 0;
+
+    for entity in dynamo.scan_entity_type("follow").await? {
+        if !entity
+            .gsi1_sk
+            .as_deref()
+            .is_some_and(|sk| sk.starts_with("USER#"))
+        {
+            continue;
+        }
+        let follow: Follow = serde_json::from_value(entity.data)?;
+        dynamo.put_item(&follow.to_entity()?).await?;
+        reindexed += 1;
+    }
+
+    println!("✅ Reindexed {} follows", reindexed);
+    Ok(())
+}
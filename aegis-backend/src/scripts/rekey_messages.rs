@@ -0,0 +1,32 @@
+use crate::models::dynamodb::{ChatMessage, ChatMessageRef};
+use crate::repositories::DynamoRepository;
+use anyhow::Result;
+
+/// Moves chat messages stored under the old `MSG#<timestamp>` sort key to
+/// `MSG#<timestamp>#<id>` and rewrites every message's ID pointer to the new
+/// key. Safe to run more than once: messages already under their new key
+/// only get their pointer rewritten.
+pub async fn rekey_messages(dynamo: &DynamoRepository) -> Result<()> {
+    let mut moved = 0;
+
+    for entity in dynamo.scan_entity_type("message").await? {
+        let message: ChatMessage = serde_json::from_value(entity.data.clone())?;
+        let rekeyed = message.to_entity()?;
+
+        if rekeyed.sk != entity.sk {
+            dynamo.put_item_if_absent(&rekeyed).await?;
+            dynamo.delete_item(&entity.pk, &entity.sk).await?;
+            moved += 1;
+        }
+
+        let message_ref = ChatMessageRef {
+            chat_id: message.chat_id.clone(),
+            message_id: message.id.clone(),
+            sort_key: rekeyed.sk,
+        };
+        dynamo.put_item(&message_ref.to_entity()?).await?;
+    }
+
+    println!("✅ Re-keyed {} chat messages", moved);
+    Ok(())
+}
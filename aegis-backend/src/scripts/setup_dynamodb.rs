@@ -1,7 +1,8 @@
 use aws_sdk_dynamodb::{
     types::{
-        AttributeDefinition, BillingMode, GlobalSecondaryIndex, KeySchemaElement, KeyType,
-        Projection, ProjectionType, ScalarAttributeType,
+        AttributeDefinition, BillingMode, CreateGlobalSecondaryIndexAction, GlobalSecondaryIndex,
+        GlobalSecondaryIndexUpdate, KeySchemaElement, KeyType, Projection, ProjectionType,
+        ScalarAttributeType, TimeToLiveSpecification, TimeToLiveStatus,
     },
     Client,
 };
@@ -12,8 +13,18 @@ pub async fn create_gaming_table(client: &Client) -> Result<(), Box<dyn std::err
 
     // 🎯 Enterprise Check: Does table already exist?
     match client.describe_table().table_name(&table_name).send().await {
-        Ok(_) => {
+        Ok(output) => {
             println!("✅ DynamoDB table '{}' already exists - skipping creation", table_name);
+            let has_gsi2 = output
+                .table
+                .and_then(|t| t.global_secondary_indexes)
+                .unwrap_or_default()
+                .iter()
+                .any(|index| index.index_name() == Some("GSI2"));
+            if !has_gsi2 {
+                add_gsi2(client, &table_name).await?;
+            }
+            enable_ttl(client, &table_name).await?;
             return Ok(());
         }
         Err(_) => {
@@ -65,6 +76,18 @@ pub async fn create_gaming_table(client: &Client) -> Result<(), Box<dyn std::err
                 .attribute_type(ScalarAttributeType::S)
                 .build()?,
         )
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("gsi2_pk")
+                .attribute_type(ScalarAttributeType::S)
+                .build()?,
+        )
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("gsi2_sk")
+                .attribute_type(ScalarAttributeType::S)
+                .build()?,
+        )
         // GSI for queries
         .global_secondary_indexes(
             GlobalSecondaryIndex::builder()
@@ -88,9 +111,114 @@ pub async fn create_gaming_table(client: &Client) -> Result<(), Box<dyn std::err
                 )
                 .build()?,
         )
+        // GSI2: inverse lookups, e.g. chats by participant
+        .global_secondary_indexes(
+            GlobalSecondaryIndex::builder()
+                .index_name("GSI2")
+                .key_schema(
+                    KeySchemaElement::builder()
+                        .attribute_name("gsi2_pk")
+                        .key_type(KeyType::Hash)
+                        .build()?,
+                )
+                .key_schema(
+                    KeySchemaElement::builder()
+                        .attribute_name("gsi2_sk")
+                        .key_type(KeyType::Range)
+                        .build()?,
+                )
+                .projection(
+                    Projection::builder()
+                        .projection_type(ProjectionType::All)
+                        .build(),
+                )
+                .build()?,
+        )
         .send()
         .await?;
 
     println!("✅ DynamoDB table '{}' created successfully", table_name);
+    enable_ttl(client, &table_name).await?;
+    Ok(())
+}
+
+// Items with a `ttl` attribute (epoch seconds), e.g. notifications, expire on their own
+async fn enable_ttl(client: &Client, table_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let status = client
+        .describe_time_to_live()
+        .table_name(table_name)
+        .send()
+        .await?
+        .time_to_live_description
+        .and_then(|d| d.time_to_live_status);
+    if matches!(status, Some(TimeToLiveStatus::Enabled | TimeToLiveStatus::Enabling)) {
+        return Ok(());
+    }
+
+    client
+        .update_time_to_live()
+        .table_name(table_name)
+        .time_to_live_specification(
+            TimeToLiveSpecification::builder()
+                .attribute_name("ttl")
+                .enabled(true)
+                .build()?,
+        )
+        .send()
+        .await?;
+
+    println!("✅ TTL enabled on '{}'", table_name);
+    Ok(())
+}
+
+// Tables created before GSI2 existed get it added in place
+async fn add_gsi2(client: &Client, table_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    println!("📊 Adding GSI2 to DynamoDB table '{}'...", table_name);
+
+    client
+        .update_table()
+        .table_name(table_name)
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("gsi2_pk")
+                .attribute_type(ScalarAttributeType::S)
+                .build()?,
+        )
+        .attribute_definitions(
+            AttributeDefinition::builder()
+                .attribute_name("gsi2_sk")
+                .attribute_type(ScalarAttributeType::S)
+                .build()?,
+        )
+        .global_secondary_index_updates(
+            GlobalSecondaryIndexUpdate::builder()
+                .create(
+                    CreateGlobalSecondaryIndexAction::builder()
+                        .index_name("GSI2")
+                        .key_schema(
+                            KeySchemaElement::builder()
+                                .attribute_name("gsi2_pk")
+                                .key_type(KeyType::Hash)
+                                .build()?,
+                        )
+                        .key_schema(
+                            KeySchemaElement::builder()
+                                .attribute_name("gsi2_sk")
+                                .key_type(KeyType::Range)
+                                .build()?,
+                        )
+                        .projection(
+                            Projection::builder()
+                                .projection_type(ProjectionType::All)
+                                .build(),
+                        )
+                        .build()?,
+                )
+                .build(),
+        )
+        .send()
+        .await?;
+
+    println!("✅ GSI2 added to '{}'", table_name);
     Ok(())
 }
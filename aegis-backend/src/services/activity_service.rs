@@ -0,0 +1,83 @@
+use crate::models::dynamodb::{ActivityLog, ActivityType};
+use crate::repositories::{ActivityRepository, Page, PageRequest};
+use anyhow::Result;
+use serde_json::Value;
+
+#[derive(Clone)]
+pub struct ActivityService {
+    activity_repo: ActivityRepository,
+}
+
+impl ActivityService {
+    pub fn new(activity_repo: ActivityRepository) -> Self {
+        Self { activity_repo }
+    }
+
+    pub async fn record(
+        &self,
+        user_id: &str,
+        activity_type: ActivityType,
+        description: String,
+        metadata: Value,
+    ) -> Result<()> {
+        let activity = ActivityLog {
+            id: uuid::Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            activity_type: activity_type.as_str().to_string(),
+            description,
+            metadata,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        };
+        self.activity_repo.record(&activity).await
+    }
+
+    /// Records the same activity for several players. The activity log is a
+    /// side channel, so failures are logged rather than returned: callers
+    /// have already committed the change being described.
+    pub async fn record_for_all(
+        &self,
+        user_ids: &[String],
+        activity_type: ActivityType,
+        description: &str,
+        metadata: Value,
+    ) {
+        for user_id in user_ids {
+            if let Err(e) = self
+                .record(
+                    user_id,
+                    activity_type,
+                    description.to_string(),
+                    metadata.clone(),
+                )
+                .await
+            {
+                tracing::warn!(
+                    "Failed to record {} activity for {}: {}",
+                    activity_type.as_str(),
+                    user_id,
+                    e
+                );
+            }
+        }
+    }
+
+    pub async fn get_player_timeline(
+        &self,
+        player_id: &str,
+        request: &PageRequest,
+    ) -> Result<Page<ActivityLog>> {
+        self.activity_repo
+            .get_user_activity(player_id, request)
+            .await
+    }
+
+    pub async fn get_recent(
+        &self,
+        activity_type: ActivityType,
+        request: &PageRequest,
+    ) -> Result<Page<ActivityLog>> {
+        self.activity_repo
+            .get_activity_by_type(activity_type.as_str(), request)
+            .await
+    }
+}
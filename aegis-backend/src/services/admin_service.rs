@@ -1,11 +1,28 @@
 use crate::models::enums::AdminRole;
+use crate::models::postgres::admin::AdminPermission;
 use crate::models::postgres::{admin, Admin};
 use crate::services::auth_service::AuthService;
 use crate::utils::errors::AppError;
 use anyhow::Result;
 use sea_orm::*;
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
+const MIN_PASSWORD_LENGTH: usize = 12;
+
+fn grants_reserved(overrides: &BTreeMap<AdminPermission, bool>) -> bool {
+    overrides.get(&AdminPermission::AdminsManage) == Some(&true)
+}
+
+fn overrides_json(overrides: &BTreeMap<AdminPermission, bool>) -> serde_json::Value {
+    serde_json::Value::Object(
+        overrides
+            .iter()
+            .map(|(permission, granted)| (permission.as_str().to_string(), (*granted).into()))
+            .collect(),
+    )
+}
+
 #[derive(Clone)]
 pub struct AdminService {
     db: DatabaseConnection,
@@ -55,14 +72,54 @@ impl AdminService {
         Ok(Admin::find_by_id(id).one(&self.db).await?)
     }
 
+    pub async fn list_admins(&self) -> Result<Vec<admin::Model>, AppError> {
+        Ok(Admin::find()
+            .order_by_asc(admin::Column::CreatedAt)
+            .all(&self.db)
+            .await?)
+    }
+
     pub async fn create_admin(
         &self,
         username: String,
         email: String,
         password: String,
         role: AdminRole,
-        permissions: serde_json::Value,
+        overrides: BTreeMap<AdminPermission, bool>,
     ) -> Result<admin::Model, AppError> {
+        if username.trim().is_empty() {
+            return Err(AppError::Validation("Username is required".to_string()));
+        }
+        if !email.contains('@') {
+            return Err(AppError::Validation("Invalid email address".to_string()));
+        }
+        if password.len() < MIN_PASSWORD_LENGTH {
+            return Err(AppError::Validation(format!(
+                "Password must be at least {} characters",
+                MIN_PASSWORD_LENGTH
+            )));
+        }
+        if grants_reserved(&overrides) {
+            return Err(AppError::Validation(
+                "admins.manage is reserved for super admins".to_string(),
+            ));
+        }
+        if self.get_by_email(email.clone()).await?.is_some() {
+            return Err(AppError::Validation(
+                "An admin with this email already exists".to_string(),
+            ));
+        }
+        let username_taken = Admin::find()
+            .filter(admin::Column::Username.eq(&username))
+            .one(&self.db)
+            .await?
+            .is_some();
+        if username_taken {
+            return Err(AppError::Validation(
+                "An admin with this username already exists".to_string(),
+            ));
+        }
+
         let hashed_password = self.auth_service.hash_password(&password)?;
         let now = chrono::Utc::now();
 
@@ -72,7 +129,7 @@ impl AdminService {
             email: Set(email),
             password: Set(hashed_password),
             role: Set(role),
-            permissions: Set(permissions),
+            permissions: Set(overrides_json(&overrides)),
             is_active: Set(true),
             last_login: Set(None),
             login_attempts: Set(0),
@@ -81,21 +138,46 @@ impl AdminService {
             updated_at: Set(now),
         };
 
-        Ok(new_admin.insert(&self.db).await?)
+        new_admin
+            .insert(&self.db)
+            .await
+            .map_err(|e| match e.sql_err() {
+                // Another request created the same admin since the checks above
+                Some(SqlErr::UniqueConstraintViolation(_)) => AppError::Validation(
+                    "An admin with this username or email already exists".to_string(),
+                ),
+                _ => e.into(),
+            })
     }
 
-    pub async fn has_permission(&self, admin_id: Uuid, permission: &str) -> Result<bool, AppError> {
-        let admin = self.get_by_id(admin_id).await?;
-        match admin {
-            Some(a) => {
-                if let Some(perm_value) = a.permissions.get(permission) {
-                    Ok(perm_value.as_bool().unwrap_or(false))
-                } else {
-                    Ok(false)
-                }
-            }
-            None => Ok(false),
+    /// Replaces an admin's role and permission overrides. Admins can't change
+    /// their own role, so the last super admin can't demote themselves.
+    pub async fn update_permissions(
+        &self,
+        acting_admin_id: Uuid,
+        admin_id: Uuid,
+        role: Option<AdminRole>,
+        overrides: BTreeMap<AdminPermission, bool>,
+    ) -> Result<admin::Model, AppError> {
+        if grants_reserved(&overrides) {
+            return Err(AppError::Validation(
+                "admins.manage is reserved for super admins".to_string(),
+            ));
+        }
+        let admin = self.get_by_id(admin_id).await?.ok_or(AppError::NotFound)?;
+        if role.as_ref().is_some_and(|r| *r != admin.role) && admin_id == acting_admin_id {
+            return Err(AppError::Validation(
+                "You can't change your own role".to_string(),
+            ));
+        }
+
+        let mut update: admin::ActiveModel = admin.into();
+        if let Some(role) = role {
+            update.role = Set(role);
         }
+        update.permissions = Set(overrides_json(&overrides));
+        update.updated_at = Set(chrono::Utc::now());
+        Ok(update.update(&self.db).await?)
     }
 
     async fn increment_login_attempts(&self, admin_id: Uuid) -> Result<(), AppError> {
@@ -1,19 +1,55 @@
 use crate::models::postgres::{api_key, ApiKey};
 use crate::services::auth_service::AuthService;
 use crate::utils::errors::AppError;
-use chrono::Utc;
+use chrono::{DateTime, Duration, Utc};
+use hmac::{Hmac, Mac};
 use sea_orm::{sea_query::Expr, *};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::task::JoinHandle;
 use uuid::Uuid;
 
+/// Scopes a key can be granted; `*` grants all of them. The routes each
+/// scope unlocks are listed in `middleware::api_key`.
+pub const API_KEY_SCOPES: &[&str] = &["webhooks:read", "webhooks:write"];
+pub const WILDCARD_SCOPE: &str = "*";
+const MAX_KEYS_PER_OWNER: u64 = 20;
+
+/// `key_hash` of keys stored as an HMAC-SHA256 digest of the secret, keyed
+/// by the server's digest secret. Older keys hold an Argon2 hash and are
+/// rewritten to a digest the first time they verify.
+const DIGEST_PREFIX: &str = "hmac-sha256$";
+/// Validated keys are reused for this long. Revocations clear this
+/// instance's cache at once; other instances pick them up on expiry.
+const CACHE_TTL_SECS: i64 = 60;
+const USAGE_FLUSH_INTERVAL_SECS: u64 = 30;
+
+#[derive(Clone)]
+struct CachedKey {
+    key: api_key::Model,
+    cached_at: DateTime<Utc>,
+}
+
 #[derive(Clone)]
 pub struct ApiKeyService {
     db: DatabaseConnection,
     auth_service: AuthService,
+    digest_secret: Arc<String>,
+    cache: Arc<RwLock<HashMap<String, CachedKey>>>,
+    // Latest use per key, written to `last_used_at` by the usage flusher
+    pending_usage: Arc<Mutex<HashMap<Uuid, DateTime<Utc>>>>,
 }
 
 impl ApiKeyService {
-    pub fn new(db: DatabaseConnection, auth_service: AuthService) -> Self {
-        Self { db, auth_service }
+    pub fn new(db: DatabaseConnection, auth_service: AuthService, digest_secret: String) -> Self {
+        Self {
+            db,
+            auth_service,
+            digest_secret: Arc::new(digest_secret),
+            cache: Arc::default(),
+            pending_usage: Arc::default(),
+        }
     }
 
     pub async fn create_api_key(
@@ -25,6 +61,38 @@ impl ApiKeyService {
         rate_limit_per_hour: Option<i32>,
         expires_at: Option<chrono::DateTime<Utc>>,
     ) -> Result<(api_key::Model, String), AppError> {
+        if name.trim().is_empty() {
+            return Err(AppError::Validation("Key name is required".to_string()));
+        }
+        if scopes.is_empty() {
+            return Err(AppError::Validation(
+                "Grant the key at least one scope".to_string(),
+            ));
+        }
+        if let Some(unknown) = scopes
+            .iter()
+            .find(|s| *s != WILDCARD_SCOPE && !API_KEY_SCOPES.contains(&s.as_str()))
+        {
+            return Err(AppError::Validation(format!("Unknown scope: {}", unknown)));
+        }
+        if expires_at.is_some_and(|at| at <= Utc::now()) {
+            return Err(AppError::Validation(
+                "Expiry must be in the future".to_string(),
+            ));
+        }
+        let existing = ApiKey::find()
+            .filter(api_key::Column::OwnerId.eq(owner_id))
+            .filter(api_key::Column::OwnerType.eq(&owner_type))
+            .filter(api_key::Column::IsActive.eq(true))
+            .count(&self.db)
+            .await?;
+        if existing >= MAX_KEYS_PER_OWNER {
+            return Err(AppError::Validation(format!(
+                "At most {} active API keys per account",
+                MAX_KEYS_PER_OWNER
+            )));
+        }
+
         let key_id = format!(
             "ak_{}",
             Uuid::new_v4().to_string().replace("-", "")[..16].to_lowercase()
@@ -32,8 +100,7 @@ impl ApiKeyService {
         let secret_key = Uuid::new_v4().to_string().replace("-", "");
         let full_key = format!("{}_{}", key_id, secret_key);
 
-        // ✅ ENTERPRISE: Use AuthService for consistent hashing
-        let key_hash = self.auth_service.hash_password(&secret_key)?;
+        let key_hash = format!("{}{}", DIGEST_PREFIX, hex::encode(self.digest(&secret_key)));
 
         let new_key = api_key::ActiveModel {
             id: Set(Uuid::new_v4()),
@@ -55,6 +122,9 @@ impl ApiKeyService {
         Ok((api_key_model, full_key))
     }
 
+    /// Resolves a full `ak_<id>_<secret>` key to its active, unexpired
+    /// record. Verification is an HMAC comparison against a cached record;
+    /// `last_used_at` is recorded in the background.
     pub async fn validate_api_key(
         &self,
         full_key: &str,
@@ -67,42 +137,164 @@ impl ApiKeyService {
         let key_id = format!("{}_{}", parts[0], parts[1]);
         let secret = parts[2];
 
-        let api_key = ApiKey::find()
+        let Some(mut key) = self.find_active_key(&key_id).await? else {
+            return Ok(None);
+        };
+
+        // Check expiration
+        if let Some(expires_at) = key.expires_at {
+            if expires_at < Utc::now() {
+                return Ok(None);
+            }
+        }
+
+        match key.key_hash.strip_prefix(DIGEST_PREFIX) {
+            Some(stored) => {
+                let Ok(stored) = hex::decode(stored) else {
+                    return Ok(None);
+                };
+                if !self.digest_matches(secret, &stored) {
+                    return Ok(None);
+                }
+            }
+            None => {
+                // Keys issued before digests: verify once with Argon2, then upgrade
+                if !self.auth_service.verify_password(secret, &key.key_hash)? {
+                    return Ok(None);
+                }
+                key = self.upgrade_to_digest(key, secret).await?;
+            }
+        }
+
+        self.pending_usage
+            .lock()
+            .unwrap()
+            .insert(key.id, Utc::now());
+        Ok(Some(key))
+    }
+
+    fn digest(&self, secret: &str) -> Vec<u8> {
+        self.mac(secret).finalize().into_bytes().to_vec()
+    }
+
+    fn digest_matches(&self, secret: &str, stored: &[u8]) -> bool {
+        // `verify_slice` compares in constant time
+        self.mac(secret).verify_slice(stored).is_ok()
+    }
+
+    fn mac(&self, secret: &str) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.digest_secret.as_bytes())
+            .expect("HMAC accepts any key length");
+        mac.update(secret.as_bytes());
+        mac
+    }
+
+    async fn find_active_key(&self, key_id: &str) -> Result<Option<api_key::Model>, AppError> {
+        if let Some(cached) = self.cache.read().unwrap().get(key_id) {
+            if Utc::now() - cached.cached_at < Duration::seconds(CACHE_TTL_SECS) {
+                return Ok(Some(cached.key.clone()));
+            }
+        }
+
+        let key = ApiKey::find()
             .filter(api_key::Column::KeyId.eq(key_id))
             .filter(api_key::Column::IsActive.eq(true))
             .one(&self.db)
             .await?;
+        let mut cache = self.cache.write().unwrap();
+        match &key {
+            Some(key) => {
+                cache.insert(
+                    key_id.to_string(),
+                    CachedKey {
+                        key: key.clone(),
+                        cached_at: Utc::now(),
+                    },
+                );
+            }
+            None => {
+                cache.remove(key_id);
+            }
+        }
+        Ok(key)
+    }
 
-        if let Some(key) = api_key {
-            // Check expiration
-            if let Some(expires_at) = key.expires_at {
-                if expires_at < Utc::now() {
-                    return Ok(None);
+    async fn upgrade_to_digest(
+        &self,
+        key: api_key::Model,
+        secret: &str,
+    ) -> Result<api_key::Model, AppError> {
+        let key_id = key.key_id.clone();
+        let mut update: api_key::ActiveModel = key.into();
+        update.key_hash = Set(format!(
+            "{}{}",
+            DIGEST_PREFIX,
+            hex::encode(self.digest(secret))
+        ));
+        update.updated_at = Set(Utc::now());
+        let key = update.update(&self.db).await?;
+        self.cache.write().unwrap().remove(&key_id);
+        Ok(key)
+    }
+
+    /// Starts the background task that writes batched `last_used_at` updates.
+    pub fn spawn_usage_flusher(&self) -> JoinHandle<()> {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(std::time::Duration::from_secs(USAGE_FLUSH_INTERVAL_SECS));
+            loop {
+                interval.tick().await;
+                if let Err(e) = service.flush_usage().await {
+                    tracing::warn!("Failed to record API key usage: {:?}", e);
                 }
             }
+        })
+    }
 
-            // ✅ ENTERPRISE: Use AuthService for consistent verification
-            if self.auth_service.verify_password(secret, &key.key_hash)? {
-                // Update last used
-                let mut update: api_key::ActiveModel = key.clone().into();
-                update.last_used_at = Set(Some(Utc::now()));
-                ApiKey::update(update).exec(&self.db).await?;
-
-                return Ok(Some(key));
+    /// Writes the uses recorded since the last flush. If a write fails, the
+    /// uses not yet written are queued again for the next flush.
+    pub async fn flush_usage(&self) -> Result<(), AppError> {
+        let pending = std::mem::take(&mut *self.pending_usage.lock().unwrap());
+        let mut pending = pending.into_iter();
+        while let Some((id, used_at)) = pending.next() {
+            let written = ApiKey::update_many()
+                .col_expr(api_key::Column::LastUsedAt, Expr::value(used_at))
+                .filter(api_key::Column::Id.eq(id))
+                .exec(&self.db)
+                .await;
+            if let Err(e) = written {
+                let mut queue = self.pending_usage.lock().unwrap();
+                for (id, used_at) in std::iter::once((id, used_at)).chain(pending) {
+                    // Keys used again since the flush began keep the later use
+                    let latest = queue.entry(id).or_insert(used_at);
+                    *latest = (*latest).max(used_at);
+                }
+                return Err(e.into());
             }
         }
-
-        Ok(None)
+        Ok(())
     }
 
-    pub async fn revoke_api_key(&self, key_id: String) -> Result<(), AppError> {
-        ApiKey::update_many()
+    /// Deactivates one of the owner's keys. Returns `false` if they have no
+    /// active key with that id.
+    pub async fn revoke_api_key(
+        &self,
+        owner_id: Uuid,
+        owner_type: String,
+        key_id: String,
+    ) -> Result<bool, AppError> {
+        let result = ApiKey::update_many()
             .col_expr(api_key::Column::IsActive, Expr::value(false))
             .col_expr(api_key::Column::UpdatedAt, Expr::value(Utc::now()))
-            .filter(api_key::Column::KeyId.eq(key_id))
+            .filter(api_key::Column::KeyId.eq(&key_id))
+            .filter(api_key::Column::OwnerId.eq(owner_id))
+            .filter(api_key::Column::OwnerType.eq(owner_type))
+            .filter(api_key::Column::IsActive.eq(true))
             .exec(&self.db)
             .await?;
-        Ok(())
+        self.cache.write().unwrap().remove(&key_id);
+        Ok(result.rows_affected > 0)
     }
 
     pub async fn list_owner_keys(
@@ -120,7 +312,170 @@ impl ApiKeyService {
     }
 
     pub async fn check_scope(&self, api_key: &api_key::Model, required_scope: &str) -> bool {
-        api_key.scopes.contains(&required_scope.to_string())
-            || api_key.scopes.contains(&"*".to_string())
+        api_key
+            .scopes
+            .iter()
+            .any(|s| s == required_scope || s == WILDCARD_SCOPE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_db::TestDb;
+
+    fn service(db: DatabaseConnection) -> ApiKeyService {
+        ApiKeyService::new(
+            db,
+            AuthService::new("secret".into(), 3600),
+            "digest-secret".into(),
+        )
+    }
+
+    async fn issue(service: &ApiKeyService) -> (api_key::Model, String) {
+        service
+            .create_api_key(
+                "Overlay".into(),
+                Uuid::new_v4(),
+                "organization".into(),
+                vec!["webhooks:read".into()],
+                None,
+                None,
+            )
+            .await
+            .unwrap()
+    }
+
+    async fn reload(db: &DatabaseConnection, key: &api_key::Model) -> api_key::Model {
+        ApiKey::find_by_id(key.id).one(db).await.unwrap().unwrap()
+    }
+
+    #[test]
+    fn digests_only_match_their_secret_and_server_key() {
+        let keys = service(DatabaseConnection::Disconnected);
+        let stored = keys.digest("s3cret");
+
+        assert!(keys.digest_matches("s3cret", &stored));
+        assert!(!keys.digest_matches("s3cret!", &stored));
+        assert!(!keys.digest_matches("s3cret", &stored[..16]));
+
+        let other_server = ApiKeyService::new(
+            DatabaseConnection::Disconnected,
+            AuthService::new("secret".into(), 3600),
+            "other-digest-secret".into(),
+        );
+        assert!(!other_server.digest_matches("s3cret", &stored));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn failed_flush_keeps_unwritten_uses() {
+        let test_db = TestDb::new().await;
+        let db = test_db.connection();
+        let keys = service(db.clone());
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        let earlier = Utc::now() - Duration::minutes(5);
+        keys.pending_usage
+            .lock()
+            .unwrap()
+            .extend([(first, earlier), (second, earlier)]);
+
+        // Every write fails without the table
+        db.execute_unprepared("DROP TABLE api_keys").await.unwrap();
+        assert!(keys.flush_usage().await.is_err());
+
+        let pending = keys.pending_usage.lock().unwrap().clone();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[&first], earlier);
+        assert_eq!(pending[&second], earlier);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn uses_are_batched_until_flushed() {
+        let test_db = TestDb::new().await;
+        let db = test_db.connection();
+        let keys = service(db.clone());
+        let (first, first_key) = issue(&keys).await;
+        let (second, second_key) = issue(&keys).await;
+
+        for key in [&first_key, &first_key, &second_key] {
+            assert!(keys.validate_api_key(key).await.unwrap().is_some());
+        }
+        // One pending use per key, and nothing written yet
+        assert_eq!(keys.pending_usage.lock().unwrap().len(), 2);
+        assert_eq!(reload(&db, &first).await.last_used_at, None);
+
+        keys.flush_usage().await.unwrap();
+        assert!(keys.pending_usage.lock().unwrap().is_empty());
+        assert!(reload(&db, &first).await.last_used_at.is_some());
+        assert!(reload(&db, &second).await.last_used_at.is_some());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn argon2_keys_are_upgraded_to_digests() {
+        let test_db = TestDb::new().await;
+        let db = test_db.connection();
+        let keys = service(db.clone());
+        let (key, _) = issue(&keys).await;
+        // As keys were stored before digests
+        let legacy = api_key::ActiveModel {
+            id: Set(key.id),
+            key_hash: Set(keys.auth_service.hash_password("Legacy-Secret-1!").unwrap()),
+            ..Default::default()
+        }
+        .update(&db)
+        .await
+        .unwrap();
+        let full_key = format!("{}_Legacy-Secret-1!", legacy.key_id);
+
+        assert!(keys
+            .validate_api_key(&format!("{}_wrong", legacy.key_id))
+            .await
+            .unwrap()
+            .is_none());
+        assert!(!reload(&db, &key).await.key_hash.starts_with(DIGEST_PREFIX));
+
+        assert!(keys.validate_api_key(&full_key).await.unwrap().is_some());
+        let upgraded = reload(&db, &key).await;
+        assert_eq!(
+            upgraded.key_hash,
+            format!(
+                "{}{}",
+                DIGEST_PREFIX,
+                hex::encode(keys.digest("Legacy-Secret-1!"))
+            )
+        );
+        assert!(keys.validate_api_key(&full_key).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs TEST_DATABASE_URL"]
+    async fn revoking_clears_the_cached_key() {
+        let test_db = TestDb::new().await;
+        let db = test_db.connection();
+        let keys = service(db.clone());
+        let (key, full_key) = issue(&keys).await;
+        assert!(keys.validate_api_key(&full_key).await.unwrap().is_some());
+
+        // Changes made elsewhere wait for the cache to expire
+        api_key::ActiveModel {
+            id: Set(key.id),
+            name: Set("Renamed".into()),
+            ..Default::default()
+        }
+        .update(&db)
+        .await
+        .unwrap();
+        let cached = keys.validate_api_key(&full_key).await.unwrap().unwrap();
+        assert_eq!(cached.name, "Overlay");
+
+        assert!(keys
+            .revoke_api_key(key.owner_id, key.owner_type.clone(), key.key_id.clone())
+            .await
+            .unwrap());
+        assert!(!keys.cache.read().unwrap().contains_key(&key.key_id));
+        assert!(keys.validate_api_key(&full_key).await.unwrap().is_none());
     }
 }
@@ -30,7 +30,7 @@ pub enum UserType {
 pub struct TempTokenClaims {
     pub sub: String,
     pub user_type: String,
-    pub token_type: String, // "reset_password" or "verify_email"
+    pub token_type: String, // "reset_password", "verify_email", "unsubscribe:<category>" or a two-factor challenge
     pub exp: usize,
     pub iat: usize,
 }
@@ -150,8 +150,26 @@ impl AuthService {
         token_type: &str,
         expiry_hours: i64,
     ) -> Result<String, AppError> {
+        self.generate_short_lived_token(
+            user_id,
+            user_type,
+            token_type,
+            Duration::hours(expiry_hours),
+        )
+        .map_err(|_| AppError::InternalServerError)
+    }
+
+    /// A temporary token for lifetimes measured in minutes, such as the
+    /// two-factor sign-in challenge.
+    pub fn generate_short_lived_token(
+        &self,
+        user_id: Uuid,
+        user_type: UserType,
+        token_type: &str,
+        lifetime: Duration,
+    ) -> Result<String> {
         let now = Utc::now();
-        let exp = (now + Duration::hours(expiry_hours)).timestamp() as usize;
+        let exp = (now + lifetime).timestamp() as usize;
 
         let claims = TempTokenClaims {
             sub: user_id.to_string(),
@@ -161,12 +179,11 @@ impl AuthService {
             iat: now.timestamp() as usize,
         };
 
-        encode(
+        Ok(encode(
             &Header::default(),
             &claims,
             &EncodingKey::from_secret(self.jwt_secret.as_ref()),
-        )
-        .map_err(|_| AppError::InternalServerError)
+        )?)
     }
 
     // Verify temporary token
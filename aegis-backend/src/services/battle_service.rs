@@ -1,4 +1,12 @@
-use crate::models::postgres::{battle, Battle};
+use crate::models::dynamodb::{ActivityType, NotificationKind};
+use crate::models::enums::BattleStatus;
+use crate::models::postgres::webhook_endpoint::WebhookEvent;
+use crate::models::postgres::{
+    battle, player, tournament_team, Battle, Player, Tournament, TournamentTeam,
+};
+use crate::services::notification_service::{NewNotification, NotificationService};
+use crate::services::team_service::roster_for_activity;
+use crate::services::{ActivityService, WebhookService};
 use crate::utils::errors::AppError;
 use sea_orm::*;
 use uuid::Uuid;
@@ -6,11 +14,24 @@ use uuid::Uuid;
 #[derive(Clone)]
 pub struct BattleService {
     db: DatabaseConnection,
+    activity: ActivityService,
+    notifications: NotificationService,
+    webhooks: WebhookService,
 }
 
 impl BattleService {
-    pub fn new(db: DatabaseConnection) -> Self {
-        Self { db }
+    pub fn new(
+        db: DatabaseConnection,
+        activity: ActivityService,
+        notifications: NotificationService,
+        webhooks: WebhookService,
+    ) -> Self {
+        Self {
+            db,
+            activity,
+            notifications,
+            webhooks,
+        }
     }
 
     pub async fn get_by_tournament(
@@ -27,4 +48,184 @@ impl BattleService {
     pub async fn get_by_id(&self, id: Uuid) -> Result<Option<battle::Model>, AppError> {
         Ok(Battle::find_by_id(id).one(&self.db).await?)
     }
+
+    pub async fn schedule_battle(
+        &self,
+        tournament_id: Uuid,
+        battle_number: i32,
+        scheduled_start_time: chrono::DateTime<chrono::Utc>,
+        map: Option<String>,
+    ) -> Result<battle::Model, AppError> {
+        let now = chrono::Utc::now();
+        let new_battle = battle::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            battle_number: Set(battle_number),
+            tournament: Set(tournament_id),
+            scheduled_start_time: Set(scheduled_start_time),
+            status: Set(BattleStatus::Scheduled),
+            map: Set(map),
+            participating_groups: Set(vec![]),
+            tags: Set(vec![]),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        };
+        let battle = new_battle.insert(&self.db).await?;
+
+        self.webhooks
+            .publish_for_tournament(
+                tournament_id,
+                WebhookEvent::BattleScheduled,
+                serde_json::json!({
+                    "tournament_id": tournament_id,
+                    "battle_id": battle.id,
+                    "battle_number": battle.battle_number,
+                    "scheduled_start_time": battle.scheduled_start_time,
+                    "map": battle.map,
+                }),
+            )
+            .await;
+
+        Ok(battle)
+    }
+
+    /// Records the final stats of a battle and marks it completed. When a
+    /// winning team is given it must be registered for the tournament, and
+    /// its roster gets a `BattleWon` activity.
+    pub async fn complete_battle(
+        &self,
+        id: Uuid,
+        battle_stats: serde_json::Value,
+        winning_team: Option<Uuid>,
+    ) -> Result<battle::Model, AppError> {
+        let battle = Battle::find_by_id(id)
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        if battle.status == BattleStatus::Completed || battle.status == BattleStatus::Cancelled {
+            return Err(AppError::Validation(format!(
+                "Battle is already {}",
+                battle.status.as_str()
+            )));
+        }
+        if let Some(team_id) = winning_team {
+            let registered = TournamentTeam::find()
+                .filter(tournament_team::Column::TournamentId.eq(battle.tournament))
+                .filter(tournament_team::Column::TeamId.eq(team_id))
+                .count(&self.db)
+                .await?;
+            if registered == 0 {
+                return Err(AppError::Validation(
+                    "Winning team is not registered for this tournament".to_string(),
+                ));
+            }
+        }
+
+        let mut battle: battle::ActiveModel = battle.into();
+        battle.status = Set(BattleStatus::Completed);
+        battle.battle_stats = Set(battle_stats);
+        battle.updated_at = Set(chrono::Utc::now());
+        let battle = battle.update(&self.db).await?;
+
+        self.webhooks
+            .publish_for_tournament(
+                battle.tournament,
+                WebhookEvent::BattleCompleted,
+                serde_json::json!({
+                    "tournament_id": battle.tournament,
+                    "battle_id": battle.id,
+                    "battle_number": battle.battle_number,
+                    "battle_stats": battle.battle_stats,
+                    "winning_team_id": winning_team,
+                }),
+            )
+            .await;
+
+        if let Some(team_id) = winning_team {
+            if let Some(roster) = roster_for_activity(&self.db, team_id).await {
+                self.activity
+                    .record_for_all(
+                        &roster,
+                        ActivityType::BattleWon,
+                        &format!("Won battle {}", battle.battle_number),
+                        serde_json::json!({
+                            "tournament_id": battle.tournament,
+                            "battle_id": battle.id,
+                            "team_id": team_id,
+                        }),
+                    )
+                    .await;
+            }
+        }
+
+        self.notify_result(&battle, winning_team).await;
+
+        Ok(battle)
+    }
+
+    /// Tells every player on a registered team that the battle is over.
+    /// Best effort: the battle is already completed.
+    async fn notify_result(&self, battle: &battle::Model, winning_team: Option<Uuid>) {
+        let recipients = async {
+            let tournament = Tournament::find_by_id(battle.tournament)
+                .one(&self.db)
+                .await?;
+            let players = Player::find()
+                .filter(
+                    player::Column::TeamId.in_subquery(
+                        TournamentTeam::find()
+                            .select_only()
+                            .column(tournament_team::Column::TeamId)
+                            .filter(tournament_team::Column::TournamentId.eq(battle.tournament))
+                            .into_query(),
+                    ),
+                )
+                .all(&self.db)
+                .await?;
+            Ok::<_, DbErr>((tournament, players))
+        };
+        let (tournament, players) = match recipients.await {
+            Ok((Some(tournament), players)) => (tournament, players),
+            Ok((None, _)) => return,
+            Err(e) => {
+                tracing::warn!("Failed to load recipients for battle {}: {}", battle.id, e);
+                return;
+            }
+        };
+
+        for player in players {
+            let won = winning_team.is_some() && player.team_id == winning_team;
+            let body = if won {
+                format!("Your team won battle {}.", battle.battle_number)
+            } else {
+                format!("Battle {} has finished.", battle.battle_number)
+            };
+            let notification = NewNotification::new(
+                NotificationKind::BattleResult,
+                format!(
+                    "{}: battle {} results",
+                    tournament.tournament_name, battle.battle_number
+                ),
+                body,
+            )
+            .with_link(format!("/tournaments/{}", battle.tournament))
+            .with_metadata(serde_json::json!({
+                "tournament_id": battle.tournament,
+                "battle_id": battle.id,
+                "winning_team_id": winning_team,
+            }));
+            if let Err(e) = self
+                .notifications
+                .notify(&player.id.to_string(), notification)
+                .await
+            {
+                tracing::warn!(
+                    "Failed to notify {} of battle {}: {}",
+                    player.id,
+                    battle.id,
+                    e
+                );
+            }
+        }
+    }
 }
@@ -1,15 +1,49 @@
 use anyhow::Result;
-use crate::models::dynamodb::{Chat, ChatMessage};
-use crate::repositories::{ChatRepository, Repository};
+use crate::models::dynamodb::{Chat, ChatMessage, ChatParticipant, MessageEdit};
+use crate::repositories::{is_version_conflict, ChatRepository, Page, PageRequest, MAX_CONFLICT_RETRIES};
+use socketioxide::SocketIo;
+
+pub const CHAT_NAMESPACE: &str = "/chat";
+
+// Limits that keep a message item well under DynamoDB's 400 KB item size
+pub const MAX_EDIT_HISTORY: usize = 10; // Older versions are dropped
+pub const MAX_DISTINCT_REACTIONS: usize = 20;
+pub const MAX_REACTIONS_PER_USER: usize = 5;
+
+pub fn chat_room(chat_id: &str) -> String {
+    format!("chat:{}", chat_id)
+}
+
+/// Every socket a user has open joins this room, for per-user pushes such as
+/// notifications.
+pub fn user_room(user_id: &str) -> String {
+    format!("user:{}", user_id)
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ChatSummary {
+    pub chat: Chat,
+    pub unread_count: i64,
+    pub last_read_at: Option<String>,
+}
+
+#[derive(Debug, serde::Serialize)]
+pub struct ReadReceipt {
+    pub chat_id: String,
+    pub user_id: String,
+    pub message_id: String,
+    pub read_at: String,
+}
 
 #[derive(Clone)]
 pub struct ChatService {
     chat_repo: ChatRepository,
+    io: SocketIo,
 }
 
 impl ChatService {
-    pub fn new(chat_repo: ChatRepository) -> Self {
-        Self { chat_repo }
+    pub fn new(chat_repo: ChatRepository, io: SocketIo) -> Self {
+        Self { chat_repo, io }
     }
 
     pub async fn create_chat(&self, name: String, chat_type: String, created_by: String) -> Result<String> {
@@ -17,48 +51,378 @@ impl ChatService {
             id: String::new(), // Will be generated in repository
             chat_type,
             name,
-            participants: vec![created_by.clone()],
-            created_by,
+            participant_count: 0, // Counted as participant items are added
+            admins: vec![created_by.clone()],
+            team_id: None,
+            created_by: created_by.clone(),
             created_at: chrono::Utc::now().to_rfc3339(),
             updated_at: chrono::Utc::now().to_rfc3339(),
+            version: 0,
         };
 
-        self.chat_repo.create_chat(chat).await
+        let chat_id = self.chat_repo.create_chat(chat).await?;
+        self.add_participants(&chat_id, &[created_by]).await?;
+        Ok(chat_id)
     }
 
     pub async fn get_chat(&self, chat_id: &str) -> Result<Option<Chat>> {
         self.chat_repo.get_chat(chat_id).await
     }
 
+    pub async fn is_participant(&self, chat_id: &str, user_id: &str) -> Result<bool> {
+        Ok(self.chat_repo.get_participant(chat_id, user_id).await?.is_some())
+    }
+
+    pub async fn get_participants(&self, chat_id: &str, request: &PageRequest) -> Result<Page<ChatParticipant>> {
+        self.chat_repo.get_participants_page(chat_id, request).await
+    }
+
+    /// Returns the one direct chat between two players, creating it on first use.
+    pub async fn get_or_create_direct_chat(&self, initiator: &str, recipient: &str) -> Result<Chat> {
+        let chat_id = Chat::direct_chat_id(initiator, recipient);
+        if let Some(chat) = self.chat_repo.get_chat(&chat_id).await? {
+            return Ok(chat);
+        }
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let chat = Chat {
+            id: chat_id.clone(),
+            chat_type: "direct".to_string(),
+            name: String::new(),
+            participant_count: 0,
+            admins: Vec::new(), // Nobody can add a third person to a DM
+            team_id: None,
+            created_by: initiator.to_string(),
+            created_at: now.clone(),
+            updated_at: now,
+            version: 0,
+        };
+
+        if self.chat_repo.create_chat_if_absent(&chat).await? {
+            self.add_participants(&chat.id, &[initiator.to_string(), recipient.to_string()])
+                .await?;
+            return self.reload(&chat_id).await;
+        }
+
+        // Lost a creation race with the other participant; use their chat
+        self.chat_repo
+            .get_chat(&chat_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Direct chat {} vanished after creation", chat_id))
+    }
+
+    /// Brings a team's chat in line with its current roster, creating the chat
+    /// if needed. The captain is the only admin.
+    pub async fn sync_team_chat(
+        &self,
+        team_id: &str,
+        team_name: &str,
+        captain: Option<String>,
+        roster: Vec<String>,
+    ) -> Result<Chat> {
+        let chat_id = Chat::team_chat_id(team_id);
+        let admins: Vec<String> = captain.into_iter().collect();
+        let now = chrono::Utc::now().to_rfc3339();
+
+        let chat = match self.chat_repo.get_chat(&chat_id).await? {
+            Some(chat) => chat,
+            None => {
+                let chat = Chat {
+                    id: chat_id.clone(),
+                    chat_type: "team".to_string(),
+                    name: team_name.to_string(),
+                    participant_count: 0,
+                    admins: admins.clone(),
+                    team_id: Some(team_id.to_string()),
+                    created_by: admins.first().cloned().unwrap_or_default(),
+                    created_at: now.clone(),
+                    updated_at: now.clone(),
+                    version: 0,
+                };
+                if self.chat_repo.create_chat_if_absent(&chat).await? {
+                    self.add_participants(&chat.id, &roster).await?;
+                    return self.reload(&chat_id).await;
+                }
+                self.chat_repo
+                    .get_chat(&chat_id)
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("Team chat {} vanished after creation", chat_id))?
+            }
+        };
+
+        let current: Vec<String> = self
+            .chat_repo
+            .get_participants(&chat_id)
+            .await?
+            .into_iter()
+            .map(|p| p.user_id)
+            .collect();
+        let joined: Vec<String> = roster
+            .iter()
+            .filter(|id| !current.contains(id))
+            .cloned()
+            .collect();
+        let left: Vec<&String> = current.iter().filter(|id| !roster.contains(id)).collect();
+
+        if joined.is_empty() && left.is_empty() && chat.admins == admins && chat.name == team_name {
+            return Ok(chat);
+        }
+
+        for user_id in left {
+            self.remove_participant(&chat_id, user_id).await?;
+        }
+        self.add_participants(&chat_id, &joined).await?;
+        if chat.admins != admins || chat.name != team_name {
+            self.chat_repo.update_chat_details(&chat_id, team_name, &admins).await?;
+        }
+
+        self.reload(&chat_id).await
+    }
+
     pub async fn send_message(&self, chat_id: String, sender: String, message: String, message_type: String) -> Result<String> {
-        let chat_message = ChatMessage {
+        let mut chat_message = ChatMessage {
             id: String::new(), // Will be generated in repository
             chat_id,
             sender,
             message,
             message_type,
             timestamp: chrono::Utc::now().to_rfc3339(),
+            edited_at: None,
+            edit_history: Vec::new(),
+            deleted: false,
+            deleted_at: None,
+            reactions: Default::default(),
+            version: 0,
         };
 
-        self.chat_repo.add_message(chat_message).await
+        chat_message.id = self.chat_repo.add_message(chat_message.clone()).await?;
+
+        // Only broadcast once the message is durable in DynamoDB
+        self.broadcast(&chat_message.chat_id, "message", &chat_message);
+
+        Ok(chat_message.id)
     }
 
-    pub async fn get_messages(&self, chat_id: &str, limit: Option<i32>) -> Result<Vec<ChatMessage>> {
-        self.chat_repo.get_messages(chat_id, limit).await
+    pub async fn get_message(&self, chat_id: &str, message_id: &str) -> Result<Option<ChatMessage>> {
+        self.chat_repo.get_message(chat_id, message_id).await
     }
 
-    pub async fn get_chats_by_type(&self, chat_type: &str) -> Result<Vec<Chat>> {
-        self.chat_repo.get_chats_by_type(chat_type).await
+    /// Replaces the message text, keeping the previous version in the history
+    /// (the last `MAX_EDIT_HISTORY` versions only).
+    pub async fn edit_message(&self, message: ChatMessage, new_text: String) -> Result<ChatMessage> {
+        self.modify_message(message, |message| {
+            if message.deleted {
+                return false;
+            }
+            let now = chrono::Utc::now().to_rfc3339();
+            let previous = std::mem::replace(&mut message.message, new_text.clone());
+            message.edit_history.push(MessageEdit {
+                message: previous,
+                edited_at: now.clone(),
+            });
+            let excess = message.edit_history.len().saturating_sub(MAX_EDIT_HISTORY);
+            message.edit_history.drain(..excess);
+            message.edited_at = Some(now);
+            true
+        })
+        .await
     }
 
-    pub async fn join_chat(&self, chat_id: &str, user_id: &str) -> Result<()> {
-        if let Some(mut chat) = self.chat_repo.get_chat(chat_id).await? {
-            if !chat.participants.contains(&user_id.to_string()) {
-                chat.participants.push(user_id.to_string());
-                chat.updated_at = chrono::Utc::now().to_rfc3339();
-                self.chat_repo.update(&chat).await?;
+    /// Soft delete: the entry stays in the timeline as a tombstone with its
+    /// content, history and reactions dropped.
+    pub async fn delete_message(&self, message: ChatMessage) -> Result<ChatMessage> {
+        self.modify_message(message, |message| {
+            if message.deleted {
+                return false;
+            }
+            message.message = String::new();
+            message.edit_history.clear();
+            message.reactions.clear();
+            message.deleted = true;
+            message.deleted_at = Some(chrono::Utc::now().to_rfc3339());
+            true
+        })
+        .await
+    }
+
+    /// Leaves the message unchanged if the user already reacted with `emoji`,
+    /// or if it would pass `MAX_DISTINCT_REACTIONS` or `MAX_REACTIONS_PER_USER`;
+    /// callers tell the two apart with `has_reacted`.
+    pub async fn add_reaction(&self, message: ChatMessage, emoji: &str, user_id: &str) -> Result<ChatMessage> {
+        self.modify_message(message, |message| {
+            if message.has_reacted(emoji, user_id) {
+                return false;
             }
+            let new_emoji = !message.reactions.contains_key(emoji);
+            if new_emoji && message.reactions.len() >= MAX_DISTINCT_REACTIONS {
+                return false;
+            }
+            if message.reaction_count(user_id) >= MAX_REACTIONS_PER_USER {
+                return false;
+            }
+            message
+                .reactions
+                .entry(emoji.to_string())
+                .or_default()
+                .push(user_id.to_string());
+            true
+        })
+        .await
+    }
+
+    pub async fn remove_reaction(&self, message: ChatMessage, emoji: &str, user_id: &str) -> Result<ChatMessage> {
+        self.modify_message(message, |message| {
+            if !message.has_reacted(emoji, user_id) {
+                return false;
+            }
+            if let Some(users) = message.reactions.get_mut(emoji) {
+                users.retain(|u| u != user_id);
+                if users.is_empty() {
+                    message.reactions.remove(emoji);
+                }
+            }
+            true
+        })
+        .await
+    }
+
+    /// Applies `change` and writes the message back. If another writer
+    /// updated it in between, the message is reloaded and `change` applied
+    /// again. `change` returns `false` when there is nothing to write.
+    async fn modify_message(&self, mut message: ChatMessage, change: impl Fn(&mut ChatMessage) -> bool) -> Result<ChatMessage> {
+        let mut attempt = 0;
+        loop {
+            if !change(&mut message) {
+                return Ok(message);
+            }
+            match self.chat_repo.update_message(&message).await {
+                Ok(()) => {
+                    message.version += 1;
+                    self.broadcast(&message.chat_id, "message_updated", &message);
+                    return Ok(message);
+                }
+                Err(e) if is_version_conflict(&e) && attempt < MAX_CONFLICT_RETRIES => {
+                    attempt += 1;
+                    message = self
+                        .chat_repo
+                        .get_message(&message.chat_id, &message.id)
+                        .await?
+                        .ok_or_else(|| anyhow::anyhow!("Message {} vanished during update", message.id))?;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Moves the participant's read marker forward to `message` (never back)
+    /// and tells the rest of the chat.
+    pub async fn mark_read(&self, message: &ChatMessage, user_id: &str) -> Result<ReadReceipt> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let participant = self
+            .chat_repo
+            .get_participant(&message.chat_id, user_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("{} is not a participant of chat {}", user_id, message.chat_id))?;
+
+        let is_newer = participant
+            .read_marker()
+            .map(|read| (message.timestamp.as_str(), message.id.as_str()) > read)
+            .unwrap_or(true);
+        if is_newer {
+            self.chat_repo
+                .update_read_marker(&message.chat_id, user_id, &message.timestamp, &message.id)
+                .await?;
+        }
+
+        let receipt = ReadReceipt {
+            chat_id: message.chat_id.clone(),
+            user_id: user_id.to_string(),
+            message_id: message.id.clone(),
+            read_at: now,
+        };
+        self.broadcast(&message.chat_id, "read", &receipt);
+        Ok(receipt)
+    }
+
+    /// Chats the user participates in, with unread counts from their read marker.
+    pub async fn get_my_chats(&self, user_id: &str, request: &PageRequest) -> Result<Page<ChatSummary>> {
+        let participations = self.chat_repo.get_participations(user_id, request).await?;
+
+        let mut summaries = Vec::with_capacity(participations.items.len());
+        for participant in &participations.items {
+            let Some(chat) = self.chat_repo.get_chat(&participant.chat_id).await? else {
+                continue;
+            };
+            let unread_count = self
+                .chat_repo
+                .count_messages_after(&chat.id, participant.read_marker(), user_id)
+                .await?;
+            summaries.push(ChatSummary {
+                chat,
+                unread_count,
+                last_read_at: participant.last_read_at.clone(),
+            });
+        }
+
+        Ok(Page {
+            items: summaries,
+            before: participations.before,
+            after: participations.after,
+            has_more: participations.has_more,
+        })
+    }
+
+    async fn add_participants(&self, chat_id: &str, user_ids: &[String]) -> Result<()> {
+        let now = chrono::Utc::now().to_rfc3339();
+        for user_id in user_ids {
+            let participant = ChatParticipant {
+                chat_id: chat_id.to_string(),
+                user_id: user_id.clone(),
+                joined_at: now.clone(),
+                last_read_at: None,
+                last_read_message_id: None,
+            };
+            self.chat_repo.add_participant(&participant).await?;
         }
         Ok(())
     }
+
+    /// Drops the participant item and takes the user's open sockets out of
+    /// the chat room, so they stop receiving its events.
+    async fn remove_participant(&self, chat_id: &str, user_id: &str) -> Result<()> {
+        self.chat_repo.remove_participant(chat_id, user_id).await?;
+        if let Some(ns) = self.io.of(CHAT_NAMESPACE) {
+            if let Err(e) = ns.within(user_room(user_id)).leave(chat_room(chat_id)) {
+                tracing::warn!("Failed to remove {}'s sockets from chat {}: {:?}", user_id, chat_id, e);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn broadcast<T: serde::Serialize>(&self, chat_id: &str, event: &'static str, data: &T) {
+        if let Some(ns) = self.io.of(CHAT_NAMESPACE) {
+            if let Err(e) = ns.within(chat_room(chat_id)).emit(event, data) {
+                tracing::warn!("Failed to broadcast {} to chat {}: {}", event, chat_id, e);
+            }
+        }
+    }
+
+    pub async fn get_messages(&self, chat_id: &str, request: &PageRequest) -> Result<Page<ChatMessage>> {
+        self.chat_repo.get_messages(chat_id, request).await
+    }
+
+    pub async fn get_chats_by_type(&self, chat_type: &str) -> Result<Vec<Chat>> {
+        self.chat_repo.get_chats_by_type(chat_type).await
+    }
+
+    pub async fn join_chat(&self, chat_id: &str, user_id: &str) -> Result<()> {
+        self.add_participants(chat_id, &[user_id.to_string()]).await
+    }
+
+    async fn reload(&self, chat_id: &str) -> Result<Chat> {
+        self.chat_repo
+            .get_chat(chat_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Chat {} not found", chat_id))
+    }
 }
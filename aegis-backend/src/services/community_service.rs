@@ -1,6 +1,18 @@
-use crate::models::dynamodb::{Community, CommunityPost};
-use crate::repositories::{CommunityRepository, Repository};
+use crate::models::dynamodb::{
+    Community, CommunityBan, CommunityJoinRequest, CommunityMember, CommunityPost, ROLE_MEMBER,
+    ROLE_MODERATOR, ROLE_OWNER,
+};
+use crate::repositories::{CommunityRepository, Page, PageRequest};
 use anyhow::Result;
+use serde::Serialize;
+use serde_json::json;
+
+#[derive(Debug, Serialize)]
+pub struct CommunityMembership {
+    pub community: Community,
+    pub role: String,
+    pub joined_at: String,
+}
 
 #[derive(Clone)]
 pub struct CommunityService {
@@ -25,27 +37,29 @@ impl CommunityService {
             description,
             community_type,
             owner: owner.clone(),
-            moderators: vec![owner], // Owner is also a moderator
-            members: Vec::new(),
-            member_count: 0,
+            member_count: 0, // Counted as member items are added
             created_at: chrono::Utc::now().to_rfc3339(),
             updated_at: chrono::Utc::now().to_rfc3339(),
+            version: 0,
         };
 
-        self.community_repo.create_community(community).await
+        let community_id = self.community_repo.create_community(community).await?;
+        self.add_member(&community_id, &owner, ROLE_OWNER).await?;
+        Ok(community_id)
     }
 
     pub async fn get_community(&self, community_id: &str) -> Result<Option<Community>> {
         self.community_repo.get_community(community_id).await
     }
 
+    /// Returns `None` if the post is already in the community.
     pub async fn add_post_to_community(
         &self,
         community_id: String,
         post_id: String,
         pinned: bool,
         added_by: String,
-    ) -> Result<String> {
+    ) -> Result<Option<String>> {
         let community_post = CommunityPost {
             id: uuid::Uuid::new_v4().to_string(),
             community_id,
@@ -55,40 +69,217 @@ impl CommunityService {
             created_at: chrono::Utc::now().to_rfc3339(),
         };
 
+        let added = self
+            .community_repo
+            .add_post_to_community(&community_post)
+            .await?;
+        Ok(added.then_some(community_post.id))
+    }
+
+    pub async fn get_community_posts(
+        &self,
+        community_id: &str,
+        request: &PageRequest,
+    ) -> Result<Page<CommunityPost>> {
+        self.community_repo
+            .get_community_posts(community_id, request)
+            .await
+    }
+
+    pub async fn get_community_post(
+        &self,
+        community_id: &str,
+        post_id: &str,
+    ) -> Result<Option<CommunityPost>> {
         self.community_repo
-            .add_post_to_community(community_post)
+            .get_community_post(community_id, post_id)
             .await
     }
 
-    pub async fn get_community_posts(&self, community_id: &str) -> Result<Vec<CommunityPost>> {
-        self.community_repo.get_community_posts(community_id).await
+    pub async fn set_post_pinned(
+        &self,
+        community_id: &str,
+        post_id: &str,
+        pinned: bool,
+    ) -> Result<bool> {
+        self.community_repo
+            .set_post_pinned(community_id, post_id, pinned)
+            .await
+    }
+
+    pub async fn remove_post(&self, community_id: &str, post_id: &str) -> Result<bool> {
+        self.community_repo.remove_post(community_id, post_id).await
+    }
+
+    pub async fn get_member(
+        &self,
+        community_id: &str,
+        user_id: &str,
+    ) -> Result<Option<CommunityMember>> {
+        self.community_repo.get_member(community_id, user_id).await
+    }
+
+    pub async fn get_members(
+        &self,
+        community_id: &str,
+        request: &PageRequest,
+    ) -> Result<Page<CommunityMember>> {
+        self.community_repo.get_members(community_id, request).await
     }
 
-    pub async fn join_community(&self, community_id: &str, user_id: &str) -> Result<()> {
-        if let Some(mut community) = self.community_repo.get_community(community_id).await? {
-            if !community.members.contains(&user_id.to_string()) {
-                community.members.push(user_id.to_string());
-                community.member_count += 1;
-                community.updated_at = chrono::Utc::now().to_rfc3339();
-                self.community_repo.update(&community).await?;
+    pub async fn get_member_ids(&self, community_id: &str) -> Result<Vec<String>> {
+        self.community_repo.get_member_ids(community_id).await
+    }
+
+    /// Communities the user belongs to, with their role in each.
+    pub async fn get_my_communities(
+        &self,
+        user_id: &str,
+        request: &PageRequest,
+    ) -> Result<Page<CommunityMembership>> {
+        let memberships = self
+            .community_repo
+            .get_memberships(user_id, request)
+            .await?;
+
+        let mut items = Vec::with_capacity(memberships.items.len());
+        for member in memberships.items {
+            if let Some(community) = self
+                .community_repo
+                .get_community(&member.community_id)
+                .await?
+            {
+                items.push(CommunityMembership {
+                    community,
+                    role: member.role,
+                    joined_at: member.joined_at,
+                });
             }
         }
-        Ok(())
+
+        Ok(Page {
+            items,
+            before: memberships.before,
+            after: memberships.after,
+            has_more: memberships.has_more,
+        })
     }
 
-    pub async fn leave_community(&self, community_id: &str, user_id: &str) -> Result<()> {
-        if let Some(mut community) = self.community_repo.get_community(community_id).await? {
-            if let Some(pos) = community.members.iter().position(|x| x == user_id) {
-                community.members.remove(pos);
-                community.member_count -= 1;
-                community.updated_at = chrono::Utc::now().to_rfc3339();
-                self.community_repo.update(&community).await?;
-            }
+    pub async fn is_banned(&self, community_id: &str, user_id: &str) -> Result<bool> {
+        self.community_repo.is_banned(community_id, user_id).await
+    }
+
+    /// Returns `false` if the user was already a member.
+    pub async fn join_community(&self, community_id: &str, user_id: &str) -> Result<bool> {
+        self.add_member(community_id, user_id, ROLE_MEMBER).await
+    }
+
+    /// Returns `false` if the user was not a member.
+    pub async fn leave_community(&self, community_id: &str, user_id: &str) -> Result<bool> {
+        self.community_repo
+            .remove_member(community_id, user_id)
+            .await
+    }
+
+    /// Returns `false` if a request is already pending.
+    pub async fn request_to_join(&self, community_id: &str, user_id: &str) -> Result<bool> {
+        let request = CommunityJoinRequest {
+            community_id: community_id.to_string(),
+            user_id: user_id.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+        self.community_repo.add_join_request(&request).await
+    }
+
+    pub async fn get_join_requests(
+        &self,
+        community_id: &str,
+        request: &PageRequest,
+    ) -> Result<Page<CommunityJoinRequest>> {
+        self.community_repo
+            .get_join_requests(community_id, request)
+            .await
+    }
+
+    /// Returns `false` if there was no pending request.
+    pub async fn approve_join_request(&self, community_id: &str, user_id: &str) -> Result<bool> {
+        if !self
+            .community_repo
+            .remove_join_request(community_id, user_id)
+            .await?
+        {
+            return Ok(false);
         }
+        self.join_community(community_id, user_id).await?;
+        Ok(true)
+    }
+
+    /// Returns `false` if there was no pending request.
+    pub async fn reject_join_request(&self, community_id: &str, user_id: &str) -> Result<bool> {
+        self.community_repo
+            .remove_join_request(community_id, user_id)
+            .await
+    }
+
+    /// Returns `false` if the user is not a member.
+    pub async fn set_moderator(
+        &self,
+        community_id: &str,
+        user_id: &str,
+        moderator: bool,
+    ) -> Result<bool> {
+        let role = if moderator {
+            ROLE_MODERATOR
+        } else {
+            ROLE_MEMBER
+        };
+        self.community_repo
+            .update_member(community_id, user_id, &[("role", json!(role))])
+            .await
+    }
+
+    /// Bans also remove the user from the community and any pending request.
+    pub async fn ban(&self, community_id: &str, user_id: &str, banned_by: &str) -> Result<()> {
+        let ban = CommunityBan {
+            community_id: community_id.to_string(),
+            user_id: user_id.to_string(),
+            banned_by: banned_by.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+        self.community_repo.add_ban(&ban).await?;
+        self.community_repo
+            .remove_member(community_id, user_id)
+            .await?;
+        self.community_repo
+            .remove_join_request(community_id, user_id)
+            .await?;
         Ok(())
     }
 
+    /// Returns `false` if the user was not banned.
+    pub async fn unban(&self, community_id: &str, user_id: &str) -> Result<bool> {
+        self.community_repo.remove_ban(community_id, user_id).await
+    }
+
+    /// Returns `false` if the user is not a member.
+    pub async fn set_muted(&self, community_id: &str, user_id: &str, muted: bool) -> Result<bool> {
+        self.community_repo
+            .update_member(community_id, user_id, &[("muted", json!(muted))])
+            .await
+    }
+
     pub async fn get_communities_by_owner(&self, owner_id: &str) -> Result<Vec<Community>> {
         self.community_repo.get_communities_by_owner(owner_id).await
     }
+
+    async fn add_member(&self, community_id: &str, user_id: &str, role: &str) -> Result<bool> {
+        let member = CommunityMember {
+            community_id: community_id.to_string(),
+            user_id: user_id.to_string(),
+            role: role.to_string(),
+            muted: false,
+            joined_at: chrono::Utc::now().to_rfc3339(),
+        };
+        self.community_repo.add_member(&member).await
+    }
 }
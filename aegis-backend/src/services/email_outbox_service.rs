@@ -0,0 +1,250 @@
+use crate::models::postgres::email_outbox::{self, FAILED, PENDING, SENT};
+use crate::models::postgres::EmailOutbox;
+use crate::services::job_queue::{self, Backoff, QueueColumns};
+use crate::services::EmailService;
+use crate::utils::errors::AppError;
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::*;
+use serde_json::Value;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// Attempts before a message is marked failed and left for an admin to retry.
+pub const MAX_ATTEMPTS: i32 = 8;
+const RETRY_BACKOFF: Backoff = Backoff {
+    base_secs: 30,
+    max_secs: 60 * 60,
+};
+const BATCH_SIZE: u64 = 20;
+/// Pending messages overdue by this much count as stuck even before they fail.
+const STUCK_AFTER_MINUTES: i64 = 15;
+
+/// An email to queue; see `EmailService::send_template` for the fields.
+#[derive(Debug, Clone)]
+pub struct OutboxEmail {
+    pub to: String,
+    pub template: String,
+    pub languages: Vec<String>,
+    pub vars: Value,
+    /// Hold the message until this time, e.g. the end of quiet hours.
+    pub send_after: Option<DateTime<Utc>>,
+}
+
+impl OutboxEmail {
+    pub fn new(to: impl Into<String>, template: impl Into<String>, vars: Value) -> Self {
+        Self {
+            to: to.into(),
+            template: template.into(),
+            languages: Vec::new(),
+            vars,
+            send_after: None,
+        }
+    }
+
+    pub fn with_languages(mut self, languages: Vec<String>) -> Self {
+        self.languages = languages;
+        self
+    }
+
+    pub fn with_send_after(mut self, send_after: Option<DateTime<Utc>>) -> Self {
+        self.send_after = send_after;
+        self
+    }
+}
+
+/// Wait before retrying a message that has failed `attempts` times: 30
+/// seconds, doubling per attempt, capped at an hour.
+pub fn retry_delay(attempts: i32) -> Duration {
+    RETRY_BACKOFF.delay(attempts)
+}
+
+const COLUMNS: QueueColumns<email_outbox::Column> = QueueColumns {
+    id: email_outbox::Column::Id,
+    status: email_outbox::Column::Status,
+    attempts: email_outbox::Column::Attempts,
+    next_attempt_at: email_outbox::Column::NextAttemptAt,
+    updated_at: email_outbox::Column::UpdatedAt,
+};
+
+#[derive(Clone)]
+pub struct EmailOutboxService {
+    db: DatabaseConnection,
+    email_service: EmailService,
+}
+
+impl EmailOutboxService {
+    pub fn new(db: DatabaseConnection, email_service: EmailService) -> Self {
+        Self { db, email_service }
+    }
+
+    /// Queues `email` on `conn`. Pass the transaction that makes the change
+    /// the email is about, so neither is committed without the other.
+    pub async fn enqueue_with<C: ConnectionTrait>(
+        conn: &C,
+        email: OutboxEmail,
+    ) -> Result<email_outbox::Model, AppError> {
+        let now = Utc::now();
+        let message = email_outbox::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            recipient: Set(email.to),
+            template: Set(email.template),
+            languages: Set(email.languages),
+            vars: Set(email.vars),
+            status: Set(PENDING.to_string()),
+            attempts: Set(0),
+            next_attempt_at: Set(email.send_after.unwrap_or(now)),
+            last_error: Set(None),
+            sent_at: Set(None),
+            created_at: Set(now),
+            updated_at: Set(now),
+        };
+
+        Ok(message.insert(conn).await?)
+    }
+
+    pub async fn enqueue(&self, email: OutboxEmail) -> Result<email_outbox::Model, AppError> {
+        Self::enqueue_with(&self.db, email).await
+    }
+
+    /// Starts the background worker that drains the outbox.
+    pub fn spawn_worker(&self) -> JoinHandle<()> {
+        let service = self.clone();
+        job_queue::spawn_worker("Email outbox", move || {
+            let service = service.clone();
+            async move { service.process_due(BATCH_SIZE).await }
+        })
+    }
+
+    /// Attempts up to `limit` due messages and returns how many were sent.
+    pub async fn process_due(&self, limit: u64) -> Result<usize, AppError> {
+        let due = EmailOutbox::find()
+            .filter(email_outbox::Column::Status.eq(PENDING))
+            .filter(email_outbox::Column::NextAttemptAt.lte(Utc::now()))
+            .order_by_asc(email_outbox::Column::NextAttemptAt)
+            .limit(limit)
+            .all(&self.db)
+            .await?;
+
+        let mut sent = 0;
+        for message in due {
+            let claimed = job_queue::claim::<EmailOutbox>(
+                &self.db,
+                &COLUMNS,
+                PENDING,
+                message.id,
+                message.next_attempt_at,
+            )
+            .await?;
+            if claimed && self.deliver(message).await? {
+                sent += 1;
+            }
+        }
+        Ok(sent)
+    }
+
+    async fn deliver(&self, message: email_outbox::Model) -> Result<bool, AppError> {
+        let attempts = message.attempts + 1;
+        let result = self
+            .email_service
+            .send_template(
+                &message.recipient,
+                &message.template,
+                &message.languages,
+                message.vars.clone(),
+            )
+            .await;
+
+        let now = Utc::now();
+        let mut update = email_outbox::ActiveModel {
+            id: Set(message.id),
+            updated_at: Set(now),
+            ..Default::default()
+        };
+        let sent = match result {
+            Ok(()) => {
+                update.status = Set(SENT.to_string());
+                update.sent_at = Set(Some(now));
+                update.last_error = Set(None);
+                true
+            }
+            Err(e) => {
+                let error = format!("{:#}", e);
+                if attempts >= MAX_ATTEMPTS {
+                    tracing::error!(
+                        "Giving up on {} email {} after {} attempts: {}",
+                        message.template,
+                        message.id,
+                        attempts,
+                        error
+                    );
+                    update.status = Set(FAILED.to_string());
+                } else {
+                    tracing::warn!(
+                        "Attempt {} of {} email {} failed: {}",
+                        attempts,
+                        message.template,
+                        message.id,
+                        error
+                    );
+                    update.next_attempt_at = Set(now + retry_delay(attempts));
+                }
+                update.last_error = Set(Some(error));
+                false
+            }
+        };
+
+        update.update(&self.db).await?;
+        Ok(sent)
+    }
+
+    /// Messages needing attention: failed ones, and pending ones that have
+    /// already failed an attempt or are long overdue. Oldest first.
+    pub async fn list_stuck(
+        &self,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<email_outbox::Model>, AppError> {
+        let overdue = Utc::now() - Duration::minutes(STUCK_AFTER_MINUTES);
+        let retrying = Condition::all()
+            .add(email_outbox::Column::Status.eq(PENDING))
+            .add(
+                Condition::any()
+                    .add(email_outbox::Column::Attempts.gt(0))
+                    .add(email_outbox::Column::NextAttemptAt.lt(overdue)),
+            );
+
+        Ok(EmailOutbox::find()
+            .filter(
+                Condition::any()
+                    .add(email_outbox::Column::Status.eq(FAILED))
+                    .add(retrying),
+            )
+            .order_by_asc(email_outbox::Column::CreatedAt)
+            .limit(limit)
+            .offset(offset)
+            .all(&self.db)
+            .await?)
+    }
+
+    /// Puts a failed message back in the queue with a fresh set of attempts.
+    pub async fn retry(&self, id: Uuid) -> Result<email_outbox::Model, AppError> {
+        let message = EmailOutbox::find_by_id(id)
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        if message.status != FAILED {
+            return Err(AppError::Validation(
+                "Only failed emails can be retried".to_string(),
+            ));
+        }
+
+        let now = Utc::now();
+        let mut message: email_outbox::ActiveModel = message.into();
+        message.status = Set(PENDING.to_string());
+        message.attempts = Set(0);
+        message.next_attempt_at = Set(now);
+        message.updated_at = Set(now);
+
+        Ok(message.update(&self.db).await?)
+    }
+}
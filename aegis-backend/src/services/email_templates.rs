@@ -0,0 +1,172 @@
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Used when none of the recipient's languages has a translation.
+pub const DEFAULT_LOCALE: &str = "en";
+
+const SUBJECT_FILE: &str = "subject.txt";
+const HTML_FILE: &str = "body.html";
+const TEXT_FILE: &str = "body.txt";
+
+#[derive(Debug, Clone)]
+pub struct RenderedEmail {
+    pub subject: String,
+    pub html: String,
+    pub text: String,
+}
+
+#[derive(Debug)]
+struct EmailTemplate {
+    subject: String,
+    html: String,
+    text: String,
+}
+
+/// File-based email templates, loaded once at startup:
+///
+/// ```text
+/// <dir>/<template>/<locale>/subject.txt
+/// <dir>/<template>/<locale>/body.html
+/// <dir>/<template>/<locale>/body.txt
+/// ```
+///
+/// Placeholders are written `{{name}}`. Values are HTML-escaped in
+/// `body.html` and inserted as-is elsewhere. Every template needs a
+/// `DEFAULT_LOCALE` translation.
+#[derive(Clone)]
+pub struct EmailTemplates {
+    templates: Arc<HashMap<(String, String), EmailTemplate>>,
+}
+
+impl EmailTemplates {
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let mut templates = HashMap::new();
+        let mut names = Vec::new();
+
+        for template_dir in read_subdirs(dir)? {
+            let name = dir_name(&template_dir)?;
+            for locale_dir in read_subdirs(&template_dir)? {
+                let locale = dir_name(&locale_dir)?;
+                let template = EmailTemplate {
+                    subject: read_part(&locale_dir, SUBJECT_FILE)?.trim().to_string(),
+                    html: read_part(&locale_dir, HTML_FILE)?,
+                    text: read_part(&locale_dir, TEXT_FILE)?,
+                };
+                templates.insert((name.clone(), locale), template);
+            }
+            names.push(name);
+        }
+
+        for name in &names {
+            if !templates.contains_key(&(name.clone(), DEFAULT_LOCALE.to_string())) {
+                return Err(anyhow!(
+                    "Email template '{}' has no '{}' translation",
+                    name,
+                    DEFAULT_LOCALE
+                ));
+            }
+        }
+
+        Ok(Self {
+            templates: Arc::new(templates),
+        })
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.templates
+            .contains_key(&(name.to_string(), DEFAULT_LOCALE.to_string()))
+    }
+
+    /// Renders `name` in the first of `languages` that has a translation.
+    /// Languages may be tags such as `es` or `pt-BR`; only the primary
+    /// subtag is matched. `vars` must be a JSON object.
+    pub fn render(&self, name: &str, languages: &[String], vars: &Value) -> Result<RenderedEmail> {
+        let template = languages
+            .iter()
+            .map(|language| primary_subtag(language))
+            .chain(std::iter::once(DEFAULT_LOCALE.to_string()))
+            .find_map(|locale| self.templates.get(&(name.to_string(), locale)))
+            .ok_or_else(|| anyhow!("Unknown email template '{}'", name))?;
+
+        Ok(RenderedEmail {
+            subject: substitute(&template.subject, vars, false)
+                .with_context(|| format!("Rendering subject of '{}'", name))?,
+            html: substitute(&template.html, vars, true)
+                .with_context(|| format!("Rendering HTML body of '{}'", name))?,
+            text: substitute(&template.text, vars, false)
+                .with_context(|| format!("Rendering text body of '{}'", name))?,
+        })
+    }
+}
+
+fn read_subdirs(dir: &Path) -> Result<Vec<std::path::PathBuf>> {
+    let mut subdirs = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Reading {}", dir.display()))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            subdirs.push(path);
+        }
+    }
+    subdirs.sort();
+    Ok(subdirs)
+}
+
+fn dir_name(path: &Path) -> Result<String> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_string)
+        .ok_or_else(|| anyhow!("Invalid template directory {}", path.display()))
+}
+
+fn read_part(dir: &Path, file: &str) -> Result<String> {
+    let path = dir.join(file);
+    std::fs::read_to_string(&path).with_context(|| format!("Reading {}", path.display()))
+}
+
+fn primary_subtag(language: &str) -> String {
+    language
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
+fn substitute(template: &str, vars: &Value, escape: bool) -> Result<String> {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after
+            .find("}}")
+            .ok_or_else(|| anyhow!("Unclosed placeholder"))?;
+        let name = after[..end].trim();
+
+        let value = match vars.get(name) {
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Null) | None => return Err(anyhow!("Missing variable '{}'", name)),
+            Some(other) => other.to_string(),
+        };
+        if escape {
+            output.push_str(&escape_html(&value));
+        } else {
+            output.push_str(&value);
+        }
+        rest = &after[end + 2..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -0,0 +1,145 @@
+use crate::models::dynamodb::{Post, TimelineItem};
+use crate::repositories::{FeedRepository, FollowRepository, Page, PageRequest, PostRepository};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Debug, Serialize)]
+pub struct FeedEntry {
+    pub post: Post,
+    pub liked_by_me: bool,
+    pub source: String,
+    pub source_id: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct FeedService {
+    feed_repo: FeedRepository,
+    follow_repo: FollowRepository,
+    post_repo: PostRepository,
+}
+
+impl FeedService {
+    pub fn new(
+        feed_repo: FeedRepository,
+        follow_repo: FollowRepository,
+        post_repo: PostRepository,
+    ) -> Self {
+        Self {
+            feed_repo,
+            follow_repo,
+            post_repo,
+        }
+    }
+
+    /// Fans a new post out to the author's own timeline, their followers,
+    /// their team's followers and their teammates (`team` is the team ID and
+    /// its roster).
+    pub async fn publish_post(
+        &self,
+        post: &Post,
+        team: Option<(String, Vec<String>)>,
+    ) -> Result<()> {
+        // One item per recipient; later inserts win, so the most specific source is kept
+        let mut recipients: HashMap<String, (&str, Option<String>)> = HashMap::new();
+
+        for follower in self
+            .follow_repo
+            .get_followers("player", &post.author)
+            .await?
+        {
+            recipients.insert(follower, ("follow", None));
+        }
+        if let Some((team_id, roster)) = team {
+            for follower in self.follow_repo.get_followers("team", &team_id).await? {
+                recipients.insert(follower, ("follow", Some(team_id.clone())));
+            }
+            for member in roster {
+                recipients.insert(member, ("team", Some(team_id.clone())));
+            }
+        }
+        recipients.insert(post.author.clone(), ("self", None));
+
+        self.write_items(post, recipients).await
+    }
+
+    /// Fans a community post out to every member (`members` is the member ID list).
+    pub async fn publish_to_community(
+        &self,
+        post: &Post,
+        community_id: &str,
+        members: Vec<String>,
+    ) -> Result<()> {
+        let recipients = members
+            .into_iter()
+            .map(|member| (member, ("community", Some(community_id.to_string()))))
+            .collect();
+
+        self.write_items(post, recipients).await
+    }
+
+    /// Newest first. Posts deleted since fan-out are skipped.
+    pub async fn get_home_feed(
+        &self,
+        user_id: &str,
+        request: &PageRequest,
+    ) -> Result<Page<FeedEntry>> {
+        let mut timeline = self.feed_repo.get_timeline(user_id, request).await?;
+
+        let post_ids: Vec<String> = timeline
+            .items
+            .iter()
+            .map(|item| item.post_id.clone())
+            .collect();
+        let mut posts: HashMap<String, Post> = self
+            .post_repo
+            .get_posts(&post_ids)
+            .await?
+            .into_iter()
+            .map(|post| (post.id.clone(), post))
+            .collect();
+        let liked = self
+            .post_repo
+            .get_liked_post_ids(user_id, &post_ids)
+            .await?;
+
+        let items = std::mem::take(&mut timeline.items)
+            .into_iter()
+            .filter_map(|item| {
+                posts.remove(&item.post_id).map(|post| FeedEntry {
+                    liked_by_me: liked.contains(&post.id),
+                    post,
+                    source: item.source,
+                    source_id: item.source_id,
+                })
+            })
+            .collect();
+
+        Ok(Page {
+            items,
+            before: timeline.before,
+            after: timeline.after,
+            has_more: timeline.has_more,
+        })
+    }
+
+    async fn write_items(
+        &self,
+        post: &Post,
+        recipients: HashMap<String, (&str, Option<String>)>,
+    ) -> Result<()> {
+        let items: Vec<TimelineItem> = recipients
+            .into_iter()
+            .map(|(user_id, (source, source_id))| TimelineItem {
+                user_id,
+                post_id: post.id.clone(),
+                author: post.author.clone(),
+                source: source.to_string(),
+                source_id,
+                post_created_at: post.created_at.clone(),
+            })
+            .collect();
+
+        self.feed_repo.add_timeline_items(&items).await
+    }
+}
@@ -0,0 +1,92 @@
+use crate::models::dynamodb::Follow;
+use crate::repositories::{FollowRepository, Page, PageRequest};
+use anyhow::Result;
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct FollowStats {
+    pub followers: i64,
+    pub following: i64,
+    pub followed_by_me: bool,
+}
+
+#[derive(Clone)]
+pub struct FollowService {
+    follow_repo: FollowRepository,
+}
+
+impl FollowService {
+    pub fn new(follow_repo: FollowRepository) -> Self {
+        Self { follow_repo }
+    }
+
+    /// Idempotent: returns `false` if already following.
+    pub async fn follow(
+        &self,
+        follower_type: &str,
+        follower_id: &str,
+        target_type: &str,
+        target_id: &str,
+    ) -> Result<bool> {
+        let follow = Follow {
+            follower_id: follower_id.to_string(),
+            target_type: target_type.to_string(),
+            target_id: target_id.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+        self.follow_repo.follow(&follow, follower_type).await
+    }
+
+    /// Idempotent: returns `false` if not following.
+    pub async fn unfollow(
+        &self,
+        follower_type: &str,
+        follower_id: &str,
+        target_type: &str,
+        target_id: &str,
+    ) -> Result<bool> {
+        self.follow_repo
+            .unfollow(follower_type, follower_id, target_type, target_id)
+            .await
+    }
+
+    pub async fn get_stats(
+        &self,
+        target_type: &str,
+        target_id: &str,
+        viewer_id: &str,
+    ) -> Result<FollowStats> {
+        let (counts, followed_by_me) = tokio::try_join!(
+            self.follow_repo.get_counts(target_type, target_id),
+            self.follow_repo
+                .is_following(viewer_id, target_type, target_id),
+        )?;
+
+        Ok(FollowStats {
+            followers: counts.followers,
+            following: counts.following,
+            followed_by_me,
+        })
+    }
+
+    pub async fn get_followers(
+        &self,
+        target_type: &str,
+        target_id: &str,
+        request: &PageRequest,
+    ) -> Result<Page<Follow>> {
+        self.follow_repo
+            .get_followers_page(target_type, target_id, request)
+            .await
+    }
+
+    pub async fn get_following(
+        &self,
+        follower_id: &str,
+        request: &PageRequest,
+    ) -> Result<Page<Follow>> {
+        self.follow_repo
+            .get_following_page(follower_id, request)
+            .await
+    }
+}
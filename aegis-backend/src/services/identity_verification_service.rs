@@ -0,0 +1,237 @@
+use crate::models::enums::ApprovalStatus;
+use crate::models::postgres::{identity_verification, player, IdentityVerification, Player};
+use crate::utils::errors::AppError;
+use chrono::Utc;
+use sea_orm::*;
+use uuid::Uuid;
+
+pub const DOCUMENT_TYPES: &[&str] = &["passport", "national_id", "drivers_license"];
+
+#[derive(Clone)]
+pub struct IdentityVerificationService {
+    db: DatabaseConnection,
+}
+
+impl IdentityVerificationService {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Refuses a submission before its documents are stored: an unknown
+    /// document type, no documents, or a player who is already verified or
+    /// awaiting review.
+    pub async fn check_submission(
+        &self,
+        player_id: Uuid,
+        document_type: &str,
+        document_count: usize,
+    ) -> Result<(), AppError> {
+        if !DOCUMENT_TYPES.contains(&document_type) {
+            return Err(AppError::Validation(
+                "Unsupported document type".to_string(),
+            ));
+        }
+        if document_count == 0 {
+            return Err(AppError::Validation(
+                "At least one document is required".to_string(),
+            ));
+        }
+
+        let player = Player::find_by_id(player_id)
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        if player.identity_verified {
+            return Err(AppError::Validation(
+                "Identity already verified".to_string(),
+            ));
+        }
+
+        let pending = IdentityVerification::find()
+            .filter(identity_verification::Column::PlayerId.eq(player_id))
+            .filter(identity_verification::Column::Status.eq(ApprovalStatus::Pending))
+            .one(&self.db)
+            .await?;
+        if pending.is_some() {
+            return Err(AppError::Validation(
+                "A verification request is already pending review".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub async fn submit(
+        &self,
+        player_id: Uuid,
+        document_type: String,
+        document_keys: Vec<String>,
+    ) -> Result<identity_verification::Model, AppError> {
+        self.check_submission(player_id, &document_type, document_keys.len())
+            .await?;
+
+        let now = Utc::now();
+        let submission = identity_verification::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            player_id: Set(player_id),
+            document_type: Set(document_type),
+            document_keys: Set(document_keys),
+            status: Set(ApprovalStatus::Pending),
+            submitted_at: Set(now),
+            reviewed_by: Set(None),
+            reviewed_at: Set(None),
+            rejection_reason: Set(None),
+            created_at: Set(now),
+            updated_at: Set(now),
+        };
+
+        // The partial unique index on pending requests closes the race
+        // between two submissions that both passed the check above.
+        submission
+            .insert(&self.db)
+            .await
+            .map_err(|e| match e.sql_err() {
+                Some(SqlErr::UniqueConstraintViolation(_)) => AppError::Validation(
+                    "A verification request is already pending review".to_string(),
+                ),
+                _ => e.into(),
+            })
+    }
+
+    pub async fn get_by_id(
+        &self,
+        id: Uuid,
+    ) -> Result<Option<identity_verification::Model>, AppError> {
+        Ok(IdentityVerification::find_by_id(id).one(&self.db).await?)
+    }
+
+    pub async fn get_latest_for_player(
+        &self,
+        player_id: Uuid,
+    ) -> Result<Option<identity_verification::Model>, AppError> {
+        Ok(IdentityVerification::find()
+            .filter(identity_verification::Column::PlayerId.eq(player_id))
+            .order_by_desc(identity_verification::Column::SubmittedAt)
+            .one(&self.db)
+            .await?)
+    }
+
+    /// Oldest submissions first so reviewers work the queue in order.
+    pub async fn get_pending_queue(
+        &self,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<identity_verification::Model>, AppError> {
+        Ok(IdentityVerification::find()
+            .filter(identity_verification::Column::Status.eq(ApprovalStatus::Pending))
+            .order_by_asc(identity_verification::Column::SubmittedAt)
+            .limit(limit)
+            .offset(offset)
+            .all(&self.db)
+            .await?)
+    }
+
+    pub async fn approve(
+        &self,
+        verification_id: Uuid,
+        admin_id: Uuid,
+    ) -> Result<identity_verification::Model, AppError> {
+        let now = Utc::now();
+        let txn = self.db.begin().await?;
+
+        let approved = Self::close_review(
+            &txn,
+            verification_id,
+            identity_verification::ActiveModel {
+                status: Set(ApprovalStatus::Approved),
+                reviewed_by: Set(Some(admin_id)),
+                reviewed_at: Set(Some(now)),
+                updated_at: Set(now),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        let player = Player::find_by_id(approved.player_id)
+            .one(&txn)
+            .await?
+            .ok_or(AppError::NotFound)?;
+        let mut player_update: player::ActiveModel = player.into();
+        player_update.identity_verified = Set(true);
+        player_update.identity_verified_at = Set(Some(now));
+        player_update.updated_at = Set(now);
+        Player::update(player_update).exec(&txn).await?;
+
+        txn.commit().await?;
+        Ok(approved)
+    }
+
+    pub async fn reject(
+        &self,
+        verification_id: Uuid,
+        admin_id: Uuid,
+        reason: String,
+    ) -> Result<identity_verification::Model, AppError> {
+        let now = Utc::now();
+        let txn = self.db.begin().await?;
+
+        let rejected = Self::close_review(
+            &txn,
+            verification_id,
+            identity_verification::ActiveModel {
+                status: Set(ApprovalStatus::Rejected),
+                reviewed_by: Set(Some(admin_id)),
+                reviewed_at: Set(Some(now)),
+                rejection_reason: Set(Some(reason)),
+                updated_at: Set(now),
+                ..Default::default()
+            },
+        )
+        .await?;
+
+        txn.commit().await?;
+        Ok(rejected)
+    }
+
+    pub async fn is_identity_verified(&self, player_id: Uuid) -> Result<bool, AppError> {
+        Ok(Player::find_by_id(player_id)
+            .one(&self.db)
+            .await?
+            .map(|p| p.identity_verified)
+            .unwrap_or(false))
+    }
+
+    /// Applies a review to a request that is still pending. The status is
+    /// part of the UPDATE's condition, so of two concurrent reviews only the
+    /// first takes effect.
+    async fn close_review(
+        txn: &DatabaseTransaction,
+        verification_id: Uuid,
+        review: identity_verification::ActiveModel,
+    ) -> Result<identity_verification::Model, AppError> {
+        let result = IdentityVerification::update_many()
+            .set(review)
+            .filter(identity_verification::Column::Id.eq(verification_id))
+            .filter(identity_verification::Column::Status.eq(ApprovalStatus::Pending))
+            .exec(txn)
+            .await?;
+
+        if result.rows_affected == 0 {
+            return Err(
+                match IdentityVerification::find_by_id(verification_id)
+                    .one(txn)
+                    .await?
+                {
+                    Some(_) => AppError::Validation(
+                        "Verification request has already been reviewed".to_string(),
+                    ),
+                    None => AppError::NotFound,
+                },
+            );
+        }
+
+        IdentityVerification::find_by_id(verification_id)
+            .one(txn)
+            .await?
+            .ok_or(AppError::NotFound)
+    }
+}
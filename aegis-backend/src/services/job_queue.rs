@@ -0,0 +1,86 @@
+//! The claim-and-retry loop behind the Postgres work queues: the email
+//! outbox and webhook deliveries. Each row is `pending` until a worker
+//! finishes it, and `next_attempt_at` says when a worker may next pick it up.
+
+use crate::utils::errors::AppError;
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+use std::future::Future;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// How long a claim hides a row from other workers. A worker that dies
+/// before recording the outcome leaves the row to become due again.
+const CLAIM_LEASE_SECS: i64 = 5 * 60;
+const POLL_INTERVAL_SECS: u64 = 5;
+
+/// Exponential retry schedule: `base_secs` after the first failure,
+/// doubling per attempt up to `max_secs`.
+#[derive(Debug, Clone, Copy)]
+pub struct Backoff {
+    pub base_secs: i64,
+    pub max_secs: i64,
+}
+
+impl Backoff {
+    pub fn delay(&self, attempts: i32) -> Duration {
+        let exponent = attempts.saturating_sub(1).clamp(0, 20) as u32;
+        Duration::seconds((self.base_secs << exponent).min(self.max_secs))
+    }
+}
+
+/// The columns of a queue table that claiming touches.
+pub struct QueueColumns<C> {
+    pub id: C,
+    pub status: C,
+    pub attempts: C,
+    pub next_attempt_at: C,
+    pub updated_at: C,
+}
+
+/// Counts an attempt on row `id` and moves it past the lease. Returns
+/// `false` if another worker claimed the row after it was read, which shows
+/// up as a changed `next_attempt_at`.
+pub async fn claim<E: EntityTrait>(
+    db: &DatabaseConnection,
+    columns: &QueueColumns<E::Column>,
+    pending: &str,
+    id: Uuid,
+    read_next_attempt_at: DateTime<Utc>,
+) -> Result<bool, AppError> {
+    let now = Utc::now();
+    let result = E::update_many()
+        .col_expr(columns.attempts, Expr::col(columns.attempts).add(1))
+        .col_expr(
+            columns.next_attempt_at,
+            Expr::value(now + Duration::seconds(CLAIM_LEASE_SECS)),
+        )
+        .col_expr(columns.updated_at, Expr::value(now))
+        .filter(columns.id.eq(id))
+        .filter(columns.status.eq(pending))
+        .filter(columns.next_attempt_at.eq(read_next_attempt_at))
+        .exec(db)
+        .await?;
+
+    Ok(result.rows_affected == 1)
+}
+
+/// Runs `process_due` every few seconds for the life of the process,
+/// logging failures as `name`.
+pub fn spawn_worker<F, Fut>(name: &'static str, mut process_due: F) -> JoinHandle<()>
+where
+    F: FnMut() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<usize, AppError>> + Send,
+{
+    tokio::spawn(async move {
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(POLL_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            if let Err(e) = process_due().await {
+                tracing::error!("{} worker failed: {:?}", name, e);
+            }
+        }
+    })
+}
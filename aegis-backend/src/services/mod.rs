@@ -1,3 +1,4 @@
+pub mod activity_service;
 pub mod admin_service;
 pub mod api_key_service;
 pub mod audit_service;
@@ -5,8 +6,15 @@ pub mod auth_service;
 pub mod battle_service;
 pub mod chat_service;
 pub mod community_service;
+pub mod email_outbox_service;
 pub mod email_service;
+pub mod email_templates;
+pub mod feed_service;
+pub mod follow_service;
+pub mod identity_verification_service;
+pub mod job_queue;
 pub mod localstack_monitor;
+pub mod notification_service;
 pub mod organization_service;
 pub mod player_game_stats_service;
 pub mod player_service;
@@ -20,7 +28,10 @@ pub mod tournament_service;
 pub mod tournament_team_invite_service;
 pub mod tournament_team_service;
 pub mod transaction_service;
+pub mod two_factor_service;
+pub mod webhook_service;
 
+pub use activity_service::ActivityService;
 pub use admin_service::AdminService;
 pub use api_key_service::ApiKeyService;
 pub use audit_service::AuditService;
@@ -28,7 +39,12 @@ pub use auth_service::AuthService;
 pub use battle_service::BattleService;
 pub use chat_service::ChatService;
 pub use community_service::CommunityService;
+pub use email_outbox_service::EmailOutboxService;
 pub use email_service::EmailService;
+pub use feed_service::FeedService;
+pub use follow_service::FollowService;
+pub use identity_verification_service::IdentityVerificationService;
+pub use notification_service::NotificationService;
 pub use organization_service::OrganizationService;
 pub use player_game_stats_service::PlayerGameStatsService;
 pub use player_service::PlayerService;
@@ -42,3 +58,5 @@ pub use tournament_service::TournamentService;
 pub use tournament_team_invite_service::TournamentTeamInviteService;
 pub use tournament_team_service::TournamentTeamService;
 pub use transaction_service::TransactionService;
+pub use two_factor_service::TwoFactorService;
+pub use webhook_service::WebhookService;
@@ -0,0 +1,317 @@
+use crate::models::dynamodb::{
+    Notification, NotificationCategory, NotificationChannel, NotificationKind,
+    NotificationPreferences,
+};
+use crate::repositories::{
+    is_version_conflict, NotificationRepository, Page, PageRequest, MAX_CONFLICT_RETRIES,
+};
+use crate::services::auth_service::UserType;
+use crate::services::chat_service::{user_room, CHAT_NAMESPACE};
+use crate::services::email_outbox_service::OutboxEmail;
+use crate::services::{AuthService, EmailOutboxService, PlayerService};
+use anyhow::Result;
+use serde_json::Value;
+use socketioxide::SocketIo;
+use uuid::Uuid;
+
+/// `TempTokenClaims::token_type` of unsubscribe links, followed by the category.
+pub const UNSUBSCRIBE_TOKEN_PREFIX: &str = "unsubscribe:";
+const UNSUBSCRIBE_TOKEN_HOURS: i64 = 24 * 30;
+
+/// A notification to deliver; see `NotificationService::notify`.
+#[derive(Debug, Clone)]
+pub struct NewNotification {
+    pub kind: NotificationKind,
+    pub title: String,
+    pub body: String,
+    pub link: Option<String>,
+    pub metadata: Value,
+}
+
+impl NewNotification {
+    pub fn new(kind: NotificationKind, title: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            kind,
+            title: title.into(),
+            body: body.into(),
+            link: None,
+            metadata: Value::Null,
+        }
+    }
+
+    pub fn with_link(mut self, link: impl Into<String>) -> Self {
+        self.link = Some(link.into());
+        self
+    }
+
+    pub fn with_metadata(mut self, metadata: Value) -> Self {
+        self.metadata = metadata;
+        self
+    }
+}
+
+#[derive(Clone)]
+pub struct NotificationService {
+    notification_repo: NotificationRepository,
+    email_outbox_service: EmailOutboxService,
+    player_service: PlayerService,
+    auth_service: AuthService,
+    io: SocketIo,
+}
+
+impl NotificationService {
+    pub fn new(
+        notification_repo: NotificationRepository,
+        email_outbox_service: EmailOutboxService,
+        player_service: PlayerService,
+        auth_service: AuthService,
+        io: SocketIo,
+    ) -> Self {
+        Self {
+            notification_repo,
+            email_outbox_service,
+            player_service,
+            auth_service,
+            io,
+        }
+    }
+
+    /// Delivers a notification on the channels the recipient chose for its
+    /// category: stored in the inbox and pushed to their open sockets for
+    /// in-app, and emailed in the background. Email chosen during quiet hours
+    /// is queued to go out when they end. Returns the stored notification, if
+    /// in-app was chosen.
+    pub async fn notify(
+        &self,
+        user_id: &str,
+        notification: NewNotification,
+    ) -> Result<Option<Notification>> {
+        let category = notification.kind.category();
+        let preferences = self.preferences_or_default(user_id).await;
+        let channels = preferences.channels_for(category);
+
+        if channels.contains(&NotificationChannel::Email) {
+            let send_after = preferences.quiet_hours_end(chrono::Utc::now());
+            self.send_email(user_id, category, &notification, send_after);
+        }
+        if !channels.contains(&NotificationChannel::InApp) {
+            return Ok(None);
+        }
+
+        let stored = Notification {
+            id: Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            kind: notification.kind,
+            title: notification.title,
+            body: notification.body,
+            link: notification.link,
+            metadata: notification.metadata,
+            status: Notification::UNREAD.to_string(),
+            read_at: None,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+        self.notification_repo.create(&stored).await?;
+
+        // Only push once the notification is durable
+        self.push(user_id, "notification", &stored);
+        Ok(Some(stored))
+    }
+
+    pub async fn get_inbox(
+        &self,
+        user_id: &str,
+        request: &PageRequest,
+    ) -> Result<Page<Notification>> {
+        self.notification_repo.get_inbox(user_id, request).await
+    }
+
+    pub async fn unread_count(&self, user_id: &str) -> Result<i64> {
+        self.notification_repo.count_unread(user_id).await
+    }
+
+    /// Returns `None` if the user has no such notification. Idempotent.
+    pub async fn mark_read(
+        &self,
+        user_id: &str,
+        notification_id: &str,
+    ) -> Result<Option<Notification>> {
+        let Some(mut notification) = self.notification_repo.get(user_id, notification_id).await?
+        else {
+            return Ok(None);
+        };
+        if notification.is_unread() {
+            let read_at = chrono::Utc::now().to_rfc3339();
+            self.notification_repo
+                .mark_read(&notification, &read_at)
+                .await?;
+            notification.status = Notification::READ.to_string();
+            notification.read_at = Some(read_at);
+        }
+        Ok(Some(notification))
+    }
+
+    /// Returns how many notifications were marked.
+    pub async fn mark_all_read(&self, user_id: &str) -> Result<usize> {
+        let read_at = chrono::Utc::now().to_rfc3339();
+        let mut marked = 0;
+        for notification in self.notification_repo.get_unread(user_id).await? {
+            if self
+                .notification_repo
+                .mark_read(&notification, &read_at)
+                .await?
+            {
+                marked += 1;
+            }
+        }
+        Ok(marked)
+    }
+
+    /// The user's saved preferences, or the defaults if they never saved any.
+    pub async fn get_preferences(&self, user_id: &str) -> Result<NotificationPreferences> {
+        Ok(self
+            .notification_repo
+            .get_preferences(user_id)
+            .await?
+            .unwrap_or_else(|| NotificationPreferences::new(user_id)))
+    }
+
+    /// Applies `change` to the user's preferences, retrying on concurrent
+    /// updates. `change` returns `false` to skip the write.
+    pub async fn update_preferences(
+        &self,
+        user_id: &str,
+        change: impl Fn(&mut NotificationPreferences) -> bool,
+    ) -> Result<NotificationPreferences> {
+        let mut attempt = 0;
+        loop {
+            let existing = self.notification_repo.get_preferences(user_id).await?;
+            let is_new = existing.is_none();
+            let mut preferences = existing.unwrap_or_else(|| NotificationPreferences::new(user_id));
+            if !change(&mut preferences) {
+                return Ok(preferences);
+            }
+            preferences.updated_at = chrono::Utc::now().to_rfc3339();
+
+            let written = if is_new {
+                self.notification_repo
+                    .create_preferences(&preferences)
+                    .await?
+            } else {
+                match self
+                    .notification_repo
+                    .update_preferences(&preferences)
+                    .await
+                {
+                    Ok(()) => true,
+                    Err(e) if is_version_conflict(&e) => false,
+                    Err(e) => return Err(e),
+                }
+            };
+            if written {
+                if !is_new {
+                    preferences.version += 1;
+                }
+                return Ok(preferences);
+            }
+
+            if attempt >= MAX_CONFLICT_RETRIES {
+                anyhow::bail!("Notification preferences for {} kept changing", user_id);
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Turns off email for a category. Returns `false` if it was already off.
+    pub async fn unsubscribe(&self, user_id: &str, category: NotificationCategory) -> Result<bool> {
+        let before = self.get_preferences(user_id).await?;
+        if !before
+            .channels_for(category)
+            .contains(&NotificationChannel::Email)
+        {
+            return Ok(false);
+        }
+        self.update_preferences(user_id, |preferences| {
+            preferences.disable(category, NotificationChannel::Email)
+        })
+        .await?;
+        Ok(true)
+    }
+
+    // Preferences can't be loaded: fall back to the defaults rather than drop
+    // the notification
+    async fn preferences_or_default(&self, user_id: &str) -> NotificationPreferences {
+        match self.get_preferences(user_id).await {
+            Ok(preferences) => preferences,
+            Err(e) => {
+                tracing::warn!("Failed to load notification preferences: {}", e);
+                NotificationPreferences::new(user_id)
+            }
+        }
+    }
+
+    fn push<T: serde::Serialize>(&self, user_id: &str, event: &'static str, data: &T) {
+        if let Some(ns) = self.io.of(CHAT_NAMESPACE) {
+            if let Err(e) = ns.within(user_room(user_id)).emit(event, data) {
+                tracing::warn!("Failed to push {} to user {}: {}", event, user_id, e);
+            }
+        }
+    }
+
+    // Email is best-effort and must not hold up in-app delivery
+    fn send_email(
+        &self,
+        user_id: &str,
+        category: NotificationCategory,
+        notification: &NewNotification,
+        send_after: Option<chrono::DateTime<chrono::Utc>>,
+    ) {
+        let Ok(player_id) = Uuid::parse_str(user_id) else {
+            return;
+        };
+        let unsubscribe_token = match self.auth_service.generate_temp_token(
+            player_id,
+            UserType::Player,
+            &format!("{}{}", UNSUBSCRIBE_TOKEN_PREFIX, category.as_str()),
+            UNSUBSCRIBE_TOKEN_HOURS,
+        ) {
+            Ok(token) => token,
+            Err(e) => {
+                tracing::warn!("Failed to create unsubscribe token: {}", e);
+                return;
+            }
+        };
+        let email_outbox_service = self.email_outbox_service.clone();
+        let player_service = self.player_service.clone();
+        let notification = notification.clone();
+        tokio::spawn(async move {
+            let player = match player_service.get_by_id(player_id).await {
+                Ok(Some(player)) => player,
+                Ok(None) => return,
+                Err(e) => {
+                    tracing::warn!("Failed to look up email for {}: {}", player_id, e);
+                    return;
+                }
+            };
+            let email = OutboxEmail::new(
+                player.email,
+                "notification",
+                serde_json::json!({
+                    "title": notification.title,
+                    "body": notification.body,
+                    "link": notification.link.as_deref().unwrap_or("/notifications"),
+                    "unsubscribe_token": unsubscribe_token,
+                }),
+            )
+            .with_languages(player.languages)
+            .with_send_after(send_after);
+            if let Err(e) = email_outbox_service.enqueue(email).await {
+                tracing::warn!(
+                    "Failed to queue {} notification email to {}: {}",
+                    notification.kind.as_str(),
+                    player_id,
+                    e
+                );
+            }
+        });
+    }
+}
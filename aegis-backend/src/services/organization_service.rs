@@ -1,6 +1,7 @@
 use crate::models::enums::{ApprovalStatus, GameType};
 use crate::models::postgres::{organization, Organization};
 use crate::services::auth_service::{AuthService, UserType};
+use crate::services::email_outbox_service::{EmailOutboxService, OutboxEmail};
 use crate::utils::errors::AppError;
 use anyhow::Result;
 use sea_orm::*;
@@ -68,7 +69,26 @@ impl OrganizationService {
             updated_at: Set(now),
         };
 
-        let org = new_org.insert(&self.db).await?;
+        // The organization and its verification email commit together
+        let txn = self.db.begin().await?;
+        let org = new_org.insert(&txn).await?;
+        let verification_token = self.auth_service.generate_temp_token(
+            org.id,
+            UserType::Organization,
+            "verify_email",
+            24, // 24 hours expiry
+        )?;
+        EmailOutboxService::enqueue_with(
+            &txn,
+            OutboxEmail::new(
+                &org.email,
+                "verify_email",
+                serde_json::json!({ "token": verification_token }),
+            ),
+        )
+        .await?;
+        txn.commit().await?;
+
         let token = self.auth_service.generate_jwt(
             org.id,
             UserType::Organization,
@@ -2,6 +2,7 @@ use crate::handlers::players::{PlayerListQuery, PlayerProfileResponse};
 use crate::models::enums::GameType;
 use crate::models::postgres::{player, Player};
 use crate::services::auth_service::{AuthService, UserType};
+use crate::services::email_outbox_service::{EmailOutboxService, OutboxEmail};
 use crate::utils::errors::AppError;
 use crate::utils::validation::validate_password;
 use anyhow::Result;
@@ -103,6 +104,8 @@ impl PlayerService {
             email: Set(email),
             password: Set(hashed_password),
             verified: Set(false),
+            identity_verified: Set(false),
+            identity_verified_at: Set(None),
             country: Set(None),
             bio: Set(String::new()),
             profile_picture: Set(String::new()),
@@ -135,9 +138,12 @@ impl PlayerService {
         };
         println!("DEBUG: ActiveModel created successfully");
 
+        // The player and their verification email commit together
+        let txn = self.db.begin().await?;
+
         // Attempt database insert with detailed error handling
         println!("DEBUG: Attempting database insert");
-        let player = match new_player.insert(&self.db).await {
+        let player = match new_player.insert(&txn).await {
             Ok(p) => {
                 println!("DEBUG: Database insert successful! Player ID: {}", p.id);
                 p
@@ -170,6 +176,24 @@ impl PlayerService {
             }
         };
 
+        let verification_token = self.auth_service.generate_temp_token(
+            player.id,
+            UserType::Player,
+            "verify_email",
+            24, // 24 hours expiry
+        )?;
+        EmailOutboxService::enqueue_with(
+            &txn,
+            OutboxEmail::new(
+                &player.email,
+                "verify_email",
+                serde_json::json!({ "token": verification_token }),
+            )
+            .with_languages(player.languages.clone()),
+        )
+        .await?;
+        txn.commit().await?;
+
         // Generate JWT token
         println!("DEBUG: Generating JWT token for player ID: {}", player.id);
         let token = match self.auth_service.generate_jwt(
@@ -457,6 +481,7 @@ impl PlayerService {
                 username: player.username,
                 email: player.email,
                 verified: player.verified,
+                identity_verified: player.identity_verified,
                 in_game_name: player.in_game_name,
                 real_name: player.real_name,
                 bio: player.bio,
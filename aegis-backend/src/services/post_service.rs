@@ -1,6 +1,23 @@
 use anyhow::Result;
-use crate::models::dynamodb::{Post, PostComment};
-use crate::repositories::{PostRepository, Repository};
+use serde::{Deserialize, Serialize};
+use crate::models::dynamodb::{Post, PostComment, PostLike};
+use crate::repositories::{Page, PageRequest, PostRepository};
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CommentSort {
+    #[default]
+    Newest,
+    Top,                               // Most replies first
+}
+
+/// A post as seen by a particular user.
+#[derive(Debug, Serialize)]
+pub struct PostView {
+    #[serde(flatten)]
+    pub post: Post,
+    pub liked_by_me: bool,
+}
 
 #[derive(Clone)]
 pub struct PostService {
@@ -12,8 +29,8 @@ impl PostService {
         Self { post_repo }
     }
 
-    pub async fn create_post(&self, author: String, title: String, content: String, post_type: String, tags: Vec<String>) -> Result<String> {
-        let post = Post {
+    pub async fn create_post(&self, author: String, title: String, content: String, post_type: String, tags: Vec<String>) -> Result<Post> {
+        let mut post = Post {
             id: String::new(), // Will be generated in repository
             author,
             title,
@@ -24,50 +41,100 @@ impl PostService {
             comments_count: 0,
             created_at: chrono::Utc::now().to_rfc3339(),
             updated_at: chrono::Utc::now().to_rfc3339(),
+            version: 0,
         };
 
-        self.post_repo.create_post(post).await
+        post.id = self.post_repo.create_post(post.clone()).await?;
+        Ok(post)
     }
 
     pub async fn get_post(&self, post_id: &str) -> Result<Option<Post>> {
         self.post_repo.get_post(post_id).await
     }
 
-    pub async fn add_comment(&self, post_id: String, author: String, content: String) -> Result<String> {
+    pub async fn get_post_view(&self, post_id: &str, viewer_id: &str) -> Result<Option<PostView>> {
+        match self.post_repo.get_post(post_id).await? {
+            Some(post) => Ok(self.to_views(vec![post], viewer_id).await?.pop()),
+            None => Ok(None),
+        }
+    }
+
+    /// `parent_id` must name a top-level comment; replies are one level deep.
+    pub async fn add_comment(&self, post_id: String, author: String, content: String, parent_id: Option<String>) -> Result<String> {
         let comment = PostComment {
             id: String::new(), // Will be generated in repository
-            post_id: post_id.clone(),
+            post_id,
             author,
             content,
             created_at: chrono::Utc::now().to_rfc3339(),
+            parent_id,
+            reply_count: 0,
+            edited_at: None,
+            deleted: false,
+            deleted_at: None,
+            deleted_by: None,
         };
 
-        let comment_id = self.post_repo.add_comment(comment).await?;
+        self.post_repo.add_comment(comment).await
+    }
 
-        // Update comment count
-        if let Some(mut post) = self.post_repo.get_post(&post_id).await? {
-            post.comments_count += 1;
-            post.updated_at = chrono::Utc::now().to_rfc3339();
-            self.post_repo.update(&post).await?;
-        }
+    pub async fn get_comment(&self, post_id: &str, comment_id: &str) -> Result<Option<PostComment>> {
+        self.post_repo.get_comment(post_id, comment_id).await
+    }
 
-        Ok(comment_id)
+    /// Returns `false` if the comment has been deleted.
+    pub async fn edit_comment(&self, post_id: &str, comment_id: &str, content: &str) -> Result<bool> {
+        let edited_at = chrono::Utc::now().to_rfc3339();
+        self.post_repo.update_comment_content(post_id, comment_id, content, &edited_at).await
     }
 
-    pub async fn get_comments(&self, post_id: &str) -> Result<Vec<PostComment>> {
-        self.post_repo.get_comments(post_id).await
+    /// Returns `false` if the comment was already deleted.
+    pub async fn delete_comment(&self, comment: &PostComment, deleted_by: &str) -> Result<bool> {
+        let deleted_at = chrono::Utc::now().to_rfc3339();
+        self.post_repo.delete_comment(comment, deleted_by, &deleted_at).await
     }
 
-    pub async fn like_post(&self, post_id: &str) -> Result<()> {
-        if let Some(mut post) = self.post_repo.get_post(post_id).await? {
-            post.likes += 1;
-            post.updated_at = chrono::Utc::now().to_rfc3339();
-            self.post_repo.update(&post).await?;
+    pub async fn get_comments(&self, post_id: &str, sort: CommentSort, request: &PageRequest) -> Result<Page<PostComment>> {
+        match sort {
+            CommentSort::Newest => self.post_repo.get_comments(post_id, request).await,
+            CommentSort::Top => self.post_repo.get_top_comments(post_id, request).await,
         }
-        Ok(())
     }
 
-    pub async fn get_posts_by_author(&self, author_id: &str) -> Result<Vec<Post>> {
-        self.post_repo.get_posts_by_author(author_id).await
+    pub async fn get_replies(&self, post_id: &str, comment_id: &str, request: &PageRequest) -> Result<Page<PostComment>> {
+        self.post_repo.get_replies(post_id, comment_id, request).await
+    }
+
+    /// Idempotent: returns `false` if the user had already liked the post.
+    pub async fn like_post(&self, post_id: &str, user_id: &str) -> Result<bool> {
+        let like = PostLike {
+            post_id: post_id.to_string(),
+            user_id: user_id.to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+        self.post_repo.add_like(&like).await
+    }
+
+    /// Idempotent: returns `false` if the user had not liked the post.
+    pub async fn unlike_post(&self, post_id: &str, user_id: &str) -> Result<bool> {
+        self.post_repo.remove_like(post_id, user_id).await
+    }
+
+    pub async fn get_posts_by_author(&self, author_id: &str, viewer_id: &str) -> Result<Vec<PostView>> {
+        let posts = self.post_repo.get_posts_by_author(author_id).await?;
+        self.to_views(posts, viewer_id).await
+    }
+
+    async fn to_views(&self, posts: Vec<Post>, viewer_id: &str) -> Result<Vec<PostView>> {
+        let post_ids: Vec<String> = posts.iter().map(|p| p.id.clone()).collect();
+        let liked = self.post_repo.get_liked_post_ids(viewer_id, &post_ids).await?;
+
+        Ok(posts
+            .into_iter()
+            .map(|post| PostView {
+                liked_by_me: liked.contains(&post.id),
+                post,
+            })
+            .collect())
     }
 }
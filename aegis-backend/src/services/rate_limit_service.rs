@@ -1,128 +1,460 @@
-use crate::models::postgres::{rate_limit, RateLimit};
 use crate::utils::errors::AppError;
-use chrono::{Duration, Utc};
-use sea_orm::*;
-use uuid::Uuid;
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ConnectionTrait, DatabaseBackend, DatabaseConnection, DatabaseTransaction, Statement,
+    TransactionTrait,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::task::JoinHandle;
 
-#[derive(Clone)]
-pub struct RateLimitService {
-    db: DatabaseConnection,
+const PRUNE_INTERVAL_SECS: u64 = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Allows bursts of up to `limit`, refilling one token every
+    /// `window / limit`.
+    TokenBucket,
+    /// At most `limit` hits in any rolling `window`, estimated from the
+    /// current and previous fixed windows.
+    SlidingWindow,
 }
 
-impl RateLimitService {
-    pub fn new(db: DatabaseConnection) -> Self {
-        Self { db }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimitPolicy {
+    /// Namespaces the buckets of this policy, so one client can be limited
+    /// by several policies independently.
+    pub name: &'static str,
+    pub algorithm: Algorithm,
+    pub limit: u32,
+    pub window_secs: u64,
+}
+
+impl RateLimitPolicy {
+    pub const fn token_bucket(name: &'static str, limit: u32, window_secs: u64) -> Self {
+        Self {
+            name,
+            algorithm: Algorithm::TokenBucket,
+            limit,
+            window_secs,
+        }
     }
 
-    pub async fn check_rate_limit(
-        &self,
-        identifier: String,
-        identifier_type: String,
-        action: String,
-        max_attempts: i32,
-        window_minutes: i64,
-    ) -> Result<bool, AppError> {
-        let window_start = Utc::now() - Duration::minutes(window_minutes);
-
-        // Find existing rate limit record
-        let existing = RateLimit::find()
-            .filter(rate_limit::Column::Identifier.eq(&identifier))
-            .filter(rate_limit::Column::IdentifierType.eq(&identifier_type))
-            .filter(rate_limit::Column::Action.eq(&action))
-            .one(&self.db)
-            .await?;
+    pub const fn sliding_window(name: &'static str, limit: u32, window_secs: u64) -> Self {
+        Self {
+            name,
+            algorithm: Algorithm::SlidingWindow,
+            limit,
+            window_secs,
+        }
+    }
+}
 
-        match existing {
-            Some(record) => {
-                // Check if currently blocked
-                if let Some(blocked_until) = record.blocked_until {
-                    if blocked_until > Utc::now() {
-                        return Err(AppError::RateLimited);
-                    }
-                }
+/// Outcome of one hit, with what the `RateLimit-*` headers report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Seconds until the bucket is full again (token bucket) or the current
+    /// window closes (sliding window).
+    pub reset_secs: u64,
+    /// Seconds until a denied hit would be allowed.
+    pub retry_after_secs: Option<u64>,
+}
 
-                // Check if within current window
-                if record.window_start > window_start {
-                    if record.attempts >= max_attempts {
-                        // Block for next window
-                        let mut update: rate_limit::ActiveModel = record.into();
-                        update.blocked_until =
-                            Set(Some(Utc::now() + Duration::minutes(window_minutes)));
-                        update.updated_at = Set(Utc::now());
-                        RateLimit::update(update).exec(&self.db).await?;
-                        return Err(AppError::RateLimited);
-                    } else {
-                        // Increment attempts - store attempts value before move
-                        let current_attempts = record.attempts;
-                        let mut update: rate_limit::ActiveModel = record.into();
-                        update.attempts = Set(current_attempts + 1);
-                        update.updated_at = Set(Utc::now());
-                        RateLimit::update(update).exec(&self.db).await?;
-                        return Ok(true);
-                    }
-                } else {
-                    // New window, reset
-                    let mut update: rate_limit::ActiveModel = record.into();
-                    update.attempts = Set(1);
-                    update.window_start = Set(Utc::now());
-                    update.blocked_until = Set(None);
-                    update.updated_at = Set(Utc::now());
-                    RateLimit::update(update).exec(&self.db).await?;
-                    return Ok(true);
-                }
+/// What a store keeps per bucket. Both algorithms share the layout so the
+/// stores don't need to know which one they hold.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BucketState {
+    /// Token bucket: tokens left. Sliding window: hits in the current window.
+    pub level: f64,
+    /// Sliding window: hits in the previous window. Unused by token buckets.
+    pub previous: f64,
+    /// Token bucket: when it was last refilled, in epoch seconds. Sliding
+    /// window: index of the current window.
+    pub stamp: f64,
+}
+
+/// Applies one hit at `now` (epoch seconds) to a bucket, returning its new
+/// state and the decision. Denied hits don't consume anything.
+pub fn apply(
+    policy: &RateLimitPolicy,
+    state: Option<BucketState>,
+    now: f64,
+) -> (BucketState, Decision) {
+    let limit = f64::from(policy.limit);
+    let window = policy.window_secs.max(1) as f64;
+
+    match policy.algorithm {
+        Algorithm::TokenBucket => {
+            let rate = limit / window;
+            let mut tokens = match state {
+                Some(s) => (s.level + (now - s.stamp).max(0.0) * rate).min(limit),
+                None => limit,
+            };
+            let allowed = tokens >= 1.0;
+            if allowed {
+                tokens -= 1.0;
+            }
+            let decision = Decision {
+                allowed,
+                limit: policy.limit,
+                remaining: tokens.floor() as u32,
+                reset_secs: ((limit - tokens) / rate).ceil() as u64,
+                retry_after_secs: (!allowed).then(|| seconds(((1.0 - tokens) / rate).ceil())),
+            };
+            let state = BucketState {
+                level: tokens,
+                previous: 0.0,
+                stamp: now,
+            };
+            (state, decision)
+        }
+        Algorithm::SlidingWindow => {
+            let index = (now / window).floor();
+            let (previous, mut current) = match state {
+                Some(s) if s.stamp == index => (s.previous, s.level),
+                Some(s) if s.stamp == index - 1.0 => (s.level, 0.0),
+                _ => (0.0, 0.0),
+            };
+            let elapsed = now - index * window;
+            let carried = previous * (1.0 - elapsed / window);
+            let allowed = carried + current + 1.0 <= limit;
+            if allowed {
+                current += 1.0;
             }
-            None => {
-                // Create new record
-                let new_record = rate_limit::ActiveModel {
-                    id: Set(Uuid::new_v4()),
-                    identifier: Set(identifier),
-                    identifier_type: Set(identifier_type),
-                    action: Set(action),
-                    attempts: Set(1),
-                    window_start: Set(Utc::now()),
-                    blocked_until: Set(None),
-                    created_at: Set(Utc::now()),
-                    updated_at: Set(Utc::now()),
+            let retry_after_secs = (!allowed).then(|| {
+                let wait = if current < limit && previous > 0.0 {
+                    // The previous window's share decays enough before this one ends
+                    window * (1.0 - (limit - 1.0 - current) / previous) - elapsed
+                } else {
+                    // Wait for this window to become the previous one and decay
+                    let share = if current > 0.0 {
+                        (1.0 - (limit - 1.0) / current).max(0.0)
+                    } else {
+                        0.0
+                    };
+                    window - elapsed + window * share
                 };
-                new_record.insert(&self.db).await?;
-                return Ok(true);
+                seconds(wait.ceil())
+            });
+            let decision = Decision {
+                allowed,
+                limit: policy.limit,
+                remaining: (limit - carried - current).max(0.0).floor() as u32,
+                reset_secs: (window - elapsed).ceil() as u64,
+                retry_after_secs,
+            };
+            let state = BucketState {
+                level: current,
+                previous,
+                stamp: index,
+            };
+            (state, decision)
+        }
+    }
+}
+
+/// Gives back a hit that was allowed at `hit_at` (epoch seconds). Hits that
+/// have already aged out of a sliding window are left alone.
+pub fn refund(policy: &RateLimitPolicy, state: BucketState, hit_at: f64) -> BucketState {
+    let limit = f64::from(policy.limit);
+    let window = policy.window_secs.max(1) as f64;
+
+    match policy.algorithm {
+        Algorithm::TokenBucket => BucketState {
+            level: (state.level + 1.0).min(limit),
+            ..state
+        },
+        Algorithm::SlidingWindow => {
+            let index = (hit_at / window).floor();
+            if state.stamp == index {
+                BucketState {
+                    level: (state.level - 1.0).max(0.0),
+                    ..state
+                }
+            } else if state.stamp == index + 1.0 {
+                BucketState {
+                    previous: (state.previous - 1.0).max(0.0),
+                    ..state
+                }
+            } else {
+                state
             }
         }
     }
+}
+
+/// When a bucket stops affecting decisions and can be dropped.
+fn expires_at(policy: &RateLimitPolicy, state: &BucketState) -> f64 {
+    let window = policy.window_secs.max(1) as f64;
+    match policy.algorithm {
+        Algorithm::TokenBucket => state.stamp + window,
+        Algorithm::SlidingWindow => (state.stamp + 2.0) * window,
+    }
+}
+
+fn seconds(secs: f64) -> u64 {
+    secs.max(1.0) as u64
+}
+
+fn epoch_secs(at: DateTime<Utc>) -> f64 {
+    at.timestamp_micros() as f64 / 1_000_000.0
+}
+
+/// Where buckets live. A hit must be atomic per key: concurrent hits on the
+/// same bucket may not both consume its last token.
+#[async_trait::async_trait]
+pub trait RateLimitStore: Send + Sync {
+    async fn hit(
+        &self,
+        key: &str,
+        policy: &RateLimitPolicy,
+        now: f64,
+    ) -> Result<Decision, AppError>;
+
+    /// Takes back an allowed hit made at `hit_at`; see `refund`.
+    async fn refund(
+        &self,
+        key: &str,
+        policy: &RateLimitPolicy,
+        hit_at: f64,
+    ) -> Result<(), AppError>;
+
+    /// Drops buckets that expired before `now`.
+    async fn prune(&self, now: f64) -> Result<(), AppError>;
+}
+
+/// Buckets held in process memory. Limits are per instance, so only use it
+/// for single-instance deploys, local runs and tests.
+#[derive(Clone, Default)]
+pub struct MemoryRateLimitStore {
+    buckets: Arc<Mutex<HashMap<String, (BucketState, f64)>>>,
+}
+
+impl MemoryRateLimitStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-    pub async fn is_blocked(
+    fn buckets(&self) -> MutexGuard<'_, HashMap<String, (BucketState, f64)>> {
+        // Buckets are replaced whole, so a poisoned map is still consistent
+        self.buckets
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait::async_trait]
+impl RateLimitStore for MemoryRateLimitStore {
+    async fn hit(
         &self,
-        identifier: String,
-        identifier_type: String,
-        action: String,
+        key: &str,
+        policy: &RateLimitPolicy,
+        now: f64,
+    ) -> Result<Decision, AppError> {
+        let mut buckets = self.buckets();
+        let current = buckets.get(key).map(|(state, _)| *state);
+        let (state, decision) = apply(policy, current, now);
+        buckets.insert(key.to_string(), (state, expires_at(policy, &state)));
+        Ok(decision)
+    }
+
+    async fn refund(
+        &self,
+        key: &str,
+        policy: &RateLimitPolicy,
+        hit_at: f64,
+    ) -> Result<(), AppError> {
+        if let Some((state, _)) = self.buckets().get_mut(key) {
+            *state = refund(policy, *state, hit_at);
+        }
+        Ok(())
+    }
+
+    async fn prune(&self, now: f64) -> Result<(), AppError> {
+        self.buckets()
+            .retain(|_, (_, expires_at)| *expires_at > now);
+        Ok(())
+    }
+}
+
+/// Buckets in the `rate_limit_buckets` table, shared by every instance.
+/// Each hit locks its bucket's row for the length of a transaction, so
+/// concurrent hits on one key wait for each other instead of racing.
+#[derive(Clone)]
+pub struct PostgresRateLimitStore {
+    db: DatabaseConnection,
+}
+
+impl PostgresRateLimitStore {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    /// Reads a bucket and locks its row until `txn` ends.
+    async fn load(txn: &DatabaseTransaction, key: &str) -> Result<Option<BucketState>, AppError> {
+        let row = txn
+            .query_one(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                "SELECT level, previous, stamp FROM rate_limit_buckets WHERE key = $1 FOR UPDATE",
+                [key.into()],
+            ))
+            .await?;
+        Ok(match row {
+            Some(row) => Some(BucketState {
+                level: row.try_get("", "level")?,
+                previous: row.try_get("", "previous")?,
+                stamp: row.try_get("", "stamp")?,
+            }),
+            None => None,
+        })
+    }
+
+    /// Writes `state` to a bucket whose row is locked by `txn`, or creates
+    /// it. Returns `false` if another transaction created the row first.
+    async fn store(
+        txn: &DatabaseTransaction,
+        key: &str,
+        policy: &RateLimitPolicy,
+        state: &BucketState,
+        exists: bool,
     ) -> Result<bool, AppError> {
-        let record = RateLimit::find()
-            .filter(rate_limit::Column::Identifier.eq(identifier))
-            .filter(rate_limit::Column::IdentifierType.eq(identifier_type))
-            .filter(rate_limit::Column::Action.eq(action))
-            .one(&self.db)
+        let sql = if exists {
+            "UPDATE rate_limit_buckets \
+             SET level = $2, previous = $3, stamp = $4, expires_at = to_timestamp($5) \
+             WHERE key = $1"
+        } else {
+            "INSERT INTO rate_limit_buckets (key, level, previous, stamp, expires_at) \
+             VALUES ($1, $2, $3, $4, to_timestamp($5)) ON CONFLICT (key) DO NOTHING"
+        };
+        let result = txn
+            .execute(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                sql,
+                [
+                    key.into(),
+                    state.level.into(),
+                    state.previous.into(),
+                    state.stamp.into(),
+                    expires_at(policy, state).into(),
+                ],
+            ))
             .await?;
+        Ok(result.rows_affected() == 1)
+    }
+}
 
-        if let Some(r) = record {
-            if let Some(blocked_until) = r.blocked_until {
-                return Ok(blocked_until > Utc::now());
-            }
+#[async_trait::async_trait]
+impl RateLimitStore for PostgresRateLimitStore {
+    async fn hit(
+        &self,
+        key: &str,
+        policy: &RateLimitPolicy,
+        now: f64,
+    ) -> Result<Decision, AppError> {
+        let txn = self.db.begin().await?;
+        let current = Self::load(&txn, key).await?;
+        let (state, decision) = apply(policy, current, now);
+        if Self::store(&txn, key, policy, &state, current.is_some()).await? {
+            txn.commit().await?;
+            return Ok(decision);
         }
-        Ok(false)
+
+        // A concurrent first hit created the bucket; its insert has committed
+        // by now, so this read sees the row and waits on its lock
+        let current = Self::load(&txn, key).await?;
+        let (state, decision) = apply(policy, current, now);
+        Self::store(&txn, key, policy, &state, current.is_some()).await?;
+        txn.commit().await?;
+        Ok(decision)
     }
 
-    pub async fn reset_rate_limit(
+    async fn refund(
         &self,
-        identifier: String,
-        identifier_type: String,
-        action: String,
+        key: &str,
+        policy: &RateLimitPolicy,
+        hit_at: f64,
     ) -> Result<(), AppError> {
-        RateLimit::delete_many()
-            .filter(rate_limit::Column::Identifier.eq(identifier))
-            .filter(rate_limit::Column::IdentifierType.eq(identifier_type))
-            .filter(rate_limit::Column::Action.eq(action))
-            .exec(&self.db)
+        let txn = self.db.begin().await?;
+        if let Some(state) = Self::load(&txn, key).await? {
+            Self::store(&txn, key, policy, &refund(policy, state, hit_at), true).await?;
+        }
+        txn.commit().await?;
+        Ok(())
+    }
+
+    async fn prune(&self, now: f64) -> Result<(), AppError> {
+        self.db
+            .execute(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                "DELETE FROM rate_limit_buckets WHERE expires_at < to_timestamp($1)",
+                [now.into()],
+            ))
             .await?;
         Ok(())
     }
 }
+
+/// Counts hits against `RateLimitPolicy`s. Buckets are keyed by the caller,
+/// usually as `<kind>:<id>` of whoever is being limited.
+#[derive(Clone)]
+pub struct RateLimitService {
+    store: Arc<dyn RateLimitStore>,
+}
+
+impl RateLimitService {
+    pub fn new(store: Arc<dyn RateLimitStore>) -> Self {
+        Self { store }
+    }
+
+    pub fn in_memory() -> Self {
+        Self::new(Arc::new(MemoryRateLimitStore::new()))
+    }
+
+    pub fn postgres(db: DatabaseConnection) -> Self {
+        Self::new(Arc::new(PostgresRateLimitStore::new(db)))
+    }
+
+    pub async fn check(&self, policy: &RateLimitPolicy, key: &str) -> Result<Decision, AppError> {
+        self.check_at(policy, key, Utc::now()).await
+    }
+
+    pub async fn check_at(
+        &self,
+        policy: &RateLimitPolicy,
+        key: &str,
+        at: DateTime<Utc>,
+    ) -> Result<Decision, AppError> {
+        let key = format!("{}:{}", policy.name, key);
+        self.store.hit(&key, policy, epoch_secs(at)).await
+    }
+
+    /// Gives back a hit allowed by `check_at(policy, key, at)`, for a request
+    /// that turned out not to count against the policy.
+    pub async fn refund(
+        &self,
+        policy: &RateLimitPolicy,
+        key: &str,
+        at: DateTime<Utc>,
+    ) -> Result<(), AppError> {
+        let key = format!("{}:{}", policy.name, key);
+        self.store.refund(&key, policy, epoch_secs(at)).await
+    }
+
+    /// Starts the background task that drops expired buckets.
+    pub fn spawn_pruner(&self) -> JoinHandle<()> {
+        let store = self.store.clone();
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(std::time::Duration::from_secs(PRUNE_INTERVAL_SECS));
+            loop {
+                interval.tick().await;
+                if let Err(e) = store.prune(epoch_secs(Utc::now())).await {
+                    tracing::warn!("Failed to prune rate limit buckets: {:?}", e);
+                }
+            }
+        })
+    }
+}
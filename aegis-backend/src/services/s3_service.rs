@@ -128,6 +128,23 @@ impl S3Service {
         self.upload_file(&key, data, &content_type).await
     }
 
+    /// Identity documents are never exposed by URL; callers keep the object key
+    /// and hand out short-lived presigned links to reviewers only.
+    pub async fn upload_identity_document(
+        &self,
+        player_id: &str,
+        filename: &str,
+        data: Vec<u8>,
+    ) -> Result<String> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+
+        let key = format!("private/kyc/{}/{}_{}", player_id, timestamp, filename);
+        let content_type = self.get_content_type_from_filename(filename);
+
+        self.upload_file(&key, data, &content_type).await?;
+        Ok(key)
+    }
+
     pub async fn get_presigned_url(&self, key: &str, expires_in_secs: u64) -> Result<String> {
         let presigning_config = aws_sdk_s3::presigning::PresigningConfig::expires_in(
             std::time::Duration::from_secs(expires_in_secs),
@@ -1,4 +1,6 @@
-use crate::models::postgres::{team, Team};
+use crate::models::dynamodb::{ActivityType, Chat};
+use crate::models::postgres::{player, team, Player, Team};
+use crate::services::{ActivityService, ChatService};
 use crate::utils::errors::AppError;
 use sea_orm::*;
 use uuid::Uuid;
@@ -6,11 +8,13 @@ use uuid::Uuid;
 #[derive(Clone)]
 pub struct TeamService {
     db: DatabaseConnection,
+    activity: ActivityService,
+    chat: ChatService,
 }
 
 impl TeamService {
-    pub fn new(db: DatabaseConnection) -> Self {
-        Self { db }
+    pub fn new(db: DatabaseConnection, activity: ActivityService, chat: ChatService) -> Self {
+        Self { db, activity, chat }
     }
 
     pub async fn create_team(
@@ -31,13 +35,60 @@ impl TeamService {
             ..Default::default()
         };
 
-        Ok(new_team.insert(&self.db).await?)
+        let team = new_team.insert(&self.db).await?;
+
+        self.activity
+            .record_for_all(
+                &[captain_id.to_string()],
+                ActivityType::TeamJoined,
+                &format!("Founded team {}", team.team_name),
+                serde_json::json!({ "team_id": team.id, "role": "captain" }),
+            )
+            .await;
+
+        if let Err(e) = self.sync_chat(team.id).await {
+            tracing::warn!("Failed to create chat for team {}: {}", team.id, e);
+        }
+
+        Ok(team)
     }
 
     pub async fn get_by_id(&self, id: Uuid) -> Result<Option<team::Model>, AppError> {
         Ok(Team::find_by_id(id).one(&self.db).await?)
     }
 
+    pub async fn get_roster(&self, team_id: Uuid) -> Result<Vec<player::Model>, AppError> {
+        Ok(Player::find()
+            .filter(player::Column::TeamId.eq(team_id))
+            .all(&self.db)
+            .await?)
+    }
+
+    /// Brings the team chat in line with the roster, captain and name. Call
+    /// after any of them change; chat reads only check the participant item.
+    pub async fn sync_chat(&self, team_id: Uuid) -> Result<Chat, AppError> {
+        let team = self.get_by_id(team_id).await?.ok_or(AppError::NotFound)?;
+        let roster = self
+            .get_roster(team_id)
+            .await?
+            .into_iter()
+            .map(|p| p.id.to_string())
+            .collect();
+
+        self.chat
+            .sync_team_chat(
+                &team_id.to_string(),
+                &team.team_name,
+                team.captain.map(|c| c.to_string()),
+                roster,
+            )
+            .await
+            .map_err(|e| {
+                tracing::error!("Failed to sync team chat: {}", e);
+                AppError::InternalServerError
+            })
+    }
+
     pub async fn get_by_organization(&self, org_id: Uuid) -> Result<Vec<team::Model>, AppError> {
         Ok(Team::find()
             .filter(team::Column::OrganizationId.eq(org_id))
@@ -45,3 +96,27 @@ impl TeamService {
             .await?)
     }
 }
+
+/// Player IDs on a team's roster, for activity fan-out after a change has
+/// been committed. Failures are logged and yield `None`, since the caller
+/// can no longer roll back.
+pub(crate) async fn roster_for_activity(
+    db: &DatabaseConnection,
+    team_id: Uuid,
+) -> Option<Vec<String>> {
+    match Player::find()
+        .filter(player::Column::TeamId.eq(team_id))
+        .all(db)
+        .await
+    {
+        Ok(players) => Some(players.into_iter().map(|p| p.id.to_string()).collect()),
+        Err(e) => {
+            tracing::warn!(
+                "Failed to load roster of team {} for activity: {}",
+                team_id,
+                e
+            );
+            None
+        }
+    }
+}
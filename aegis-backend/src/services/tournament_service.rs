@@ -1,4 +1,6 @@
+use crate::models::postgres::webhook_endpoint::WebhookEvent;
 use crate::models::postgres::{tournament, Tournament};
+use crate::services::WebhookService;
 use crate::utils::errors::AppError;
 use sea_orm::*;
 use uuid::Uuid;
@@ -6,11 +8,12 @@ use uuid::Uuid;
 #[derive(Clone)]
 pub struct TournamentService {
     db: DatabaseConnection,
+    webhooks: WebhookService,
 }
 
 impl TournamentService {
-    pub fn new(db: DatabaseConnection) -> Self {
-        Self { db }
+    pub fn new(db: DatabaseConnection, webhooks: WebhookService) -> Self {
+        Self { db, webhooks }
     }
 
     pub async fn get_tournaments(&self) -> Result<Vec<tournament::Model>, AppError> {
@@ -21,6 +24,19 @@ impl TournamentService {
         Ok(Tournament::find_by_id(id).one(&self.db).await?)
     }
 
+    /// The tournament, if the organization submitted it.
+    pub async fn get_organized_by(
+        &self,
+        organization_id: Uuid,
+        id: Uuid,
+    ) -> Result<tournament::Model, AppError> {
+        Tournament::find_by_id(id)
+            .filter(tournament::Column::SubmittedBy.eq(organization_id))
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::NotFound)
+    }
+
     pub async fn get_by_status(&self, status: String) -> Result<Vec<tournament::Model>, AppError> {
         Ok(Tournament::find()
             .filter(tournament::Column::Status.eq(status))
@@ -59,4 +75,34 @@ impl TournamentService {
 
         Ok(new_tournament.insert(&self.db).await?)
     }
+
+    /// Replaces the tournament's standings table.
+    pub async fn update_standings(
+        &self,
+        id: Uuid,
+        final_standings: serde_json::Value,
+    ) -> Result<tournament::Model, AppError> {
+        let tournament = Tournament::find_by_id(id)
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::NotFound)?;
+
+        let mut tournament: tournament::ActiveModel = tournament.into();
+        tournament.final_standings = Set(final_standings);
+        tournament.updated_at = Set(chrono::Utc::now());
+        let tournament = tournament.update(&self.db).await?;
+
+        self.webhooks
+            .publish_for_tournament(
+                tournament.id,
+                WebhookEvent::StandingsUpdated,
+                serde_json::json!({
+                    "tournament_id": tournament.id,
+                    "standings": tournament.final_standings,
+                }),
+            )
+            .await;
+
+        Ok(tournament)
+    }
 }
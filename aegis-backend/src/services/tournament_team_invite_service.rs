@@ -1,5 +1,7 @@
+use crate::models::dynamodb::NotificationKind;
 use crate::models::enums::InviteStatus;
-use crate::models::postgres::{tournament_team_invite, TournamentTeamInvite};
+use crate::models::postgres::{tournament_team_invite, Team, Tournament, TournamentTeamInvite};
+use crate::services::notification_service::{NewNotification, NotificationService};
 use crate::utils::errors::AppError;
 use sea_orm::{sea_query::Expr, *};
 use uuid::Uuid;
@@ -7,11 +9,12 @@ use uuid::Uuid;
 #[derive(Clone)]
 pub struct TournamentTeamInviteService {
     db: DatabaseConnection,
+    notifications: NotificationService,
 }
 
 impl TournamentTeamInviteService {
-    pub fn new(db: DatabaseConnection) -> Self {
-        Self { db }
+    pub fn new(db: DatabaseConnection, notifications: NotificationService) -> Self {
+        Self { db, notifications }
     }
 
     pub async fn create_invite(
@@ -38,7 +41,10 @@ impl TournamentTeamInviteService {
             updated_at: Set(chrono::Utc::now()),
         };
 
-        Ok(new_invite.insert(&self.db).await?)
+        let invite = new_invite.insert(&self.db).await?;
+        self.notify_captain(&invite).await;
+
+        Ok(invite)
     }
 
     pub async fn get_team_invites(
@@ -67,4 +73,51 @@ impl TournamentTeamInviteService {
             .await?;
         Ok(())
     }
+
+    // Invites are addressed to the team; its captain is the one who can accept
+    async fn notify_captain(&self, invite: &tournament_team_invite::Model) {
+        let lookup = tokio::try_join!(
+            Team::find_by_id(invite.team).one(&self.db),
+            Tournament::find_by_id(invite.tournament).one(&self.db),
+        );
+        let (team, tournament) = match lookup {
+            Ok((Some(team), Some(tournament))) => (team, tournament),
+            Ok(_) => return,
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to load invite {} for notification: {}",
+                    invite.id,
+                    e
+                );
+                return;
+            }
+        };
+        let Some(captain) = team.captain else {
+            return;
+        };
+
+        let notification = NewNotification::new(
+            NotificationKind::TeamInvite,
+            format!("{} invited {}", tournament.tournament_name, team.team_name),
+            invite.message.clone().unwrap_or_else(|| {
+                format!(
+                    "Your team has been invited to {}.",
+                    tournament.tournament_name
+                )
+            }),
+        )
+        .with_link(format!("/tournaments/{}", tournament.id))
+        .with_metadata(serde_json::json!({
+            "invite_id": invite.id,
+            "tournament_id": tournament.id,
+            "team_id": team.id,
+        }));
+        if let Err(e) = self
+            .notifications
+            .notify(&captain.to_string(), notification)
+            .await
+        {
+            tracing::warn!("Failed to notify captain of invite {}: {}", invite.id, e);
+        }
+    }
 }
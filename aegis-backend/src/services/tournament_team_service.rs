@@ -1,4 +1,8 @@
-use crate::models::postgres::{tournament_team, TournamentTeam};
+use crate::models::dynamodb::ActivityType;
+use crate::models::postgres::webhook_endpoint::WebhookEvent;
+use crate::models::postgres::{player, tournament_team, Player, Tournament, TournamentTeam};
+use crate::services::team_service::roster_for_activity;
+use crate::services::{ActivityService, WebhookService};
 use crate::utils::errors::AppError;
 use sea_orm::*;
 use uuid::Uuid;
@@ -6,11 +10,21 @@ use uuid::Uuid;
 #[derive(Clone)]
 pub struct TournamentTeamService {
     db: DatabaseConnection,
+    activity: ActivityService,
+    webhooks: WebhookService,
 }
 
 impl TournamentTeamService {
-    pub fn new(db: DatabaseConnection) -> Self {
-        Self { db }
+    pub fn new(
+        db: DatabaseConnection,
+        activity: ActivityService,
+        webhooks: WebhookService,
+    ) -> Self {
+        Self {
+            db,
+            activity,
+            webhooks,
+        }
     }
 
     pub async fn join_tournament(
@@ -19,6 +33,26 @@ impl TournamentTeamService {
         team_id: Uuid,
         qualified_through: Option<String>,
     ) -> Result<tournament_team::Model, AppError> {
+        let tournament = Tournament::find_by_id(tournament_id)
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::NotFound)?;
+
+        if tournament.requires_identity_verification() {
+            let unverified = Player::find()
+                .filter(player::Column::TeamId.eq(team_id))
+                .filter(player::Column::IdentityVerified.eq(false))
+                .count(&self.db)
+                .await?;
+
+            if unverified > 0 {
+                return Err(AppError::Validation(
+                    "All roster players must complete identity verification for this tournament"
+                        .to_string(),
+                ));
+            }
+        }
+
         let new_entry = tournament_team::ActiveModel {
             id: Set(Uuid::new_v4()),
             tournament_id: Set(tournament_id),
@@ -28,7 +62,32 @@ impl TournamentTeamService {
             ..Default::default()
         };
 
-        Ok(new_entry.insert(&self.db).await?)
+        let entry = new_entry.insert(&self.db).await?;
+
+        if let Some(roster) = roster_for_activity(&self.db, team_id).await {
+            self.activity
+                .record_for_all(
+                    &roster,
+                    ActivityType::TournamentRegistered,
+                    &format!("Registered for {}", tournament.tournament_name),
+                    serde_json::json!({ "tournament_id": tournament_id, "team_id": team_id }),
+                )
+                .await;
+        }
+        self.webhooks
+            .publish_for_tournament(
+                tournament_id,
+                WebhookEvent::TournamentRegistration,
+                serde_json::json!({
+                    "tournament_id": tournament_id,
+                    "team_id": team_id,
+                    "qualified_through": entry.qualified_through,
+                    "joined_at": entry.joined_at,
+                }),
+            )
+            .await;
+
+        Ok(entry)
     }
 
     pub async fn get_tournament_teams(
@@ -1,5 +1,6 @@
-use crate::models::postgres::{transaction, Transaction};
+use crate::models::postgres::{transaction, Player, Transaction};
 use crate::utils::errors::AppError;
+use rust_decimal::Decimal;
 use sea_orm::*;
 use uuid::Uuid;
 
@@ -27,4 +28,64 @@ impl TransactionService {
     pub async fn get_by_id(&self, id: Uuid) -> Result<Option<transaction::Model>, AppError> {
         Ok(Transaction::find_by_id(id).one(&self.db).await?)
     }
+
+    pub async fn request_withdrawal(
+        &self,
+        player_id: Uuid,
+        amount: Decimal,
+        currency: String,
+    ) -> Result<transaction::Model, AppError> {
+        if amount <= Decimal::ZERO {
+            return Err(AppError::Validation(
+                "Withdrawal amount must be positive".to_string(),
+            ));
+        }
+
+        // The player row stays locked until the withdrawal is recorded, so
+        // concurrent requests see each other's pending amounts
+        let txn = self.db.begin().await?;
+        let player = Player::find_by_id(player_id)
+            .lock_exclusive()
+            .one(&txn)
+            .await?
+            .ok_or(AppError::NotFound)?;
+
+        // Prize payouts are only released to players who passed KYC review
+        if !player.identity_verified {
+            return Err(AppError::Validation(
+                "Identity verification is required before withdrawing prizes".to_string(),
+            ));
+        }
+
+        let pending: Option<Decimal> = Transaction::find()
+            .select_only()
+            .column_as(transaction::Column::Amount.sum(), "pending")
+            .filter(transaction::Column::PlayerId.eq(player_id))
+            .filter(transaction::Column::TransactionType.eq("withdrawal"))
+            .filter(transaction::Column::Status.eq("pending"))
+            .into_tuple()
+            .one(&txn)
+            .await?
+            .flatten();
+        if amount > player.earnings - pending.unwrap_or_default() {
+            return Err(AppError::Validation("Insufficient earnings".to_string()));
+        }
+
+        let withdrawal = transaction::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            player_id: Set(player_id),
+            tournament_id: Set(None),
+            transaction_type: Set("withdrawal".to_string()),
+            amount: Set(amount),
+            currency: Set(currency),
+            status: Set("pending".to_string()),
+            description: Set(Some("Prize withdrawal request".to_string())),
+            created_at: Set(chrono::Utc::now()),
+            processed_at: Set(None),
+        };
+
+        let withdrawal = withdrawal.insert(&txn).await?;
+        txn.commit().await?;
+        Ok(withdrawal)
+    }
 }
@@ -0,0 +1,449 @@
+use crate::models::postgres::{
+    two_factor_credential, two_factor_policy, TwoFactorCredential, TwoFactorPolicy,
+};
+use crate::utils::errors::AppError;
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sea_orm::*;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Roles a sign-in policy can be set for: the admin roles, `organization`
+/// and `player`.
+pub const TWO_FACTOR_ROLES: &[&str] = &[
+    "super_admin",
+    "admin",
+    "moderator",
+    "organization",
+    "player",
+];
+pub const TOTP_ISSUER: &str = "Aegis";
+pub const TOTP_STEP_SECS: i64 = 30;
+pub const TOTP_DIGITS: u32 = 6;
+/// Codes from one step either side of now are accepted, for clock drift.
+const TOTP_DRIFT_STEPS: i64 = 1;
+const SECRET_BYTES: usize = 20;
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// RFC 4648 base32 without padding, as authenticator apps expect secrets.
+pub fn base32_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for byte in bytes {
+        buffer = (buffer << 8) | u32::from(*byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(BASE32_ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        encoded.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    encoded
+}
+
+/// The RFC 6238 code (HMAC-SHA1, six digits) for a 30-second time step.
+pub fn totp(secret: &[u8], step: i64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(&step.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    format!(
+        "{:0width$}",
+        binary % 10u32.pow(TOTP_DIGITS),
+        width = TOTP_DIGITS as usize
+    )
+}
+
+/// The time step `code` is valid for at `now`, if any. Steps at or before
+/// `last_used_step` are refused so an intercepted code can't be replayed.
+pub fn matching_step(
+    secret: &[u8],
+    code: &str,
+    now: DateTime<Utc>,
+    last_used_step: Option<i64>,
+) -> Option<i64> {
+    let code: String = code.chars().filter(|c| !c.is_whitespace()).collect();
+    if code.len() != TOTP_DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let current = now.timestamp().div_euclid(TOTP_STEP_SECS);
+    (current - TOTP_DRIFT_STEPS..=current + TOTP_DRIFT_STEPS)
+        .filter(|step| last_used_step.is_none_or(|last| *step > last))
+        .find(|step| totp(secret, *step) == code)
+}
+
+/// The `otpauth://` URI authenticator apps read from the enrolment QR code.
+pub fn provisioning_uri(account: &str, secret: &[u8]) -> String {
+    format!(
+        "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={digits}&period={period}",
+        issuer = TOTP_ISSUER,
+        account = uri_encode(account),
+        secret = base32_encode(secret),
+        digits = TOTP_DIGITS,
+        period = TOTP_STEP_SECS,
+    )
+}
+
+fn uri_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'@' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Recovery codes are matched case-insensitively, ignoring dashes and spaces.
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    hex::encode(Sha256::digest(normalized.as_bytes()))
+}
+
+fn random_bytes(len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; len];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let code = base32_encode(&random_bytes(7)).to_lowercase();
+            format!("{}-{}", &code[..5], &code[5..10])
+        })
+        .collect()
+}
+
+fn looks_like_totp(code: &str) -> bool {
+    code.chars()
+        .filter(|c| !c.is_whitespace())
+        .all(|c| c.is_ascii_digit())
+}
+
+/// What a sign-in was completed with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TwoFactorMethod {
+    Totp,
+    RecoveryCode,
+}
+
+impl TwoFactorMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TwoFactorMethod::Totp => "totp",
+            TwoFactorMethod::RecoveryCode => "recovery_code",
+        }
+    }
+}
+
+/// What an authenticator app needs to start producing codes.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct Enrolment {
+    pub secret: String,
+    pub provisioning_uri: String,
+}
+
+#[derive(Clone)]
+pub struct TwoFactorService {
+    db: DatabaseConnection,
+}
+
+impl TwoFactorService {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+
+    pub async fn get(
+        &self,
+        user_id: Uuid,
+        user_type: &str,
+    ) -> Result<Option<two_factor_credential::Model>, AppError> {
+        Ok(TwoFactorCredential::find()
+            .filter(two_factor_credential::Column::UserId.eq(user_id))
+            .filter(two_factor_credential::Column::UserType.eq(user_type))
+            .one(&self.db)
+            .await?)
+    }
+
+    pub async fn is_enabled(&self, user_id: Uuid, user_type: &str) -> Result<bool, AppError> {
+        Ok(self
+            .get(user_id, user_type)
+            .await?
+            .is_some_and(|credential| credential.is_enabled()))
+    }
+
+    /// Starts (or restarts) enrolment with a fresh secret. The credential
+    /// stays pending until `confirm_enrolment` sees a code from it.
+    pub async fn begin_enrolment(
+        &self,
+        user_id: Uuid,
+        user_type: &str,
+        account: &str,
+    ) -> Result<Enrolment, AppError> {
+        let secret = random_bytes(SECRET_BYTES);
+        let now = Utc::now();
+
+        match self.get(user_id, user_type).await? {
+            Some(credential) if credential.is_enabled() => {
+                return Err(AppError::Validation(
+                    "Two-factor authentication is already enabled".to_string(),
+                ));
+            }
+            Some(credential) => {
+                let mut pending: two_factor_credential::ActiveModel = credential.into();
+                pending.secret = Set(hex::encode(&secret));
+                pending.updated_at = Set(now);
+                pending.update(&self.db).await?;
+            }
+            None => {
+                two_factor_credential::ActiveModel {
+                    id: Set(Uuid::new_v4()),
+                    user_id: Set(user_id),
+                    user_type: Set(user_type.to_string()),
+                    secret: Set(hex::encode(&secret)),
+                    recovery_code_hashes: Set(Vec::new()),
+                    last_used_step: Set(None),
+                    enabled_at: Set(None),
+                    created_at: Set(now),
+                    updated_at: Set(now),
+                }
+                .insert(&self.db)
+                .await?;
+            }
+        }
+
+        Ok(Enrolment {
+            secret: base32_encode(&secret),
+            provisioning_uri: provisioning_uri(account, &secret),
+        })
+    }
+
+    /// Enables a pending enrolment once `code` checks out, returning the
+    /// recovery codes. They are only stored hashed, so this is the one time
+    /// they can be shown.
+    pub async fn confirm_enrolment(
+        &self,
+        user_id: Uuid,
+        user_type: &str,
+        code: &str,
+    ) -> Result<Vec<String>, AppError> {
+        let credential = self
+            .get(user_id, user_type)
+            .await?
+            .filter(|credential| !credential.is_enabled())
+            .ok_or_else(|| {
+                AppError::Validation("No two-factor enrolment is pending".to_string())
+            })?;
+        let step = matching_step(
+            &secret_bytes(&credential).ok_or(AppError::InternalServerError)?,
+            code,
+            Utc::now(),
+            None,
+        )
+        .ok_or_else(|| AppError::Validation("Invalid two-factor code".to_string()))?;
+
+        let codes = generate_recovery_codes();
+        let now = Utc::now();
+        let mut enabled: two_factor_credential::ActiveModel = credential.into();
+        enabled.recovery_code_hashes = Set(codes.iter().map(|c| hash_recovery_code(c)).collect());
+        enabled.last_used_step = Set(Some(step));
+        enabled.enabled_at = Set(Some(now));
+        enabled.updated_at = Set(now);
+        enabled.update(&self.db).await?;
+
+        Ok(codes)
+    }
+
+    /// Checks a TOTP code or an unused recovery code against an enabled
+    /// credential. Each is accepted once: the code's time step is recorded
+    /// and the recovery code is struck off.
+    pub async fn verify(
+        &self,
+        user_id: Uuid,
+        user_type: &str,
+        code: &str,
+    ) -> Result<Option<TwoFactorMethod>, AppError> {
+        let Some(credential) = self
+            .get(user_id, user_type)
+            .await?
+            .filter(|credential| credential.is_enabled())
+        else {
+            return Ok(None);
+        };
+
+        if looks_like_totp(code) {
+            let Some(step) = matching_step(
+                &secret_bytes(&credential).ok_or(AppError::InternalServerError)?,
+                code,
+                Utc::now(),
+                credential.last_used_step,
+            ) else {
+                return Ok(None);
+            };
+            // Conditional on the stored step, so concurrent requests can't
+            // both spend the same code
+            let result = self
+                .db
+                .execute(Statement::from_sql_and_values(
+                    DatabaseBackend::Postgres,
+                    "UPDATE two_factor_credentials SET last_used_step = $1, updated_at = NOW() \
+                     WHERE id = $2 AND (last_used_step IS NULL OR last_used_step < $1)",
+                    [step.into(), credential.id.into()],
+                ))
+                .await?;
+            return Ok((result.rows_affected() == 1).then_some(TwoFactorMethod::Totp));
+        }
+
+        let hash = hash_recovery_code(code);
+        let result = self
+            .db
+            .execute(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                "UPDATE two_factor_credentials \
+                 SET recovery_code_hashes = array_remove(recovery_code_hashes, $1), updated_at = NOW() \
+                 WHERE id = $2 AND $1 = ANY(recovery_code_hashes)",
+                [hash.into(), credential.id.into()],
+            ))
+            .await?;
+        Ok((result.rows_affected() == 1).then_some(TwoFactorMethod::RecoveryCode))
+    }
+
+    /// Replaces every recovery code, once `code` proves the caller holds
+    /// the second factor.
+    pub async fn regenerate_recovery_codes(
+        &self,
+        user_id: Uuid,
+        user_type: &str,
+        code: &str,
+    ) -> Result<Vec<String>, AppError> {
+        if self.verify(user_id, user_type, code).await?.is_none() {
+            return Err(AppError::Validation("Invalid two-factor code".to_string()));
+        }
+
+        let codes = generate_recovery_codes();
+        let hashes: Vec<String> = codes.iter().map(|c| hash_recovery_code(c)).collect();
+        TwoFactorCredential::update_many()
+            .col_expr(
+                two_factor_credential::Column::RecoveryCodeHashes,
+                sea_query::Expr::value(hashes),
+            )
+            .col_expr(
+                two_factor_credential::Column::UpdatedAt,
+                sea_query::Expr::value(Utc::now()),
+            )
+            .filter(two_factor_credential::Column::UserId.eq(user_id))
+            .filter(two_factor_credential::Column::UserType.eq(user_type))
+            .exec(&self.db)
+            .await?;
+
+        Ok(codes)
+    }
+
+    pub async fn disable(
+        &self,
+        user_id: Uuid,
+        user_type: &str,
+        code: &str,
+    ) -> Result<(), AppError> {
+        if self.verify(user_id, user_type, code).await?.is_none() {
+            return Err(AppError::Validation("Invalid two-factor code".to_string()));
+        }
+
+        TwoFactorCredential::delete_many()
+            .filter(two_factor_credential::Column::UserId.eq(user_id))
+            .filter(two_factor_credential::Column::UserType.eq(user_type))
+            .exec(&self.db)
+            .await?;
+        Ok(())
+    }
+
+    /// Whether accounts with `role` must complete two-factor sign-in.
+    pub async fn is_required(&self, role: &str) -> Result<bool, AppError> {
+        Ok(TwoFactorPolicy::find_by_id(role)
+            .one(&self.db)
+            .await?
+            .is_some_and(|policy| policy.required))
+    }
+
+    /// Every role's policy, including roles that have never been set.
+    pub async fn list_policies(&self) -> Result<Vec<two_factor_policy::Model>, AppError> {
+        let stored = TwoFactorPolicy::find().all(&self.db).await?;
+
+        Ok(TWO_FACTOR_ROLES
+            .iter()
+            .map(|role| {
+                stored
+                    .iter()
+                    .find(|policy| policy.role == *role)
+                    .cloned()
+                    .unwrap_or_else(|| two_factor_policy::Model {
+                        role: role.to_string(),
+                        required: false,
+                        updated_by: None,
+                        updated_at: DateTime::<Utc>::UNIX_EPOCH,
+                    })
+            })
+            .collect())
+    }
+
+    pub async fn set_required(
+        &self,
+        role: &str,
+        required: bool,
+        admin_id: Uuid,
+    ) -> Result<two_factor_policy::Model, AppError> {
+        if !TWO_FACTOR_ROLES.contains(&role) {
+            return Err(AppError::Validation(format!("Unknown role: {}", role)));
+        }
+
+        let policy = two_factor_policy::ActiveModel {
+            role: Set(role.to_string()),
+            required: Set(required),
+            updated_by: Set(Some(admin_id)),
+            updated_at: Set(Utc::now()),
+        };
+        TwoFactorPolicy::insert(policy)
+            .on_conflict(
+                sea_query::OnConflict::column(two_factor_policy::Column::Role)
+                    .update_columns([
+                        two_factor_policy::Column::Required,
+                        two_factor_policy::Column::UpdatedBy,
+                        two_factor_policy::Column::UpdatedAt,
+                    ])
+                    .to_owned(),
+            )
+            .exec(&self.db)
+            .await?;
+
+        TwoFactorPolicy::find_by_id(role)
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::InternalServerError)
+    }
+}
+
+fn secret_bytes(credential: &two_factor_credential::Model) -> Option<Vec<u8>> {
+    hex::decode(&credential.secret).ok()
+}
@@ -0,0 +1,548 @@
+use crate::models::postgres::webhook_delivery::{self, DELIVERED, FAILED, PENDING};
+use crate::models::postgres::webhook_endpoint::{self, WebhookEvent};
+use crate::models::postgres::{Tournament, WebhookDelivery, WebhookEndpoint};
+use crate::services::job_queue::{self, Backoff, QueueColumns};
+use crate::utils::errors::AppError;
+use anyhow::Context;
+use chrono::{Duration, Utc};
+use hmac::{Hmac, Mac};
+use sea_orm::*;
+use serde_json::Value;
+use sha2::Sha256;
+use std::net::{IpAddr, SocketAddr};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+pub const SIGNATURE_HEADER: &str = "X-Aegis-Signature";
+pub const EVENT_HEADER: &str = "X-Aegis-Event";
+pub const DELIVERY_HEADER: &str = "X-Aegis-Delivery";
+
+/// Attempts before a delivery is marked failed.
+pub const MAX_ATTEMPTS: i32 = 10;
+const RETRY_BACKOFF: Backoff = Backoff {
+    base_secs: 60,
+    max_secs: 6 * 60 * 60,
+};
+const BATCH_SIZE: u64 = 20;
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+const MAX_ENDPOINTS_PER_ORGANIZATION: u64 = 10;
+const MAX_ERROR_LEN: usize = 500;
+
+/// `t=<unix seconds>,v1=<hex HMAC-SHA256 of "<t>.<body>">`. Receivers
+/// recompute the HMAC with their endpoint secret and should reject stale
+/// timestamps to prevent replays.
+pub fn signature(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!(
+        "t={},v1={}",
+        timestamp,
+        hex::encode(mac.finalize().into_bytes())
+    )
+}
+
+/// Wait before retrying a delivery that has failed `attempts` times: a
+/// minute, doubling per attempt, capped at six hours.
+pub fn retry_delay(attempts: i32) -> Duration {
+    RETRY_BACKOFF.delay(attempts)
+}
+
+const COLUMNS: QueueColumns<webhook_delivery::Column> = QueueColumns {
+    id: webhook_delivery::Column::Id,
+    status: webhook_delivery::Column::Status,
+    attempts: webhook_delivery::Column::Attempts,
+    next_attempt_at: webhook_delivery::Column::NextAttemptAt,
+    updated_at: webhook_delivery::Column::UpdatedAt,
+};
+
+/// Whether webhooks may be sent to `ip`. Organizations choose the URL, so
+/// anything that reaches our own network is refused: loopback, private,
+/// shared and link-local space (which holds the cloud metadata services),
+/// plus addresses that are never valid destinations.
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0 // "This network"
+                || (a == 100 && (64..128).contains(&b)) // Carrier-grade NAT
+                || (a == 192 && b == 0 && c == 0) // IETF protocol assignments
+                || (a == 198 && (18..20).contains(&b)) // Benchmarking
+                || a >= 240) // Reserved
+        }
+        IpAddr::V6(ip) => {
+            if let Some(mapped) = ip.to_ipv4_mapped() {
+                return is_public_address(IpAddr::V4(mapped));
+            }
+            let [first, second, ..] = ip.segments();
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || (first & 0xfe00) == 0xfc00 // Unique local
+                || (first & 0xffc0) == 0xfe80 // Link-local
+                || (first & 0xffc0) == 0xfec0 // Site-local
+                || (first == 0x2001 && second == 0x0db8) // Documentation
+                || (first == 0x0064 && second == 0xff9b) // NAT64, embeds IPv4
+                || first == 0x2002 // 6to4, embeds IPv4
+                || ip.segments()[..6] == [0; 6]) // IPv4-compatible
+        }
+    }
+}
+
+fn http_client() -> reqwest::ClientBuilder {
+    reqwest::Client::builder()
+        .timeout(std::time::Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .redirect(reqwest::redirect::Policy::none())
+}
+
+#[derive(Clone)]
+pub struct WebhookService {
+    db: DatabaseConnection,
+    http: reqwest::Client,
+    allow_local_destinations: bool,
+}
+
+impl WebhookService {
+    pub fn new(db: DatabaseConnection) -> Self {
+        let http = http_client()
+            .build()
+            .expect("Failed to build webhook HTTP client");
+        Self {
+            db,
+            http,
+            allow_local_destinations: false,
+        }
+    }
+
+    /// Also sends to http and non-public addresses; for tests and local
+    /// receivers only.
+    pub fn allow_local_destinations(mut self) -> Self {
+        self.allow_local_destinations = true;
+        self
+    }
+
+    /// Resolves `url` to the addresses a delivery may connect to. Checked
+    /// when an endpoint is created and again before every request, since
+    /// DNS can change in between.
+    async fn resolve_destination(&self, url: &reqwest::Url) -> Result<Vec<SocketAddr>, String> {
+        match url.scheme() {
+            "https" => {}
+            "http" if self.allow_local_destinations => {}
+            _ => return Err("Webhook URL must use https".to_string()),
+        }
+        let host = url
+            .host_str()
+            .ok_or_else(|| "Webhook URL must have a host".to_string())?;
+        let port = url.port_or_known_default().unwrap_or(443);
+
+        let literal = host.trim_start_matches('[').trim_end_matches(']');
+        let addrs: Vec<SocketAddr> = match literal.parse::<IpAddr>() {
+            Ok(ip) => vec![SocketAddr::new(ip, port)],
+            Err(_) => tokio::net::lookup_host((host, port))
+                .await
+                .map_err(|_| format!("Could not resolve {}", host))?
+                .collect(),
+        };
+        if addrs.is_empty() {
+            return Err(format!("Could not resolve {}", host));
+        }
+        if !self.allow_local_destinations && !addrs.iter().all(|a| is_public_address(a.ip())) {
+            return Err("Webhook URL must resolve to a public address".to_string());
+        }
+        Ok(addrs)
+    }
+
+    /// Registers an endpoint and returns it with its signing secret, which is
+    /// not shown again.
+    pub async fn create_endpoint(
+        &self,
+        organization_id: Uuid,
+        url: String,
+        events: Vec<WebhookEvent>,
+    ) -> Result<(webhook_endpoint::Model, String), AppError> {
+        let parsed = reqwest::Url::parse(&url)
+            .map_err(|_| AppError::Validation("Invalid webhook URL".to_string()))?;
+        self.resolve_destination(&parsed)
+            .await
+            .map_err(AppError::Validation)?;
+        let mut events: Vec<String> = events
+            .iter()
+            .filter(|e| **e != WebhookEvent::Test)
+            .map(|e| e.as_str().to_string())
+            .collect();
+        events.sort();
+        events.dedup();
+        if events.is_empty() {
+            return Err(AppError::Validation(
+                "Subscribe to at least one event".to_string(),
+            ));
+        }
+
+        let existing = WebhookEndpoint::find()
+            .filter(webhook_endpoint::Column::OrganizationId.eq(organization_id))
+            .count(&self.db)
+            .await?;
+        if existing >= MAX_ENDPOINTS_PER_ORGANIZATION {
+            return Err(AppError::Validation(format!(
+                "At most {} webhook endpoints per organization",
+                MAX_ENDPOINTS_PER_ORGANIZATION
+            )));
+        }
+
+        let secret = format!(
+            "whsec_{}{}",
+            Uuid::new_v4().simple(),
+            Uuid::new_v4().simple()
+        );
+        let now = Utc::now();
+        let endpoint = webhook_endpoint::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            organization_id: Set(organization_id),
+            url: Set(url),
+            secret: Set(secret.clone()),
+            events: Set(events),
+            is_active: Set(true),
+            created_at: Set(now),
+            updated_at: Set(now),
+        };
+
+        Ok((endpoint.insert(&self.db).await?, secret))
+    }
+
+    pub async fn list_endpoints(
+        &self,
+        organization_id: Uuid,
+    ) -> Result<Vec<webhook_endpoint::Model>, AppError> {
+        Ok(WebhookEndpoint::find()
+            .filter(webhook_endpoint::Column::OrganizationId.eq(organization_id))
+            .order_by_asc(webhook_endpoint::Column::CreatedAt)
+            .all(&self.db)
+            .await?)
+    }
+
+    /// The endpoint, if it belongs to the organization.
+    pub async fn get_endpoint(
+        &self,
+        organization_id: Uuid,
+        endpoint_id: Uuid,
+    ) -> Result<webhook_endpoint::Model, AppError> {
+        WebhookEndpoint::find_by_id(endpoint_id)
+            .filter(webhook_endpoint::Column::OrganizationId.eq(organization_id))
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::NotFound)
+    }
+
+    pub async fn delete_endpoint(
+        &self,
+        organization_id: Uuid,
+        endpoint_id: Uuid,
+    ) -> Result<(), AppError> {
+        let result = WebhookEndpoint::delete_many()
+            .filter(webhook_endpoint::Column::Id.eq(endpoint_id))
+            .filter(webhook_endpoint::Column::OrganizationId.eq(organization_id))
+            .exec(&self.db)
+            .await?;
+        if result.rows_affected == 0 {
+            return Err(AppError::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Delivery log of an endpoint, newest first.
+    pub async fn list_deliveries(
+        &self,
+        organization_id: Uuid,
+        endpoint_id: Uuid,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Vec<webhook_delivery::Model>, AppError> {
+        let endpoint = self.get_endpoint(organization_id, endpoint_id).await?;
+        Ok(WebhookDelivery::find()
+            .filter(webhook_delivery::Column::EndpointId.eq(endpoint.id))
+            .order_by_desc(webhook_delivery::Column::CreatedAt)
+            .limit(limit)
+            .offset(offset)
+            .all(&self.db)
+            .await?)
+    }
+
+    /// Queues `event` for every active endpoint of the organization that
+    /// subscribes to it; the worker delivers them.
+    pub async fn publish(
+        &self,
+        organization_id: Uuid,
+        event: WebhookEvent,
+        data: Value,
+    ) -> Result<(), AppError> {
+        let endpoints = WebhookEndpoint::find()
+            .filter(webhook_endpoint::Column::OrganizationId.eq(organization_id))
+            .filter(webhook_endpoint::Column::IsActive.eq(true))
+            .all(&self.db)
+            .await?;
+
+        let deliveries: Vec<_> = endpoints
+            .iter()
+            .filter(|endpoint| endpoint.subscribes_to(event))
+            .map(|endpoint| new_delivery(endpoint.id, event, data.clone()))
+            .collect();
+        if !deliveries.is_empty() {
+            WebhookDelivery::insert_many(deliveries)
+                .exec(&self.db)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Publishes to the organization that submitted the tournament, if any.
+    /// Best effort: the change has already been made, so failures are logged.
+    pub async fn publish_for_tournament(
+        &self,
+        tournament_id: Uuid,
+        event: WebhookEvent,
+        data: Value,
+    ) {
+        let organizer = match Tournament::find_by_id(tournament_id).one(&self.db).await {
+            Ok(tournament) => tournament.and_then(|t| t.submitted_by),
+            Err(e) => {
+                tracing::warn!("Failed to look up organizer of {}: {}", tournament_id, e);
+                return;
+            }
+        };
+        let Some(organization_id) = organizer else {
+            return;
+        };
+        if let Err(e) = self.publish(organization_id, event, data).await {
+            tracing::warn!(
+                "Failed to queue {} webhooks for {}: {}",
+                event.as_str(),
+                organization_id,
+                e
+            );
+        }
+    }
+
+    /// Sends a `webhook.test` event to the endpoint right away and returns
+    /// the logged delivery; failures are retried like any other delivery.
+    pub async fn send_test_event(
+        &self,
+        organization_id: Uuid,
+        endpoint_id: Uuid,
+    ) -> Result<webhook_delivery::Model, AppError> {
+        let endpoint = self.get_endpoint(organization_id, endpoint_id).await?;
+        let data = serde_json::json!({
+            "message": "Test event from Aegis",
+            "endpoint_id": endpoint.id,
+        });
+        let delivery = new_delivery(endpoint.id, WebhookEvent::Test, data)
+            .insert(&self.db)
+            .await?;
+
+        if self.claim(&delivery).await? {
+            self.attempt(&endpoint, &delivery).await?;
+        }
+        WebhookDelivery::find_by_id(delivery.id)
+            .one(&self.db)
+            .await?
+            .ok_or(AppError::NotFound)
+    }
+
+    /// POSTs a signed payload and returns the response status. Only
+    /// transport failures and refused destinations are errors; callers
+    /// decide what a status means.
+    pub async fn send_signed(
+        &self,
+        url: &str,
+        secret: &str,
+        event: &str,
+        delivery_id: Uuid,
+        payload: &Value,
+    ) -> anyhow::Result<u16> {
+        let url = reqwest::Url::parse(url).context("invalid URL")?;
+        let addrs = self
+            .resolve_destination(&url)
+            .await
+            .map_err(anyhow::Error::msg)?;
+        // Connect to the addresses just checked rather than resolving again
+        let http = match url.domain() {
+            Some(domain) if !self.allow_local_destinations => http_client()
+                .resolve_to_addrs(domain, &addrs)
+                .build()
+                .context("failed to build HTTP client")?,
+            _ => self.http.clone(),
+        };
+
+        let body = serde_json::to_vec(payload)?;
+        let response = http
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, event)
+            .header(DELIVERY_HEADER, delivery_id.to_string())
+            .header(
+                SIGNATURE_HEADER,
+                signature(secret, Utc::now().timestamp(), &body),
+            )
+            .body(body)
+            .send()
+            .await
+            .context("request failed")?;
+
+        Ok(response.status().as_u16())
+    }
+
+    /// Starts the background worker that sends queued deliveries.
+    pub fn spawn_worker(&self) -> JoinHandle<()> {
+        let service = self.clone();
+        job_queue::spawn_worker("Webhook", move || {
+            let service = service.clone();
+            async move { service.process_due(BATCH_SIZE).await }
+        })
+    }
+
+    /// Attempts up to `limit` due deliveries and returns how many succeeded.
+    pub async fn process_due(&self, limit: u64) -> Result<usize, AppError> {
+        let due = WebhookDelivery::find()
+            .find_also_related(WebhookEndpoint)
+            .filter(webhook_delivery::Column::Status.eq(PENDING))
+            .filter(webhook_delivery::Column::NextAttemptAt.lte(Utc::now()))
+            .order_by_asc(webhook_delivery::Column::NextAttemptAt)
+            .limit(limit)
+            .all(&self.db)
+            .await?;
+
+        let mut delivered = 0;
+        for (delivery, endpoint) in due {
+            // Endpoints cascade-delete their deliveries, so this is a race
+            let Some(endpoint) = endpoint else {
+                continue;
+            };
+            if self.claim(&delivery).await? && self.attempt(&endpoint, &delivery).await? {
+                delivered += 1;
+            }
+        }
+        Ok(delivered)
+    }
+
+    async fn claim(&self, delivery: &webhook_delivery::Model) -> Result<bool, AppError> {
+        job_queue::claim::<WebhookDelivery>(
+            &self.db,
+            &COLUMNS,
+            PENDING,
+            delivery.id,
+            delivery.next_attempt_at,
+        )
+        .await
+    }
+
+    async fn attempt(
+        &self,
+        endpoint: &webhook_endpoint::Model,
+        delivery: &webhook_delivery::Model,
+    ) -> Result<bool, AppError> {
+        let attempts = delivery.attempts + 1;
+        let (response_status, result) = if !endpoint.is_active {
+            (None, Err("endpoint is disabled".to_string()))
+        } else {
+            match self
+                .send_signed(
+                    &endpoint.url,
+                    &endpoint.secret,
+                    &delivery.event_type,
+                    delivery.id,
+                    &delivery.payload,
+                )
+                .await
+            {
+                Ok(status) if (200..300).contains(&status) => (Some(status), Ok(())),
+                Ok(status) => (
+                    Some(status),
+                    Err(format!("endpoint responded with {}", status)),
+                ),
+                Err(e) => (None, Err(format!("{:#}", e))),
+            }
+        };
+
+        let now = Utc::now();
+        let mut update = webhook_delivery::ActiveModel {
+            id: Set(delivery.id),
+            updated_at: Set(now),
+            ..Default::default()
+        };
+        update.response_status = Set(response_status.map(i32::from));
+        let delivered = match result {
+            Ok(()) => {
+                update.status = Set(DELIVERED.to_string());
+                update.delivered_at = Set(Some(now));
+                update.last_error = Set(None);
+                true
+            }
+            Err(mut error) => {
+                truncate_at_char_boundary(&mut error, MAX_ERROR_LEN);
+                if attempts >= MAX_ATTEMPTS {
+                    tracing::warn!(
+                        "Giving up on webhook delivery {} to {} after {} attempts: {}",
+                        delivery.id,
+                        endpoint.url,
+                        attempts,
+                        error
+                    );
+                    update.status = Set(FAILED.to_string());
+                } else {
+                    update.next_attempt_at = Set(now + retry_delay(attempts));
+                }
+                update.last_error = Set(Some(error));
+                false
+            }
+        };
+
+        update.update(&self.db).await?;
+        Ok(delivered)
+    }
+}
+
+fn new_delivery(
+    endpoint_id: Uuid,
+    event: WebhookEvent,
+    data: Value,
+) -> webhook_delivery::ActiveModel {
+    let id = Uuid::new_v4();
+    let now = Utc::now();
+    webhook_delivery::ActiveModel {
+        id: Set(id),
+        endpoint_id: Set(endpoint_id),
+        event_type: Set(event.as_str().to_string()),
+        payload: Set(serde_json::json!({
+            "id": id,
+            "event": event.as_str(),
+            "created_at": now,
+            "data": data,
+        })),
+        status: Set(PENDING.to_string()),
+        attempts: Set(0),
+        next_attempt_at: Set(now),
+        response_status: Set(None),
+        last_error: Set(None),
+        delivered_at: Set(None),
+        created_at: Set(now),
+        updated_at: Set(now),
+    }
+}
+
+fn truncate_at_char_boundary(text: &mut String, max_len: usize) {
+    if text.len() > max_len {
+        let end = (0..=max_len)
+            .rev()
+            .find(|&i| text.is_char_boundary(i))
+            .unwrap_or(0);
+        text.truncate(end);
+    }
+}
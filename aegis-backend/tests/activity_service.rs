@@ -0,0 +1,87 @@
+//! Activity log behaviour against the in-memory storage backend.
+
+use aegis_backend::models::dynamodb::ActivityType;
+use aegis_backend::repositories::{
+    ActivityRepository, DynamoRepository, PageRequest, ScanDirection,
+};
+use aegis_backend::services::ActivityService;
+use serde_json::json;
+
+fn activity_service() -> ActivityService {
+    ActivityService::new(ActivityRepository::new(DynamoRepository::in_memory()))
+}
+
+#[tokio::test]
+async fn timeline_lists_a_players_activity_newest_first() {
+    let activity = activity_service();
+    activity
+        .record(
+            "alice",
+            ActivityType::TeamJoined,
+            "Joined".into(),
+            json!({}),
+        )
+        .await
+        .unwrap();
+    activity
+        .record(
+            "alice",
+            ActivityType::TournamentRegistered,
+            "Registered".into(),
+            json!({}),
+        )
+        .await
+        .unwrap();
+    activity
+        .record("bob", ActivityType::TeamJoined, "Joined".into(), json!({}))
+        .await
+        .unwrap();
+
+    let request = PageRequest::new(None, ScanDirection::Backward);
+    let timeline = activity
+        .get_player_timeline("alice", &request)
+        .await
+        .unwrap();
+    let descriptions: Vec<_> = timeline
+        .items
+        .iter()
+        .map(|a| a.description.as_str())
+        .collect();
+    assert_eq!(descriptions, ["Registered", "Joined"]);
+}
+
+#[tokio::test]
+async fn recent_activity_is_grouped_by_type() {
+    let activity = activity_service();
+    let players = ["alice".to_string(), "bob".to_string()];
+    activity
+        .record_for_all(
+            &players,
+            ActivityType::TournamentRegistered,
+            "Registered",
+            json!({ "tournament_id": "t1" }),
+        )
+        .await;
+    activity
+        .record(
+            "carol",
+            ActivityType::TeamJoined,
+            "Joined".into(),
+            json!({}),
+        )
+        .await
+        .unwrap();
+
+    let request = PageRequest::new(None, ScanDirection::Backward);
+    let recent = activity
+        .get_recent(ActivityType::TournamentRegistered, &request)
+        .await
+        .unwrap();
+    let mut users: Vec<_> = recent.items.iter().map(|a| a.user_id.as_str()).collect();
+    users.sort();
+    assert_eq!(users, ["alice", "bob"]);
+    assert!(recent
+        .items
+        .iter()
+        .all(|a| a.activity_type == "tournament_registered"));
+}
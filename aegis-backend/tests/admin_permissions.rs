@@ -0,0 +1,143 @@
+//! Admin permissions: role defaults, per-admin overrides and the reserved
+//! `admins.manage` permission, and creating admins against Postgres.
+
+mod common;
+
+use aegis_backend::models::enums::AdminRole;
+use aegis_backend::models::postgres::admin::{self, role_permissions, AdminPermission};
+use aegis_backend::services::{AdminService, AuthService};
+use aegis_backend::utils::errors::AppError;
+use chrono::Utc;
+use common::TestDb;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use uuid::Uuid;
+
+fn admin(role: AdminRole, permissions: serde_json::Value) -> admin::Model {
+    admin::Model {
+        id: Uuid::new_v4(),
+        username: "ops".into(),
+        email: "ops@aegis.gg".into(),
+        password: "hash".into(),
+        role,
+        permissions,
+        is_active: true,
+        last_login: None,
+        login_attempts: 0,
+        lock_until: None,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+#[test]
+fn permissions_round_trip_through_their_names() {
+    for permission in AdminPermission::ALL {
+        assert_eq!(
+            AdminPermission::parse(permission.as_str()),
+            Some(*permission)
+        );
+        assert_eq!(
+            serde_json::to_value(permission).unwrap(),
+            json!(permission.as_str())
+        );
+    }
+    assert_eq!(AdminPermission::parse("orgs.delete"), None);
+}
+
+#[test]
+fn roles_grant_their_defaults() {
+    let moderator = admin(AdminRole::Moderator, json!({}));
+    assert_eq!(
+        moderator.effective_permissions(),
+        BTreeSet::from([
+            AdminPermission::UsersBan,
+            AdminPermission::IdentityReview,
+            AdminPermission::ContentModerate,
+        ])
+    );
+    assert!(!moderator.can(AdminPermission::PayoutsApprove));
+
+    let regular = admin(AdminRole::Admin, json!({}));
+    assert!(regular.can(AdminPermission::PayoutsApprove));
+    assert!(!regular.can(AdminPermission::AdminsManage));
+
+    assert_eq!(
+        role_permissions(&AdminRole::SuperAdmin),
+        AdminPermission::ALL
+    );
+}
+
+#[test]
+fn overrides_grant_and_revoke_on_top_of_the_role() {
+    let moderator = admin(
+        AdminRole::Moderator,
+        json!({
+            "audit.read": true,
+            "users.ban": false,
+            "orgs.delete": true,
+            "identity.review": "yes",
+        }),
+    );
+
+    // Unknown permissions and non-boolean values are ignored
+    assert_eq!(
+        moderator.effective_permissions(),
+        BTreeSet::from([
+            AdminPermission::AuditRead,
+            AdminPermission::IdentityReview,
+            AdminPermission::ContentModerate,
+        ])
+    );
+}
+
+#[test]
+fn admins_manage_is_reserved_for_super_admins() {
+    let regular = admin(AdminRole::Admin, json!({ "admins.manage": true }));
+    assert!(!regular.can(AdminPermission::AdminsManage));
+
+    // Overrides can't take anything away from a super admin either
+    let super_admin = admin(
+        AdminRole::SuperAdmin,
+        json!({ "admins.manage": false, "audit.read": false }),
+    );
+    assert_eq!(
+        super_admin.effective_permissions(),
+        AdminPermission::ALL.iter().copied().collect()
+    );
+}
+
+#[test]
+fn password_hashes_are_never_serialized() {
+    let value = serde_json::to_value(admin(AdminRole::Admin, json!({}))).unwrap();
+    assert!(value.get("password").is_none());
+    assert_eq!(value["email"], "ops@aegis.gg");
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn usernames_and_emails_are_unique_among_admins() {
+    let test_db = TestDb::new().await;
+    let db = test_db.connection();
+    let admins = AdminService::new(db, AuthService::new("secret".into(), 3600));
+    let create = |username: &str, email: &str| {
+        admins.create_admin(
+            username.to_string(),
+            email.to_string(),
+            "Correct-Horse-9!".to_string(),
+            AdminRole::Moderator,
+            BTreeMap::new(),
+        )
+    };
+
+    create("ops", "ops@aegis.gg").await.unwrap();
+    assert!(matches!(
+        create("ops", "other@aegis.gg").await,
+        Err(AppError::Validation(message)) if message.contains("username")
+    ));
+    assert!(matches!(
+        create("other", "ops@aegis.gg").await,
+        Err(AppError::Validation(message)) if message.contains("email")
+    ));
+    create("other", "other@aegis.gg").await.unwrap();
+}
@@ -0,0 +1,235 @@
+//! API key scopes: which routes a key may call and what it must be granted,
+//! and the key middleware in a router against Postgres.
+
+mod common;
+
+use aegis_backend::config::settings::{
+    ApiKeyConfig, DatabaseConfig, JwtConfig, RateLimitConfig, ServerConfig, StorageConfig,
+};
+use aegis_backend::config::{
+    AwsClients, EmailConfig, EmailTransportKind, RateLimitBackend, Settings, StorageBackend,
+};
+use aegis_backend::middleware::api_key::{required_scope, API_KEY_HEADER, API_KEY_ROUTES};
+use aegis_backend::middleware::{api_key_auth_middleware, RateLimitLayer};
+use aegis_backend::models::postgres::api_key;
+use aegis_backend::services::api_key_service::API_KEY_SCOPES;
+use aegis_backend::services::{ApiKeyService, AuthService};
+use aegis_backend::utils::errors::AppError;
+use aegis_backend::AppState;
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use axum::routing::get;
+use axum::{middleware, Router};
+use chrono::Utc;
+use common::TestDb;
+use sea_orm::DatabaseConnection;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+fn api_keys() -> ApiKeyService {
+    ApiKeyService::new(
+        sea_orm::DatabaseConnection::Disconnected,
+        AuthService::new("secret".into(), 3600),
+        "digest-secret".into(),
+    )
+}
+
+fn key_with_scopes(scopes: &[&str]) -> api_key::Model {
+    api_key::Model {
+        id: Uuid::new_v4(),
+        key_id: "ak_0123456789abcdef".into(),
+        key_hash: String::new(),
+        name: "Overlay".into(),
+        owner_id: Uuid::new_v4(),
+        owner_type: "organization".into(),
+        scopes: scopes.iter().map(|s| s.to_string()).collect(),
+        rate_limit_per_hour: 1000,
+        expires_at: None,
+        last_used_at: None,
+        is_active: true,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    }
+}
+
+#[test]
+fn routes_declare_scopes_per_method() {
+    assert_eq!(
+        required_scope(&Method::GET, "/organizations/me/webhooks"),
+        Some("webhooks:read")
+    );
+    assert_eq!(
+        required_scope(&Method::POST, "/organizations/me/webhooks"),
+        Some("webhooks:write")
+    );
+    // Key management and unlisted routes stay JWT-only
+    assert_eq!(
+        required_scope(&Method::POST, "/organizations/me/api-keys"),
+        None
+    );
+    assert_eq!(required_scope(&Method::GET, "/players/me"), None);
+
+    for (_, route, scope) in API_KEY_ROUTES {
+        assert!(
+            API_KEY_SCOPES.contains(scope),
+            "{} uses unknown scope",
+            route
+        );
+    }
+}
+
+#[tokio::test]
+async fn keys_need_the_scope_or_the_wildcard() {
+    let api_keys = api_keys();
+    let reader = key_with_scopes(&["webhooks:read"]);
+    assert!(api_keys.check_scope(&reader, "webhooks:read").await);
+    assert!(!api_keys.check_scope(&reader, "webhooks:write").await);
+
+    let admin = key_with_scopes(&["*"]);
+    assert!(api_keys.check_scope(&admin, "webhooks:write").await);
+}
+
+#[tokio::test]
+async fn unknown_scopes_are_rejected() {
+    let result = api_keys()
+        .create_api_key(
+            "Overlay".into(),
+            Uuid::new_v4(),
+            "organization".into(),
+            vec!["webhooks:read".into(), "players:delete".into()],
+            None,
+            None,
+        )
+        .await;
+
+    assert!(
+        matches!(result, Err(AppError::Validation(message)) if message.contains("players:delete"))
+    );
+}
+
+#[tokio::test]
+async fn malformed_keys_are_rejected_without_a_lookup() {
+    // The service has no database, so reaching a query would be an error
+    let api_keys = api_keys();
+    for key in ["", "ak_only", "sk_0123456789abcdef_secret", "ak_a_b_c"] {
+        assert!(api_keys.validate_api_key(key).await.unwrap().is_none());
+    }
+}
+
+fn settings() -> Settings {
+    Settings {
+        server: ServerConfig {
+            host: "127.0.0.1".into(),
+            port: 0,
+        },
+        database: DatabaseConfig {
+            url: String::new(),
+            max_connections: 1,
+        },
+        jwt: JwtConfig {
+            secret: "secret".into(),
+            expiration: 3600,
+        },
+        api_keys: ApiKeyConfig {
+            digest_secret: "digest-secret".into(),
+        },
+        email: EmailConfig {
+            smtp_host: "localhost".into(),
+            smtp_port: 25,
+            smtp_user: String::new(),
+            smtp_pass: String::new(),
+            from_email: "noreply@example.com".into(),
+            from_name: "Aegis".into(),
+            frontend_url: "https://aegis.gg/".into(),
+            templates_dir: concat!(env!("CARGO_MANIFEST_DIR"), "/templates/email").into(),
+            transport: EmailTransportKind::Memory,
+        },
+        storage: StorageConfig {
+            backend: StorageBackend::Memory,
+        },
+        rate_limit: RateLimitConfig {
+            backend: RateLimitBackend::Memory,
+        },
+    }
+}
+
+/// Key-scoped routes behind the real key middleware and rate limits,
+/// layered as `routes::api` layers them.
+async fn keyed_app(db: DatabaseConnection) -> (Router, AppState) {
+    let (_, io) = socketioxide::SocketIo::new_layer();
+    let state = AppState::new(db, AwsClients::new().await, settings(), io).await;
+    let app = Router::new()
+        .route(
+            "/organizations/me/webhooks",
+            get(|| async { "listed" }).post(|| async { "created" }),
+        )
+        .route_layer(RateLimitLayer::new(state.rate_limit_service.clone()))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            api_key_auth_middleware,
+        ));
+    (app, state)
+}
+
+fn with_key(method: Method, key: Option<&str>) -> Request<Body> {
+    let mut request = Request::builder()
+        .method(method)
+        .uri("/organizations/me/webhooks");
+    if let Some(key) = key {
+        request = request.header(API_KEY_HEADER, key);
+    }
+    request.body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn router_answers_401_403_and_429_for_api_keys() {
+    let test_db = TestDb::new().await;
+    let db = test_db.connection();
+    let (app, state) = keyed_app(db).await;
+    let (_, reader) = state
+        .api_key_service
+        .create_api_key(
+            "Overlay".into(),
+            Uuid::new_v4(),
+            "organization".into(),
+            vec!["webhooks:read".into()],
+            Some(2),
+            None,
+        )
+        .await
+        .unwrap();
+    let status = |method: Method, key: Option<String>| {
+        let request = with_key(method, key.as_deref());
+        let app = app.clone();
+        async move { app.oneshot(request).await.unwrap().status() }
+    };
+
+    // Missing, unknown and wrong-secret keys
+    let (key_id, _) = reader.rsplit_once('_').unwrap();
+    for key in [
+        None,
+        Some("ak_0123456789abcdef_secret".to_string()),
+        Some(format!("{}_wrong", key_id)),
+    ] {
+        assert_eq!(status(Method::GET, key).await, StatusCode::UNAUTHORIZED);
+    }
+
+    // A scope the key wasn't granted
+    assert_eq!(
+        status(Method::POST, Some(reader.clone())).await,
+        StatusCode::FORBIDDEN
+    );
+
+    // Two requests an hour, then the quota answers
+    for _ in 0..2 {
+        assert_eq!(
+            status(Method::GET, Some(reader.clone())).await,
+            StatusCode::OK
+        );
+    }
+    assert_eq!(
+        status(Method::GET, Some(reader)).await,
+        StatusCode::TOO_MANY_REQUESTS
+    );
+}
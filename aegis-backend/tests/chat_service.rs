@@ -0,0 +1,324 @@
+//! Chat service behaviour against the in-memory storage backend.
+
+use aegis_backend::models::dynamodb::ChatMessage;
+use aegis_backend::repositories::{ChatRepository, DynamoRepository, PageRequest, ScanDirection};
+use aegis_backend::services::chat_service::{
+    MAX_DISTINCT_REACTIONS, MAX_EDIT_HISTORY, MAX_REACTIONS_PER_USER,
+};
+use aegis_backend::services::ChatService;
+use socketioxide::SocketIo;
+
+fn chat_service() -> ChatService {
+    let (_layer, io) = SocketIo::new_layer();
+    ChatService::new(ChatRepository::new(DynamoRepository::in_memory()), io)
+}
+
+#[tokio::test]
+async fn creator_is_first_participant() {
+    let chats = chat_service();
+
+    let chat_id = chats
+        .create_chat("General".into(), "general".into(), "alice".into())
+        .await
+        .unwrap();
+
+    let chat = chats.get_chat(&chat_id).await.unwrap().unwrap();
+    assert_eq!(chat.participant_count, 1);
+    assert!(chat.is_admin("alice"));
+    assert!(chats.is_participant(&chat_id, "alice").await.unwrap());
+    assert!(!chats.is_participant(&chat_id, "bob").await.unwrap());
+}
+
+#[tokio::test]
+async fn joining_twice_counts_once() {
+    let chats = chat_service();
+    let chat_id = chats
+        .create_chat("General".into(), "general".into(), "alice".into())
+        .await
+        .unwrap();
+
+    chats.join_chat(&chat_id, "bob").await.unwrap();
+    chats.join_chat(&chat_id, "bob").await.unwrap();
+
+    let chat = chats.get_chat(&chat_id).await.unwrap().unwrap();
+    assert_eq!(chat.participant_count, 2);
+}
+
+#[tokio::test]
+async fn direct_chat_is_shared_by_both_players() {
+    let chats = chat_service();
+
+    let first = chats
+        .get_or_create_direct_chat("bob", "alice")
+        .await
+        .unwrap();
+    let second = chats
+        .get_or_create_direct_chat("alice", "bob")
+        .await
+        .unwrap();
+
+    assert_eq!(first.id, second.id);
+    assert_eq!(second.participant_count, 2);
+    assert!(second.admins.is_empty());
+}
+
+#[tokio::test]
+async fn team_chat_follows_roster() {
+    let chats = chat_service();
+
+    let chat = chats
+        .sync_team_chat(
+            "t1",
+            "Team",
+            Some("alice".into()),
+            vec!["alice".into(), "bob".into()],
+        )
+        .await
+        .unwrap();
+    assert_eq!(chat.participant_count, 2);
+
+    let chat = chats
+        .sync_team_chat(
+            "t1",
+            "Renamed",
+            Some("carol".into()),
+            vec!["alice".into(), "carol".into()],
+        )
+        .await
+        .unwrap();
+    assert_eq!(chat.name, "Renamed");
+    assert_eq!(chat.admins, vec!["carol".to_string()]);
+    assert_eq!(chat.participant_count, 2);
+    assert!(!chats.is_participant(&chat.id, "bob").await.unwrap());
+    assert!(chats.is_participant(&chat.id, "carol").await.unwrap());
+}
+
+#[tokio::test]
+async fn messages_page_newest_first() {
+    let chats = chat_service();
+    let chat_id = chats
+        .create_chat("General".into(), "general".into(), "alice".into())
+        .await
+        .unwrap();
+    for text in ["one", "two", "three"] {
+        chats
+            .send_message(chat_id.clone(), "alice".into(), text.into(), "text".into())
+            .await
+            .unwrap();
+    }
+
+    let request = PageRequest::new(Some(2), ScanDirection::Backward);
+    let page = chats.get_messages(&chat_id, &request).await.unwrap();
+    let texts: Vec<_> = page.items.iter().map(|m| m.message.as_str()).collect();
+    assert_eq!(texts, ["three", "two"]);
+    assert!(page.has_more);
+
+    let request = PageRequest::from_cursors(
+        Some(2),
+        page.before.as_deref(),
+        None,
+        ScanDirection::Backward,
+    )
+    .unwrap();
+    let page = chats.get_messages(&chat_id, &request).await.unwrap();
+    let texts: Vec<_> = page.items.iter().map(|m| m.message.as_str()).collect();
+    assert_eq!(texts, ["one"]);
+    assert!(!page.has_more);
+}
+
+#[tokio::test]
+async fn stale_message_update_is_reapplied() {
+    let chats = chat_service();
+    let chat_id = chats
+        .create_chat("General".into(), "general".into(), "alice".into())
+        .await
+        .unwrap();
+    let message_id = chats
+        .send_message(
+            chat_id.clone(),
+            "alice".into(),
+            "hello".into(),
+            "text".into(),
+        )
+        .await
+        .unwrap();
+
+    let stale = chats
+        .get_message(&chat_id, &message_id)
+        .await
+        .unwrap()
+        .unwrap();
+    let fresh = chats
+        .get_message(&chat_id, &message_id)
+        .await
+        .unwrap()
+        .unwrap();
+    chats
+        .edit_message(fresh, "hello there".into())
+        .await
+        .unwrap();
+    chats.add_reaction(stale, "👍", "bob").await.unwrap();
+
+    let message = chats
+        .get_message(&chat_id, &message_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(message.message, "hello there");
+    assert_eq!(message.edit_history.len(), 1);
+    assert!(message.has_reacted("👍", "bob"));
+    assert_eq!(message.version, 2);
+}
+
+#[tokio::test]
+async fn edit_history_and_reactions_are_capped() {
+    let chats = chat_service();
+    let chat_id = chats
+        .create_chat("General".into(), "general".into(), "alice".into())
+        .await
+        .unwrap();
+    let message_id = chats
+        .send_message(chat_id.clone(), "alice".into(), "v0".into(), "text".into())
+        .await
+        .unwrap();
+    let mut message = chats
+        .get_message(&chat_id, &message_id)
+        .await
+        .unwrap()
+        .unwrap();
+
+    for version in 1..=MAX_EDIT_HISTORY + 3 {
+        message = chats
+            .edit_message(message, format!("v{}", version))
+            .await
+            .unwrap();
+    }
+    assert_eq!(message.edit_history.len(), MAX_EDIT_HISTORY);
+    assert_eq!(message.edit_history[0].message, "v3");
+
+    for i in 0..MAX_REACTIONS_PER_USER + 1 {
+        message = chats
+            .add_reaction(message, &format!("e{}", i), "bob")
+            .await
+            .unwrap();
+    }
+    assert_eq!(message.reaction_count("bob"), MAX_REACTIONS_PER_USER);
+
+    for i in 0..MAX_DISTINCT_REACTIONS {
+        message = chats
+            .add_reaction(message, &format!("x{}", i), &format!("user{}", i))
+            .await
+            .unwrap();
+    }
+    assert_eq!(message.reactions.len(), MAX_DISTINCT_REACTIONS);
+    let last = MAX_DISTINCT_REACTIONS - 1;
+    assert!(!message.has_reacted(&format!("x{}", last), &format!("user{}", last)));
+
+    // Existing emoji can still be joined at the cap
+    message = chats.add_reaction(message, "e0", "carol").await.unwrap();
+    assert!(message.has_reacted("e0", "carol"));
+}
+
+#[tokio::test]
+async fn deleted_message_keeps_its_place() {
+    let chats = chat_service();
+    let chat_id = chats
+        .create_chat("General".into(), "general".into(), "alice".into())
+        .await
+        .unwrap();
+    let message_id = chats
+        .send_message(
+            chat_id.clone(),
+            "alice".into(),
+            "oops".into(),
+            "text".into(),
+        )
+        .await
+        .unwrap();
+
+    let message = chats
+        .get_message(&chat_id, &message_id)
+        .await
+        .unwrap()
+        .unwrap();
+    let message = chats.add_reaction(message, "😂", "alice").await.unwrap();
+    chats.delete_message(message).await.unwrap();
+
+    let message = chats
+        .get_message(&chat_id, &message_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(message.deleted);
+    assert!(message.message.is_empty());
+    assert!(message.reactions.is_empty());
+}
+
+#[tokio::test]
+async fn unread_counts_follow_read_marker() {
+    let chats = chat_service();
+    let chat = chats
+        .get_or_create_direct_chat("alice", "bob")
+        .await
+        .unwrap();
+
+    let mut last = String::new();
+    for text in ["hi", "are you there?"] {
+        last = chats
+            .send_message(chat.id.clone(), "alice".into(), text.into(), "text".into())
+            .await
+            .unwrap();
+    }
+    chats
+        .send_message(chat.id.clone(), "bob".into(), "yes".into(), "text".into())
+        .await
+        .unwrap();
+
+    let request = PageRequest::new(None, ScanDirection::Forward);
+    let summaries = chats.get_my_chats("bob", &request).await.unwrap();
+    assert_eq!(summaries.items.len(), 1);
+    assert_eq!(summaries.items[0].unread_count, 2);
+
+    let message = chats.get_message(&chat.id, &last).await.unwrap().unwrap();
+    chats.mark_read(&message, "bob").await.unwrap();
+
+    let summaries = chats.get_my_chats("bob", &request).await.unwrap();
+    assert_eq!(summaries.items[0].unread_count, 0);
+}
+
+#[tokio::test]
+async fn messages_in_the_same_instant_are_both_kept() {
+    let repo = ChatRepository::new(DynamoRepository::in_memory());
+    let message = |id: &str, text: &str| ChatMessage {
+        id: id.into(),
+        chat_id: "c1".into(),
+        sender: "alice".into(),
+        message: text.into(),
+        message_type: "text".into(),
+        timestamp: "2026-10-18T12:00:00+00:00".into(),
+        edited_at: None,
+        edit_history: Vec::new(),
+        deleted: false,
+        deleted_at: None,
+        reactions: Default::default(),
+        version: 0,
+    };
+
+    repo.add_message(message("m1", "one")).await.unwrap();
+    repo.add_message(message("m2", "two")).await.unwrap();
+    assert!(repo.add_message(message("m1", "again")).await.is_err());
+
+    let first = repo.get_message("c1", "m1").await.unwrap().unwrap();
+    assert_eq!(first.message, "one");
+    let second = repo.get_message("c1", "m2").await.unwrap().unwrap();
+    assert_eq!(second.message, "two");
+    assert_eq!(
+        repo.count_messages_after("c1", None, "bob").await.unwrap(),
+        2
+    );
+    let read = Some((first.timestamp.as_str(), first.id.as_str()));
+    assert_eq!(
+        repo.count_messages_after("c1", read, "bob").await.unwrap(),
+        1
+    );
+}
@@ -0,0 +1,86 @@
+//! Postgres fixture shared by the tests that need a real database.
+//!
+//! These tests are `#[ignore]`d so a plain `cargo test` reports them as not
+//! run; run them with `TEST_DATABASE_URL` set and `cargo test -- --ignored`.
+
+use sea_orm::{ConnectOptions, ConnectionTrait, Database, DatabaseConnection};
+
+/// A schema of its own on the database named by `TEST_DATABASE_URL`, with
+/// every migration in `migrations/postgres` applied. Dropped with the value.
+pub struct TestDb {
+    db: DatabaseConnection,
+    url: String,
+    schema: String,
+}
+
+impl TestDb {
+    pub async fn new() -> Self {
+        let url = std::env::var("TEST_DATABASE_URL")
+            .expect("TEST_DATABASE_URL must be set to run database tests");
+        let schema = format!("test_{}", uuid::Uuid::new_v4().simple());
+        Database::connect(&url)
+            .await
+            .unwrap()
+            .execute_unprepared(&format!("CREATE SCHEMA {}", schema))
+            .await
+            .unwrap();
+
+        let mut options = ConnectOptions::new(url.clone());
+        options.set_schema_search_path(schema.clone());
+        let db = Database::connect(options).await.unwrap();
+        let test_db = Self { db, url, schema };
+        for (name, sql) in migrations() {
+            if let Err(e) = test_db.db.execute_unprepared(&sql).await {
+                panic!("Failed to apply {}: {}", name, e);
+            }
+        }
+        test_db
+    }
+
+    /// A handle on the schema; only valid while `self` is alive.
+    pub fn connection(&self) -> DatabaseConnection {
+        self.db.clone()
+    }
+}
+
+impl Drop for TestDb {
+    fn drop(&mut self) {
+        let url = self.url.clone();
+        let drop_schema = format!("DROP SCHEMA {} CASCADE", self.schema);
+        // Drop can't await, and runs inside the test's runtime
+        let dropped = std::thread::spawn(move || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()?
+                .block_on(async {
+                    Database::connect(&url)
+                        .await?
+                        .execute_unprepared(&drop_schema)
+                        .await?;
+                    Ok::<_, anyhow::Error>(())
+                })
+        })
+        .join();
+        if !matches!(dropped, Ok(Ok(()))) {
+            eprintln!("Failed to drop test schema {}", self.schema);
+        }
+    }
+}
+
+/// The SQL migrations, in the order their names give.
+fn migrations() -> Vec<(String, String)> {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/migrations/postgres");
+    let mut files: Vec<_> = std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "sql"))
+        .collect();
+    files.sort();
+    files
+        .into_iter()
+        .map(|path| {
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            (name, std::fs::read_to_string(&path).unwrap())
+        })
+        .collect()
+}
@@ -0,0 +1,240 @@
+//! Community service behaviour against the in-memory storage backend.
+
+use aegis_backend::models::dynamodb::CommunityMember;
+use aegis_backend::repositories::{
+    CommunityRepository, DynamoRepository, PageRequest, ScanDirection,
+};
+use aegis_backend::services::CommunityService;
+
+fn community_service() -> CommunityService {
+    CommunityService::new(CommunityRepository::new(DynamoRepository::in_memory()))
+}
+
+async fn create_community(communities: &CommunityService, community_type: &str) -> String {
+    communities
+        .create_community(
+            "Speedrunners".into(),
+            "Going fast".into(),
+            community_type.into(),
+            "owner".into(),
+        )
+        .await
+        .unwrap()
+}
+
+async fn member_count(communities: &CommunityService, community_id: &str) -> i32 {
+    communities
+        .get_community(community_id)
+        .await
+        .unwrap()
+        .unwrap()
+        .member_count
+}
+
+#[tokio::test]
+async fn owner_is_first_member() {
+    let communities = community_service();
+    let community_id = create_community(&communities, "public").await;
+
+    let owner = communities
+        .get_member(&community_id, "owner")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(owner.is_owner());
+    assert!(owner.is_moderator());
+    assert_eq!(member_count(&communities, &community_id).await, 1);
+}
+
+#[tokio::test]
+async fn join_and_leave_keep_count() {
+    let communities = community_service();
+    let community_id = create_community(&communities, "public").await;
+
+    assert!(communities
+        .join_community(&community_id, "bob")
+        .await
+        .unwrap());
+    assert!(!communities
+        .join_community(&community_id, "bob")
+        .await
+        .unwrap());
+    assert_eq!(member_count(&communities, &community_id).await, 2);
+
+    assert!(communities
+        .leave_community(&community_id, "bob")
+        .await
+        .unwrap());
+    assert!(!communities
+        .leave_community(&community_id, "bob")
+        .await
+        .unwrap());
+    assert_eq!(member_count(&communities, &community_id).await, 1);
+}
+
+#[tokio::test]
+async fn member_is_not_written_without_its_community() {
+    let repo = CommunityRepository::new(DynamoRepository::in_memory());
+    let member = CommunityMember {
+        community_id: "missing".into(),
+        user_id: "bob".into(),
+        role: "member".into(),
+        muted: false,
+        joined_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    // The count update fails, so the member put is rolled back with it
+    assert!(repo.add_member(&member).await.is_err());
+    assert!(repo.get_member("missing", "bob").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn memberships_list_communities_with_role() {
+    let communities = community_service();
+    let first = create_community(&communities, "public").await;
+    let second = create_community(&communities, "public").await;
+    create_community(&communities, "public").await;
+    communities.join_community(&first, "bob").await.unwrap();
+    communities.join_community(&second, "bob").await.unwrap();
+    communities
+        .set_moderator(&second, "bob", true)
+        .await
+        .unwrap();
+
+    let request = PageRequest::new(None, ScanDirection::Forward);
+    let mine = communities
+        .get_my_communities("bob", &request)
+        .await
+        .unwrap();
+    assert_eq!(mine.items.len(), 2);
+    for membership in mine.items {
+        let expected = if membership.community.id == second {
+            "moderator"
+        } else {
+            "member"
+        };
+        assert_eq!(membership.role, expected);
+    }
+
+    let members = communities.get_member_ids(&second).await.unwrap();
+    assert_eq!(members.len(), 2);
+}
+
+#[tokio::test]
+async fn join_requests_need_approval() {
+    let communities = community_service();
+    let community_id = create_community(&communities, "private").await;
+
+    assert!(communities
+        .request_to_join(&community_id, "bob")
+        .await
+        .unwrap());
+    assert!(!communities
+        .request_to_join(&community_id, "bob")
+        .await
+        .unwrap());
+    assert!(communities
+        .get_member(&community_id, "bob")
+        .await
+        .unwrap()
+        .is_none());
+
+    let request = PageRequest::new(None, ScanDirection::Forward);
+    let pending = communities
+        .get_join_requests(&community_id, &request)
+        .await
+        .unwrap();
+    assert_eq!(pending.items.len(), 1);
+
+    assert!(communities
+        .approve_join_request(&community_id, "bob")
+        .await
+        .unwrap());
+    assert!(!communities
+        .approve_join_request(&community_id, "bob")
+        .await
+        .unwrap());
+    assert_eq!(member_count(&communities, &community_id).await, 2);
+}
+
+#[tokio::test]
+async fn ban_removes_member_until_unbanned() {
+    let communities = community_service();
+    let community_id = create_community(&communities, "public").await;
+    communities
+        .join_community(&community_id, "bob")
+        .await
+        .unwrap();
+
+    communities
+        .ban(&community_id, "bob", "owner")
+        .await
+        .unwrap();
+    assert!(communities.is_banned(&community_id, "bob").await.unwrap());
+    assert!(communities
+        .get_member(&community_id, "bob")
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(member_count(&communities, &community_id).await, 1);
+
+    assert!(communities.unban(&community_id, "bob").await.unwrap());
+    assert!(!communities.unban(&community_id, "bob").await.unwrap());
+    assert!(!communities.is_banned(&community_id, "bob").await.unwrap());
+}
+
+#[tokio::test]
+async fn mute_requires_membership() {
+    let communities = community_service();
+    let community_id = create_community(&communities, "public").await;
+    communities
+        .join_community(&community_id, "bob")
+        .await
+        .unwrap();
+
+    assert!(communities
+        .set_muted(&community_id, "bob", true)
+        .await
+        .unwrap());
+    assert!(!communities
+        .set_muted(&community_id, "carol", true)
+        .await
+        .unwrap());
+
+    let bob = communities
+        .get_member(&community_id, "bob")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(bob.muted);
+}
+
+#[tokio::test]
+async fn community_posts_can_be_pinned_and_removed() {
+    let communities = community_service();
+    let community_id = create_community(&communities, "public").await;
+    assert!(communities
+        .add_post_to_community(community_id.clone(), "p1".into(), false, "owner".into())
+        .await
+        .unwrap()
+        .is_some());
+    assert!(communities
+        .add_post_to_community(community_id.clone(), "p1".into(), true, "bob".into())
+        .await
+        .unwrap()
+        .is_none());
+
+    assert!(communities
+        .set_post_pinned(&community_id, "p1", true)
+        .await
+        .unwrap());
+    let post = communities
+        .get_community_post(&community_id, "p1")
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(post.pinned);
+
+    assert!(communities.remove_post(&community_id, "p1").await.unwrap());
+    assert!(!communities.remove_post(&community_id, "p1").await.unwrap());
+}
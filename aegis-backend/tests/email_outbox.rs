@@ -0,0 +1,266 @@
+//! Email transports usable without an SMTP server, the outbox retry
+//! schedule, and the outbox worker against Postgres.
+
+mod common;
+
+use aegis_backend::config::settings::{EmailConfig, EmailTransportKind};
+use aegis_backend::models::postgres::{email_outbox, EmailOutbox};
+use aegis_backend::services::email_outbox_service::{retry_delay, OutboxEmail, MAX_ATTEMPTS};
+use aegis_backend::services::{EmailOutboxService, EmailService};
+use chrono::{Duration, Utc};
+use common::TestDb;
+use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, Set};
+use serde_json::json;
+
+fn email_service(transport: EmailTransportKind) -> EmailService {
+    EmailService::new(EmailConfig {
+        smtp_host: "localhost".into(),
+        smtp_port: 25,
+        smtp_user: String::new(),
+        smtp_pass: String::new(),
+        from_email: "noreply@example.com".into(),
+        from_name: "Aegis".into(),
+        frontend_url: "https://aegis.gg/".into(),
+        templates_dir: concat!(env!("CARGO_MANIFEST_DIR"), "/templates/email").into(),
+        transport,
+    })
+    .unwrap()
+}
+
+#[tokio::test]
+async fn memory_transport_keeps_rendered_messages() {
+    let email = email_service(EmailTransportKind::Memory);
+    email
+        .send_template(
+            "alice@example.com",
+            "password_reset",
+            &["es".to_string()],
+            json!({ "token": "abc" }),
+        )
+        .await
+        .unwrap();
+
+    let sent = email.sent_emails();
+    assert_eq!(sent.len(), 1);
+    assert_eq!(sent[0].to, "alice@example.com");
+    assert_eq!(sent[0].template, "password_reset");
+    assert_eq!(sent[0].subject, "Restablecer contraseña - Aegis Gaming");
+    assert!(sent[0].text.contains("https://aegis.gg/reset-password/abc"));
+}
+
+#[tokio::test]
+async fn file_transport_writes_one_eml_per_message() {
+    let dir = std::env::temp_dir().join(format!("aegis-emails-{}", uuid::Uuid::new_v4()));
+    let email = email_service(EmailTransportKind::File(dir.display().to_string()));
+    email
+        .send_template(
+            "bob@example.com",
+            "verify_email",
+            &[],
+            json!({ "token": "xyz" }),
+        )
+        .await
+        .unwrap();
+
+    let files: Vec<_> = std::fs::read_dir(&dir).unwrap().collect();
+    assert_eq!(files.len(), 1);
+    let contents = std::fs::read_to_string(files[0].as_ref().unwrap().path()).unwrap();
+    assert!(contents.contains("To: bob@example.com"));
+    assert!(email.sent_emails().is_empty());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn rendering_errors_are_reported_to_the_caller() {
+    let email = email_service(EmailTransportKind::Memory);
+    let result = email
+        .send_template("carol@example.com", "password_reset", &[], json!({}))
+        .await;
+
+    assert!(result.is_err());
+    assert!(email.sent_emails().is_empty());
+}
+
+#[test]
+fn retries_back_off_exponentially_up_to_an_hour() {
+    assert_eq!(retry_delay(1), Duration::seconds(30));
+    assert_eq!(retry_delay(2), Duration::seconds(60));
+    assert_eq!(retry_delay(4), Duration::seconds(240));
+    assert_eq!(retry_delay(MAX_ATTEMPTS), Duration::hours(1));
+    assert_eq!(retry_delay(1000), Duration::hours(1));
+}
+
+fn password_reset(to: &str) -> OutboxEmail {
+    OutboxEmail::new(to, "password_reset", json!({ "token": "secret-token" }))
+}
+
+// Renders fail without the token, so delivery always errors
+fn undeliverable(to: &str) -> OutboxEmail {
+    OutboxEmail::new(to, "password_reset", json!({}))
+}
+
+async fn reload(db: &DatabaseConnection, message: &email_outbox::Model) -> email_outbox::Model {
+    EmailOutbox::find_by_id(message.id)
+        .one(db)
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn worker_sends_due_messages_once() {
+    let test_db = TestDb::new().await;
+    let db = test_db.connection();
+    let email = email_service(EmailTransportKind::Memory);
+    let outbox = EmailOutboxService::new(db.clone(), email.clone());
+
+    let message = outbox
+        .enqueue(password_reset("alice@example.com"))
+        .await
+        .unwrap();
+    assert_eq!(outbox.process_due(10).await.unwrap(), 1);
+    assert_eq!(outbox.process_due(10).await.unwrap(), 0);
+
+    assert_eq!(email.sent_emails().len(), 1);
+    let message = reload(&db, &message).await;
+    assert_eq!(message.status, email_outbox::SENT);
+    assert_eq!(message.attempts, 1);
+    assert!(message.sent_at.is_some());
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn worker_leaves_messages_held_until_later() {
+    let test_db = TestDb::new().await;
+    let db = test_db.connection();
+    let email = email_service(EmailTransportKind::Memory);
+    let outbox = EmailOutboxService::new(db.clone(), email.clone());
+
+    let message = outbox
+        .enqueue(
+            password_reset("alice@example.com")
+                .with_send_after(Some(Utc::now() + Duration::hours(1))),
+        )
+        .await
+        .unwrap();
+    assert_eq!(outbox.process_due(10).await.unwrap(), 0);
+
+    assert!(email.sent_emails().is_empty());
+    assert_eq!(reload(&db, &message).await.attempts, 0);
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn failed_delivery_is_retried_after_a_backoff() {
+    let test_db = TestDb::new().await;
+    let db = test_db.connection();
+    let email = email_service(EmailTransportKind::Memory);
+    let outbox = EmailOutboxService::new(db.clone(), email.clone());
+
+    let message = outbox
+        .enqueue(undeliverable("bob@example.com"))
+        .await
+        .unwrap();
+    let before = Utc::now();
+    assert_eq!(outbox.process_due(10).await.unwrap(), 0);
+
+    let message = reload(&db, &message).await;
+    assert_eq!(message.status, email_outbox::PENDING);
+    assert_eq!(message.attempts, 1);
+    assert!(message.last_error.is_some());
+    assert!(message.next_attempt_at >= before + retry_delay(1));
+    assert!(message.next_attempt_at <= Utc::now() + retry_delay(1));
+
+    // Not due again until the backoff has passed
+    assert_eq!(outbox.process_due(10).await.unwrap(), 0);
+    assert_eq!(reload(&db, &message).await.attempts, 1);
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn last_failed_attempt_marks_the_message_failed() {
+    let test_db = TestDb::new().await;
+    let db = test_db.connection();
+    let email = email_service(EmailTransportKind::Memory);
+    let outbox = EmailOutboxService::new(db.clone(), email.clone());
+
+    let message = outbox
+        .enqueue(undeliverable("carol@example.com"))
+        .await
+        .unwrap();
+    email_outbox::ActiveModel {
+        id: Set(message.id),
+        attempts: Set(MAX_ATTEMPTS - 1),
+        ..Default::default()
+    }
+    .update(&db)
+    .await
+    .unwrap();
+    assert_eq!(outbox.process_due(10).await.unwrap(), 0);
+
+    let failed = reload(&db, &message).await;
+    assert_eq!(failed.status, email_outbox::FAILED);
+    assert_eq!(failed.attempts, MAX_ATTEMPTS);
+    let stuck = outbox.list_stuck(10, 0).await.unwrap();
+    assert_eq!(stuck.len(), 1);
+    assert_eq!(stuck[0].id, message.id);
+
+    let retried = outbox.retry(message.id).await.unwrap();
+    assert_eq!(retried.status, email_outbox::PENDING);
+    assert_eq!(retried.attempts, 0);
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn concurrent_workers_claim_each_message_once() {
+    let test_db = TestDb::new().await;
+    let db = test_db.connection();
+    let email = email_service(EmailTransportKind::Memory);
+    let first = EmailOutboxService::new(db.clone(), email.clone());
+    let second = EmailOutboxService::new(db.clone(), email.clone());
+
+    for i in 0..5 {
+        first
+            .enqueue(password_reset(&format!("player{}@example.com", i)))
+            .await
+            .unwrap();
+    }
+    let (a, b) = tokio::join!(first.process_due(10), second.process_due(10));
+
+    assert_eq!(a.unwrap() + b.unwrap(), 5);
+    assert_eq!(email.sent_emails().len(), 5);
+    let attempts: Vec<i32> = EmailOutbox::find()
+        .all(&db)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|m| m.attempts)
+        .collect();
+    assert_eq!(attempts, [1; 5]);
+}
+
+#[test]
+fn admin_view_redacts_tokens() {
+    let now = Utc::now();
+    let message = email_outbox::Model {
+        id: uuid::Uuid::new_v4(),
+        recipient: "alice@example.com".into(),
+        template: "notification".into(),
+        languages: vec![],
+        vars: json!({ "title": "Hi", "token": "reset", "unsubscribe_token": "unsub" }),
+        status: email_outbox::FAILED.into(),
+        attempts: MAX_ATTEMPTS,
+        next_attempt_at: now,
+        last_error: None,
+        sent_at: None,
+        created_at: now,
+        updated_at: now,
+    };
+
+    let redacted = message.redacted();
+    assert_eq!(
+        redacted.vars,
+        json!({ "title": "Hi", "token": "[redacted]", "unsubscribe_token": "[redacted]" })
+    );
+}
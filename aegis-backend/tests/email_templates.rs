@@ -0,0 +1,61 @@
+//! Rendering of the shipped email templates.
+
+use aegis_backend::services::email_templates::EmailTemplates;
+use serde_json::json;
+
+fn templates() -> EmailTemplates {
+    EmailTemplates::load(concat!(env!("CARGO_MANIFEST_DIR"), "/templates/email")).unwrap()
+}
+
+#[test]
+fn picks_the_first_translated_language() {
+    let templates = templates();
+    let vars = json!({ "frontend_url": "https://aegis.gg", "token": "abc" });
+
+    let spanish = templates
+        .render(
+            "password_reset",
+            &["fr".to_string(), "es-MX".to_string()],
+            &vars,
+        )
+        .unwrap();
+    assert_eq!(spanish.subject, "Restablecer contraseña - Aegis Gaming");
+    assert!(spanish.text.contains("https://aegis.gg/reset-password/abc"));
+    assert!(spanish.html.contains("https://aegis.gg/reset-password/abc"));
+
+    let fallback = templates.render("password_reset", &[], &vars).unwrap();
+    assert_eq!(fallback.subject, "Password Reset Request - Aegis Gaming");
+}
+
+#[test]
+fn escapes_values_in_html_only() {
+    let rendered = templates()
+        .render(
+            "notification",
+            &[],
+            &json!({
+                "frontend_url": "https://aegis.gg",
+                "title": "Mentioned",
+                "body": "<b>gg</b> & well played",
+                "link": "/chats/1",
+                "unsubscribe_token": "t",
+            }),
+        )
+        .unwrap();
+
+    assert!(rendered
+        .html
+        .contains("&lt;b&gt;gg&lt;/b&gt; &amp; well played"));
+    assert!(rendered.text.contains("<b>gg</b> & well played"));
+    assert!(rendered.text.contains("https://aegis.gg/unsubscribe/t"));
+}
+
+#[test]
+fn missing_variables_and_templates_are_errors() {
+    let templates = templates();
+    assert!(templates
+        .render("verify_email", &[], &json!({ "frontend_url": "x" }))
+        .is_err());
+    assert!(templates.render("no_such_email", &[], &json!({})).is_err());
+    assert!(templates.contains("verify_email"));
+}
@@ -0,0 +1,84 @@
+//! Follow edges and their counters against the in-memory storage backend.
+
+use aegis_backend::repositories::{DynamoRepository, FollowRepository, PageRequest, ScanDirection};
+use aegis_backend::services::FollowService;
+
+fn follow_service() -> FollowService {
+    FollowService::new(FollowRepository::new(DynamoRepository::in_memory()))
+}
+
+#[tokio::test]
+async fn follow_and_unfollow_keep_both_counts() {
+    let follows = follow_service();
+
+    assert!(follows
+        .follow("player", "alice", "team", "t1")
+        .await
+        .unwrap());
+    assert!(!follows
+        .follow("player", "alice", "team", "t1")
+        .await
+        .unwrap());
+    assert!(follows.follow("player", "bob", "team", "t1").await.unwrap());
+
+    let team = follows.get_stats("team", "t1", "alice").await.unwrap();
+    assert_eq!(team.followers, 2);
+    assert!(team.followed_by_me);
+    let alice = follows.get_stats("player", "alice", "bob").await.unwrap();
+    assert_eq!(alice.following, 1);
+    assert_eq!(alice.followers, 0);
+
+    assert!(follows
+        .unfollow("player", "alice", "team", "t1")
+        .await
+        .unwrap());
+    assert!(!follows
+        .unfollow("player", "alice", "team", "t1")
+        .await
+        .unwrap());
+
+    let team = follows.get_stats("team", "t1", "alice").await.unwrap();
+    assert_eq!(team.followers, 1);
+    assert!(!team.followed_by_me);
+    let alice = follows.get_stats("player", "alice", "bob").await.unwrap();
+    assert_eq!(alice.following, 0);
+}
+
+#[tokio::test]
+async fn unfollow_without_an_edge_leaves_counts_alone() {
+    let follows = follow_service();
+
+    assert!(!follows
+        .unfollow("player", "alice", "team", "t1")
+        .await
+        .unwrap());
+
+    let team = follows.get_stats("team", "t1", "alice").await.unwrap();
+    assert_eq!(team.followers, 0);
+    let alice = follows.get_stats("player", "alice", "alice").await.unwrap();
+    assert_eq!(alice.following, 0);
+}
+
+#[tokio::test]
+async fn followers_come_back_newest_first() {
+    let follows = follow_service();
+
+    for follower in ["zed", "amy", "max"] {
+        follows
+            .follow("player", follower, "team", "t1")
+            .await
+            .unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(2)).await;
+    }
+
+    let page = follows
+        .get_followers(
+            "team",
+            "t1",
+            &PageRequest::new(None, ScanDirection::Backward),
+        )
+        .await
+        .unwrap();
+    let followers: Vec<_> = page.items.iter().map(|f| f.follower_id.as_str()).collect();
+    assert_eq!(followers, ["max", "amy", "zed"]);
+}
@@ -0,0 +1,220 @@
+//! Notification inbox behaviour against the in-memory storage backend.
+
+use aegis_backend::config::settings::{EmailConfig, EmailTransportKind};
+use aegis_backend::models::dynamodb::{
+    NotificationCategory, NotificationChannel, NotificationKind, NotificationPreferences,
+    QuietHours,
+};
+use aegis_backend::repositories::{
+    DynamoRepository, NotificationRepository, PageRequest, ScanDirection,
+};
+use aegis_backend::services::notification_service::NewNotification;
+use aegis_backend::services::{
+    AuthService, EmailOutboxService, EmailService, NotificationService, PlayerService,
+};
+use chrono::{NaiveTime, TimeZone, Utc};
+use socketioxide::SocketIo;
+
+fn notification_service() -> NotificationService {
+    let (_layer, io) = SocketIo::new_layer();
+    let email = EmailService::new(EmailConfig {
+        smtp_host: "localhost".into(),
+        smtp_port: 25,
+        smtp_user: String::new(),
+        smtp_pass: String::new(),
+        from_email: "noreply@example.com".into(),
+        from_name: "Aegis".into(),
+        frontend_url: "http://localhost:5173".into(),
+        templates_dir: concat!(env!("CARGO_MANIFEST_DIR"), "/templates/email").into(),
+        transport: EmailTransportKind::Memory,
+    })
+    .unwrap();
+    let auth = AuthService::new("secret".into(), 3600);
+    let db = sea_orm::DatabaseConnection::Disconnected;
+    let players = PlayerService::new(db.clone(), auth.clone());
+    NotificationService::new(
+        NotificationRepository::new(DynamoRepository::in_memory()),
+        EmailOutboxService::new(db, email),
+        players,
+        auth,
+        io,
+    )
+}
+
+fn mention(body: &str) -> NewNotification {
+    NewNotification::new(NotificationKind::ChatMention, "Mentioned", body)
+}
+
+#[tokio::test]
+async fn inbox_lists_newest_first_with_unread_count() {
+    let notifications = notification_service();
+    notifications
+        .notify("alice", mention("first"))
+        .await
+        .unwrap();
+    notifications
+        .notify("alice", mention("second"))
+        .await
+        .unwrap();
+    notifications.notify("bob", mention("other")).await.unwrap();
+
+    let request = PageRequest::new(None, ScanDirection::Backward);
+    let inbox = notifications.get_inbox("alice", &request).await.unwrap();
+    let bodies: Vec<_> = inbox.items.iter().map(|n| n.body.as_str()).collect();
+    assert_eq!(bodies, ["second", "first"]);
+    assert_eq!(notifications.unread_count("alice").await.unwrap(), 2);
+}
+
+#[tokio::test]
+async fn marking_read_is_idempotent_and_scoped_to_the_recipient() {
+    let notifications = notification_service();
+    let stored = notifications
+        .notify("alice", mention("hi"))
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert!(notifications
+        .mark_read("bob", &stored.id)
+        .await
+        .unwrap()
+        .is_none());
+
+    let read = notifications
+        .mark_read("alice", &stored.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(!read.is_unread());
+    assert!(read.read_at.is_some());
+    assert_eq!(notifications.unread_count("alice").await.unwrap(), 0);
+
+    let again = notifications
+        .mark_read("alice", &stored.id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(again.read_at, read.read_at);
+}
+
+#[tokio::test]
+async fn mark_all_read_clears_the_unread_count() {
+    let notifications = notification_service();
+    for body in ["a", "b", "c"] {
+        notifications.notify("alice", mention(body)).await.unwrap();
+    }
+    let request = PageRequest::new(None, ScanDirection::Forward);
+    let inbox = notifications.get_inbox("alice", &request).await.unwrap();
+    let first_id = inbox.items[0].id.clone();
+    notifications.mark_read("alice", &first_id).await.unwrap();
+
+    assert_eq!(notifications.mark_all_read("alice").await.unwrap(), 2);
+    assert_eq!(notifications.unread_count("alice").await.unwrap(), 0);
+    assert_eq!(notifications.mark_all_read("alice").await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn disabled_in_app_channel_skips_the_inbox() {
+    let notifications = notification_service();
+    notifications
+        .update_preferences("alice", |preferences| {
+            preferences
+                .channels
+                .insert(NotificationCategory::ChatMentions, Vec::new());
+            true
+        })
+        .await
+        .unwrap();
+
+    assert!(notifications
+        .notify("alice", mention("hi"))
+        .await
+        .unwrap()
+        .is_none());
+    assert_eq!(notifications.unread_count("alice").await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn unsubscribe_turns_off_email_only() {
+    let notifications = notification_service();
+    let category = NotificationCategory::TeamInvites;
+
+    assert!(notifications.unsubscribe("alice", category).await.unwrap());
+    assert!(!notifications.unsubscribe("alice", category).await.unwrap());
+
+    let preferences = notifications.get_preferences("alice").await.unwrap();
+    assert_eq!(
+        preferences.channels_for(category),
+        [NotificationChannel::InApp]
+    );
+    assert_eq!(
+        preferences.channels_for(NotificationCategory::TournamentUpdates),
+        [NotificationChannel::InApp, NotificationChannel::Email]
+    );
+}
+
+#[test]
+fn quiet_hours_follow_the_users_timezone_across_midnight() {
+    let mut preferences = NotificationPreferences::new("alice");
+    preferences.timezone = chrono_tz::America::New_York;
+    preferences.quiet_hours = Some(QuietHours {
+        start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+        end: NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+    });
+
+    // 03:00 UTC in January is 22:00 in New York
+    let late_evening = Utc.with_ymd_and_hms(2025, 1, 15, 3, 0, 0).unwrap();
+    let early_morning = Utc.with_ymd_and_hms(2025, 1, 15, 11, 30, 0).unwrap();
+    let morning = Utc.with_ymd_and_hms(2025, 1, 15, 12, 30, 0).unwrap();
+    let afternoon = Utc.with_ymd_and_hms(2025, 1, 15, 18, 0, 0).unwrap();
+    assert!(preferences.in_quiet_hours(late_evening));
+    assert!(preferences.in_quiet_hours(early_morning));
+    assert!(!preferences.in_quiet_hours(morning));
+    assert!(!preferences.in_quiet_hours(afternoon));
+}
+
+#[test]
+fn email_during_quiet_hours_waits_for_the_window_to_end() {
+    let mut preferences = NotificationPreferences::new("alice");
+    preferences.timezone = chrono_tz::America::New_York;
+    preferences.quiet_hours = Some(QuietHours {
+        start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+        end: NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+    });
+
+    // The night of 14 January ends at 07:00 New York time, 12:00 UTC on the 15th
+    let late_evening = Utc.with_ymd_and_hms(2025, 1, 15, 3, 0, 0).unwrap();
+    let after_midnight = Utc.with_ymd_and_hms(2025, 1, 15, 6, 0, 0).unwrap();
+    let before_midnight = Utc.with_ymd_and_hms(2025, 1, 15, 4, 30, 0).unwrap();
+    let end = Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap();
+    assert_eq!(preferences.quiet_hours_end(late_evening), Some(end));
+    assert_eq!(preferences.quiet_hours_end(after_midnight), Some(end));
+    assert_eq!(preferences.quiet_hours_end(before_midnight), Some(end));
+
+    let evening = Utc.with_ymd_and_hms(2025, 1, 15, 23, 30, 0).unwrap();
+    assert_eq!(preferences.quiet_hours_end(evening), None);
+}
+
+#[test]
+fn marketing_email_is_opt_in() {
+    let mut preferences = NotificationPreferences::new("alice");
+    assert_eq!(
+        preferences.channels_for(NotificationCategory::Marketing),
+        [NotificationChannel::InApp]
+    );
+
+    preferences.channels.insert(
+        NotificationCategory::Marketing,
+        vec![NotificationChannel::Email, NotificationChannel::Webhook],
+    );
+    let stored: NotificationPreferences =
+        serde_json::from_value(serde_json::to_value(&preferences).unwrap()).unwrap();
+    assert_eq!(
+        stored.channels_for(NotificationCategory::Marketing),
+        [NotificationChannel::Email, NotificationChannel::Webhook]
+    );
+    assert_eq!(
+        NotificationCategory::parse("marketing"),
+        Some(NotificationCategory::Marketing)
+    );
+}
@@ -0,0 +1,162 @@
+//! Post service behaviour against the in-memory storage backend.
+
+use aegis_backend::repositories::{DynamoRepository, PageRequest, PostRepository, ScanDirection};
+use aegis_backend::services::post_service::CommentSort;
+use aegis_backend::services::PostService;
+
+fn post_service() -> PostService {
+    PostService::new(PostRepository::new(DynamoRepository::in_memory()))
+}
+
+async fn create_post(posts: &PostService, author: &str) -> String {
+    posts
+        .create_post(
+            author.into(),
+            "Title".into(),
+            "Content".into(),
+            "general".into(),
+            vec!["tag".into()],
+        )
+        .await
+        .unwrap()
+        .id
+}
+
+#[tokio::test]
+async fn likes_are_counted_once_per_user() {
+    let posts = post_service();
+    let post_id = create_post(&posts, "alice").await;
+
+    assert!(posts.like_post(&post_id, "bob").await.unwrap());
+    assert!(!posts.like_post(&post_id, "bob").await.unwrap());
+    assert!(posts.like_post(&post_id, "carol").await.unwrap());
+
+    let view = posts.get_post_view(&post_id, "bob").await.unwrap().unwrap();
+    assert_eq!(view.post.likes, 2);
+    assert!(view.liked_by_me);
+
+    assert!(posts.unlike_post(&post_id, "bob").await.unwrap());
+    assert!(!posts.unlike_post(&post_id, "bob").await.unwrap());
+
+    let view = posts.get_post_view(&post_id, "bob").await.unwrap().unwrap();
+    assert_eq!(view.post.likes, 1);
+    assert!(!view.liked_by_me);
+}
+
+#[tokio::test]
+async fn replies_are_threaded_under_their_comment() {
+    let posts = post_service();
+    let post_id = create_post(&posts, "alice").await;
+
+    let quiet = posts
+        .add_comment(post_id.clone(), "bob".into(), "First".into(), None)
+        .await
+        .unwrap();
+    let busy = posts
+        .add_comment(post_id.clone(), "carol".into(), "Second".into(), None)
+        .await
+        .unwrap();
+    for reply in ["Agreed", "Same"] {
+        posts
+            .add_comment(
+                post_id.clone(),
+                "dave".into(),
+                reply.into(),
+                Some(busy.clone()),
+            )
+            .await
+            .unwrap();
+    }
+
+    let post = posts.get_post(&post_id).await.unwrap().unwrap();
+    assert_eq!(post.comments_count, 4);
+
+    let request = PageRequest::new(None, ScanDirection::Forward);
+    let replies = posts.get_replies(&post_id, &busy, &request).await.unwrap();
+    let texts: Vec<_> = replies.items.iter().map(|c| c.content.as_str()).collect();
+    assert_eq!(texts, ["Agreed", "Same"]);
+
+    let top = posts
+        .get_comments(&post_id, CommentSort::Top, &request)
+        .await
+        .unwrap();
+    let ids: Vec<_> = top.items.iter().map(|c| c.id.as_str()).collect();
+    assert_eq!(ids, [busy.as_str(), quiet.as_str()]);
+    assert_eq!(top.items[0].reply_count, 2);
+
+    let newest = posts
+        .get_comments(&post_id, CommentSort::Newest, &request)
+        .await
+        .unwrap();
+    let ids: Vec<_> = newest.items.iter().map(|c| c.id.as_str()).collect();
+    assert_eq!(ids, [busy.as_str(), quiet.as_str()]);
+}
+
+#[tokio::test]
+async fn comment_on_missing_post_writes_nothing() {
+    let posts = post_service();
+
+    assert!(posts
+        .add_comment("missing".into(), "bob".into(), "Hello".into(), None)
+        .await
+        .is_err());
+
+    let request = PageRequest::new(None, ScanDirection::Forward);
+    let comments = posts
+        .get_comments("missing", CommentSort::Newest, &request)
+        .await
+        .unwrap();
+    assert!(comments.items.is_empty());
+}
+
+#[tokio::test]
+async fn deleted_comment_becomes_tombstone() {
+    let posts = post_service();
+    let post_id = create_post(&posts, "alice").await;
+    let comment_id = posts
+        .add_comment(post_id.clone(), "bob".into(), "Hot take".into(), None)
+        .await
+        .unwrap();
+
+    assert!(posts
+        .edit_comment(&post_id, &comment_id, "Lukewarm take")
+        .await
+        .unwrap());
+    let comment = posts
+        .get_comment(&post_id, &comment_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(comment.content, "Lukewarm take");
+    assert!(comment.edited_at.is_some());
+
+    assert!(posts.delete_comment(&comment, "alice").await.unwrap());
+    assert!(!posts.delete_comment(&comment, "alice").await.unwrap());
+    assert!(!posts
+        .edit_comment(&post_id, &comment_id, "Too late")
+        .await
+        .unwrap());
+
+    let comment = posts
+        .get_comment(&post_id, &comment_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(comment.deleted);
+    assert_eq!(comment.deleted_by.as_deref(), Some("alice"));
+}
+
+#[tokio::test]
+async fn posts_by_author_show_viewer_likes() {
+    let posts = post_service();
+    let liked = create_post(&posts, "alice").await;
+    create_post(&posts, "alice").await;
+    create_post(&posts, "bob").await;
+    posts.like_post(&liked, "carol").await.unwrap();
+
+    let views = posts.get_posts_by_author("alice", "carol").await.unwrap();
+    assert_eq!(views.len(), 2);
+    for view in views {
+        assert_eq!(view.liked_by_me, view.post.id == liked);
+    }
+}
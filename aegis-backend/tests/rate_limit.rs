@@ -0,0 +1,358 @@
+//! Rate limiting: the token bucket and sliding window algorithms on the
+//! in-memory store, concurrent hits on the Postgres store, and the layer's
+//! route policies, keys and headers.
+
+mod common;
+
+use aegis_backend::middleware::api_key::ApiKeyPrincipal;
+use aegis_backend::middleware::rate_limit::{LOGIN, RATE_LIMIT_LIMIT, RATE_LIMIT_REMAINING};
+use aegis_backend::middleware::RateLimitLayer;
+use aegis_backend::models::postgres::api_key;
+use aegis_backend::services::auth_service::Claims;
+use aegis_backend::services::rate_limit_service::RateLimitPolicy;
+use aegis_backend::services::RateLimitService;
+use axum::body::Body;
+use axum::extract::{ConnectInfo, Request};
+use axum::http::{header::RETRY_AFTER, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::Response;
+use axum::routing::{get, post};
+use axum::Router;
+use chrono::{Duration, TimeZone, Utc};
+use common::TestDb;
+use std::net::SocketAddr;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn token_bucket_allows_a_burst_then_refills_steadily() {
+    let limiter = RateLimitService::in_memory();
+    let policy = RateLimitPolicy::token_bucket("test", 3, 60);
+    let start = Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap();
+
+    for remaining in [2, 1, 0] {
+        let decision = limiter.check_at(&policy, "ip:1", start).await.unwrap();
+        assert!(decision.allowed);
+        assert_eq!(decision.remaining, remaining);
+    }
+    let denied = limiter.check_at(&policy, "ip:1", start).await.unwrap();
+    assert!(!denied.allowed);
+    assert_eq!(denied.retry_after_secs, Some(20));
+    assert_eq!(denied.reset_secs, 60);
+
+    // One token every 20 seconds
+    let later = start + Duration::seconds(20);
+    assert!(
+        limiter
+            .check_at(&policy, "ip:1", later)
+            .await
+            .unwrap()
+            .allowed
+    );
+    assert!(
+        !limiter
+            .check_at(&policy, "ip:1", later)
+            .await
+            .unwrap()
+            .allowed
+    );
+
+    // Other keys have their own bucket
+    assert!(
+        limiter
+            .check_at(&policy, "ip:2", start)
+            .await
+            .unwrap()
+            .allowed
+    );
+}
+
+#[tokio::test]
+async fn sliding_window_weighs_the_previous_window() {
+    let limiter = RateLimitService::in_memory();
+    let policy = RateLimitPolicy::sliding_window("test", 4, 60);
+    let start = Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap();
+
+    for _ in 0..4 {
+        assert!(
+            limiter
+                .check_at(&policy, "ip:1", start)
+                .await
+                .unwrap()
+                .allowed
+        );
+    }
+    let denied = limiter.check_at(&policy, "ip:1", start).await.unwrap();
+    assert!(!denied.allowed);
+    assert_eq!(denied.remaining, 0);
+    // Until the window closes and a quarter of the next one has passed
+    assert_eq!(denied.retry_after_secs, Some(75));
+
+    // Halfway through the next window half of the previous hits still count
+    let half = start + Duration::seconds(90);
+    let decision = limiter.check_at(&policy, "ip:1", half).await.unwrap();
+    assert!(decision.allowed);
+    assert_eq!(decision.remaining, 1);
+    assert!(
+        limiter
+            .check_at(&policy, "ip:1", half)
+            .await
+            .unwrap()
+            .allowed
+    );
+    assert!(
+        !limiter
+            .check_at(&policy, "ip:1", half)
+            .await
+            .unwrap()
+            .allowed
+    );
+
+    // Two windows on, the old hits are forgotten
+    let quiet = start + Duration::seconds(180);
+    let decision = limiter.check_at(&policy, "ip:1", quiet).await.unwrap();
+    assert_eq!(decision.remaining, 3);
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn concurrent_postgres_hits_never_overspend_a_bucket() {
+    let test_db = TestDb::new().await;
+    let limiter = RateLimitService::postgres(test_db.connection());
+    let start = Utc.with_ymd_and_hms(2026, 1, 1, 12, 0, 0).unwrap();
+
+    for policy in [
+        RateLimitPolicy::token_bucket("burst", 5, 60),
+        RateLimitPolicy::sliding_window("window", 5, 60),
+    ] {
+        let mut hits = tokio::task::JoinSet::new();
+        for _ in 0..20 {
+            let limiter = limiter.clone();
+            hits.spawn(async move { limiter.check_at(&policy, "ip:1", start).await });
+        }
+        let mut allowed = 0;
+        while let Some(decision) = hits.join_next().await {
+            if decision.unwrap().unwrap().allowed {
+                allowed += 1;
+            }
+        }
+        assert_eq!(allowed, 5, "{}", policy.name);
+    }
+}
+
+async fn ok() -> &'static str {
+    "ok"
+}
+
+// Signs in when `x-password` is "right", taking a moment like a password
+// hash does so concurrent attempts overlap
+async fn login(headers: axum::http::HeaderMap) -> StatusCode {
+    tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    match headers.get("x-password") {
+        Some(password) if password == "right" => StatusCode::OK,
+        _ => StatusCode::UNAUTHORIZED,
+    }
+}
+
+fn from_ip(request: Request<Body>, ip: [u8; 4]) -> Request<Body> {
+    let mut request = request;
+    request
+        .extensions_mut()
+        .insert(ConnectInfo(SocketAddr::from((ip, 4000))));
+    request
+}
+
+fn post_to(uri: &str, ip: [u8; 4]) -> Request<Body> {
+    from_ip(Request::post(uri).body(Body::empty()).unwrap(), ip)
+}
+
+fn claims(sub: &str) -> Claims {
+    Claims {
+        sub: sub.to_string(),
+        user_type: "player".to_string(),
+        role: None,
+        session_id: Uuid::new_v4().to_string(),
+        exp: usize::MAX,
+        iat: 0,
+        jti: Uuid::new_v4().to_string(),
+    }
+}
+
+// Stands in for the auth middleware: `x-user` becomes the player's claims
+// and `x-api-key` a principal with a two-request quota
+async fn fake_auth(mut request: Request, next: Next) -> Response {
+    let user = request
+        .headers()
+        .get("x-user")
+        .map(|v| v.to_str().unwrap().to_string());
+    if let Some(user) = user {
+        request.extensions_mut().insert(claims(&user));
+    }
+    if request.headers().contains_key("x-api-key") {
+        request
+            .extensions_mut()
+            .insert(ApiKeyPrincipal(api_key::Model {
+                id: Uuid::new_v4(),
+                key_id: "ak_0123456789abcdef".into(),
+                key_hash: String::new(),
+                name: "Overlay".into(),
+                owner_id: Uuid::new_v4(),
+                owner_type: "organization".into(),
+                scopes: vec!["*".into()],
+                rate_limit_per_hour: 2,
+                expires_at: None,
+                last_used_at: None,
+                is_active: true,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            }));
+    }
+    next.run(request).await
+}
+
+fn app() -> Router {
+    Router::new()
+        .route("/auth/login", post(login))
+        .route("/chats/:chat_id/messages", post(ok))
+        .route("/health", get(ok))
+        .layer(RateLimitLayer::new(RateLimitService::in_memory()))
+        .layer(middleware::from_fn(fake_auth))
+}
+
+fn sign_in(ip: [u8; 4], password: &str) -> Request<Body> {
+    let mut request = post_to("/auth/login", ip);
+    request
+        .headers_mut()
+        .insert("x-password", password.parse().unwrap());
+    request
+}
+
+#[tokio::test]
+async fn failed_logins_are_limited_per_ip_with_headers() {
+    let app = app();
+
+    for failed in 1..=LOGIN.limit {
+        let response = app
+            .clone()
+            .oneshot(sign_in([10, 0, 0, 1], "wrong"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            response.headers()[RATE_LIMIT_LIMIT],
+            LOGIN.limit.to_string()
+        );
+        assert_eq!(
+            response.headers()[RATE_LIMIT_REMAINING],
+            (LOGIN.limit - failed).to_string()
+        );
+    }
+
+    // Locked out, even with the right password
+    let response = app
+        .clone()
+        .oneshot(sign_in([10, 0, 0, 1], "right"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key(RETRY_AFTER));
+
+    // Another client is unaffected, and unlisted routes carry no headers
+    let response = app
+        .clone()
+        .oneshot(sign_in([10, 0, 0, 2], "right"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = app
+        .oneshot(from_ip(
+            Request::get("/health").body(Body::empty()).unwrap(),
+            [10, 0, 0, 1],
+        ))
+        .await
+        .unwrap();
+    assert!(!response.headers().contains_key(RATE_LIMIT_LIMIT));
+}
+
+#[tokio::test]
+async fn concurrent_failed_logins_share_the_limit() {
+    let app = app();
+
+    let mut attempts = tokio::task::JoinSet::new();
+    for _ in 0..LOGIN.limit * 3 {
+        attempts.spawn(app.clone().oneshot(sign_in([10, 0, 0, 4], "wrong")));
+    }
+    let mut unauthorized = 0;
+    while let Some(response) = attempts.join_next().await {
+        match response.unwrap().unwrap().status() {
+            StatusCode::UNAUTHORIZED => unauthorized += 1,
+            status => assert_eq!(status, StatusCode::TOO_MANY_REQUESTS),
+        }
+    }
+    assert_eq!(unauthorized, LOGIN.limit);
+}
+
+#[tokio::test]
+async fn successful_logins_are_not_counted() {
+    let app = app();
+
+    for _ in 0..LOGIN.limit * 2 {
+        let response = app
+            .clone()
+            .oneshot(sign_in([10, 0, 0, 3], "right"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+    let response = app.oneshot(sign_in([10, 0, 0, 3], "wrong")).await.unwrap();
+    assert_eq!(
+        response.headers()[RATE_LIMIT_REMAINING],
+        (LOGIN.limit - 1).to_string()
+    );
+}
+
+#[tokio::test]
+async fn messages_are_limited_per_user_across_ips() {
+    let app = app();
+    let send = |user: &str, ip: [u8; 4]| {
+        let mut request = post_to("/chats/general/messages", ip);
+        request
+            .headers_mut()
+            .insert("x-user", user.parse().unwrap());
+        app.clone().oneshot(request)
+    };
+
+    let mut last = StatusCode::OK;
+    for i in 0..40u8 {
+        last = send("alice", [10, 0, 1, i]).await.unwrap().status();
+        if last != StatusCode::OK {
+            break;
+        }
+    }
+    assert_eq!(last, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(
+        send("bob", [10, 0, 1, 0]).await.unwrap().status(),
+        StatusCode::OK
+    );
+}
+
+#[tokio::test]
+async fn api_keys_are_held_to_their_hourly_quota() {
+    let app = app();
+    let call = || {
+        let mut request = Request::get("/health").body(Body::empty()).unwrap();
+        request
+            .headers_mut()
+            .insert("x-api-key", "ak_0123456789abcdef_secret".parse().unwrap());
+        app.clone().oneshot(from_ip(request, [10, 0, 2, 1]))
+    };
+
+    let response = call().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[RATE_LIMIT_LIMIT], "2");
+    assert_eq!(call().await.unwrap().status(), StatusCode::OK);
+    assert_eq!(
+        call().await.unwrap().status(),
+        StatusCode::TOO_MANY_REQUESTS
+    );
+}
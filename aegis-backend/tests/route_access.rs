@@ -0,0 +1,183 @@
+//! Route authorization: every route declares who may call it, and the
+//! router enforces it.
+
+use aegis_backend::config::settings::{
+    ApiKeyConfig, DatabaseConfig, JwtConfig, RateLimitConfig, ServerConfig, StorageConfig,
+};
+use aegis_backend::config::{
+    AwsClients, EmailConfig, EmailTransportKind, RateLimitBackend, Settings, StorageBackend,
+};
+use aegis_backend::models::postgres::admin::AdminPermission;
+use aegis_backend::routes::api::{create_routes, route_table, Access};
+use aegis_backend::AppState;
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use axum::Router;
+use std::collections::BTreeSet;
+use tower::ServiceExt;
+
+fn settings() -> Settings {
+    Settings {
+        server: ServerConfig {
+            host: "127.0.0.1".into(),
+            port: 0,
+        },
+        database: DatabaseConfig {
+            url: String::new(),
+            max_connections: 1,
+        },
+        jwt: JwtConfig {
+            secret: "secret".into(),
+            expiration: 3600,
+        },
+        api_keys: ApiKeyConfig {
+            digest_secret: "digest-secret".into(),
+        },
+        email: EmailConfig {
+            smtp_host: "localhost".into(),
+            smtp_port: 25,
+            smtp_user: String::new(),
+            smtp_pass: String::new(),
+            from_email: "noreply@example.com".into(),
+            from_name: "Aegis".into(),
+            frontend_url: "https://aegis.gg/".into(),
+            templates_dir: concat!(env!("CARGO_MANIFEST_DIR"), "/templates/email").into(),
+            transport: EmailTransportKind::Memory,
+        },
+        storage: StorageConfig {
+            backend: StorageBackend::Memory,
+        },
+        rate_limit: RateLimitConfig {
+            backend: RateLimitBackend::Memory,
+        },
+    }
+}
+
+async fn app() -> Router {
+    let (_, io) = socketioxide::SocketIo::new_layer();
+    let state = AppState::new(
+        sea_orm::DatabaseConnection::Disconnected,
+        AwsClients::new().await,
+        settings(),
+        io,
+    )
+    .await;
+    create_routes(state.clone()).with_state(state)
+}
+
+/// A request path for a route pattern, with every parameter filled in.
+fn concrete(pattern: &str) -> String {
+    pattern
+        .split('/')
+        .map(|segment| {
+            if segment.starts_with(':') {
+                "00000000-0000-0000-0000-000000000000"
+            } else {
+                segment
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[test]
+fn every_route_declares_its_access() {
+    let routes = route_table();
+    let mut public = BTreeSet::new();
+
+    for (access, path, _) in &routes {
+        match access {
+            Access::Public => {
+                public.insert(*path);
+            }
+            Access::Admin(permission) => {
+                assert!(path.starts_with("/admin/"), "{} is not an admin path", path);
+                // Managing admins stays behind the one permission overrides can't grant
+                assert_eq!(
+                    path.starts_with("/admin/admins")
+                        || path.starts_with("/admin/two-factor")
+                        || *path == "/admin/permissions",
+                    *permission == AdminPermission::AdminsManage,
+                    "{} requires {:?}",
+                    path,
+                    permission
+                );
+            }
+            Access::Organization => assert!(
+                path.starts_with("/organizations/me/"),
+                "{} is not an organization path",
+                path
+            ),
+            Access::Authenticated | Access::Player => assert!(
+                !path.starts_with("/admin") && !path.starts_with("/organizations"),
+                "{} needs a narrower policy",
+                path
+            ),
+        }
+    }
+
+    // Opening a route to anonymous callers has to be a deliberate change here
+    assert_eq!(
+        public,
+        BTreeSet::from([
+            "/auth/login",
+            "/auth/register",
+            "/auth/forgot-password",
+            "/auth/reset-password/:token",
+            "/auth/verify-email/:token",
+            "/auth/2fa/challenge",
+            "/auth/2fa/challenge/enroll",
+            "/notifications/unsubscribe/:token",
+            "/activity/recent/:activity_type",
+            "/players/username/:username",
+        ])
+    );
+
+    // One access per path: a path split across tiers is easy to misread
+    let mut seen = std::collections::HashMap::new();
+    for (access, path, _) in &routes {
+        if let Some(previous) = seen.insert(*path, *access) {
+            panic!(
+                "{} is declared as both {:?} and {:?}",
+                path, previous, access
+            );
+        }
+    }
+}
+
+#[tokio::test]
+async fn protected_routes_reject_anonymous_requests() {
+    let app = app().await;
+
+    for (access, path, _) in route_table() {
+        if access == Access::Public {
+            continue;
+        }
+        let mut statuses = Vec::new();
+        for method in [Method::GET, Method::POST, Method::PUT, Method::DELETE] {
+            let request = Request::builder()
+                .method(method)
+                .uri(concrete(path))
+                .body(Body::empty())
+                .unwrap();
+            statuses.push(app.clone().oneshot(request).await.unwrap().status());
+        }
+
+        assert!(
+            statuses.contains(&StatusCode::UNAUTHORIZED),
+            "{} ({:?}) answered {:?}",
+            path,
+            access,
+            statuses
+        );
+        assert!(
+            statuses
+                .iter()
+                .all(|s| *s == StatusCode::UNAUTHORIZED || *s == StatusCode::METHOD_NOT_ALLOWED),
+            "{} ({:?}) answered {:?}",
+            path,
+            access,
+            statuses
+        );
+    }
+}
@@ -0,0 +1,295 @@
+//! TOTP two-factor authentication: codes, replay protection, provisioning
+//! URIs and recovery code hashing, and signing in with a second factor
+//! through the router against Postgres.
+
+mod common;
+
+use aegis_backend::config::settings::{
+    ApiKeyConfig, DatabaseConfig, JwtConfig, RateLimitConfig, ServerConfig, StorageConfig,
+};
+use aegis_backend::config::{
+    AwsClients, EmailConfig, EmailTransportKind, RateLimitBackend, Settings, StorageBackend,
+};
+use aegis_backend::middleware::rate_limit::TWO_FACTOR;
+use aegis_backend::routes::api::create_routes;
+use aegis_backend::services::two_factor_service::{
+    base32_encode, hash_recovery_code, matching_step, provisioning_uri, totp, TOTP_STEP_SECS,
+};
+use aegis_backend::AppState;
+use axum::body::Body;
+use axum::extract::ConnectInfo;
+use axum::http::{header, Request, StatusCode};
+use axum::Router;
+use chrono::{TimeZone, Utc};
+use common::TestDb;
+use sea_orm::DatabaseConnection;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use tower::ServiceExt;
+
+// The SHA-1 secret from RFC 6238 appendix B
+const SECRET: &[u8] = b"12345678901234567890";
+
+#[test]
+fn codes_match_the_rfc_test_vectors() {
+    // The RFC lists eight digits; six-digit codes are their last six
+    for (time, code) in [
+        (59, "287082"),
+        (1111111109, "081804"),
+        (1234567890, "005924"),
+        (2000000000, "279037"),
+    ] {
+        assert_eq!(totp(SECRET, time / 30), code, "at {}", time);
+    }
+}
+
+#[test]
+fn secrets_are_encoded_in_base32() {
+    assert_eq!(base32_encode(SECRET), "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ");
+    assert_eq!(base32_encode(b"f"), "MY");
+    assert_eq!(base32_encode(b"foobar"), "MZXW6YTBOI");
+}
+
+#[test]
+fn codes_are_accepted_one_step_either_side_of_now() {
+    let now = Utc.timestamp_opt(1111111109, 0).unwrap();
+    let current = now.timestamp() / 30;
+
+    assert_eq!(matching_step(SECRET, "081804", now, None), Some(current));
+    assert_eq!(
+        matching_step(SECRET, &totp(SECRET, current - 1), now, None),
+        Some(current - 1)
+    );
+    assert_eq!(
+        matching_step(SECRET, &totp(SECRET, current + 1), now, None),
+        Some(current + 1)
+    );
+    assert_eq!(
+        matching_step(SECRET, &totp(SECRET, current - 2), now, None),
+        None
+    );
+
+    // Spaces are tolerated, anything but six digits is not
+    assert_eq!(matching_step(SECRET, "081 804", now, None), Some(current));
+    assert_eq!(matching_step(SECRET, "81804", now, None), None);
+    assert_eq!(matching_step(SECRET, "08180a", now, None), None);
+}
+
+#[test]
+fn a_code_is_not_accepted_twice() {
+    let now = Utc.timestamp_opt(1111111109, 0).unwrap();
+    let step = matching_step(SECRET, "081804", now, None).unwrap();
+
+    assert_eq!(matching_step(SECRET, "081804", now, Some(step)), None);
+    // Nor is an older one after a newer one was used
+    let previous = totp(SECRET, step - 1);
+    assert_eq!(matching_step(SECRET, &previous, now, Some(step)), None);
+}
+
+#[test]
+fn provisioning_uri_names_the_issuer_and_account() {
+    assert_eq!(
+        provisioning_uri("ops+2fa@aegis.gg", SECRET),
+        "otpauth://totp/Aegis:ops%2B2fa@aegis.gg?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ\
+         &issuer=Aegis&algorithm=SHA1&digits=6&period=30"
+    );
+}
+
+#[test]
+fn recovery_codes_are_hashed_and_matched_loosely() {
+    let hash = hash_recovery_code("abcde-fghij");
+    assert_eq!(hash.len(), 64);
+    assert!(!hash.contains("abcde"));
+    assert_eq!(hash_recovery_code("ABCDE FGHIJ"), hash);
+    assert_eq!(hash_recovery_code("abcdefghij"), hash);
+    assert_ne!(hash_recovery_code("abcde-fghik"), hash);
+}
+
+fn settings() -> Settings {
+    Settings {
+        server: ServerConfig {
+            host: "127.0.0.1".into(),
+            port: 0,
+        },
+        database: DatabaseConfig {
+            url: String::new(),
+            max_connections: 1,
+        },
+        jwt: JwtConfig {
+            secret: "secret".into(),
+            expiration: 3600,
+        },
+        api_keys: ApiKeyConfig {
+            digest_secret: "digest-secret".into(),
+        },
+        email: EmailConfig {
+            smtp_host: "localhost".into(),
+            smtp_port: 25,
+            smtp_user: String::new(),
+            smtp_pass: String::new(),
+            from_email: "noreply@example.com".into(),
+            from_name: "Aegis".into(),
+            frontend_url: "https://aegis.gg/".into(),
+            templates_dir: concat!(env!("CARGO_MANIFEST_DIR"), "/templates/email").into(),
+            transport: EmailTransportKind::Memory,
+        },
+        storage: StorageConfig {
+            backend: StorageBackend::Memory,
+        },
+        rate_limit: RateLimitConfig {
+            backend: RateLimitBackend::Memory,
+        },
+    }
+}
+
+/// The router with a player who has enrolled in 2FA, and their TOTP secret
+/// and the time step of the code that confirmed enrolment.
+async fn enrolled_player(db: DatabaseConnection) -> (Router, Vec<u8>, i64) {
+    let (_, io) = socketioxide::SocketIo::new_layer();
+    let state = AppState::new(db, AwsClients::new().await, settings(), io).await;
+    let (player, _) = state
+        .player_service
+        .create_player(
+            "alice@aegis.gg".into(),
+            "alice".into(),
+            "Correct-Horse-9!".into(),
+        )
+        .await
+        .unwrap();
+
+    let two_factor = &state.two_factor_service;
+    two_factor
+        .begin_enrolment(player.id, "player", &player.email)
+        .await
+        .unwrap();
+    let credential = two_factor.get(player.id, "player").await.unwrap().unwrap();
+    let secret = hex::decode(&credential.secret).unwrap();
+    let step = Utc::now().timestamp() / TOTP_STEP_SECS;
+    two_factor
+        .confirm_enrolment(player.id, "player", &totp(&secret, step))
+        .await
+        .unwrap();
+
+    (create_routes(state.clone()).with_state(state), secret, step)
+}
+
+async fn post_json(app: &Router, uri: &str, ip: [u8; 4], body: Value) -> (StatusCode, Value) {
+    let mut request = Request::post(uri)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap();
+    request
+        .extensions_mut()
+        .insert(ConnectInfo(SocketAddr::from((ip, 4000))));
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (
+        status,
+        serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+    )
+}
+
+async fn challenge_token(app: &Router, ip: [u8; 4]) -> String {
+    let (status, body) = post_json(
+        app,
+        "/auth/login",
+        ip,
+        json!({ "email": "alice@aegis.gg", "password": "Correct-Horse-9!" }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    // The password alone doesn't sign in
+    assert!(body.get("token").is_none());
+    body["challenge_token"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn login_challenge_and_code_start_a_session() {
+    let test_db = TestDb::new().await;
+    let db = test_db.connection();
+    let (app, secret, step) = enrolled_player(db).await;
+    let token = challenge_token(&app, [10, 0, 0, 1]).await;
+
+    let wrong = if totp(&secret, step + 1) == "000000" {
+        "111111"
+    } else {
+        "000000"
+    };
+    let (status, _) = post_json(
+        &app,
+        "/auth/2fa/challenge",
+        [10, 0, 0, 1],
+        json!({ "challenge_token": token, "code": wrong }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // The code that confirmed enrolment was spent; the next one signs in
+    let (status, _) = post_json(
+        &app,
+        "/auth/2fa/challenge",
+        [10, 0, 0, 1],
+        json!({ "challenge_token": token, "code": totp(&secret, step) }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, session) = post_json(
+        &app,
+        "/auth/2fa/challenge",
+        [10, 0, 0, 1],
+        json!({ "challenge_token": token, "code": totp(&secret, step + 1) }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // The session's token works on signed-in routes
+    let request = Request::get("/auth/2fa")
+        .header(
+            header::AUTHORIZATION,
+            format!("Bearer {}", session["token"].as_str().unwrap()),
+        )
+        .body(Body::empty())
+        .unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let status: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(status["data"]["enabled"], true);
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn challenge_guesses_are_limited_per_account() {
+    let test_db = TestDb::new().await;
+    let db = test_db.connection();
+    let (app, secret, step) = enrolled_player(db).await;
+    let token = challenge_token(&app, [10, 0, 1, 0]).await;
+    let code = totp(&secret, step + 1);
+    let wrong = if code == "000000" { "111111" } else { "000000" };
+
+    // Each guess from its own address, so only the account's limit applies
+    for i in 1..=TWO_FACTOR.limit as u8 {
+        let (status, _) = post_json(
+            &app,
+            "/auth/2fa/challenge",
+            [10, 0, 1, i],
+            json!({ "challenge_token": token, "code": wrong }),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+    let (status, _) = post_json(
+        &app,
+        "/auth/2fa/challenge",
+        [10, 0, 2, 1],
+        json!({ "challenge_token": token, "code": code }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+}
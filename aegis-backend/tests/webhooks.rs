@@ -0,0 +1,401 @@
+//! Signed webhook requests against a local HTTP stand-in for an organization's
+//! endpoint, destination checks, and the delivery worker against Postgres.
+
+mod common;
+
+use aegis_backend::models::postgres::webhook_delivery;
+use aegis_backend::models::postgres::webhook_endpoint::{self, WebhookEvent};
+use aegis_backend::services::webhook_service::{
+    is_public_address, retry_delay, signature, DELIVERY_HEADER, EVENT_HEADER, MAX_ATTEMPTS,
+    SIGNATURE_HEADER,
+};
+use aegis_backend::services::WebhookService;
+use aegis_backend::utils::errors::AppError;
+use axum::http::{HeaderMap, StatusCode};
+use axum::{routing::post, Router};
+use chrono::{Duration, Utc};
+use common::TestDb;
+use hmac::{Hmac, Mac};
+use sea_orm::{ActiveModelTrait, ConnectionTrait, DatabaseConnection, Set};
+use serde_json::json;
+use sha2::Sha256;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+type Received = Arc<Mutex<Vec<(HeaderMap, Vec<u8>)>>>;
+
+/// Serves `status` on every request and records what was received.
+async fn stand_in(status: StatusCode) -> (String, Received) {
+    let received = Received::default();
+    let recorder = received.clone();
+    let app = Router::new().route(
+        "/hook",
+        post(
+            move |headers: HeaderMap, body: axum::body::Bytes| async move {
+                recorder.lock().unwrap().push((headers, body.to_vec()));
+                status
+            },
+        ),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (url, received)
+}
+
+fn webhooks() -> WebhookService {
+    WebhookService::new(sea_orm::DatabaseConnection::Disconnected).allow_local_destinations()
+}
+
+#[tokio::test]
+async fn deliveries_are_signed_with_the_endpoint_secret() {
+    let (url, received) = stand_in(StatusCode::NO_CONTENT).await;
+    let delivery_id = Uuid::new_v4();
+    let payload = json!({ "event": "battle.completed", "data": { "battle_number": 3 } });
+
+    let status = webhooks()
+        .send_signed(
+            &url,
+            "whsec_test",
+            "battle.completed",
+            delivery_id,
+            &payload,
+        )
+        .await
+        .unwrap();
+    assert_eq!(status, 204);
+
+    let received = received.lock().unwrap();
+    let (headers, body) = &received[0];
+    assert_eq!(headers[EVENT_HEADER], "battle.completed");
+    assert_eq!(headers[DELIVERY_HEADER], delivery_id.to_string().as_str());
+    assert_eq!(
+        serde_json::from_slice::<serde_json::Value>(body).unwrap(),
+        payload
+    );
+
+    // Verify the way a receiver would: HMAC-SHA256 over "<t>.<body>"
+    let header = headers[SIGNATURE_HEADER].to_str().unwrap();
+    let (timestamp, digest) = header
+        .strip_prefix("t=")
+        .and_then(|rest| rest.split_once(",v1="))
+        .unwrap();
+    assert!((Utc::now().timestamp() - timestamp.parse::<i64>().unwrap()).abs() < 60);
+    let mut mac = Hmac::<Sha256>::new_from_slice(b"whsec_test").unwrap();
+    mac.update(format!("{}.", timestamp).as_bytes());
+    mac.update(body);
+    mac.verify_slice(&hex::decode(digest).unwrap()).unwrap();
+}
+
+#[tokio::test]
+async fn error_statuses_are_reported_not_raised() {
+    let (url, _) = stand_in(StatusCode::INTERNAL_SERVER_ERROR).await;
+    let status = webhooks()
+        .send_signed(
+            &url,
+            "whsec_test",
+            "webhook.test",
+            Uuid::new_v4(),
+            &json!({}),
+        )
+        .await
+        .unwrap();
+    assert_eq!(status, 500);
+
+    let unreachable = webhooks()
+        .send_signed(
+            "http://127.0.0.1:1/hook",
+            "whsec_test",
+            "webhook.test",
+            Uuid::new_v4(),
+            &json!({}),
+        )
+        .await;
+    assert!(unreachable.is_err());
+}
+
+#[test]
+fn signature_depends_on_secret_timestamp_and_body() {
+    let base = signature("secret", 1_700_000_000, b"{}");
+    assert!(base.starts_with("t=1700000000,v1="));
+    assert_ne!(base, signature("other", 1_700_000_000, b"{}"));
+    assert_ne!(base, signature("secret", 1_700_000_001, b"{}"));
+    assert_ne!(base, signature("secret", 1_700_000_000, b"[]"));
+}
+
+#[test]
+fn endpoints_receive_subscribed_events_and_tests() {
+    let endpoint = webhook_endpoint::Model {
+        id: Uuid::new_v4(),
+        organization_id: Uuid::new_v4(),
+        url: "https://bot.example.com/aegis".into(),
+        secret: "whsec_test".into(),
+        events: vec!["battle.completed".into()],
+        is_active: true,
+        created_at: Utc::now(),
+        updated_at: Utc::now(),
+    };
+    assert!(endpoint.subscribes_to(WebhookEvent::BattleCompleted));
+    assert!(endpoint.subscribes_to(WebhookEvent::Test));
+    assert!(!endpoint.subscribes_to(WebhookEvent::BattleScheduled));
+}
+
+#[test]
+fn retries_back_off_exponentially_up_to_six_hours() {
+    assert_eq!(retry_delay(1), Duration::minutes(1));
+    assert_eq!(retry_delay(3), Duration::minutes(4));
+    assert_eq!(retry_delay(20), Duration::hours(6));
+}
+
+#[test]
+fn internal_addresses_are_not_public() {
+    for internal in [
+        "127.0.0.1",
+        "10.1.2.3",
+        "172.16.0.1",
+        "192.168.1.1",
+        "169.254.169.254",
+        "100.100.100.200",
+        "0.0.0.0",
+        "255.255.255.255",
+        "::1",
+        "::",
+        "fe80::1",
+        "fd00:ec2::254",
+        "::ffff:127.0.0.1",
+        "::ffff:169.254.169.254",
+    ] {
+        assert!(
+            !is_public_address(internal.parse().unwrap()),
+            "{}",
+            internal
+        );
+    }
+    for public in ["93.184.216.34", "1.1.1.1", "2606:4700:4700::1111"] {
+        assert!(is_public_address(public.parse().unwrap()), "{}", public);
+    }
+}
+
+#[tokio::test]
+async fn endpoints_must_be_https_on_public_addresses() {
+    let strict = WebhookService::new(sea_orm::DatabaseConnection::Disconnected);
+    for url in [
+        "http://93.184.216.34/hook",
+        "ftp://93.184.216.34/hook",
+        "https://127.0.0.1/hook",
+        "https://localhost/hook",
+        "https://169.254.169.254/latest/meta-data",
+        "https://[::1]/hook",
+        "https://[::ffff:10.0.0.1]/hook",
+    ] {
+        let created = strict
+            .create_endpoint(
+                Uuid::new_v4(),
+                url.to_string(),
+                vec![WebhookEvent::BattleCompleted],
+            )
+            .await;
+        assert!(matches!(created, Err(AppError::Validation(_))), "{}", url);
+    }
+}
+
+#[tokio::test]
+async fn deliveries_to_internal_addresses_are_refused() {
+    // An endpoint whose host later resolves internally is refused at send time
+    let (url, received) = stand_in(StatusCode::NO_CONTENT).await;
+    let strict = WebhookService::new(sea_orm::DatabaseConnection::Disconnected);
+    for url in [url.as_str(), "https://localhost/hook"] {
+        let sent = strict
+            .send_signed(
+                url,
+                "whsec_test",
+                "webhook.test",
+                Uuid::new_v4(),
+                &json!({}),
+            )
+            .await;
+        assert!(sent.is_err(), "{}", url);
+    }
+    assert!(received.lock().unwrap().is_empty());
+}
+
+/// An organization with one endpoint at `url` subscribed to battle.completed.
+async fn subscribed(
+    db: &DatabaseConnection,
+    webhooks: &WebhookService,
+    url: &str,
+) -> (Uuid, webhook_endpoint::Model) {
+    let organization_id = Uuid::new_v4();
+    db.execute_unprepared(&format!(
+        "INSERT INTO organizations (id, org_name, owner_name, email, password, country)
+        VALUES ('{0}', 'Org {0}', 'Owner', '{0}@example.com', 'hash', 'India')",
+        organization_id
+    ))
+    .await
+    .unwrap();
+    let (endpoint, _) = webhooks
+        .create_endpoint(
+            organization_id,
+            url.to_string(),
+            vec![WebhookEvent::BattleCompleted],
+        )
+        .await
+        .unwrap();
+    (organization_id, endpoint)
+}
+
+async fn only_delivery(
+    webhooks: &WebhookService,
+    organization_id: Uuid,
+    endpoint: &webhook_endpoint::Model,
+) -> webhook_delivery::Model {
+    let mut log = webhooks
+        .list_deliveries(organization_id, endpoint.id, 10, 0)
+        .await
+        .unwrap();
+    assert_eq!(log.len(), 1);
+    log.remove(0)
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn worker_delivers_subscribed_events_once_and_logs_them() {
+    let test_db = TestDb::new().await;
+    let db = test_db.connection();
+    let (url, received) = stand_in(StatusCode::NO_CONTENT).await;
+    let webhooks = WebhookService::new(db.clone()).allow_local_destinations();
+    let (organization_id, endpoint) = subscribed(&db, &webhooks, &url).await;
+
+    webhooks
+        .publish(
+            organization_id,
+            WebhookEvent::BattleScheduled,
+            json!({ "battle_number": 1 }),
+        )
+        .await
+        .unwrap();
+    webhooks
+        .publish(
+            organization_id,
+            WebhookEvent::BattleCompleted,
+            json!({ "battle_number": 1 }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(webhooks.process_due(10).await.unwrap(), 1);
+    assert_eq!(webhooks.process_due(10).await.unwrap(), 0);
+
+    let received = received.lock().unwrap().clone();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].0[EVENT_HEADER], "battle.completed");
+
+    let delivery = only_delivery(&webhooks, organization_id, &endpoint).await;
+    assert_eq!(delivery.event_type, "battle.completed");
+    assert_eq!(delivery.status, webhook_delivery::DELIVERED);
+    assert_eq!(delivery.attempts, 1);
+    assert_eq!(delivery.response_status, Some(204));
+    assert!(delivery.delivered_at.is_some());
+    assert_eq!(delivery.last_error, None);
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn failed_delivery_is_retried_after_a_backoff() {
+    let test_db = TestDb::new().await;
+    let db = test_db.connection();
+    let (url, received) = stand_in(StatusCode::INTERNAL_SERVER_ERROR).await;
+    let webhooks = WebhookService::new(db.clone()).allow_local_destinations();
+    let (organization_id, endpoint) = subscribed(&db, &webhooks, &url).await;
+
+    webhooks
+        .publish(organization_id, WebhookEvent::BattleCompleted, json!({}))
+        .await
+        .unwrap();
+    let before = Utc::now();
+    assert_eq!(webhooks.process_due(10).await.unwrap(), 0);
+
+    let delivery = only_delivery(&webhooks, organization_id, &endpoint).await;
+    assert_eq!(delivery.status, webhook_delivery::PENDING);
+    assert_eq!(delivery.attempts, 1);
+    assert_eq!(delivery.response_status, Some(500));
+    assert_eq!(
+        delivery.last_error.as_deref(),
+        Some("endpoint responded with 500")
+    );
+    assert!(delivery.next_attempt_at >= before + retry_delay(1));
+    assert!(delivery.next_attempt_at <= Utc::now() + retry_delay(1));
+
+    // Not due again until the backoff has passed
+    assert_eq!(webhooks.process_due(10).await.unwrap(), 0);
+    assert_eq!(received.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn last_failed_attempt_marks_the_delivery_failed() {
+    let test_db = TestDb::new().await;
+    let db = test_db.connection();
+    let (url, _) = stand_in(StatusCode::BAD_GATEWAY).await;
+    let webhooks = WebhookService::new(db.clone()).allow_local_destinations();
+    let (organization_id, endpoint) = subscribed(&db, &webhooks, &url).await;
+
+    webhooks
+        .publish(organization_id, WebhookEvent::BattleCompleted, json!({}))
+        .await
+        .unwrap();
+    let queued = only_delivery(&webhooks, organization_id, &endpoint).await;
+    webhook_delivery::ActiveModel {
+        id: Set(queued.id),
+        attempts: Set(MAX_ATTEMPTS - 1),
+        ..Default::default()
+    }
+    .update(&db)
+    .await
+    .unwrap();
+    assert_eq!(webhooks.process_due(10).await.unwrap(), 0);
+
+    let failed = only_delivery(&webhooks, organization_id, &endpoint).await;
+    assert_eq!(failed.status, webhook_delivery::FAILED);
+    assert_eq!(failed.attempts, MAX_ATTEMPTS);
+    assert_eq!(failed.response_status, Some(502));
+
+    // Failed deliveries are never picked up again
+    webhook_delivery::ActiveModel {
+        id: Set(failed.id),
+        next_attempt_at: Set(Utc::now() - Duration::minutes(1)),
+        ..Default::default()
+    }
+    .update(&db)
+    .await
+    .unwrap();
+    assert_eq!(webhooks.process_due(10).await.unwrap(), 0);
+    let failed = only_delivery(&webhooks, organization_id, &endpoint).await;
+    assert_eq!(failed.attempts, MAX_ATTEMPTS);
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn worker_checks_the_destination_before_every_attempt() {
+    let test_db = TestDb::new().await;
+    let db = test_db.connection();
+    let (url, received) = stand_in(StatusCode::NO_CONTENT).await;
+    let (organization_id, endpoint) = subscribed(
+        &db,
+        &WebhookService::new(db.clone()).allow_local_destinations(),
+        &url,
+    )
+    .await;
+    let webhooks = WebhookService::new(db.clone());
+
+    webhooks
+        .publish(organization_id, WebhookEvent::BattleCompleted, json!({}))
+        .await
+        .unwrap();
+    assert_eq!(webhooks.process_due(10).await.unwrap(), 0);
+
+    let delivery = only_delivery(&webhooks, organization_id, &endpoint).await;
+    assert_eq!(delivery.status, webhook_delivery::PENDING);
+    assert_eq!(delivery.response_status, None);
+    assert!(delivery.last_error.unwrap().contains("https"));
+    assert!(received.lock().unwrap().is_empty());
+}